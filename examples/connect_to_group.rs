@@ -55,7 +55,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     };
 
     info!("Connecting...");
-    let mut client = config.connect((address.as_str(), port))?;
+    let client = config.connect((address.as_str(), port))?;
 
     info!("Connected!");
     info!("Capabilities: {:#?}", client.capabilities());
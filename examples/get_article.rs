@@ -1,6 +1,5 @@
 use std::time::Duration;
 
-use brokaw::types::command as cmd;
 use brokaw::{ClientConfig, ConnectionConfig};
 
 fn main() -> anyhow::Result<()> {
@@ -17,9 +16,7 @@ fn main() -> anyhow::Result<()> {
 
     let highest_article = client.group().unwrap().high;
 
-    let article = client
-        .article(cmd::Article::Number(highest_article))
-        .and_then(|a| a.to_text())?;
+    let article = client.article(highest_article).and_then(|a| a.to_text())?;
 
     println!("~~~ 📰 `{}` ~~~", article.message_id());
     println!("~~~ Headers ~~~");
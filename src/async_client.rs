@@ -0,0 +1,705 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::convert::{TryFrom, TryInto};
+
+use log::*;
+use tokio::io::AsyncWriteExt;
+use tokio::net::ToSocketAddrs;
+
+use crate::client::{
+    authinfo_pass_response, authinfo_user_response, capabilities_response, check_auth_supported,
+    check_discontinued, check_service_available, first_article, greeting_software, group_response,
+    is_access_denied, mode_reader_response, navigate_response, needs_mode_reader,
+    record_posting_statuses, stat_response, validate_wildmat, AuthMethod, ClientConfig,
+    ConnectionInfo, HandshakeOrder, RawLine,
+};
+use crate::error::{Error, Result};
+use crate::raw::async_connection::AsyncNntpConnection;
+use crate::raw::response::RawResponse;
+use crate::types::command as cmd;
+use crate::types::prelude::*;
+
+/// An async client that returns typed responses and provides state management
+///
+/// `AsyncNntpClient` mirrors the [`NntpClient`](crate::client::NntpClient) on top of an
+/// [`AsyncNntpConnection`], allowing many connections to be driven from a single thread.
+/// Responses are parsed in the same way, so the methods return the same types.
+///
+/// Clients are created with [`ClientConfig::connect_async`]. The following settings are not
+/// supported by the async client and cause `connect_async` to return [`Error::Unsupported`]:
+///
+/// * [`starttls`](ClientConfig::starttls)
+/// * [`compress`](ClientConfig::compress)
+/// * [`authinfo_sasl`](ClientConfig::authinfo_sasl)
+/// * [`on_auth_or_mode_required`](ClientConfig::on_auth_or_mode_required)
+/// * [`auto_reconnect`](ClientConfig::auto_reconnect)
+/// * [`proxy`](ClientConfig::proxy)
+///
+/// ```no_run
+/// use brokaw::ClientConfig;
+/// use brokaw::error::Result;
+/// use brokaw::types::prelude::*;
+///
+/// async fn latest_subjects() -> Result<Vec<String>> {
+///     let mut client = ClientConfig::default()
+///         .connect_async(("news.example.com", 119))
+///         .await?;
+///     let low = client.select_group("misc.test").await?.high.saturating_sub(10);
+///     let overviews = client.overviews(ArticleRange::from(low..)).await?;
+///     Ok(overviews.into_iter().map(|o| o.subject).collect())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct AsyncNntpClient {
+    conn: AsyncNntpConnection,
+    greeting: RawResponse,
+    greeting_software: Option<String>,
+    config: ClientConfig,
+    capabilities: Capabilities,
+    capabilities_fetched: bool,
+    group: Option<Group>,
+    current_article: Option<ArticleNumber>,
+    posting_statuses: HashMap<String, GroupStatus>,
+    overview_fmt: Option<OverviewFmt>,
+    /// Set once `OVER` was rejected with a `500`
+    legacy_overview: bool,
+    /// The `400` response if the server discontinued the session
+    discontinued: Option<RawResponse>,
+}
+
+impl AsyncNntpClient {
+    /// Get the raw [`AsyncNntpConnection`] for the client
+    ///
+    /// See [`NntpClient::conn`](crate::client::NntpClient::conn) for the caveats.
+    pub fn conn(&mut self) -> &mut AsyncNntpConnection {
+        &mut self.conn
+    }
+
+    /// Send a command
+    ///
+    /// See [`NntpClient::command`](crate::client::NntpClient::command).
+    pub async fn command(&mut self, c: impl NntpCommand) -> Result<RawResponse> {
+        let resp = self.send_command(&c).await?;
+        // Keep track of groups selected via GROUP or LISTGROUP
+        if resp.code() == ResponseCode::Known(Kind::GroupSelected) {
+            if let Ok(group) = Group::try_from(&resp) {
+                self.set_group(Some(group));
+            }
+        }
+        Ok(resp)
+    }
+
+    /// Send a single command line
+    ///
+    /// See [`NntpClient::raw_command`](crate::client::NntpClient::raw_command).
+    pub async fn raw_command(&mut self, line: &str) -> Result<RawResponse> {
+        self.command(RawLine::new(line)?).await
+    }
+
+    /// Details about the underlying connection
+    pub fn connection_info(&self) -> ConnectionInfo {
+        ConnectionInfo {
+            peer_addr: Some(self.conn.peer_addr()),
+            tls_domain: self.conn.tls_domain().map(ToString::to_string),
+            greeting: self.greeting.clone(),
+        }
+    }
+
+    /// The greeting returned by the server upon connection
+    pub fn greeting(&self) -> &RawResponse {
+        &self.greeting
+    }
+
+    /// Returns true if the server's [greeting](Self::greeting) indicated that posting is allowed
+    ///
+    /// See [`NntpClient::posting_allowed`](crate::client::NntpClient::posting_allowed).
+    pub fn posting_allowed(&self) -> bool {
+        self.greeting.code() == ResponseCode::Known(Kind::PostingAllowed)
+    }
+
+    /// The server software identified in the [greeting](Self::greeting), if any
+    ///
+    /// See [`NntpClient::greeting_software`](crate::client::NntpClient::greeting_software).
+    pub fn greeting_software(&self) -> Option<&str> {
+        self.greeting_software.as_deref()
+    }
+
+    /// Get the client's configuration
+    pub fn config(&self) -> &ClientConfig {
+        &self.config
+    }
+
+    /// Get the last selected group
+    pub fn group(&self) -> Option<&Group> {
+        self.group.as_ref()
+    }
+
+    /// The number of the current article in the selected group
+    ///
+    /// See [`NntpClient::current_article`](crate::client::NntpClient::current_article).
+    pub fn current_article(&self) -> Option<ArticleNumber> {
+        self.current_article
+    }
+
+    /// Select a newsgroup
+    ///
+    /// See [`NntpClient::select_group`](crate::client::NntpClient::select_group).
+    pub async fn select_group(&mut self, name: impl AsRef<str>) -> Result<Group> {
+        if self.config.skip_redundant_group_select {
+            if let Some(group) = self.group.as_ref().filter(|g| g.name == name.as_ref()) {
+                trace!("Group {} is already selected, skipping GROUP", group.name);
+                return Ok(group.clone());
+            }
+        }
+
+        let resp = self
+            .send_command(&cmd::Group(name.as_ref().to_string()))
+            .await?;
+
+        let group = self.with_posting_status(group_response(resp)?);
+        self.set_group(Some(group.clone()));
+        Ok(group)
+    }
+
+    /// The capabilities cached in the client
+    ///
+    /// See [`NntpClient::capabilities`](crate::client::NntpClient::capabilities).
+    pub fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+
+    /// The capabilities cached in the client, retrieving them first if they haven't been yet
+    ///
+    /// See [`NntpClient::try_capabilities`](crate::client::NntpClient::try_capabilities).
+    pub async fn try_capabilities(&mut self) -> Result<&Capabilities> {
+        if !self.capabilities_fetched {
+            return self.update_capabilities().await;
+        }
+        Ok(&self.capabilities)
+    }
+
+    /// Retrieve updated capabilities from the server
+    pub async fn update_capabilities(&mut self) -> Result<&Capabilities> {
+        let resp = self.send_command(&cmd::Capabilities).await?;
+        self.capabilities = capabilities_response(resp)?;
+        self.capabilities_fetched = true;
+
+        Ok(&self.capabilities)
+    }
+
+    /// Retrieve an article from the server
+    ///
+    /// See [`NntpClient::article`](crate::client::NntpClient::article).
+    pub async fn article(&mut self, id: impl Into<ArticleId>) -> Result<BinaryArticle> {
+        let id = self.resolve_article_id(id.into())?;
+        let resp = self
+            .command_expecting(&cmd::Article::from(id.clone()), Kind::Article)
+            .await?;
+        self.article_selected(&id);
+        resp.borrow().try_into()
+    }
+
+    /// Retrieve the body for an article
+    pub async fn body(&mut self, id: impl Into<ArticleId>) -> Result<Body> {
+        let id = self.resolve_article_id(id.into())?;
+        let resp = self
+            .command_expecting(&cmd::Body::from(id.clone()), Kind::Body)
+            .await?;
+        self.article_selected(&id);
+        resp.borrow().try_into()
+    }
+
+    /// Retrieve the headers for an article
+    pub async fn head(&mut self, id: impl Into<ArticleId>) -> Result<Head> {
+        let id = self.resolve_article_id(id.into())?;
+        let resp = self
+            .command_expecting(&cmd::Head::from(id.clone()), Kind::Head)
+            .await?;
+        self.article_selected(&id);
+        resp.borrow().try_into()
+    }
+
+    /// Retrieve the status of an article
+    ///
+    /// See [`NntpClient::stat`](crate::client::NntpClient::stat).
+    pub async fn stat(&mut self, id: impl Into<ArticleId>) -> Result<Stat> {
+        let id = self.resolve_article_id(id.into())?;
+        let resp = self.send_command(&cmd::Stat::from(id.clone())).await?;
+        let stat = stat_response(id.clone(), resp)?;
+        self.article_selected(&id);
+        Ok(stat)
+    }
+
+    /// Move to the next article in the selected group via `NEXT`
+    ///
+    /// See [`NntpClient::next`](crate::client::NntpClient::next).
+    #[allow(clippy::should_implement_trait)]
+    pub async fn next(&mut self) -> Result<Stat> {
+        self.navigate(&cmd::Next).await
+    }
+
+    /// Move to the previous article in the selected group via `LAST`
+    ///
+    /// See [`NntpClient::last`](crate::client::NntpClient::last).
+    pub async fn last(&mut self) -> Result<Stat> {
+        self.navigate(&cmd::Last).await
+    }
+
+    /// List the groups available on the server via `LIST ACTIVE`
+    pub async fn list_active(&mut self, wildmat: Option<&str>) -> Result<Vec<ActiveGroup>> {
+        let command = cmd::List::Active {
+            wildmat: validate_wildmat(wildmat)?,
+        };
+        let resp = self.command_expecting(&command, Kind::List).await?;
+        let groups = parse_active(&resp, Kind::List)?;
+        record_posting_statuses(&mut self.posting_statuses, self.group.as_mut(), &groups);
+        Ok(groups)
+    }
+
+    /// The posting status of a group, if it was included in a previous `LIST ACTIVE` response
+    ///
+    /// See [`NntpClient::posting_status`](crate::client::NntpClient::posting_status).
+    pub fn posting_status(&self, group: impl AsRef<str>) -> Option<&GroupStatus> {
+        self.posting_statuses.get(group.as_ref())
+    }
+
+    /// Retrieve the format of the overviews returned by the server via `LIST OVERVIEW.FMT`
+    ///
+    /// See [`NntpClient::list_overview_fmt`](crate::client::NntpClient::list_overview_fmt).
+    pub async fn list_overview_fmt(&mut self) -> Result<OverviewFmt> {
+        let resp = self
+            .command_expecting(&cmd::List::OverviewFmt, Kind::List)
+            .await?;
+        let fmt = OverviewFmt::try_from(&resp)?;
+        self.overview_fmt = Some(fmt.clone());
+        Ok(fmt)
+    }
+
+    /// Retrieve the overviews for a range of articles in the selected group
+    ///
+    /// See [`NntpClient::overviews`](crate::client::NntpClient::overviews).
+    pub async fn overviews(&mut self, range: impl Into<ArticleRange>) -> Result<Vec<Overview>> {
+        let range = range.into();
+        let over = self.try_capabilities().await?.overview_command() == "OVER";
+        let resp = if over && !self.legacy_overview {
+            match self
+                .command_expecting(&cmd::Over::Articles(range), Kind::Overview)
+                .await
+            {
+                Err(Error::UnexpectedCode {
+                    got: ResponseCode::Known(Kind::UnknownCommand),
+                    ..
+                }) => {
+                    debug!("OVER is not recognized, falling back to XOVER");
+                    self.legacy_overview = true;
+                    self.command_expecting(&cmd::XOver::Articles(range), Kind::Overview)
+                        .await?
+                }
+                res => res?,
+            }
+        } else {
+            self.command_expecting(&cmd::XOver::Articles(range), Kind::Overview)
+                .await?
+        };
+
+        parse_overviews(&resp, self.overview_fmt.as_ref())
+    }
+
+    /// Retrieve the server's time via `DATE`
+    pub async fn date(&mut self) -> Result<ServerDate> {
+        let resp = self.command_expecting(&cmd::Date, Kind::Date).await?;
+        ServerDate::try_from(&resp)
+    }
+
+    /// Close the connection to the server
+    ///
+    /// Sends `QUIT`, returns the server's `205` response, and shuts down the socket.
+    ///
+    /// Unlike [`NntpClient`](crate::client::NntpClient), dropping an `AsyncNntpClient` cannot
+    /// send a `QUIT` as async I/O is not possible in `Drop`.
+    pub async fn close(mut self) -> Result<RawResponse> {
+        let resp = self
+            .command_expecting(&cmd::Quit, Kind::ConnectionClosing)
+            .await?;
+
+        if let Err(e) = self.conn.stream_mut().shutdown().await {
+            debug!("Failed to shut down the connection: {}", e);
+        }
+
+        Ok(resp)
+    }
+
+    /// Shut down the connection without sending `QUIT`
+    ///
+    /// See [`NntpClient::abort`](crate::client::NntpClient::abort).
+    pub async fn abort(mut self) {
+        if let Err(e) = self.conn.stream_mut().shutdown().await {
+            debug!("Failed to shut down the connection: {}", e);
+        }
+    }
+
+    /// Update the selected group and reset the current article to its first article
+    fn set_group(&mut self, group: Option<Group>) {
+        let group = group.map(|group| self.with_posting_status(group));
+        self.current_article = group.as_ref().and_then(first_article);
+        self.group = group;
+    }
+
+    /// Fill in the group's posting status if it was included in a previous listing
+    fn with_posting_status(&self, mut group: Group) -> Group {
+        if group.posting_status.is_none() {
+            group.posting_status = self.posting_statuses.get(&group.name).cloned();
+        }
+        group
+    }
+
+    /// Send `NEXT` or `LAST` and update the current article
+    async fn navigate(&mut self, command: &impl NntpCommand) -> Result<Stat> {
+        if self.group.is_none() {
+            return Err(Error::NoGroupSelected(ArticleId::Current));
+        }
+
+        let resp = self.send_command(command).await?;
+        let stat = navigate_response(resp)?;
+        self.current_article = Some(stat.number);
+        Ok(stat)
+    }
+
+    /// Update the current article after an article was successfully retrieved
+    fn article_selected(&mut self, id: &ArticleId) {
+        if let ArticleId::Number(number) = id {
+            self.current_article = Some(*number);
+        }
+    }
+
+    /// Check that an article ID can be used in the current session
+    fn resolve_article_id(&self, id: ArticleId) -> Result<ArticleId> {
+        if id.requires_group() && self.group.is_none() {
+            return Err(Error::NoGroupSelected(id));
+        }
+        Ok(id)
+    }
+
+    /// Send a command and return the response if it is of the expected kind
+    async fn command_expecting(
+        &mut self,
+        command: &impl NntpCommand,
+        kind: Kind,
+    ) -> Result<RawResponse> {
+        let policy = self.config.unknown_code_policy;
+        check_discontinued(&self.discontinued)?;
+        self.conn.send(command).await?;
+        let resp = self
+            .conn
+            .read_response_with(|code| policy.is_multiline(code, kind))
+            .await?;
+        let resp = check_service_available(&mut self.discontinued, resp)?;
+
+        policy.check(resp, kind)
+    }
+
+    /// Send a command and read the response
+    ///
+    /// Once the server discontinued the session with a `400`, [`Error::ServiceUnavailable`] is
+    /// returned without sending anything.
+    async fn send_command(&mut self, command: &impl NntpCommand) -> Result<RawResponse> {
+        check_discontinued(&self.discontinued)?;
+        let resp = self.conn.command(command).await?;
+        check_service_available(&mut self.discontinued, resp)
+    }
+}
+
+impl ClientConfig {
+    /// Resolves the configuration into an [`AsyncNntpClient`]
+    ///
+    /// Some settings are not supported by the async client, see [`AsyncNntpClient`].
+    pub async fn connect_async(&self, addr: impl ToSocketAddrs) -> Result<AsyncNntpClient> {
+        let unsupported = [
+            ("STARTTLS", self.starttls.is_some()),
+            ("COMPRESS DEFLATE", self.compress),
+            ("AUTHINFO SASL", self.sasl.is_some()),
+            ("AUTHINFO GENERIC", self.authinfo_generic.is_some()),
+            (
+                "on_auth_or_mode_required",
+                self.auth_or_mode_handler.is_some(),
+            ),
+            ("auto_reconnect", self.auto_reconnect > 0),
+            ("SOCKS5 proxy", self.conn_config.proxy.is_some()),
+        ];
+        if let Some((command, _)) = unsupported.iter().find(|(_, enabled)| *enabled) {
+            return Err(Error::Unsupported {
+                command: command.to_string(),
+                resp: None,
+            });
+        }
+
+        let (mut conn, conn_response) =
+            AsyncNntpConnection::connect(addr, self.conn_config.clone()).await?;
+
+        debug!(
+            "Connected. Server returned `{}`",
+            conn_response.first_line_to_utf8_lossy()
+        );
+
+        let (capabilities, group) = self.init_session_async(&mut conn).await?;
+
+        Ok(AsyncNntpClient {
+            conn,
+            greeting_software: greeting_software(&conn_response),
+            greeting: conn_response,
+            config: self.clone(),
+            capabilities_fetched: capabilities.is_some(),
+            capabilities: capabilities.unwrap_or_default(),
+            current_article: group.as_ref().and_then(first_article),
+            posting_statuses: HashMap::new(),
+            group,
+            overview_fmt: None,
+            legacy_overview: false,
+            discontinued: None,
+        })
+    }
+
+    /// Switch modes, authenticate, retrieve capabilities, and select the initial group
+    async fn init_session_async(
+        &self,
+        conn: &mut AsyncNntpConnection,
+    ) -> Result<(Option<Capabilities>, Option<Group>)> {
+        // Capabilities that are still valid, they change after switching modes or authenticating
+        let mut capabilities = None;
+
+        match self.handshake_order {
+            HandshakeOrder::ModeReaderFirst => {
+                match self.init_mode_reader_async(conn, &mut capabilities).await {
+                    Err(e) if is_access_denied(&e) && self.authinfo.is_some() => {
+                        debug!(
+                            "Access denied before authenticating, authenticating first -- {}",
+                            e
+                        );
+                        // n.b. the capabilities were just denied, so they aren't requested again
+                        self.send_credentials_async(conn, &mut capabilities).await?;
+                        self.init_mode_reader_async(conn, &mut capabilities).await?;
+                    }
+                    res => {
+                        res?;
+                        self.init_auth_async(conn, &mut capabilities).await?;
+                    }
+                }
+            }
+            HandshakeOrder::AuthFirst => {
+                self.init_auth_async(conn, &mut capabilities).await?;
+                self.init_mode_reader_async(conn, &mut capabilities).await?;
+            }
+        }
+
+        let capabilities = match capabilities {
+            Some(capabilities) => Some(capabilities),
+            None if self.lazy_capabilities => None,
+            None => {
+                debug!("Retrieving capabilities...");
+                Some(capabilities_response(
+                    conn.command(&cmd::Capabilities).await?,
+                )?)
+            }
+        };
+
+        let group = if let Some(name) = &self.group {
+            debug!("Connecting to group {}...", name);
+            Some(group_response(
+                conn.command(&cmd::Group(name.clone())).await?,
+            )?)
+        } else {
+            debug!("No initial group specified");
+            None
+        };
+
+        Ok((capabilities, group))
+    }
+
+    /// Send `MODE READER` if configured or required by the server
+    ///
+    /// See [`ClientConfig::handshake_order`].
+    async fn init_mode_reader_async(
+        &self,
+        conn: &mut AsyncNntpConnection,
+        capabilities: &mut Option<Capabilities>,
+    ) -> Result<()> {
+        if self.mode_reader == Some(false) {
+            return Ok(());
+        }
+
+        let current = match (self.mode_reader, capabilities.take()) {
+            (Some(true), _) => None,
+            (_, Some(capabilities)) => Some(capabilities),
+            (_, None) => {
+                debug!("Retrieving capabilities...");
+                Some(capabilities_response(
+                    conn.command(&cmd::Capabilities).await?,
+                )?)
+            }
+        };
+
+        if current.as_ref().is_none_or(needs_mode_reader) {
+            debug!("Switching to reader mode");
+            mode_reader_response(conn.command(&cmd::ModeReader).await?)?;
+        } else {
+            *capabilities = current;
+        }
+        Ok(())
+    }
+
+    /// Authenticate via AUTHINFO USER/PASS if credentials are configured
+    async fn init_auth_async(
+        &self,
+        conn: &mut AsyncNntpConnection,
+        capabilities: &mut Option<Capabilities>,
+    ) -> Result<()> {
+        if self.authinfo.is_some() {
+            let current = match capabilities.take() {
+                Some(capabilities) => Ok(capabilities),
+                None => {
+                    debug!("Retrieving capabilities...");
+                    capabilities_response(conn.command(&cmd::Capabilities).await?)
+                }
+            };
+            match current {
+                Ok(current) => check_auth_supported(&current, AuthMethod::UserPass, conn.is_tls())?,
+                // The server won't say what it supports until we authenticate, so try anyway
+                Err(Error::AccessDenied { .. }) => {
+                    debug!("Capabilities are unavailable before authenticating")
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        self.send_credentials_async(conn, capabilities).await
+    }
+
+    /// Authenticate with the configured credentials without checking the capabilities first
+    async fn send_credentials_async(
+        &self,
+        conn: &mut AsyncNntpConnection,
+        capabilities: &mut Option<Capabilities>,
+    ) -> Result<()> {
+        if let Some(provider) = &self.authinfo {
+            let (username, password) = provider.credentials()?;
+            if !conn.is_tls() {
+                warn!("TLS is not enabled, credentials will be sent in the clear!");
+            }
+            debug!("Sending AUTHINFO USER");
+            let resp = conn.send_then_read(&cmd::AuthInfo::User(username)).await?;
+            authinfo_user_response(resp)?;
+
+            debug!("Sending AUTHINFO PASS");
+            let resp = conn.command(&cmd::AuthInfo::Pass(password)).await?;
+            authinfo_pass_response(resp)?;
+            debug!("Successfully authenticated");
+            *capabilities = None;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_support::MockScript;
+
+    #[tokio::test]
+    async fn test_session() {
+        let server = MockScript::new()
+            .expect(
+                "CAPABILITIES",
+                b"101 Capability list:\r\nVERSION 2\r\nREADER\r\nAUTHINFO USER\r\n.\r\n",
+            )
+            .expect("AUTHINFO USER user", b"381 Password required\r\n")
+            .expect("AUTHINFO PASS pass", b"281 Authentication accepted\r\n")
+            .expect(
+                "CAPABILITIES",
+                b"101 Capability list:\r\nVERSION 2\r\nREADER\r\nOVER\r\n.\r\n",
+            )
+            .expect("GROUP misc.test", b"211 2 1 2 misc.test\r\n")
+            .expect(
+                "OVER 1-2",
+                b"224 Overview follows\r\n\
+                  1\tfirst\tposter\tdate\t<1@example.com>\t\t10\t1\r\n\
+                  2\tsecond\tposter\tdate\t<2@example.com>\t\t10\t1\r\n.\r\n",
+            )
+            .expect("NEXT", b"223 2 <2@example.com>\r\n")
+            .expect("NEXT", b"421 No next article\r\n")
+            .expect("QUIT", b"205 Bye\r\n")
+            .start()
+            .unwrap();
+
+        let mut client = ClientConfig::default()
+            .authinfo_user_pass("user", "pass")
+            .group(Some("misc.test"))
+            .connect_async(server.addr())
+            .await
+            .unwrap();
+
+        assert_eq!(client.group().unwrap().name, "misc.test");
+        assert_eq!(client.current_article(), Some(1));
+
+        let overviews = client.overviews(1..=2).await.unwrap();
+        assert_eq!(
+            overviews
+                .iter()
+                .map(|o| o.subject.as_str())
+                .collect::<Vec<_>>(),
+            vec!["first", "second"]
+        );
+
+        assert_eq!(client.next().await.unwrap().number, 2);
+        assert_eq!(client.current_article(), Some(2));
+        assert!(matches!(
+            client.next().await,
+            Err(Error::NoNextArticle { .. })
+        ));
+
+        client.close().await.unwrap();
+        server.finish().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_service_unavailable() {
+        let server = MockScript::new()
+            .expect(
+                "CAPABILITIES",
+                b"101 Capability list:\r\nVERSION 2\r\nREADER\r\n.\r\n",
+            )
+            .expect("DATE", b"400 Idle timeout\r\n")
+            .start()
+            .unwrap();
+
+        let mut client = ClientConfig::default()
+            .connect_async(server.addr())
+            .await
+            .unwrap();
+        for _ in 0..2 {
+            // The second DATE fails without being sent
+            match client.date().await {
+                Err(Error::ServiceUnavailable { resp }) => {
+                    assert_eq!(resp.first_line(), b"400 Idle timeout\r\n")
+                }
+                res => panic!("Unexpected result {:?}", res),
+            }
+        }
+
+        client.abort().await;
+        server.finish().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_config() {
+        let res = ClientConfig::default()
+            .compress(true)
+            .connect_async("127.0.0.1:1")
+            .await;
+
+        match res {
+            Err(Error::Unsupported { command, .. }) => assert_eq!(command, "COMPRESS DEFLATE"),
+            res => panic!("Unexpected result {:?}", res),
+        }
+    }
+}
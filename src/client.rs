@@ -1,13 +1,29 @@
 use std::borrow::Borrow;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::convert::{TryFrom, TryInto};
-use std::net::ToSocketAddrs;
+use std::fmt;
+use std::io::Write;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::ops::{Deref, DerefMut, RangeInclusive};
+#[cfg(unix)]
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use log::*;
 
 use crate::error::{Error, Result};
 
-use crate::raw::connection::{ConnectionConfig, NntpConnection};
-use crate::raw::response::RawResponse;
+use crate::posting::IHaveOutcome;
+use crate::raw::addr::ServerAddr;
+use crate::raw::connection::{
+    AddressFamily, ConnectionConfig, DataBlockReader, NntpConnection, TlsConfig,
+};
+use crate::raw::proxy::ProxyConfig;
+use crate::raw::response::{Exchange, RawResponse};
+use crate::raw::trace::TraceFn;
+use crate::sasl::{cram_md5_response, plain_initial_response, SaslMechanism, ScramSha256};
 use crate::types::command as cmd;
 use crate::types::prelude::*;
 
@@ -24,9 +40,27 @@ use crate::types::prelude::*;
 #[derive(Debug)]
 pub struct NntpClient {
     conn: NntpConnection,
+    greeting: RawResponse,
+    /// See [`greeting_software`](NntpClient::greeting_software)
+    greeting_software: Option<String>,
     config: ClientConfig,
     capabilities: Capabilities,
+    capabilities_fetched: bool,
     group: Option<Group>,
+    last_group_name: Option<String>,
+    posting_statuses: HashMap<String, GroupStatus>,
+    current_article: Option<ArticleNumber>,
+    overview_fmt: Option<OverviewFmt>,
+    /// Set once `OVER` was rejected with a `500`
+    legacy_overview: bool,
+    /// Set once `HDR` was rejected with a `500`
+    legacy_header: bool,
+    reconnects: ReconnectHistory,
+    in_recovery: bool,
+    closed: bool,
+    /// The `400` response if the server discontinued the session
+    discontinued: Option<RawResponse>,
+    cache: ArticleCache,
 }
 
 impl NntpClient {
@@ -50,6 +84,14 @@ impl NntpClient {
     /// This is useful if you want to use a command you have implemented or one that is not
     /// provided by a client method
     ///
+    /// If [`ClientConfig::enforce_capabilities`] is enabled, [`Error::CapabilityMissing`] is
+    /// returned without contacting the server when the command's
+    /// [required capability](NntpCommand::required_capability) is not advertised.
+    ///
+    /// The response is returned whatever its code, e.g. a `502` is not turned into an
+    /// [`Error::AccessDenied`] as it is by the typed methods. The exception is a `400`, after
+    /// which the session can't be used, so [`Error::ServiceUnavailable`] is returned.
+    ///
     /// # Example
     ///
     /// Say we have a server that uses mode switching for whatever reason. Brokaw implements
@@ -101,10 +143,127 @@ impl NntpClient {
     /// ```
     /// </details>
     pub fn command(&mut self, c: impl NntpCommand) -> Result<RawResponse> {
-        let resp = self.conn.command(&c)?;
+        self.check_required_capability(&c)?;
+        let resp = self.send_and_read_raw(&c, ResponseCode::is_multiline)?;
+        self.track_selected_group(&resp);
+        Ok(resp)
+    }
+
+    /// Send a command and read its response, giving up once `cancel` is set
+    ///
+    /// The flag is checked before the command is sent and after each line of the response is
+    /// read, in which case [`Error::Cancelled`] is returned. A read that is blocked waiting on
+    /// the server is not interrupted, so pair this with a
+    /// [read timeout](ConnectionConfig::read_timeout) to bound how long cancelling can take.
+    ///
+    /// Unlike [`command`](Self::command), the exchange is not retried on reconnect and no
+    /// [recovery handler](ClientConfig::on_auth_or_mode_required) is invoked.
+    ///
+    /// **A cancelled response has not been fully read.** The connection is left
+    /// [desynchronized](crate::raw::error::Error::Desynchronized), so the client must be
+    /// dropped or [reconnected](Self::reconnect) rather than reused.
+    pub fn command_with_cancel(
+        &mut self,
+        c: impl NntpCommand,
+        cancel: &AtomicBool,
+    ) -> Result<RawResponse> {
+        self.check_required_capability(&c)?;
+        if cancel.load(Ordering::Relaxed) {
+            return Err(Error::Cancelled);
+        }
+        check_discontinued(&self.discontinued)?;
+
+        self.conn.send(&c)?;
+        let (mut resp, reader) = self.conn.read_response_streaming(None)?;
+        if let Some(mut reader) = reader {
+            let mut data_blocks = DataBlocks::empty();
+            loop {
+                if cancel.load(Ordering::Relaxed) {
+                    debug!("Cancelled after reading {} lines", reader.lines_read());
+                    return Err(Error::Cancelled);
+                }
+                match reader.next_line()? {
+                    Some(line) => data_blocks.push_line(line),
+                    None => break,
+                }
+            }
+            data_blocks.finish();
+            resp.data_blocks = Some(data_blocks);
+        }
+
+        let resp = self.check_service_available(resp)?;
+        self.track_selected_group(&resp);
         Ok(resp)
     }
 
+    /// Send a single command line, e.g. a vendor extension such as `XFEATURE COMPRESS GZIP`
+    ///
+    /// The line should not include the CRLF terminator. [`Error::InvalidCommand`] is returned
+    /// without contacting the server if the line contains a CR or LF, as that would smuggle
+    /// additional commands onto the connection. The response is handled as in
+    /// [`command`](Self::command).
+    pub fn raw_command(&mut self, line: &str) -> Result<RawResponse> {
+        self.command(RawLine::new(line)?)
+    }
+
+    /// Details about the underlying connection
+    ///
+    /// This can be useful for audit logging or for determining which server a load-balanced
+    /// hostname resolved to.
+    pub fn connection_info(&self) -> ConnectionInfo {
+        ConnectionInfo {
+            peer_addr: self.conn.peer_addr(),
+            tls_domain: self.conn.tls_domain().map(ToString::to_string),
+            greeting: self.greeting.clone(),
+        }
+    }
+
+    /// The number of bytes received from the server during the session
+    ///
+    /// See [`NntpConnection::bytes_read`] for exactly which bytes are counted.
+    pub fn bytes_read(&self) -> u64 {
+        self.conn.bytes_read()
+    }
+
+    /// The number of bytes sent to the server during the session
+    ///
+    /// See [`NntpConnection::bytes_written`].
+    pub fn bytes_written(&self) -> u64 {
+        self.conn.bytes_written()
+    }
+
+    /// Reset [`bytes_read`](Self::bytes_read) and [`bytes_written`](Self::bytes_written) to zero
+    ///
+    /// This can be used to measure the traffic of an individual operation.
+    pub fn reset_counters(&mut self) {
+        self.conn.reset_counters()
+    }
+
+    /// The greeting returned by the server upon connection
+    ///
+    /// This is updated when the client [reconnects](Self::reconnect).
+    pub fn greeting(&self) -> &RawResponse {
+        &self.greeting
+    }
+
+    /// Returns true if the server's [greeting](Self::greeting) indicated that posting is allowed
+    ///
+    /// This is derived from the greeting's code (`200` rather than `201`), so it does not
+    /// require a round trip. Note that the response to `MODE READER` may differ from the greeting.
+    pub fn posting_allowed(&self) -> bool {
+        self.greeting.code() == ResponseCode::Known(Kind::PostingAllowed)
+    }
+
+    /// The server software identified in the [greeting](Self::greeting), if any
+    ///
+    /// This is the part of the greeting after the code and the server's hostname, e.g.
+    /// `InterNetNews NNRP server INN 2.6.3 ready` for
+    /// `200 news.example.com InterNetNews NNRP server INN 2.6.3 ready`. It is best-effort:
+    /// `None` is returned if the greeting doesn't start with something resembling a hostname.
+    pub fn greeting_software(&self) -> Option<&str> {
+        self.greeting_software.as_deref()
+    }
+
     /// Get the currently selected group
     pub fn config(&self) -> &ClientConfig {
         &self.config
@@ -115,30 +274,215 @@ impl NntpClient {
         self.group.as_ref()
     }
 
+    /// The name of the last group that was successfully selected
+    ///
+    /// Unlike [`group`](Self::group), this is kept if the group is lost when
+    /// [reconnecting](Self::reconnect), see [`reselect_group`](Self::reselect_group).
+    pub fn last_group_name(&self) -> Option<&str> {
+        self.last_group_name.as_deref()
+    }
+
+    /// The posting status of a group, if it was included in a previous
+    /// [`list_active`](Self::list_active) or [`newgroups`](Self::newgroups) response
+    ///
+    /// This can be used to avoid [posting](Self::post) to a read-only group. The status of the
+    /// selected group is also available as [`Group::posting_status`].
+    pub fn posting_status(&self, group: impl AsRef<str>) -> Option<&GroupStatus> {
+        self.posting_statuses.get(group.as_ref())
+    }
+
+    /// The number of the current article in the selected group
+    ///
+    /// Selecting a group sets this to the first article in the group (or `None` if the group is
+    /// empty), after which it is moved by [`next`](Self::next) and [`last`](Self::last), and by
+    /// retrieving an article by number. Retrieving an article by message ID leaves it unchanged.
+    ///
+    /// [`ArticleId::Current`] can be passed to [`article`](Self::article), [`head`](Self::head),
+    /// [`body`](Self::body), and [`stat`](Self::stat) to operate on this article.
+    pub fn current_article(&self) -> Option<ArticleNumber> {
+        self.current_article
+    }
+
     /// Select a newsgroup
+    ///
+    /// If [`ClientConfig::skip_redundant_group_select`] is enabled and the group is already
+    /// selected, the cached [`Group`] is returned without contacting the server.
+    /// Use [`select_group_status`](Self::select_group_status) to tell whether this happened.
+    ///
+    /// Returns [`Error::NoSuchNewsgroup`] if the group does not exist.
     pub fn select_group(&mut self, name: impl AsRef<str>) -> Result<Group> {
-        let resp = self.conn.command(&cmd::Group(name.as_ref().to_string()))?;
+        self.select_group_status(name)
+            .map(GroupSelection::into_group)
+    }
 
-        match resp.code() {
-            ResponseCode::Known(Kind::GroupSelected) => {
-                let group = Group::try_from(&resp)?;
-                self.group = Some(group.clone());
-                Ok(group)
+    /// Select a newsgroup, reporting whether the cached [`Group`] was returned
+    ///
+    /// This behaves like [`select_group`](Self::select_group), but the result distinguishes
+    /// a [`Cached`](GroupSelection::Cached) group, which may have stale water marks, from one
+    /// that was freshly [`Selected`](GroupSelection::Selected) by sending `GROUP`.
+    pub fn select_group_status(&mut self, name: impl AsRef<str>) -> Result<GroupSelection> {
+        if self.config.skip_redundant_group_select {
+            if let Some(group) = self.group.as_ref().filter(|g| g.name == name.as_ref()) {
+                trace!("Group {} is already selected, skipping GROUP", group.name);
+                return Ok(GroupSelection::Cached(group.clone()));
+            }
+        }
+
+        self.send_group(name.as_ref().to_string())
+            .map(GroupSelection::Selected)
+    }
+
+    /// Send `GROUP` again to refresh the article count and water marks of the selected group
+    ///
+    /// `GROUP` is always sent, even if [`ClientConfig::skip_redundant_group_select`] is enabled,
+    /// so the returned [`Group`] reflects the server's current state. As with any `GROUP`, the
+    /// current article is reset to the first article in the group.
+    ///
+    /// If no group is selected (e.g. after a [reconnect](Self::reconnect)), `when_unselected`
+    /// decides whether the [last selected group](Self::last_group_name) is selected again or
+    /// [`Error::NoGroupSelected`] is returned. `None` is only returned, without contacting the
+    /// server, if a group has never been selected.
+    ///
+    /// To poll a group other than the selected one, see [`group_stats`](Self::group_stats).
+    pub fn reselect_group(&mut self, when_unselected: WhenUnselected) -> Result<Option<Group>> {
+        let name = match (&self.group, when_unselected) {
+            (Some(group), _) => group.name.clone(),
+            (None, WhenUnselected::SelectLast) => match &self.last_group_name {
+                Some(name) => name.clone(),
+                None => return Ok(None),
+            },
+            (None, WhenUnselected::Fail) => return Err(Error::NoGroupSelected(ArticleId::Current)),
+        };
+        self.send_group(name).map(Some)
+    }
+
+    /// Retrieve the article count and water marks of a group without losing the reader's
+    /// position
+    ///
+    /// `GROUP` changes the selected group and current article on the server, so afterwards the
+    /// previously selected group is selected again with a second `GROUP`, and if the current
+    /// article had moved past the first article of that group it is restored with a `STAT`.
+    /// **Polling a group therefore costs up to two extra round trips** compared to
+    /// [`select_group`](Self::select_group). The restored group has fresh water marks.
+    ///
+    /// If no group was selected, the polled group remains selected as NNTP offers no way to
+    /// deselect a group. If the current article has since been removed from the server, the
+    /// current article is left at the first article of the group.
+    pub fn group_stats(&mut self, name: impl AsRef<str>) -> Result<Group> {
+        let previous = self.group.as_ref().map(|g| g.name.clone());
+        let article = self.current_article;
+
+        let stats = self.send_group(name.as_ref().to_string())?;
+        if let Some(previous) = previous {
+            if previous != stats.name {
+                self.send_group(previous)?;
+            }
+            if let Some(number) = article.filter(|&n| Some(n) != self.current_article) {
+                match self.stat(number) {
+                    Err(Error::NoSuchArticle { .. }) => {
+                        debug!("Article {} no longer exists, not restoring it", number)
+                    }
+                    res => {
+                        res?;
+                    }
+                }
             }
-            ResponseCode::Known(Kind::NoSuchNewsgroup) => Err(Error::failure(resp)),
-            code => Err(Error::Failure {
-                code,
-                msg: Some(format!("{}", resp.first_line_to_utf8_lossy())),
-                resp,
-            }),
         }
+
+        Ok(stats)
+    }
+
+    /// Send `GROUP` and update the selected group
+    ///
+    /// The selected [`Group`] is replaced entirely, so no water marks of a previous selection
+    /// are kept.
+    fn send_group(&mut self, name: String) -> Result<Group> {
+        let resp = self.send_and_read(&cmd::Group(name), ResponseCode::is_multiline)?;
+
+        let group = self.with_posting_status(group_response(resp)?);
+        self.set_group(Some(group.clone()));
+        Ok(group)
+    }
+
+    /// Select a newsgroup and verify that the response from the server is sane
+    ///
+    /// This behaves like [`select_group`](Self::select_group) but returns
+    /// [`Error::MalformedGroupResponse`] if the article count and water marks are inconsistent
+    /// (e.g. the low water mark is greater than the high water mark in a non-empty group).
+    ///
+    /// Note that the group is still selected on the server even if validation fails.
+    pub fn select_group_checked(&mut self, name: impl AsRef<str>) -> Result<Group> {
+        let group = self.select_group(name)?;
+
+        group
+            .validate()
+            .map_err(|msg| Error::MalformedGroupResponse {
+                group: group.clone(),
+                msg,
+            })?;
+
+        Ok(group)
     }
 
     /// The capabilities cached in the client
+    ///
+    /// If [`ClientConfig::lazy_capabilities`] is enabled and the capabilities have not been
+    /// retrieved yet, these are empty. Use [`try_capabilities`](Self::try_capabilities) to
+    /// retrieve them if necessary.
     pub fn capabilities(&self) -> &Capabilities {
         &self.capabilities
     }
 
+    /// The capabilities cached in the client, retrieving them first if they haven't been yet
+    ///
+    /// This only contacts the server if [`ClientConfig::lazy_capabilities`] is enabled and
+    /// nothing has needed the capabilities so far.
+    pub fn try_capabilities(&mut self) -> Result<&Capabilities> {
+        if !self.capabilities_fetched {
+            return self.update_capabilities();
+        }
+        Ok(&self.capabilities)
+    }
+
+    /// The overview command that will be used based on the cached capabilities
+    ///
+    /// Returns `"OVER"` if the server advertises it and `"XOVER"` otherwise, or if the server
+    /// rejected `OVER` as an unknown command earlier in the session.
+    /// Note that this does not retrieve [lazy](ClientConfig::lazy_capabilities) capabilities.
+    pub fn overview_command(&self) -> &'static str {
+        match self.legacy_overview {
+            true => "XOVER",
+            false => self.capabilities.overview_command(),
+        }
+    }
+
+    /// How [`overviews`](Self::overviews) will retrieve and parse overviews
+    ///
+    /// This reflects the [command](Self::overview_command) chosen from the cached
+    /// capabilities, including any fallback to `XOVER` earlier in the session, and the format
+    /// retrieved by [`list_overview_fmt`](Self::list_overview_fmt), if any.
+    /// As with `overview_command`, this does not retrieve
+    /// [lazy](ClientConfig::lazy_capabilities) capabilities.
+    pub fn overview_method(&self) -> OverviewMethod {
+        OverviewMethod {
+            command: self.overview_command(),
+            fallback: self.legacy_overview,
+            format: self.overview_fmt.clone(),
+        }
+    }
+
+    /// The header command that will be used based on the cached capabilities
+    ///
+    /// Returns `"HDR"` if the server advertises it and `"XHDR"` otherwise, or if the server
+    /// rejected `HDR` as an unknown command earlier in the session.
+    /// Note that this does not retrieve [lazy](ClientConfig::lazy_capabilities) capabilities.
+    pub fn header_command(&self) -> &'static str {
+        match self.legacy_header {
+            true => "XHDR",
+            false => self.capabilities.header_command(),
+        }
+    }
+
     /// Retrieve updated capabilities from the server
     pub fn update_capabilities(&mut self) -> Result<&Capabilities> {
         let resp = self
@@ -149,6 +493,7 @@ impl NntpClient {
         let capabilities = Capabilities::try_from(&resp)?;
 
         self.capabilities = capabilities;
+        self.capabilities_fetched = true;
 
         Ok(&self.capabilities)
     }
@@ -169,184 +514,5432 @@ impl NntpClient {
     /// use brokaw::types::command::Article;
     ///
     /// fn checked_conversion(client: &mut NntpClient) -> Result<TextArticle> {
-    ///     client.article(Article::Number(42))
+    ///     client.article(42)
     ///         .and_then(|b| b.to_text())
     /// }
     ///
     /// fn lossy_conversion(client: &mut NntpClient) -> Result<TextArticle> {
-    ///     client.article(Article::Number(42))
+    ///     client.article("<foo@example.com>")
     ///         .map(|b| b.to_text_lossy())
     /// }
     ///
     /// ```
-    pub fn article(&mut self, article: cmd::Article) -> Result<BinaryArticle> {
-        let resp = self.conn.command(&article)?.fail_unless(Kind::Article)?;
+    ///
+    /// Article numbers are relative to the selected group, so [`Error::NoGroupSelected`] is
+    /// returned without contacting the server if an article number (or [`ArticleId::Current`]) is
+    /// provided before a group has been selected. Retrieving an article by number makes it the
+    /// [current article](Self::current_article).
+    ///
+    /// If the [article cache](ClientConfig::article_cache) is enabled, articles retrieved by
+    /// message ID are returned from the cache when possible.
+    pub fn article(&mut self, id: impl Into<ArticleId>) -> Result<BinaryArticle> {
+        let id = self.resolve_article_id(id.into())?;
+        if let ArticleId::MessageId(message_id) = &id {
+            if let Some(article) = self.cache.article(message_id) {
+                trace!("Returning {} from the article cache", message_id);
+                return Ok(article);
+            }
+        }
 
-        resp.borrow().try_into()
+        let resp = self.command_expecting(&cmd::Article::from(id.clone()), Kind::Article)?;
+        self.article_selected(&id);
+
+        let article = BinaryArticle::try_from(resp.borrow())?;
+        if let ArticleId::MessageId(message_id) = id {
+            self.cache
+                .insert(message_id, CachedArticle::Article(article.clone()));
+        }
+        Ok(article)
     }
 
-    /// Retrieve the body for an article
-    pub fn body(&mut self, body: cmd::Body) -> Result<Body> {
-        let resp = self.conn.command(&body)?.fail_unless(Kind::Head)?;
-        resp.borrow().try_into()
+    /// Select `group` and retrieve the article numbered `number` within it
+    ///
+    /// `GROUP` is only sent if `group` isn't already selected, regardless of
+    /// [`ClientConfig::skip_redundant_group_select`]. If the group doesn't exist
+    /// [`Error::NoSuchNewsgroup`] is returned without requesting the article.
+    pub fn article_in(
+        &mut self,
+        group: impl AsRef<str>,
+        number: ArticleNumber,
+    ) -> Result<BinaryArticle> {
+        let group = group.as_ref();
+        match &self.group {
+            Some(selected) if selected.name == group => {
+                trace!("Group {} is already selected, skipping GROUP", group)
+            }
+            _ => {
+                self.send_group(group.to_string())?;
+            }
+        }
+
+        self.article(number)
     }
 
-    /// Retrieve the headers for an article
-    pub fn head(&mut self, head: cmd::Head) -> Result<Head> {
-        let resp = self.conn.command(&head)?.fail_unless(Kind::Head)?;
-        resp.borrow().try_into()
+    /// Retrieve an article, writing it to `out` as it arrives rather than buffering it
+    ///
+    /// This is intended for large (e.g. binary) articles. The article is written line by line,
+    /// with dot-stuffing undone and each line terminated by a CRLF, so only a single line is held
+    /// in memory. The article number and message ID from the response are returned.
+    ///
+    /// Failures writing to `out` are returned as [`Error::Output`] while failures reading from
+    /// the server are returned as [`Error::Connection`]. In either case the rest of the article is
+    /// left unread and the client must be [reconnected](Self::reconnect) before it can be reused.
+    /// Article IDs are handled in the same way as [`article`](Self::article).
+    pub fn article_to_writer(
+        &mut self,
+        id: impl Into<ArticleId>,
+        out: &mut impl Write,
+    ) -> Result<Stat> {
+        let id = self.resolve_article_id(id.into())?;
+        check_discontinued(&self.discontinued)?;
+        self.conn.send(&cmd::Article::from(id.clone()))?;
+
+        let (resp, reader) = self.conn.read_response_streaming(None)?;
+        let resp = check_service_available(&mut self.discontinued, resp)?;
+        let mut reader = match reader {
+            Some(reader) if resp.code() == ResponseCode::Known(Kind::Article) => reader,
+            _ => return Err(Error::unexpected_code(Kind::Article, resp)),
+        };
+        let stat = Stat::from_first_line(&resp)?;
+
+        while let Some(line) = reader.next_line()? {
+            out.write_all(line)
+                .and_then(|_| out.write_all(b"\r\n"))
+                .map_err(Error::Output)?;
+        }
+        out.flush().map_err(Error::Output)?;
+        self.article_selected(&id);
+
+        Ok(stat)
     }
 
-    /// Retrieve the status of an article
-    pub fn stat(&mut self, stat: cmd::Stat) -> Result<Option<Stat>> {
-        let resp = self.conn.command(&stat)?;
-        match resp.code() {
-            ResponseCode::Known(Kind::ArticleExists) => resp.borrow().try_into().map(Some),
-            ResponseCode::Known(Kind::NoArticleWithMessageId)
-            | ResponseCode::Known(Kind::InvalidCurrentArticleNumber)
-            | ResponseCode::Known(Kind::NoArticleWithNumber) => Ok(None),
-            _ => Err(Error::failure(resp)),
+    /// Lazily retrieve the articles in a range of the selected group
+    ///
+    /// Articles are requested one at a time as the iterator is advanced. Numbers for which the
+    /// server returns `423` (no article with that number) are skipped. Open-ended ranges start no
+    /// lower than the group's low water mark and stop at its high water mark.
+    /// [`ArticleRange::Current`] yields the [current article](Self::current_article), if any.
+    ///
+    /// If no group is selected, the iterator yields a single [`Error::NoGroupSelected`]. Iteration
+    /// also stops after a [`Connection`](Error::Connection) error, as the connection is unlikely
+    /// to be usable afterwards.
+    pub fn iter_articles(&mut self, range: impl Into<ArticleRange>) -> ArticleIter<'_> {
+        match self.resolve_article_range(range.into()) {
+            Ok(numbers) => ArticleIter {
+                client: self,
+                numbers,
+                error: None,
+            },
+            Err(e) => ArticleIter {
+                client: self,
+                numbers: None,
+                error: Some(e),
+            },
         }
     }
 
-    /// Close the connection to the server
-    pub fn close(&mut self) -> Result<RawResponse> {
-        let resp = self
-            .conn
-            .command(&cmd::Quit)?
-            .fail_unless(Kind::ConnectionClosing)?;
+    /// Retrieve every article in a range of the selected group at once
+    ///
+    /// The `ARTICLE` commands are pipelined in batches of
+    /// [`max_pipeline_depth`](ClientConfig::max_pipeline_depth), without materialising the
+    /// whole range up front. Numbers for which the server
+    /// returns `423` (no article with that number) are skipped, so each article is returned
+    /// along with its number. Ranges are resolved as in [`iter_articles`](Self::iter_articles),
+    /// which is better suited to large ranges as every article is held in memory.
+    ///
+    /// If retrieving an article fails (e.g. because the connection dropped),
+    /// [`Error::IncompleteArticles`] is returned with the articles retrieved so far.
+    pub fn articles(
+        &mut self,
+        range: impl Into<ArticleRange>,
+    ) -> Result<Vec<(ArticleNumber, BinaryArticle)>> {
+        let mut numbers = match self.resolve_article_range(range.into())? {
+            Some(numbers) => numbers,
+            None => return Ok(Vec::new()),
+        };
 
-        Ok(resp)
-    }
-}
+        let mut articles = Vec::new();
+        let depth = self.conn.config().max_pipeline_depth;
+        loop {
+            let batch = numbers.by_ref().take(depth).collect::<Vec<_>>();
+            if batch.is_empty() {
+                break;
+            }
+            if let Err(e) = self.pipeline_articles(&batch, &mut articles) {
+                return Err(Error::IncompleteArticles {
+                    articles,
+                    source: Box::new(e),
+                });
+            }
+        }
 
-/// Configuration for an [`NntpClient`]
-#[derive(Clone, Debug, Default)]
-pub struct ClientConfig {
-    authinfo: Option<(String, String)>,
-    group: Option<String>,
-    conn_config: ConnectionConfig,
-}
+        Ok(articles)
+    }
 
-impl ClientConfig {
-    /// Perform an AUTHINFO USER/PASS authentication after connecting to the server
+    /// Retrieve a batch of articles for [`articles`](Self::articles), skipping missing ones
     ///
-    /// https://tools.ietf.org/html/rfc4643#section-2.3
-    pub fn authinfo_user_pass(
+    /// Each article is added to `articles` as soon as its response is read, so those read
+    /// before a failure are kept. After an error that leaves the connection usable (e.g. a
+    /// malformed article) the remaining responses of the batch are still read so that the
+    /// connection stays at a command boundary, and the first error is returned.
+    fn pipeline_articles(
         &mut self,
-        username: impl AsRef<str>,
-        password: impl AsRef<str>,
-    ) -> &mut Self {
-        self.authinfo = Some((username.as_ref().to_string(), password.as_ref().to_string()));
-        self
+        numbers: &[ArticleNumber],
+        articles: &mut Vec<(ArticleNumber, BinaryArticle)>,
+    ) -> Result<()> {
+        check_discontinued(&self.discontinued)?;
+
+        for number in numbers {
+            self.conn
+                .queue(&cmd::Article::from(ArticleId::Number(*number)))?;
+        }
+        self.conn.flush_commands()?;
+
+        let mut result = Ok(());
+        for number in numbers {
+            let resp = match self.conn.read_response_auto() {
+                Ok(resp) => resp,
+                Err(e) => return result.and(Err(e.into())),
+            };
+            if result.is_err() {
+                continue;
+            }
+            match self
+                .check_service_available(resp)
+                .and_then(|resp| self.check_response(resp, Kind::Article))
+            {
+                Ok(resp) => match BinaryArticle::try_from(&resp) {
+                    Ok(article) => {
+                        articles.push((*number, article));
+                        self.current_article = Some(*number);
+                    }
+                    Err(e) => result = Err(e),
+                },
+                Err(Error::UnexpectedCode {
+                    got: ResponseCode::Known(Kind::NoArticleWithNumber),
+                    ..
+                }) => trace!("Article {} does not exist, skipping", number),
+                Err(e @ Error::ServiceUnavailable { .. }) => return Err(e),
+                Err(e) => result = Err(e),
+            }
+        }
+
+        result
     }
 
-    /// Join a group upon connection
+    /// The article numbers in a range of the selected group
     ///
-    /// If this is set to None then no `GROUP` command will be sent when the client is initialized
-    pub fn group(&mut self, name: Option<impl AsRef<str>>) -> &mut Self {
-        self.group = name.map(|s| s.as_ref().to_string());
-        self
+    /// Returns `None` for [`ArticleRange::Current`] if there is no current article.
+    fn resolve_article_range(
+        &self,
+        range: ArticleRange,
+    ) -> Result<Option<RangeInclusive<ArticleNumber>>> {
+        let (low, high) = match (range, self.group.as_ref()) {
+            (ArticleRange::Single(number), Some(_)) => (number, number),
+            (ArticleRange::From(low), Some(group)) => (low.max(group.low), group.high),
+            (ArticleRange::Between { low, high }, Some(_)) => (low, high),
+            (ArticleRange::Current, Some(_)) => match self.current_article {
+                Some(number) => (number, number),
+                None => return Ok(None),
+            },
+            (range, None) => {
+                let id = match range {
+                    ArticleRange::Single(low)
+                    | ArticleRange::From(low)
+                    | ArticleRange::Between { low, .. } => ArticleId::Number(low),
+                    ArticleRange::Current => ArticleId::Current,
+                };
+                return Err(Error::NoGroupSelected(id));
+            }
+        };
+
+        Ok(Some(low..=high))
     }
 
-    /// Set the configuration of the underlying [`NntpConnection`]
-    pub fn connection_config(&mut self, config: ConnectionConfig) -> &mut Self {
-        self.conn_config = config;
-        self
+    /// Retrieve several articles by message ID
+    ///
+    /// The results are returned in the same order as the IDs. A failure to retrieve an
+    /// individual article (e.g. `430` because it has expired) is returned as an `Err` item
+    /// rather than aborting the rest of the batch.
+    pub fn articles_by_ids(&mut self, ids: &[impl AsRef<str>]) -> Vec<Result<BinaryArticle>> {
+        ids.iter().map(|id| self.article(id.as_ref())).collect()
     }
 
-    /// Resolves the configuration into a client
-    pub fn connect(&self, addr: impl ToSocketAddrs) -> Result<NntpClient> {
-        let (mut conn, conn_response) = NntpConnection::connect(addr, self.conn_config.clone())?;
+    /// Retrieve the headers of several articles by message ID
+    ///
+    /// The `HEAD` commands are [pipelined](NntpConnection::pipeline) in batches of
+    /// [`max_pipeline_depth`](ClientConfig::max_pipeline_depth), saving a round
+    /// trip per article. If pipelining fails (e.g. because the connection dropped), the remaining
+    /// headers are retrieved one at a time via [`head`](Self::head). If the failure left the
+    /// connection part way through a response, the client is [reconnected](Self::reconnect)
+    /// first.
+    ///
+    /// Duplicate IDs are only retrieved once, so one entry is returned per distinct ID in the order
+    /// in which they first appear. A failure to retrieve an individual article (e.g. `430` because
+    /// it has expired) is returned alongside its ID rather than aborting the rest of the batch.
+    pub fn heads_by_id(&mut self, ids: &[MessageId]) -> Vec<(MessageId, Result<Headers>)> {
+        let mut seen = HashSet::new();
+        let unique = ids
+            .iter()
+            .filter(|id| seen.insert(*id))
+            .cloned()
+            .collect::<Vec<_>>();
 
-        debug!(
-            "Connected. Server returned `{}`",
-            conn_response.first_line_to_utf8_lossy()
-        );
+        let mut results = Vec::with_capacity(unique.len());
+        let mut pipelining = self.discontinued.is_none();
+        let depth = self.conn.config().max_pipeline_depth;
+        for batch in unique.chunks(depth) {
+            if pipelining {
+                let commands = batch
+                    .iter()
+                    .map(|id| cmd::Head::from(ArticleId::from(id)))
+                    .collect::<Vec<_>>();
+                let commands = commands
+                    .iter()
+                    .map(|command| command as &dyn NntpCommand)
+                    .collect::<Vec<_>>();
 
-        // FIXME(ux) check capabilities before attempting auth info
-        if let Some((username, password)) = &self.authinfo {
-            if self.conn_config.tls_config.is_none() {
-                warn!("TLS is not enabled, credentials will be sent in the clear!");
+                match self.conn.pipeline(&commands) {
+                    Ok(resps) => {
+                        for (id, resp) in batch.iter().zip(resps) {
+                            let head = self
+                                .check_service_available(resp)
+                                .and_then(|resp| self.check_response(resp, Kind::Head))
+                                .and_then(|resp| Head::try_from(&resp));
+                            results.push((id.clone(), head.map(|head| head.headers)));
+                        }
+                        // Leave reconnecting after a `400` to the sequential path
+                        pipelining = self.discontinued.is_none();
+                        continue;
+                    }
+                    Err(e) => {
+                        debug!(
+                            "Failed to pipeline HEAD, falling back to sequential -- {}",
+                            e
+                        );
+                        pipelining = false;
+                        // Any unread responses would be mistaken for those of the fallback
+                        if !self.conn.is_at_command_boundary() {
+                            if let Err(e) = self.reconnect() {
+                                debug!("Failed to reconnect after pipelining HEAD -- {}", e);
+                            }
+                        }
+                    }
+                }
             }
-            debug!("Authenticating with AUTHINFO USER/PASS");
-            authenticate(&mut conn, username, password)?;
-        }
 
-        debug!("Retrieving capabilities...");
-        let capabilities = get_capabilities(&mut conn)?;
+            for id in batch {
+                results.push((id.clone(), self.head(id).map(|head| head.headers)));
+            }
+        }
 
-        let group = if let Some(name) = &self.group {
-            debug!("Connecting to group {}...", name);
-            select_group(&mut conn, name)?.into()
-        } else {
-            debug!("No initial group specified");
-            None
-        };
+        results
+    }
 
-        Ok(NntpClient {
-            conn,
-            config: self.clone(),
-            capabilities,
-            group,
-        })
+    /// Retrieve the body for an article
+    ///
+    /// Article IDs are handled in the same way as [`article`](Self::article).
+    pub fn body(&mut self, id: impl Into<ArticleId>) -> Result<Body> {
+        let id = self.resolve_article_id(id.into())?;
+        let resp = self.command_expecting(&cmd::Body::from(id.clone()), Kind::Body)?;
+        self.article_selected(&id);
+        resp.borrow().try_into()
     }
-}
 
-impl RawResponse {}
+    /// Retrieve the headers for an article
+    ///
+    /// Article IDs are handled in the same way as [`article`](Self::article) and the headers are
+    /// parsed in the same way as [`BinaryArticle::headers`].
+    ///
+    /// As with [`article`](Self::article), headers retrieved by message ID are returned from the
+    /// [article cache](ClientConfig::article_cache) when possible.
+    pub fn head(&mut self, id: impl Into<ArticleId>) -> Result<Head> {
+        let id = self.resolve_article_id(id.into())?;
+        if let ArticleId::MessageId(message_id) = &id {
+            if let Some(head) = self.cache.head(message_id) {
+                trace!(
+                    "Returning the headers of {} from the article cache",
+                    message_id
+                );
+                return Ok(head);
+            }
+        }
 
-/// Perform an AUTHINFO USER/PASS exchange
-fn authenticate(
-    conn: &mut NntpConnection,
-    username: impl AsRef<str>,
-    password: impl AsRef<str>,
-) -> Result<()> {
-    debug!("Sending AUTHINFO USER");
-    let user_resp = conn.command(&cmd::AuthInfo::User(username.as_ref().to_string()))?;
+        let resp = self.command_expecting(&cmd::Head::from(id.clone()), Kind::Head)?;
+        self.article_selected(&id);
 
-    if user_resp.code != ResponseCode::from(381) {
-        return Err(Error::Failure {
-            code: user_resp.code,
-            resp: user_resp,
-            msg: Some("AUTHINFO USER failed".to_string()),
-        });
+        let head = Head::try_from(resp.borrow())?;
+        if let ArticleId::MessageId(message_id) = id {
+            self.cache
+                .insert(message_id, CachedArticle::Head(head.clone()));
+        }
+        Ok(head)
     }
 
-    debug!("Sending AUTHINFO PASS");
-    let pass_resp = conn.command(&cmd::AuthInfo::Pass(password.as_ref().to_string()))?;
+    /// Empty the [article cache](ClientConfig::article_cache)
+    ///
+    /// The [statistics](Self::cache_stats) are kept.
+    pub fn clear_cache(&mut self) {
+        self.cache.clear()
+    }
 
-    if pass_resp.code() != ResponseCode::Known(Kind::AuthenticationAccepted) {
-        return Err(Error::Failure {
-            code: pass_resp.code,
-            resp: pass_resp,
-            msg: Some("AUTHINFO PASS failed".to_string()),
-        });
+    /// How effective the [article cache](ClientConfig::article_cache) has been
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache.stats()
     }
-    debug!("Successfully authenticated");
 
-    Ok(())
-}
+    /// Retrieve the raw headers for an article
+    ///
+    /// Unlike [`head`](Self::head), the headers are returned as name/value pairs in the order
+    /// they were sent by the server. Duplicate headers are preserved and folded values are
+    /// returned as-is (i.e. they are not unfolded).
+    ///
+    /// This is useful for tools that need the exact header sequence (e.g. signature verification).
+    pub fn head_raw(&mut self, id: impl Into<ArticleId>) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let id = self.resolve_article_id(id.into())?;
+        let resp = self.command_expecting(&cmd::Head::from(id.clone()), Kind::Head)?;
+        self.article_selected(&id);
+        raw_headers(&resp)
+    }
 
-fn get_capabilities(conn: &mut NntpConnection) -> Result<Capabilities> {
-    let resp = conn.command(&cmd::Capabilities)?;
+    /// Retrieve the status of an article
+    ///
+    /// This checks whether an article exists and returns its number and message ID without
+    /// transferring the article itself. Article IDs are handled in the same way as
+    /// [`article`](Self::article).
+    ///
+    /// [`Error::NoSuchArticle`] is returned if the article does not exist.
+    pub fn stat(&mut self, id: impl Into<ArticleId>) -> Result<Stat> {
+        let id = self.resolve_article_id(id.into())?;
+        let resp = self.send_and_read(&cmd::Stat::from(id.clone()), ResponseCode::is_multiline)?;
+        let stat = stat_response(id.clone(), resp)?;
+        self.article_selected(&id);
+        Ok(stat)
+    }
 
-    if resp.code() != ResponseCode::Known(Kind::Capabilities) {
-        Err(Error::failure(resp))
-    } else {
-        Capabilities::try_from(&resp)
+    /// Move to the next article in the selected group via `NEXT`
+    ///
+    /// [`Error::NoNextArticle`] is returned once the end of the group has been reached, and
+    /// [`Error::NoGroupSelected`] is returned without contacting the server if no group has been
+    /// selected.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Result<Stat> {
+        self.navigate(&cmd::Next)
     }
-}
 
-fn select_group(conn: &mut NntpConnection, group: impl AsRef<str>) -> Result<Group> {
-    let resp = conn.command(&cmd::Group(group.as_ref().to_string()))?;
+    /// Move to the previous article in the selected group via `LAST`
+    ///
+    /// This behaves like [`next`](Self::next) but returns [`Error::NoPreviousArticle`] once the
+    /// start of the group has been reached.
+    pub fn last(&mut self) -> Result<Stat> {
+        self.navigate(&cmd::Last)
+    }
 
-    match resp.code() {
-        ResponseCode::Known(Kind::GroupSelected) => Group::try_from(&resp),
-        ResponseCode::Known(Kind::NoSuchNewsgroup) => Err(Error::failure(resp)),
-        code => Err(Error::Failure {
-            code,
-            msg: Some(format!("{}", resp.first_line_to_utf8_lossy())),
-            resp,
-        }),
+    /// Select a group and retrieve the numbers of the articles within it via `LISTGROUP`
+    ///
+    /// Unlike the water marks returned by [`select_group`](Self::select_group), these are the
+    /// articles that actually exist. If no `group` is provided, the selected group is listed
+    /// (and the `range` applies to it). The selected group is updated as with `select_group`.
+    ///
+    /// If the server sends the `211` without any article numbers, a
+    /// [`MalformedResponse`](crate::raw::error::Error::MalformedResponse) error is returned once
+    /// the [ambiguous response timeout](ConnectionConfig::ambiguous_response_timeout) elapses, if
+    /// one is configured. The connection must be [reconnected](Self::reconnect) afterwards.
+    ///
+    /// `LISTGROUP` has no current-article form, so [`ArticleRange::Current`] is rejected with
+    /// [`Error::InvalidCommand`] without contacting the server.
+    pub fn listgroup(
+        &mut self,
+        group: Option<&str>,
+        range: Option<ArticleRange>,
+    ) -> Result<(Group, Vec<ArticleNumber>)> {
+        let range = range
+            .map(|range| range.require_explicit("LISTGROUP"))
+            .transpose()?;
+        // LISTGROUP only accepts a range after a group name
+        let group = group
+            .map(ToString::to_string)
+            .or_else(|| range.and(self.group.as_ref().map(|g| g.name.clone())));
+
+        // n.b. 211 is only multi-line in response to LISTGROUP
+        let resp = self.send_and_read(&cmd::ListGroup { group, range }, |code| {
+            code.is_multiline() || *code == ResponseCode::Known(Kind::GroupSelected)
+        })?;
+
+        if resp.code() != ResponseCode::Known(Kind::GroupSelected) {
+            return Err(Error::unexpected_code(Kind::GroupSelected, resp));
+        }
+
+        let group = self.with_posting_status(Group::try_from(&resp)?);
+        let numbers = parse_article_numbers(&resp)?;
+        self.set_group(Some(group.clone()));
+
+        Ok((group, numbers))
+    }
+
+    /// List the groups available on the server via `LIST ACTIVE`
+    ///
+    /// If a `wildmat` (e.g. `comp.lang.*`) is provided only the matching groups are returned.
+    /// [`Error::InvalidWildmat`] is returned without contacting the server if it is malformed.
+    /// A [`Wildmat`] built from parts can be passed with [`as_str`](Wildmat::as_str).
+    pub fn list_active(&mut self, wildmat: Option<&str>) -> Result<Vec<ActiveGroup>> {
+        let command = cmd::List::Active {
+            wildmat: validate_wildmat(wildmat)?,
+        };
+        let resp = self.command_expecting(&command, Kind::List)?;
+        let groups = parse_active(&resp, Kind::List)?;
+        record_posting_statuses(&mut self.posting_statuses, self.group.as_mut(), &groups);
+        Ok(groups)
+    }
+
+    /// Lazily list the groups available on the server via `LIST ACTIVE`
+    ///
+    /// This sends the same command as [`list_active`](Self::list_active) but parses each line as
+    /// it is read rather than buffering the whole response, which matters on servers carrying
+    /// hundreds of thousands of groups. Posting statuses are not recorded for
+    /// [`posting_status`](Self::posting_status) as that would hold on to every group after all.
+    ///
+    /// As with [`overviews_iter`](Self::overviews_iter), a line that fails to parse is yielded
+    /// as an `Err` and iteration continues, iteration stops after an unexpected response code or
+    /// a read error, and the client must be reconnected if the iterator is dropped early.
+    pub fn list_active_iter(&mut self, wildmat: Option<&str>) -> ActiveGroupIter<'_> {
+        let reader = validate_wildmat(wildmat).and_then(move |wildmat| {
+            self.send_and_stream(&cmd::List::Active { wildmat }, Kind::List)
+        });
+        ParsedLineIter::new(reader, ActiveGroup::parse)
+    }
+
+    /// List the groups created since a date and time via `NEWGROUPS`
+    ///
+    /// The groups are returned in the same form as [`list_active`](Self::list_active).
+    pub fn newgroups(&mut self, since: NewsDateTime) -> Result<Vec<ActiveGroup>> {
+        let resp = self.command_expecting(&cmd::NewGroups::from(since), Kind::NewGroups)?;
+        let groups = parse_active(&resp, Kind::NewGroups)?;
+        record_posting_statuses(&mut self.posting_statuses, self.group.as_mut(), &groups);
+        Ok(groups)
+    }
+
+    /// List the message IDs of the articles posted to matching groups since a date and time
+    /// via `NEWNEWS`
+    ///
+    /// [`Error::Unsupported`] is returned without contacting the server if the server does not
+    /// advertise the `NEWNEWS` capability, and [`Error::InvalidWildmat`] if the wildmat is
+    /// malformed.
+    pub fn newnews(
+        &mut self,
+        wildmat: impl Into<Wildmat>,
+        since: NewsDateTime,
+    ) -> Result<Vec<String>> {
+        let wildmat = wildmat.into();
+        wildmat.validate()?;
+        if self.try_capabilities()?.get("NEWNEWS").is_none() {
+            return Err(Error::Unsupported {
+                command: "NEWNEWS".to_string(),
+                resp: None,
+            });
+        }
+
+        let command = cmd::NewNews {
+            wildmat: wildmat.into_string(),
+            date: since.date(),
+            time: since.time(),
+            gmt: since.gmt_enabled(),
+        };
+        let resp = self.command_expecting(&command, Kind::NewNews)?;
+        parse_newnews(&resp)
+    }
+
+    /// List the descriptions of the groups available on the server via `LIST NEWSGROUPS`
+    ///
+    /// If a `wildmat` (e.g. `comp.lang.*`) is provided only the matching groups are returned.
+    /// [`Error::InvalidWildmat`] is returned without contacting the server if it is malformed.
+    pub fn list_newsgroups(&mut self, wildmat: Option<&str>) -> Result<Vec<GroupDescription>> {
+        let command = cmd::List::Newsgroups {
+            wildmat: validate_wildmat(wildmat)?,
+        };
+        let resp = self.command_expecting(&command, Kind::List)?;
+        parse_newsgroups(&resp)
+    }
+
+    /// List the headers and metadata items that may be retrieved via `HDR` (`LIST HEADERS`)
+    ///
+    /// The names are returned as sent by the server, including the special `:`, which means any
+    /// header may be retrieved, and metadata items such as `:bytes` and `:lines`.
+    pub fn list_headers(&mut self) -> Result<Vec<String>> {
+        let resp = self.command_expecting(&cmd::List::Headers, Kind::List)?;
+        parse_list_headers(&resp)
+    }
+
+    /// Send `LIST <keyword> <args...>` and return the raw response
+    ///
+    /// This supports keywords without a dedicated method, such as `LIST MOTD`,
+    /// `LIST HEADERS MSGID`, or vendor extensions. The response must be a `215`, and its data blocks
+    /// are left for the caller to parse.
+    ///
+    /// [`Error::InvalidCommand`] is returned without contacting the server if the keyword or an
+    /// argument is empty or contains whitespace or control characters.
+    ///
+    /// ```no_run
+    /// use brokaw::client::NntpClient;
+    /// use brokaw::error::Result;
+    ///
+    /// fn motd(client: &mut NntpClient) -> Result<Vec<String>> {
+    ///     let resp = client.list_raw("MOTD", &[])?;
+    ///     Ok(resp.data_blocks().map(|db| db.to_utf8_lines_lossy()).unwrap_or_default())
+    /// }
+    /// ```
+    pub fn list_raw(&mut self, keyword: &str, args: &[&str]) -> Result<RawResponse> {
+        for token in std::iter::once(&keyword).chain(args) {
+            if token.is_empty() || token.chars().any(|c| c.is_whitespace() || c.is_control()) {
+                return Err(Error::InvalidCommand(format!(
+                    "LIST argument {:?} is empty or contains whitespace",
+                    token
+                )));
+            }
+        }
+
+        let command = cmd::List::Other {
+            keyword: keyword.to_string(),
+            args: args.iter().map(ToString::to_string).collect(),
+        };
+        self.command_expecting(&command, Kind::List)
+    }
+
+    /// Retrieve the format of the overviews returned by the server via `LIST OVERVIEW.FMT`
+    ///
+    /// The format is cached by the client and used to name any additional fields returned by
+    /// [`overviews`](Self::overviews) (see [`Overview::extra`]).
+    pub fn list_overview_fmt(&mut self) -> Result<OverviewFmt> {
+        let resp = self.command_expecting(&cmd::List::OverviewFmt, Kind::List)?;
+        let fmt = OverviewFmt::try_from(&resp)?;
+        self.overview_fmt = Some(fmt.clone());
+        Ok(fmt)
+    }
+
+    /// Retrieve the overviews for a range of articles in the selected group
+    ///
+    /// `OVER` is used if the server advertises it, otherwise `XOVER` is used
+    /// (see [`overview_command`](Self::overview_command)). If the server responds to `OVER`
+    /// with `500` (unknown command), the command is retried with `XOVER`, which is then used
+    /// for the rest of the session.
+    ///
+    /// If [`list_overview_fmt`](Self::list_overview_fmt) has been called, additional fields are
+    /// aligned with the server's format rather than identified by their contents.
+    ///
+    /// ```no_run
+    /// use brokaw::client::NntpClient;
+    /// use brokaw::error::Result;
+    /// use brokaw::types::prelude::*;
+    ///
+    /// fn latest_subjects(client: &mut NntpClient) -> Result<Vec<String>> {
+    ///     let low = client.select_group("misc.test")?.high.saturating_sub(10);
+    ///     let overviews = client.overviews(ArticleRange::from(low..))?;
+    ///     Ok(overviews.into_iter().map(|o| o.subject).collect())
+    /// }
+    /// ```
+    pub fn overviews(&mut self, range: impl Into<ArticleRange>) -> Result<Vec<Overview>> {
+        let range = range.into();
+        self.try_capabilities()?;
+        let resp = if self.overview_command() == "OVER" {
+            match self.command_expecting(&cmd::Over::Articles(range), Kind::Overview) {
+                Err(Error::UnexpectedCode {
+                    got: ResponseCode::Known(Kind::UnknownCommand),
+                    ..
+                }) => {
+                    debug!("OVER is not recognized, falling back to XOVER");
+                    self.legacy_overview = true;
+                    self.command_expecting(&cmd::XOver::Articles(range), Kind::Overview)?
+                }
+                res => res?,
+            }
+        } else {
+            self.command_expecting(&cmd::XOver::Articles(range), Kind::Overview)?
+        };
+
+        parse_overviews(&resp, self.overview_fmt.as_ref())
+    }
+
+    /// Lazily retrieve the overviews for a range of articles in the selected group
+    ///
+    /// This sends the same command as [`overviews`](Self::overviews) but parses each line as it
+    /// is read rather than buffering the whole response, so memory use is bounded by a single
+    /// line. This is intended for large ranges, e.g. streaming the overviews of a busy group
+    /// into a database.
+    ///
+    /// A line that fails to parse is yielded as an `Err` and iteration continues with the next
+    /// line. Iteration stops after an error sending the command, an unexpected response code,
+    /// or an error reading from the server. Unlike `overviews`, this does not fall back to
+    /// `XOVER` if `OVER` is rejected.
+    ///
+    /// If the iterator is dropped before the end of the response, the remaining lines are not
+    /// read. The connection is left mid-response, so further commands fail with
+    /// [`Error::Desynchronized`](crate::raw::error::Error::Desynchronized) until the client is
+    /// [reconnected](Self::reconnect).
+    pub fn overviews_iter(&mut self, range: impl Into<ArticleRange>) -> OverviewIter<'_> {
+        let range = range.into();
+        let fmt = self.overview_fmt.clone();
+        let parse = move |line: &[u8]| Overview::parse(line, fmt.as_ref());
+
+        if let Err(e) = self.try_capabilities() {
+            return ParsedLineIter::new(Err(e), parse);
+        }
+        let reader = if self.overview_command() == "OVER" {
+            self.send_and_stream(&cmd::Over::Articles(range), Kind::Overview)
+        } else {
+            self.send_and_stream(&cmd::XOver::Articles(range), Kind::Overview)
+        };
+        ParsedLineIter::new(reader, parse)
+    }
+
+    /// Retrieve the overview of an article by its message-id via `OVER <message-id>`
+    ///
+    /// This does not require a group to be selected. `None` is returned if the server has no
+    /// article with the message-id (`430`).
+    ///
+    /// [`Error::Unsupported`] is returned without contacting the server unless the server
+    /// advertises `OVER MSGID`, as `XOVER` does not accept message-ids.
+    pub fn overview_by_id(&mut self, id: &MessageId) -> Result<Option<Overview>> {
+        let msgid_supported = self
+            .try_capabilities()?
+            .over_fields()
+            .is_some_and(|args| args.iter().any(|arg| arg.eq_ignore_ascii_case("MSGID")));
+        if !msgid_supported {
+            return Err(Error::Unsupported {
+                command: "OVER <message-id>".to_string(),
+                resp: None,
+            });
+        }
+
+        let command = cmd::Over::MessageId(id.to_string());
+        let resp = self.send_and_read(&command, ResponseCode::is_multiline)?;
+        match resp.code() {
+            ResponseCode::Known(Kind::Overview) => {
+                Ok(parse_overviews(&resp, self.overview_fmt.as_ref())?
+                    .into_iter()
+                    .next())
+            }
+            ResponseCode::Known(Kind::NoArticleWithMessageId) => Ok(None),
+            _ => Err(Error::unexpected_code(Kind::Overview, resp)),
+        }
+    }
+
+    /// Retrieve a single header for a range of articles in the selected group
+    ///
+    /// `HDR` is used if the server advertises it, otherwise `XHDR` is used
+    /// (see [`header_command`](Self::header_command)). As with [`overviews`](Self::overviews),
+    /// the command is retried with `XHDR` if the server doesn't recognize `HDR`.
+    ///
+    /// Each article number is paired with the raw value of the header. Some servers send
+    /// `(none)` for articles that lack the header, these are returned as `None` so they can be
+    /// told apart from headers that are present but empty (`Some` of an empty value).
+    pub fn hdr(
+        &mut self,
+        field: &str,
+        range: impl Into<ArticleRange>,
+    ) -> Result<Vec<(ArticleNumber, Option<Vec<u8>>)>> {
+        let range = range.into();
+        self.try_capabilities()?;
+        if self.header_command() == "HDR" {
+            let command = cmd::Hdr::Articles {
+                field: field.to_string(),
+                range,
+            };
+            match self.command_expecting(&command, Kind::Hdr) {
+                Err(Error::UnexpectedCode {
+                    got: ResponseCode::Known(Kind::UnknownCommand),
+                    ..
+                }) => {
+                    debug!("HDR is not recognized, falling back to XHDR");
+                    self.legacy_header = true;
+                }
+                res => return parse_hdr(&res?, Kind::Hdr),
+            }
+        }
+
+        let command = cmd::XHdr::Articles {
+            header: field.to_string(),
+            range,
+        };
+        let resp = self.command_expecting(&command, Kind::Head)?;
+        parse_hdr(&resp, Kind::Head)
+    }
+
+    /// Retrieve a single header of the [current article](Self::current_article)
+    ///
+    /// This sends `HDR` (or `XHDR`, see [`hdr`](Self::hdr)) without a range, which is much
+    /// cheaper than retrieving the whole [`head`](Self::head) when only one header is needed,
+    /// e.g. after moving to an article with [`stat`](Self::stat) or [`next`](Self::next).
+    ///
+    /// `None` is returned if the article lacks the header. As servers send an empty value for
+    /// missing headers, a header that is present but empty is also returned as `None`.
+    ///
+    /// [`Error::NoGroupSelected`] is returned without contacting the server if no group is
+    /// selected, and [`Error::NoSuchArticle`] is returned if the server reports that there is no
+    /// current article (`420`).
+    pub fn hdr_current(&mut self, field: &str) -> Result<Option<Vec<u8>>> {
+        self.resolve_article_id(ArticleId::Current)?;
+        match self.hdr(field, ArticleRange::Current) {
+            Ok(values) => Ok(values
+                .into_iter()
+                .next()
+                .and_then(|(_, value)| value)
+                .filter(|value| !value.is_empty())),
+            Err(Error::UnexpectedCode {
+                got: code @ ResponseCode::Known(Kind::InvalidCurrentArticleNumber),
+                resp,
+                ..
+            }) => Err(Error::NoSuchArticle {
+                id: ArticleId::Current,
+                code,
+                resp,
+            }),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Search a header across a range of articles in the selected group via `XPAT`
+    ///
+    /// Only the articles whose header matches at least one of the wildmat `patterns` are
+    /// returned, paired with the header value as in [`hdr`](Self::hdr).
+    ///
+    /// `XPAT` is an extension from [RFC 2980](https://tools.ietf.org/html/rfc2980#section-2.9)
+    /// and is not advertised in the capabilities, so it is always attempted and
+    /// [`Error::Unsupported`] is returned if the server responds with `500` or `501`.
+    /// [`Error::InvalidWildmat`] is returned without contacting the server if any of the
+    /// patterns are malformed, and [`Error::InvalidCommand`] if there are no patterns or the range
+    /// is [`ArticleRange::Current`] as `XPAT` has no current-article form.
+    pub fn xpat<W: Into<Wildmat> + Clone>(
+        &mut self,
+        field: &str,
+        range: impl Into<ArticleRange>,
+        patterns: &[W],
+    ) -> Result<Vec<(ArticleNumber, Option<Vec<u8>>)>> {
+        let range = range.into().require_explicit("XPAT")?;
+        if patterns.is_empty() {
+            return Err(Error::InvalidCommand(
+                "XPAT requires at least one pattern".to_string(),
+            ));
+        }
+        let patterns = patterns
+            .iter()
+            .map(|pattern| {
+                let wildmat = pattern.clone().into();
+                wildmat.validate().map(|_| wildmat.into_string())
+            })
+            .collect::<Result<_>>()?;
+        let command = cmd::XPat {
+            header: field.to_string(),
+            range,
+            patterns,
+        };
+
+        let resp = self.send_and_read(&command, ResponseCode::is_multiline)?;
+        match resp.code() {
+            ResponseCode::Known(Kind::Head) => parse_hdr(&resp, Kind::Head),
+            ResponseCode::Known(Kind::UnknownCommand) | ResponseCode::Known(Kind::SyntaxError) => {
+                Err(Error::Unsupported {
+                    command: "XPAT".to_string(),
+                    resp: Some(resp),
+                })
+            }
+            _ => Err(Error::unexpected_code(Kind::Head, resp)),
+        }
+    }
+
+    /// Retrieve the server's help text via `HELP`
+    ///
+    /// The lines of the help text are joined with `\n` and converted to UTF-8 lossily.
+    /// This is useful for discovering commands that the server does not advertise in its
+    /// capabilities.
+    pub fn help(&mut self) -> Result<String> {
+        let resp = self.command_expecting(&cmd::Help, Kind::Help)?;
+        let data_blocks = resp.data_blocks().ok_or_else(Error::missing_data_blocks)?;
+
+        Ok(data_blocks.to_utf8_lines_lossy().join("\n"))
+    }
+
+    /// Retrieve the server's time via `DATE`
+    ///
+    /// This is also a cheap way to check that the connection is still alive.
+    pub fn date(&mut self) -> Result<ServerDate> {
+        let resp = self.command_expecting(&cmd::Date, Kind::Date)?;
+        ServerDate::try_from(&resp)
+    }
+
+    /// Send a `DATE` if the [keepalive interval](ClientConfig::keepalive_interval) has elapsed
+    /// since the last command was sent
+    ///
+    /// Returns true if a keepalive was sent. This is intended to be called from the caller's
+    /// event loop while the connection is otherwise idle.
+    pub fn keepalive_if_needed(&mut self) -> Result<bool> {
+        let interval = match self.config.keepalive_interval {
+            Some(interval) => interval,
+            None => return Ok(false),
+        };
+
+        let idle = self.conn.last_sent().elapsed();
+        if idle < interval {
+            return Ok(false);
+        }
+
+        debug!("Connection has been idle for {:?}, sending DATE", idle);
+        self.date()?;
+        Ok(true)
+    }
+
+    /// Check whether the connection is still usable, e.g. before reusing a pooled client
+    ///
+    /// Sends `DATE` if the cached capabilities advertise `READER` and `CAPABILITIES` otherwise,
+    /// as neither changes the state of the session. Returns true if the server sent the expected
+    /// response and false on any error, including an unexpected response code. Unlike other
+    /// commands, this never [reconnects](ClientConfig::auto_reconnect) or invokes the
+    /// [recovery handler](ClientConfig::on_auth_or_mode_required).
+    pub fn is_healthy(&mut self) -> bool {
+        if self.discontinued.is_some() {
+            return false;
+        }
+
+        let res = if self.capabilities.get("READER").is_some() {
+            self.conn
+                .command(&cmd::Date)
+                .map_err(Error::from)
+                .and_then(|resp| self.check_service_available(resp))
+                .map(|resp| resp.code() == ResponseCode::Known(Kind::Date))
+        } else {
+            self.conn
+                .command(&cmd::Capabilities)
+                .map_err(Error::from)
+                .and_then(|resp| self.check_service_available(resp))
+                .map(|resp| resp.code() == ResponseCode::Known(Kind::Capabilities))
+        };
+
+        match res {
+            Ok(healthy) => healthy,
+            Err(e) => {
+                debug!("Health check failed -- {}", e);
+                false
+            }
+        }
+    }
+
+    /// Post an article
+    ///
+    /// The article must contain the headers and body separated by a blank line
+    /// (see [`ArticleBuilder`](crate::posting::ArticleBuilder)).
+    /// It is dot-stuffed and terminated by the connection so it should be provided as-is.
+    ///
+    /// [`Error::PostingNotPermitted`] is returned without sending the article if the server does
+    /// not advertise the `POST` capability or responds with `440`.
+    pub fn post(&mut self, article: impl AsRef<[u8]>) -> Result<()> {
+        if self.try_capabilities()?.get("POST").is_none() {
+            return Err(Error::PostingNotPermitted { resp: None });
+        }
+
+        let resp = self.send_and_read(&cmd::Post, ResponseCode::is_multiline)?;
+        match Exchange::from(resp) {
+            Exchange::Continue(_) => {}
+            Exchange::Complete(resp) => match resp.code() {
+                ResponseCode::Known(Kind::PostingNotPermitted) => {
+                    return Err(Error::PostingNotPermitted { resp: Some(resp) })
+                }
+                _ => return Err(Error::unexpected_code(Kind::PostSendArticle, resp)),
+            },
+        }
+
+        self.conn.send_data_blocks(article)?;
+        let resp = self.conn.read_response_auto()?;
+        let resp = self.check_service_available(resp)?;
+        self.check_response(resp, Kind::ArticlePostedOk)?;
+
+        Ok(())
+    }
+
+    /// Offer an article to the server via `IHAVE`, sending it if the server wants it
+    ///
+    /// This is intended for peering and feeding rather than reading. The article is dot-stuffed
+    /// and terminated by the connection in the same way as [`post`](Self::post).
+    ///
+    /// Responses indicating that the article was not wanted, should be retried later, or was
+    /// rejected are returned as an [`IHaveOutcome`] rather than an error.
+    pub fn ihave(
+        &mut self,
+        message_id: impl AsRef<str>,
+        article: impl AsRef<[u8]>,
+    ) -> Result<IHaveOutcome> {
+        let command = cmd::IHave(message_id.as_ref().to_string());
+        let resp = self.send_and_read(&command, ResponseCode::is_multiline)?;
+        match Exchange::from(resp) {
+            Exchange::Continue(_) => {}
+            Exchange::Complete(resp) => match resp.code() {
+                ResponseCode::Known(Kind::ArticleNotWanted) => return Ok(IHaveOutcome::NotWanted),
+                ResponseCode::Known(Kind::TransferFailed) => return Ok(IHaveOutcome::RetryLater),
+                _ => return Err(Error::unexpected_code(Kind::IHaveSendArticle, resp)),
+            },
+        }
+
+        self.conn.send_data_blocks(article)?;
+        let resp = self.conn.read_response_auto()?;
+        let resp = self.check_service_available(resp)?;
+        match resp.code() {
+            ResponseCode::Known(Kind::ArticleTransferredOk) => Ok(IHaveOutcome::Accepted),
+            ResponseCode::Known(Kind::TransferFailed) => Ok(IHaveOutcome::RetryLater),
+            ResponseCode::Known(Kind::TransferRejected) => Ok(IHaveOutcome::Rejected),
+            _ => Err(Error::unexpected_code(Kind::ArticleTransferredOk, resp)),
+        }
+    }
+
+    /// Reconnect to the server
+    ///
+    /// The connection is [re-established](NntpConnection::reconnect) to the same server using
+    /// the client's configuration. Authentication is performed again and the previously selected
+    /// group (if any) is re-selected.
+    ///
+    /// If the [reconnect rate limit](ClientConfig::reconnect_rate_limit) has been exceeded,
+    /// [`Error::ReconnectRateLimited`] is returned without attempting to connect.
+    pub fn reconnect(&mut self) -> Result<()> {
+        self.reconnects
+            .record(self.config.reconnect_rate_limit, Instant::now())?;
+
+        let mut config = self.config.clone();
+        if let Some(name) = &self.last_group_name {
+            config.group = Some(name.clone());
+        }
+
+        match self.conn.peer_addr() {
+            Some(peer_addr) => debug!("Reconnecting to {}...", peer_addr),
+            None => debug!("Reconnecting..."),
+        }
+        let greeting = self.conn.reconnect(config.conn_config.clone())?;
+        let (capabilities, group) = config.init_session(&mut self.conn)?;
+
+        self.greeting_software = greeting_software(&greeting);
+        self.greeting = greeting;
+        self.discontinued = None;
+        self.legacy_overview = false;
+        self.legacy_header = false;
+        self.capabilities_fetched = capabilities.is_some();
+        self.capabilities = capabilities.unwrap_or_default();
+        self.set_group(group);
+
+        Ok(())
+    }
+
+    /// Override the read timeout until the returned guard is dropped
+    ///
+    /// Useful for commands that take the server a long time to answer (e.g. `LIST ACTIVE` on a
+    /// large server) without raising the [timeout](ConnectionConfig::read_timeout) of every
+    /// other command. The guard dereferences to the client and restores the previous timeout
+    /// when dropped, including when a command fails or panics.
+    ///
+    /// A [reconnect](Self::reconnect) while the guard is alive uses the configured timeout.
+    /// An error is returned for connections created from a custom stream.
+    ///
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use brokaw::NntpClient;
+    /// # fn example(client: &mut NntpClient) -> brokaw::error::Result<()> {
+    /// let groups = client.with_timeout(Duration::from_secs(120))?.list_active(None)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_timeout(&mut self, timeout: Duration) -> Result<TimeoutGuard<'_>> {
+        let previous = self.conn.read_timeout()?;
+        self.conn.set_read_timeout(Some(timeout))?;
+        Ok(TimeoutGuard {
+            client: self,
+            previous,
+        })
+    }
+
+    /// Close the connection to the server
+    ///
+    /// Sends `QUIT`, returns the server's `205` response, and shuts down the socket.
+    ///
+    /// Dropping the client without calling `close` will also send a `QUIT` but any errors
+    /// are silently ignored.
+    pub fn close(mut self) -> Result<RawResponse> {
+        self.closed = true;
+        let resp = self.command_expecting(&cmd::Quit, Kind::ConnectionClosing);
+        self.shutdown();
+        resp
+    }
+
+    /// Shut down the socket without sending `QUIT`
+    ///
+    /// Useful when the session is no longer needed and waiting for the server to acknowledge
+    /// `QUIT` isn't worth a round trip.
+    pub fn abort(mut self) {
+        self.closed = true;
+        self.shutdown();
+    }
+
+    /// Shut down both halves of the underlying socket so the OS releases it promptly
+    fn shutdown(&self) {
+        if let Err(e) = self.conn.stream().get_ref().shutdown() {
+            debug!("Failed to shut down the connection: {}", e);
+        }
+    }
+
+    /// Update the selected group and reset the current article to its first article
+    fn set_group(&mut self, group: Option<Group>) {
+        let group = group.map(|group| self.with_posting_status(group));
+        self.current_article = group.as_ref().and_then(first_article);
+        if let Some(group) = &group {
+            self.last_group_name = Some(group.name.clone());
+        }
+        self.group = group;
+    }
+
+    /// Fill in the group's posting status if it was included in a previous listing
+    fn with_posting_status(&self, mut group: Group) -> Group {
+        if group.posting_status.is_none() {
+            group.posting_status = self.posting_statuses.get(&group.name).cloned();
+        }
+        group
+    }
+
+    /// Send `NEXT` or `LAST` and update the current article
+    fn navigate(&mut self, command: &impl NntpCommand) -> Result<Stat> {
+        if self.group.is_none() {
+            return Err(Error::NoGroupSelected(ArticleId::Current));
+        }
+
+        let resp = self.send_and_read(command, ResponseCode::is_multiline)?;
+        let stat = navigate_response(resp)?;
+        self.current_article = Some(stat.number);
+        Ok(stat)
+    }
+
+    /// Update the current article after an article was successfully retrieved
+    ///
+    /// Per [RFC 3977](https://tools.ietf.org/html/rfc3977#section-6.2.1) only retrieving an
+    /// article by number changes the current article.
+    fn article_selected(&mut self, id: &ArticleId) {
+        if let ArticleId::Number(number) = id {
+            self.current_article = Some(*number);
+        }
+    }
+
+    /// Check that an article ID can be used in the current session
+    fn resolve_article_id(&self, id: ArticleId) -> Result<ArticleId> {
+        if id.requires_group() && self.group.is_none() {
+            return Err(Error::NoGroupSelected(id));
+        }
+        Ok(id)
+    }
+
+    /// Send a command and return the response if it is of the expected kind
+    ///
+    /// If the [`UnknownCodePolicy`] accepts an unknown response code, the response will be read
+    /// as a multi-line response when the expected kind is multi-line.
+    fn command_expecting(&mut self, command: &impl NntpCommand, kind: Kind) -> Result<RawResponse> {
+        let policy = self.config.unknown_code_policy;
+        let resp = self.send_and_read(command, |code| policy.is_multiline(*code, kind))?;
+
+        self.check_response(resp, kind)
+    }
+
+    /// Send a command via [`send_and_read`](Self::send_and_read), streaming the data blocks of a
+    /// `kind` response rather than buffering them
+    ///
+    /// Any other response is read in full and returned as an error as in
+    /// [`command_expecting`](Self::command_expecting).
+    fn send_and_stream<C: NntpCommand>(
+        &mut self,
+        command: &C,
+        kind: Kind,
+    ) -> Result<DataBlockReader<'_>> {
+        let policy = self.config.unknown_code_policy;
+        let resp = self.send_and_read(command, |code| {
+            *code != ResponseCode::Known(kind)
+                && !policy.accepts(*code)
+                && policy.is_multiline(*code, kind)
+        })?;
+        self.check_response(resp, kind)?;
+
+        Ok(self.conn.data_block_reader())
+    }
+
+    /// Send a command and read the response, returning [`Error::AccessDenied`] for a `502`
+    ///
+    /// See [`send_and_read_raw`](Self::send_and_read_raw).
+    fn send_and_read<C: NntpCommand>(
+        &mut self,
+        command: &C,
+        is_multiline: impl Fn(&ResponseCode) -> bool,
+    ) -> Result<RawResponse> {
+        self.send_and_read_raw(command, is_multiline)
+            .and_then(check_access)
+    }
+
+    /// Send a command and read the response
+    ///
+    /// If the server requires TLS (code `483`) and [`ClientConfig::auto_starttls`] is enabled,
+    /// the connection is upgraded and the command is retried once.
+    ///
+    /// If the server requires authentication, a mode switch, or TLS (codes `480`, `401`, and
+    /// `483`) and a [handler](ClientConfig::on_auth_or_mode_required) is configured,
+    /// the handler is invoked and the command is retried once.
+    fn send_and_read_raw<C: NntpCommand>(
+        &mut self,
+        command: &C,
+        is_multiline: impl Fn(&ResponseCode) -> bool,
+    ) -> Result<RawResponse> {
+        let mut resp = self.exchange(command, &is_multiline)?;
+
+        if resp.code() == ResponseCode::Known(Kind::SecureConnectionRequired) {
+            if let Some(tls_config) = self.auto_starttls_config() {
+                debug!("Server requires TLS, upgrading the connection via STARTTLS");
+                self.upgrade_tls(tls_config)?;
+                resp = self.exchange(command, &is_multiline)?;
+            }
+        }
+
+        let handler = match &self.config.auth_or_mode_handler {
+            Some(handler) if !self.in_recovery && needs_recovery(resp.code()) => handler.clone(),
+            _ => return Ok(resp),
+        };
+
+        debug!(
+            "Server returned {}, invoking the recovery handler",
+            resp.code()
+        );
+        self.in_recovery = true;
+        let recovered = (handler.0)(self);
+        self.in_recovery = false;
+        recovered?;
+
+        self.conn.send(command)?;
+        let resp = self.conn.read_response_with(|code| is_multiline(&code))?;
+        self.check_service_available(resp)
+    }
+
+    /// The TLS config to upgrade the connection with if it should be upgraded on a `483`
+    fn auto_starttls_config(&self) -> Option<TlsConfig> {
+        if !self.config.auto_starttls || self.in_recovery || self.conn.is_tls() {
+            return None;
+        }
+        self.config.starttls.clone()
+    }
+
+    /// Upgrade the connection via `STARTTLS`, then refresh the capabilities and the group
+    fn upgrade_tls(&mut self, tls_config: TlsConfig) -> Result<()> {
+        if self.capabilities_fetched && self.capabilities.get("STARTTLS").is_none() {
+            return Err(Error::Unsupported {
+                command: "STARTTLS".to_string(),
+                resp: None,
+            });
+        }
+        self.conn.starttls(tls_config)?;
+
+        // The capabilities may change once TLS is active
+        self.capabilities_fetched = false;
+        self.capabilities = Default::default();
+        if !self.config.lazy_capabilities {
+            self.capabilities = get_capabilities(&mut self.conn)?;
+            self.capabilities_fetched = true;
+        }
+        if let Some(name) = self.last_group_name.clone() {
+            self.send_group(name)?;
+        }
+        Ok(())
+    }
+
+    /// Send a command and read the response, reconnecting and retrying if the connection dropped
+    ///
+    /// See [`ClientConfig::auto_reconnect`]
+    fn exchange<C: NntpCommand>(
+        &mut self,
+        command: &C,
+        is_multiline: &impl Fn(&ResponseCode) -> bool,
+    ) -> Result<RawResponse> {
+        let mut retries = 0;
+        loop {
+            let res = check_discontinued(&self.discontinued).and_then(|_| {
+                self.conn
+                    .send(command)
+                    .and_then(|_| self.conn.read_response_with(|code| is_multiline(&code)))
+                    .map_err(Error::from)
+                    .and_then(|resp| self.check_service_available(resp))
+            });
+
+            let reconnect = match &res {
+                Err(Error::Connection(e)) => is_connection_dropped(e),
+                Err(Error::ServiceUnavailable { .. }) => true,
+                _ => false,
+            };
+            match res {
+                Err(e)
+                    if reconnect
+                        && retries < self.config.auto_reconnect
+                        && is_retryable(command) =>
+                {
+                    retries += 1;
+                    warn!(
+                        "Connection lost ({}), reconnecting (attempt {} of {})",
+                        e, retries, self.config.auto_reconnect
+                    );
+                    self.reconnect()?;
+                }
+                res => return res,
+            }
+        }
+    }
+
+    /// Return [`Error::CapabilityMissing`] if capabilities are
+    /// [enforced](ClientConfig::enforce_capabilities) and the server doesn't advertise the one
+    /// `command` requires
+    fn check_required_capability(&mut self, command: &impl NntpCommand) -> Result<()> {
+        if self.config.enforce_capabilities {
+            if let Some(capability) = command.required_capability() {
+                if !self.try_capabilities()?.supports(capability) {
+                    return Err(Error::CapabilityMissing {
+                        capability: capability.to_string(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Keep track of groups selected via `GROUP` or `LISTGROUP`
+    fn track_selected_group(&mut self, resp: &RawResponse) {
+        if resp.code() == ResponseCode::Known(Kind::GroupSelected) {
+            if let Ok(group) = Group::try_from(resp) {
+                self.set_group(Some(group));
+            }
+        }
+    }
+
+    /// Return [`Error::ServiceUnavailable`] if the server discontinued the session with a `400`
+    ///
+    /// The client is marked as unusable until it reconnects.
+    fn check_service_available(&mut self, resp: RawResponse) -> Result<RawResponse> {
+        check_service_available(&mut self.discontinued, resp)
+    }
+
+    /// Return the response if it is of the expected kind or is accepted by the [`UnknownCodePolicy`]
+    ///
+    /// Accepted responses have their code replaced by the expected kind so that they can be
+    /// parsed into typed responses.
+    fn check_response(&self, resp: RawResponse, kind: Kind) -> Result<RawResponse> {
+        self.config.unknown_code_policy.check(resp, kind)
+    }
+}
+
+/// An iterator over the articles in a range of the selected group
+///
+/// Created by [`NntpClient::iter_articles`]
+#[derive(Debug)]
+pub struct ArticleIter<'a> {
+    client: &'a mut NntpClient,
+    numbers: Option<RangeInclusive<ArticleNumber>>,
+    error: Option<Error>,
+}
+
+impl Iterator for ArticleIter<'_> {
+    type Item = Result<BinaryArticle>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(e) = self.error.take() {
+            return Some(Err(e));
+        }
+
+        while let Some(number) = self.numbers.as_mut().and_then(Iterator::next) {
+            match self.client.article(number) {
+                Err(Error::UnexpectedCode {
+                    got: ResponseCode::Known(Kind::NoArticleWithNumber),
+                    ..
+                }) => trace!("Article {} does not exist, skipping", number),
+                Err(e @ Error::Connection(_)) => {
+                    self.numbers = None;
+                    return Some(Err(e));
+                }
+                res => return Some(res),
+            }
+        }
+
+        None
+    }
+}
+
+/// A lazily parsed iterator over the lines of a multi-line response
+///
+/// Each line is parsed as it is read, so a line that fails to parse is yielded as an `Err`
+/// without ending iteration. Iteration ends after the last line or an error reading from the
+/// server. If the iterator is dropped early the remaining lines are not read, leaving the
+/// connection mid-response until the client is [reconnected](NntpClient::reconnect).
+///
+/// See [`OverviewIter`] and [`ActiveGroupIter`]
+pub struct ParsedLineIter<'a, T> {
+    reader: Option<DataBlockReader<'a>>,
+    parse: LineParser<'a, T>,
+    error: Option<Error>,
+}
+
+/// Parses a single line for a [`ParsedLineIter`]
+type LineParser<'a, T> = Box<dyn Fn(&[u8]) -> Result<T> + 'a>;
+
+/// A lazily parsed iterator over the overviews of a range of articles
+///
+/// Created by [`NntpClient::overviews_iter`]
+pub type OverviewIter<'a> = ParsedLineIter<'a, Overview>;
+
+/// A lazily parsed iterator over the groups listed by `LIST ACTIVE`
+///
+/// Created by [`NntpClient::list_active_iter`]
+pub type ActiveGroupIter<'a> = ParsedLineIter<'a, ActiveGroup>;
+
+impl<'a, T> ParsedLineIter<'a, T> {
+    fn new(reader: Result<DataBlockReader<'a>>, parse: impl Fn(&[u8]) -> Result<T> + 'a) -> Self {
+        let (reader, error) = match reader {
+            Ok(reader) => (Some(reader), None),
+            Err(e) => (None, Some(e)),
+        };
+        Self {
+            reader,
+            parse: Box::new(parse),
+            error,
+        }
+    }
+}
+
+impl<T> Iterator for ParsedLineIter<'_, T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(e) = self.error.take() {
+            return Some(Err(e));
+        }
+
+        match self.reader.as_mut()?.next_line() {
+            Ok(Some(line)) => Some((self.parse)(line)),
+            Ok(None) => {
+                self.reader = None;
+                None
+            }
+            Err(e) => {
+                // The connection is unlikely to be usable after a read error
+                self.reader = None;
+                Some(Err(e.into()))
+            }
+        }
+    }
+}
+
+impl<T> fmt::Debug for ParsedLineIter<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParsedLineIter")
+            .field("reader", &self.reader)
+            .field("error", &self.error)
+            .finish()
+    }
+}
+
+/// Restores the read timeout overridden by [`NntpClient::with_timeout`] when dropped
+///
+/// Dereferences to the [`NntpClient`].
+#[derive(Debug)]
+pub struct TimeoutGuard<'a> {
+    client: &'a mut NntpClient,
+    previous: Option<Duration>,
+}
+
+impl Deref for TimeoutGuard<'_> {
+    type Target = NntpClient;
+
+    fn deref(&self) -> &NntpClient {
+        self.client
+    }
+}
+
+impl DerefMut for TimeoutGuard<'_> {
+    fn deref_mut(&mut self) -> &mut NntpClient {
+        self.client
+    }
+}
+
+impl Drop for TimeoutGuard<'_> {
+    fn drop(&mut self) {
+        if let Err(e) = self.client.conn.set_read_timeout(self.previous) {
+            debug!("Failed to restore the read timeout: {}", e);
+        }
+    }
+}
+
+/// Details about the connection underlying an [`NntpClient`]
+///
+/// Created by [`NntpClient::connection_info`]
+///
+/// Note that the negotiated TLS protocol version and cipher are not exposed by `native-tls`
+/// and are therefore unavailable.
+#[derive(Clone, Debug)]
+pub struct ConnectionInfo {
+    /// The resolved address of the server
+    ///
+    /// This is `None` if the connection has no socket address, e.g. a Unix domain socket, a
+    /// [custom stream](crate::raw::connection::NntpConnection::from_stream), or a host resolved
+    /// by a proxy.
+    pub peer_addr: Option<SocketAddr>,
+    /// The domain used to validate the server's certificate if TLS is active
+    pub tls_domain: Option<String>,
+    /// The greeting returned by the server upon connection
+    pub greeting: RawResponse,
+}
+
+impl ConnectionInfo {
+    /// Returns true if the connection is using TLS
+    pub fn is_tls(&self) -> bool {
+        self.tls_domain.is_some()
+    }
+}
+
+/// How overviews are retrieved and parsed
+///
+/// Created by [`NntpClient::overview_method`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OverviewMethod {
+    /// The command that will be sent, either `"OVER"` or `"XOVER"`
+    pub command: &'static str,
+    /// Whether `XOVER` is used because the server rejected `OVER` as an unknown command
+    pub fallback: bool,
+    /// The format used to name additional fields
+    ///
+    /// If this is `None` the first seven fields are assumed to follow RFC 3977, and additional
+    /// fields are identified by their contents (see [`Overview::extra`]).
+    pub format: Option<OverviewFmt>,
+}
+
+/// The result of [`NntpClient::select_group_status`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GroupSelection {
+    /// The group was already selected so `GROUP` was not sent
+    ///
+    /// See [`ClientConfig::skip_redundant_group_select`].
+    Cached(Group),
+    /// The group was selected by sending `GROUP`
+    Selected(Group),
+}
+
+impl GroupSelection {
+    /// Returns true if the group was returned from the cache
+    pub fn is_cached(&self) -> bool {
+        matches!(self, GroupSelection::Cached(_))
+    }
+
+    /// The selected group
+    pub fn group(&self) -> &Group {
+        match self {
+            GroupSelection::Cached(group) | GroupSelection::Selected(group) => group,
+        }
+    }
+
+    /// Consume the selection, returning the group
+    pub fn into_group(self) -> Group {
+        match self {
+            GroupSelection::Cached(group) | GroupSelection::Selected(group) => group,
+        }
+    }
+}
+
+/// What [`NntpClient::reselect_group`] does if no group is selected
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WhenUnselected {
+    /// Select the [last selected group](NntpClient::last_group_name) again, e.g. after a
+    /// reconnect
+    SelectLast,
+    /// Fail with [`Error::NoGroupSelected`]
+    Fail,
+}
+
+/// Statistics for the [article cache](ClientConfig::article_cache) returned by
+/// [`NntpClient::cache_stats`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Lookups by message ID that were answered from the cache
+    pub hits: u64,
+    /// Lookups by message ID that had to be sent to the server
+    pub misses: u64,
+    /// The number of articles (or headers) in the cache
+    pub len: usize,
+    /// The maximum number of articles (or headers) in the cache
+    pub capacity: usize,
+}
+
+impl Drop for NntpClient {
+    /// Send a best-effort `QUIT` and shut down the socket if the client was not explicitly
+    /// [closed](NntpClient::close) or [aborted](NntpClient::abort)
+    fn drop(&mut self) {
+        if self.closed {
+            return;
+        }
+
+        if self.discontinued.is_none() {
+            trace!("Sending QUIT before dropping the client");
+            if let Err(e) = self.conn.send(&cmd::Quit) {
+                debug!("Failed to send QUIT while dropping the client: {}", e);
+            }
+        }
+        self.shutdown();
+    }
+}
+
+/// Configuration for an [`NntpClient`]
+#[derive(Clone, Debug, Default)]
+pub struct ClientConfig {
+    pub(crate) authinfo: Option<CredentialsProvider>,
+    pub(crate) authinfo_generic: Option<String>,
+    pub(crate) sasl: Option<(SaslMechanism, String, String)>,
+    pub(crate) group: Option<String>,
+    pub(crate) skip_redundant_group_select: bool,
+    reconnect_rate_limit: Option<(u32, Duration)>,
+    pub(crate) auto_reconnect: u32,
+    keepalive_interval: Option<Duration>,
+    enforce_capabilities: bool,
+    pub(crate) lazy_capabilities: bool,
+    pub(crate) unknown_code_policy: UnknownCodePolicy,
+    pub(crate) auth_or_mode_handler: Option<RecoveryHandler>,
+    user_agent: UserAgent,
+    pub(crate) mode_reader: Option<bool>,
+    pub(crate) handshake_order: HandshakeOrder,
+    pub(crate) starttls: Option<TlsConfig>,
+    auto_starttls: bool,
+    pub(crate) compress: bool,
+    article_cache: usize,
+    pub(crate) conn_config: ConnectionConfig,
+}
+
+/// The default `User-Agent` header sent with posts
+const DEFAULT_USER_AGENT: &str = concat!("brokaw/", env!("CARGO_PKG_VERSION"));
+
+#[derive(Clone, Debug)]
+struct UserAgent(Option<String>);
+
+impl Default for UserAgent {
+    fn default() -> Self {
+        Self(Some(DEFAULT_USER_AGENT.to_string()))
+    }
+}
+
+impl ClientConfig {
+    /// Perform an AUTHINFO USER/PASS authentication after connecting to the server
+    ///
+    /// The credentials are only sent if the server advertises `AUTHINFO USER`, otherwise
+    /// [`Error::AuthNotSupported`] or [`Error::AuthRequiresTls`] is returned. Servers that
+    /// refuse to list their capabilities (or to switch modes) with a `502` until the client
+    /// authenticates are sent the credentials regardless.
+    ///
+    /// https://tools.ietf.org/html/rfc4643#section-2.3
+    pub fn authinfo_user_pass(
+        &mut self,
+        username: impl AsRef<str>,
+        password: impl AsRef<str>,
+    ) -> &mut Self {
+        let credentials = (username.as_ref().to_string(), password.as_ref().to_string());
+        self.authinfo_provider(Arc::new(move || Ok(credentials.clone())))
+    }
+
+    /// Obtain the AUTHINFO USER/PASS credentials from `provider` whenever they are needed
+    ///
+    /// This behaves like [`authinfo_user_pass`](Self::authinfo_user_pass), but the provider is
+    /// invoked each time the client authenticates, i.e. on connect and on every
+    /// [reconnect](NntpClient::reconnect), so credentials can be fetched from a secrets manager
+    /// and rotated without rebuilding the configuration. Errors returned by the provider are
+    /// returned from `connect` or `reconnect`.
+    ///
+    /// ```no_run
+    /// use std::sync::Arc;
+    /// use brokaw::ClientConfig;
+    ///
+    /// let client = ClientConfig::default()
+    ///     .authinfo_provider(Arc::new(|| {
+    ///         let password = std::fs::read_to_string("/run/secrets/nntp-password")
+    ///             .map_err(brokaw::raw::error::Error::from)?;
+    ///         Ok(("alice".to_string(), password.trim().to_string()))
+    ///     }))
+    ///     .connect(("news.example.com", 119));
+    /// ```
+    pub fn authinfo_provider(&mut self, provider: Arc<CredentialsFn>) -> &mut Self {
+        self.authinfo = Some(CredentialsProvider(provider));
+        self
+    }
+
+    /// Perform an AUTHINFO SASL authentication after connecting to the server
+    ///
+    /// If both SASL and [USER/PASS](Self::authinfo_user_pass) authentication are configured,
+    /// SASL will be used.
+    ///
+    /// <https://tools.ietf.org/html/rfc4643#section-2.4>
+    pub fn authinfo_sasl(
+        &mut self,
+        mechanism: SaslMechanism,
+        username: impl AsRef<str>,
+        password: impl AsRef<str>,
+    ) -> &mut Self {
+        self.sasl = Some((
+            mechanism,
+            username.as_ref().to_string(),
+            password.as_ref().to_string(),
+        ));
+        self
+    }
+
+    /// Perform a legacy AUTHINFO GENERIC authentication after connecting to the server
+    ///
+    /// `args` holds the program name followed by its arguments and is sent verbatim as
+    /// `AUTHINFO GENERIC <args>`. The client does not interpret the mechanism, a `281` response is
+    /// treated as success and any other response results in an [`Error::Failure`].
+    ///
+    /// As servers don't advertise this method in their capabilities, it is sent without checking
+    /// them first. If [SASL](Self::authinfo_sasl) or [USER/PASS](Self::authinfo_user_pass)
+    /// authentication is configured as well, that is used instead.
+    ///
+    /// <https://tools.ietf.org/html/rfc2980#section-3.1.3>
+    pub fn authinfo_generic(&mut self, args: impl AsRef<str>) -> &mut Self {
+        self.authinfo_generic = Some(args.as_ref().to_string());
+        self
+    }
+
+    /// Join a group upon connection
+    ///
+    /// If this is set to None then no `GROUP` command will be sent when the client is initialized
+    pub fn group(&mut self, name: Option<impl AsRef<str>>) -> &mut Self {
+        self.group = name.map(|s| s.as_ref().to_string());
+        self
+    }
+
+    /// Skip sending `GROUP` when selecting the group that is already selected
+    ///
+    /// This saves a round trip for applications that repeatedly re-select the same group.
+    ///
+    /// **Be warned**: when enabled, [`NntpClient::select_group`] may return stale information
+    /// as the low/high water marks may have advanced since the group was originally selected.
+    /// This is disabled by default.
+    pub fn skip_redundant_group_select(&mut self, skip: bool) -> &mut Self {
+        self.skip_redundant_group_select = skip;
+        self
+    }
+
+    /// Cache up to `capacity` articles retrieved by message ID
+    ///
+    /// [`article`](NntpClient::article) and [`head`](NntpClient::head) check the cache before
+    /// contacting the server when called with a message ID, which saves fetching the same
+    /// articles repeatedly (e.g. while following `References`). Once the cache is full, the
+    /// least recently used entry is evicted. Articles retrieved by number are never cached as
+    /// numbers are relative to a group.
+    ///
+    /// Cached headers are returned by `head` but `article` still fetches the full article.
+    /// See [`NntpClient::cache_stats`] to tune the capacity. Disabled (`0`) by default.
+    pub fn article_cache(&mut self, capacity: usize) -> &mut Self {
+        self.article_cache = capacity;
+        self
+    }
+
+    /// Limit the number of reconnects that may occur within a period of time
+    ///
+    /// For example, `reconnect_rate_limit(Some((5, Duration::from_secs(60))))` allows no more
+    /// than 5 reconnects per minute. Once the limit is exceeded, reconnects will fail with
+    /// [`Error::ReconnectRateLimited`] rather than hammering a server that is down.
+    ///
+    /// Reconnects are not rate limited by default.
+    pub fn reconnect_rate_limit(&mut self, limit: Option<(u32, Duration)>) -> &mut Self {
+        self.reconnect_rate_limit = limit;
+        self
+    }
+
+    /// Transparently reconnect and retry commands when the connection is dropped
+    ///
+    /// If a command fails because the server closed or reset the connection (e.g. after being
+    /// idle), the client [reconnects](NntpClient::reconnect), which re-runs the session setup
+    /// and re-selects the last group, and retries the command up to `max_retries` times.
+    /// Reconnects count towards the [rate limit](Self::reconnect_rate_limit).
+    ///
+    /// A `400` response, sent when the server discontinues the session, is treated the same way
+    /// as a dropped connection (see [`Error::ServiceUnavailable`]).
+    ///
+    /// Commands with side effects (`POST` and `IHAVE`) are never retried to avoid sending
+    /// duplicate articles. Neither are commands that depend on the current article (e.g. `NEXT`,
+    /// `LAST`, or `ARTICLE` without an argument), as a reconnect resets it to the first article
+    /// of the group. Defaults to `0` (no retries).
+    pub fn auto_reconnect(&mut self, max_retries: u32) -> &mut Self {
+        self.auto_reconnect = max_retries;
+        self
+    }
+
+    /// Set how long the connection may be idle before [`NntpClient::keepalive_if_needed`]
+    /// sends a keepalive
+    ///
+    /// Servers frequently drop idle connections after a few minutes. No background thread is
+    /// spawned, the caller is responsible for calling `keepalive_if_needed` periodically.
+    /// Defaults to `None` (no keepalives).
+    pub fn keepalive_interval(&mut self, interval: Option<Duration>) -> &mut Self {
+        self.keepalive_interval = interval;
+        self
+    }
+
+    /// Check that the server advertises a command's
+    /// [required capability](NntpCommand::required_capability) before sending it via
+    /// [`NntpClient::command`]
+    ///
+    /// If it doesn't, [`Error::CapabilityMissing`] is returned without contacting the server.
+    /// Some servers under-report their capabilities, so this defaults to `false`.
+    pub fn enforce_capabilities(&mut self, enforce: bool) -> &mut Self {
+        self.enforce_capabilities = enforce;
+        self
+    }
+
+    /// Retrieve the server's capabilities when they are first needed rather than on connect
+    ///
+    /// This saves a round trip for short sessions. The capabilities are retrieved by
+    /// [`NntpClient::try_capabilities`] and by methods that check them before sending a command
+    /// (e.g. [`overviews`](NntpClient::overviews), [`post`](NntpClient::post), or any command
+    /// if [`enforce_capabilities`](Self::enforce_capabilities) is enabled). Until then,
+    /// [`NntpClient::capabilities`] is empty.
+    ///
+    /// Capabilities that are needed while connecting (e.g. for STARTTLS, authentication, or
+    /// deciding whether to send `MODE READER`) are still retrieved and kept. Defaults to `false`.
+    pub fn lazy_capabilities(&mut self, lazy: bool) -> &mut Self {
+        self.lazy_capabilities = lazy;
+        self
+    }
+
+    /// Set how response codes unknown to Brokaw are handled
+    ///
+    /// Defaults to [`UnknownCodePolicy::Strict`]
+    pub fn unknown_code_policy(&mut self, policy: UnknownCodePolicy) -> &mut Self {
+        self.unknown_code_policy = policy;
+        self
+    }
+
+    /// Set a handler that is invoked when a command fails because the server requires
+    /// authentication, a mode switch, or TLS
+    ///
+    /// When a command returns `480` (authentication required), `401` (wrong mode), or `483`
+    /// (secure connection required), the handler is called with the client so that it can
+    /// remedy the situation (e.g. by sending `AUTHINFO` or `MODE READER`). If the handler succeeds,
+    /// the original command is retried once. Errors returned by the handler are returned to the
+    /// caller of the original command.
+    ///
+    /// The handler is not invoked for commands sent by the handler itself.
+    ///
+    /// ```no_run
+    /// use brokaw::ClientConfig;
+    /// use brokaw::types::command as cmd;
+    ///
+    /// let client = ClientConfig::default()
+    ///     .on_auth_or_mode_required(Some(Box::new(|client| {
+    ///         client.command(cmd::ModeReader)?;
+    ///         Ok(())
+    ///     })))
+    ///     .connect(("news.example.com", 119));
+    /// ```
+    pub fn on_auth_or_mode_required(&mut self, handler: Option<Box<RecoveryFn>>) -> &mut Self {
+        self.auth_or_mode_handler = handler.map(|h| RecoveryHandler(Arc::from(h)));
+        self
+    }
+
+    /// Send `MODE READER` after connecting
+    ///
+    /// Mode switching servers start in transit mode and reject reader commands such as `GROUP`
+    /// and `ARTICLE` until `MODE READER` is sent. By default, `MODE READER` is sent only if the
+    /// server advertises the `MODE-READER` capability but not `READER`. Passing `true` always
+    /// sends it and `false` never does.
+    ///
+    /// By default `MODE READER` is sent before authenticating (see
+    /// [`handshake_order`](Self::handshake_order)) and the capabilities are retrieved again
+    /// afterwards as they usually change.
+    pub fn mode_reader(&mut self, enabled: bool) -> &mut Self {
+        self.mode_reader = Some(enabled);
+        self
+    }
+
+    /// Set whether `MODE READER` is sent before or after authenticating
+    ///
+    /// Defaults to [`HandshakeOrder::ModeReaderFirst`]. Either way, the capabilities are
+    /// retrieved again after each step that changes the state of the session.
+    pub fn handshake_order(&mut self, order: HandshakeOrder) -> &mut Self {
+        self.handshake_order = order;
+        self
+    }
+
+    /// Set the `User-Agent` header added to articles posted via
+    /// [`ArticleBuilder::post_with`](crate::posting::ArticleBuilder::post_with)
+    ///
+    /// The header is not added to articles that already have a `User-Agent` or `X-Newsreader`
+    /// header. Defaults to `brokaw/<version>`; set to `None` to omit the header.
+    pub fn user_agent(&mut self, user_agent: Option<impl AsRef<str>>) -> &mut Self {
+        self.user_agent = UserAgent(user_agent.map(|ua| ua.as_ref().to_string()));
+        self
+    }
+
+    /// The `User-Agent` header that should be added to posted articles
+    pub(crate) fn default_user_agent(&self) -> Option<&str> {
+        self.user_agent.0.as_deref()
+    }
+
+    /// Upgrade the connection to TLS via `STARTTLS` using the default TLS implementation
+    ///
+    /// The client connects in plain text and, before doing anything else, checks that the server
+    /// advertises the `STARTTLS` capability and upgrades the connection (see
+    /// [`NntpConnection::starttls`]). [`Error::Unsupported`] is returned if the capability is
+    /// missing. The `domain` is used to validate the server's certificate. See
+    /// [`auto_starttls`](Self::auto_starttls) to defer the upgrade until the server requires it.
+    ///
+    /// This should not be combined with a [`ConnectionConfig`] that uses TLS from the start.
+    pub fn starttls(&mut self, domain: String) -> Result<&mut Self> {
+        self.starttls = Some(TlsConfig::default_connector(domain)?);
+        Ok(self)
+    }
+
+    /// Only upgrade the connection via [`STARTTLS`](Self::starttls) once the server requires it
+    ///
+    /// When enabled, the client stays in plain text after connecting. If a command is rejected
+    /// with `483` (secure connection required), the connection is upgraded, the capabilities and
+    /// the selected group are refreshed, and the command is retried once. Without a `starttls`
+    /// configuration, or if this is disabled (the default), a `483` is returned as
+    /// [`Error::SecureConnectionRequired`].
+    ///
+    /// This has no effect if credentials are configured. The connection is then always upgraded
+    /// before authenticating, as otherwise the credentials would be sent in plain text and an
+    /// attacker could keep the session in plain text by suppressing the `483`.
+    pub fn auto_starttls(&mut self, enabled: bool) -> &mut Self {
+        self.auto_starttls = enabled;
+        self
+    }
+
+    /// Compress the connection via `COMPRESS DEFLATE` if the server supports it
+    ///
+    /// Compression is enabled after authenticating, and only if the server advertises
+    /// `COMPRESS DEFLATE` (see [`NntpConnection::compress_deflate`]). Otherwise the connection is
+    /// left uncompressed.
+    pub fn compress(&mut self, enabled: bool) -> &mut Self {
+        self.compress = enabled;
+        self
+    }
+
+    /// Set the read timeout on the socket
+    ///
+    /// See [`ConnectionConfig::read_timeout`]
+    pub fn read_timeout(&mut self, duration: Option<Duration>) -> &mut Self {
+        self.conn_config.read_timeout(duration);
+        self
+    }
+
+    /// Set the write timeout on the socket
+    ///
+    /// A write that times out (e.g. while posting a large article to a stalled server) returns a
+    /// [`Timeout`](crate::raw::error::Error::Timeout) error rather than hanging forever.
+    /// See [`ConnectionConfig::write_timeout`].
+    pub fn write_timeout(&mut self, duration: Option<Duration>) -> &mut Self {
+        self.conn_config.write_timeout(duration);
+        self
+    }
+
+    /// Set the timeout for establishing the TCP connection
+    ///
+    /// See [`ConnectionConfig::connect_timeout`]
+    pub fn connect_timeout(&mut self, duration: Option<Duration>) -> &mut Self {
+        self.conn_config.connect_timeout(duration);
+        self
+    }
+
+    /// Choose which of the server's resolved addresses are tried, and in which order
+    ///
+    /// See [`ConnectionConfig::address_family`]
+    pub fn address_family(&mut self, pref: AddressFamily) -> &mut Self {
+        self.conn_config.address_family(pref);
+        self
+    }
+
+    /// Set the size of the buffer used to read responses from the socket
+    ///
+    /// See [`ConnectionConfig::read_buf_size`]
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes` is zero.
+    pub fn read_buffer_size(&mut self, bytes: usize) -> &mut Self {
+        self.conn_config.read_buf_size(bytes);
+        self
+    }
+
+    /// Limit the size of multi-line responses
+    ///
+    /// Once the data blocks of a response exceed `limit` bytes, reading stops and a
+    /// [`ResponseTooLarge`](crate::raw::error::Error::ResponseTooLarge) error is returned.
+    /// The rest of the response is left unread, so the client must be
+    /// [reconnected](NntpClient::reconnect) before it can be used again.
+    /// Responses are unbounded by default. See [`ConnectionConfig::max_response_bytes`].
+    pub fn max_response_bytes(&mut self, limit: Option<usize>) -> &mut Self {
+        self.conn_config.max_response_bytes(limit);
+        self
+    }
+
+    /// Limit the length of a single response line
+    ///
+    /// Once a line exceeds `limit` bytes, reading stops and a
+    /// [`LineTooLong`](crate::raw::error::Error::LineTooLong) error is returned. As with
+    /// [`max_response_bytes`](Self::max_response_bytes), the client must then be
+    /// [reconnected](NntpClient::reconnect). See [`ConnectionConfig::max_line_bytes`].
+    pub fn max_line_bytes(&mut self, limit: Option<usize>) -> &mut Self {
+        self.conn_config.max_line_bytes(limit);
+        self
+    }
+
+    /// Accept a lone LF as a line terminator in responses from misconfigured servers
+    ///
+    /// When enabled, lines in [`DataBlocks::lines`](crate::raw::DataBlocks::lines) may end with
+    /// a lone LF rather than a CRLF. Disabled by default.
+    /// See [`ConnectionConfig::lenient_line_endings`]
+    pub fn lenient_line_endings(&mut self, lenient: bool) -> &mut Self {
+        self.conn_config.lenient_line_endings(lenient);
+        self
+    }
+
+    /// Invoke `hook` with the raw bytes of every command sent and response received
+    ///
+    /// See [`ConnectionConfig::trace_hook`]
+    pub fn trace_hook(&mut self, hook: Arc<TraceFn>) -> &mut Self {
+        self.conn_config.trace_hook(hook);
+        self
+    }
+
+    /// Limit the number of commands pipelined before reading their responses
+    ///
+    /// See [`ConnectionConfig::max_pipeline_depth`] for why this shouldn't be set too high.
+    pub fn max_pipeline_depth(&mut self, depth: usize) -> &mut Self {
+        self.conn_config.max_pipeline_depth(depth);
+        self
+    }
+
+    /// Redact the password sent via `AUTHINFO PASS` from the [trace](Self::trace_hook)
+    ///
+    /// See [`ConnectionConfig::redact_passwords`]
+    pub fn redact_passwords(&mut self, redact: bool) -> &mut Self {
+        self.conn_config.redact_passwords(redact);
+        self
+    }
+
+    /// Connect to the server through a SOCKS5 proxy
+    ///
+    /// See [`ConnectionConfig::proxy`]
+    pub fn proxy(&mut self, proxy: ProxyConfig) -> &mut Self {
+        self.conn_config.proxy(Some(proxy));
+        self
+    }
+
+    /// Set the configuration of the underlying [`NntpConnection`]
+    pub fn connection_config(&mut self, config: ConnectionConfig) -> &mut Self {
+        self.conn_config = config;
+        self
+    }
+
+    /// Connect to exactly `addr` without resolving a hostname
+    ///
+    /// This is intended for callers that resolve addresses themselves (e.g. with a custom
+    /// resolver or to race IPv4 and IPv6). The domain used to validate the server's certificate
+    /// is still taken from the [`TlsConfig`], so this can connect to an IP address while
+    /// validating the certificate of a hostname. [`connect`](Self::connect) is a convenience that
+    /// also accepts hostnames.
+    pub fn connect_to(&self, addr: SocketAddr) -> Result<NntpClient> {
+        self.connect(addr)
+    }
+
+    /// Resolves the configuration into a client
+    ///
+    /// Hostnames are resolved via [`ToSocketAddrs`], which blocks. See
+    /// [`connect_to`](Self::connect_to) to connect to an address that was resolved elsewhere.
+    pub fn connect(&self, addr: impl ToSocketAddrs) -> Result<NntpClient> {
+        let (conn, conn_response) = NntpConnection::connect(addr, self.conn_config.clone())?;
+        self.start_session(conn, conn_response)
+    }
+
+    /// Resolves the configuration into a client, leaving the resolution of the host to the
+    /// [proxy](Self::proxy) if there is one
+    ///
+    /// See [`NntpConnection::connect_host`].
+    pub fn connect_host(&self, addr: &ServerAddr) -> Result<NntpClient> {
+        let (conn, conn_response) = NntpConnection::connect_host(addr, self.conn_config.clone())?;
+        self.start_session(conn, conn_response)
+    }
+
+    /// Connect to a server listening on a Unix domain socket, e.g. one fronted by a local helper
+    ///
+    /// The session is set up exactly as with [`connect`](Self::connect). TLS doesn't apply to a
+    /// local socket, so a [`TlsConfig`] in the connection config and a
+    /// [`starttls`](Self::starttls) domain are ignored with a warning.
+    ///
+    /// See [`NntpConnection::connect_unix`] for the details of the connection. As with other
+    /// streams that have no [peer address](NntpConnection::peer_addr),
+    /// [`NntpClient::reconnect`] is unsupported.
+    #[cfg(unix)]
+    pub fn connect_unix(&self, path: impl AsRef<Path>) -> Result<NntpClient> {
+        let mut config = self.clone();
+        if config.starttls.take().is_some() {
+            warn!("Ignoring STARTTLS, TLS is not used over Unix domain sockets");
+        }
+        let (conn, conn_response) = NntpConnection::connect_unix(path, config.conn_config.clone())?;
+        config.start_session(conn, conn_response)
+    }
+
+    /// Set up the session on a new connection and wrap it in a client
+    fn start_session(
+        &self,
+        mut conn: NntpConnection,
+        conn_response: RawResponse,
+    ) -> Result<NntpClient> {
+        debug!(
+            "Connected. Server returned `{}`",
+            conn_response.first_line_to_utf8_lossy()
+        );
+
+        let (capabilities, group) = self.init_session(&mut conn)?;
+
+        Ok(NntpClient {
+            conn,
+            greeting_software: greeting_software(&conn_response),
+            greeting: conn_response,
+            config: self.clone(),
+            capabilities_fetched: capabilities.is_some(),
+            capabilities: capabilities.unwrap_or_default(),
+            current_article: group.as_ref().and_then(first_article),
+            last_group_name: group.as_ref().map(|g| g.name.clone()),
+            posting_statuses: HashMap::new(),
+            group,
+            overview_fmt: None,
+            legacy_overview: false,
+            legacy_header: false,
+            reconnects: Default::default(),
+            in_recovery: false,
+            closed: false,
+            discontinued: None,
+            cache: ArticleCache::new(self.article_cache),
+        })
+    }
+
+    /// Authenticate, retrieve capabilities, and select the initial group on a new connection
+    fn init_session(
+        &self,
+        conn: &mut NntpConnection,
+    ) -> Result<(Option<Capabilities>, Option<Group>)> {
+        // Capabilities that are still valid, they change after switching modes or authenticating
+        let mut capabilities = None;
+
+        // n.b. credentials are never sent in the clear, so the upgrade isn't deferred if there
+        // are any, as a MITM could simply suppress the 483
+        let eager = !self.auto_starttls || self.has_auth();
+        if let Some(tls_config) = self.starttls.as_ref().filter(|_| eager) {
+            debug!("Retrieving capabilities...");
+            if get_capabilities(conn)?.get("STARTTLS").is_none() {
+                return Err(Error::Unsupported {
+                    command: "STARTTLS".to_string(),
+                    resp: None,
+                });
+            }
+            debug!("Upgrading the connection with STARTTLS");
+            conn.starttls(tls_config.clone())?;
+        }
+
+        match self.handshake_order {
+            HandshakeOrder::ModeReaderFirst => match self.init_mode_reader(conn, &mut capabilities)
+            {
+                Err(e) if is_access_denied(&e) && self.has_auth() => {
+                    debug!(
+                        "Access denied before authenticating, authenticating first -- {}",
+                        e
+                    );
+                    // n.b. the capabilities were just denied, so they aren't requested again
+                    self.send_credentials(conn, &mut capabilities)?;
+                    self.init_mode_reader(conn, &mut capabilities)?;
+                }
+                res => {
+                    res?;
+                    self.init_auth(conn, &mut capabilities)?;
+                }
+            },
+            HandshakeOrder::AuthFirst => {
+                self.init_auth(conn, &mut capabilities)?;
+                self.init_mode_reader(conn, &mut capabilities)?;
+            }
+        }
+
+        if self.compress {
+            let current = match capabilities.take() {
+                Some(capabilities) => capabilities,
+                None => {
+                    debug!("Retrieving capabilities...");
+                    get_capabilities(conn)?
+                }
+            };
+
+            let deflate = current
+                .get("COMPRESS")
+                .and_then(|cap| cap.args.as_ref())
+                .is_some_and(|args| args.contains("DEFLATE"));
+            if deflate {
+                debug!("Enabling COMPRESS DEFLATE");
+                conn.compress_deflate()?;
+            } else {
+                debug!("Server does not support COMPRESS DEFLATE, continuing uncompressed");
+                capabilities = Some(current);
+            }
+        }
+
+        let capabilities = match capabilities {
+            Some(capabilities) => Some(capabilities),
+            None if self.lazy_capabilities => None,
+            None => {
+                debug!("Retrieving capabilities...");
+                Some(get_capabilities(conn)?)
+            }
+        };
+
+        let group = if let Some(name) = &self.group {
+            debug!("Connecting to group {}...", name);
+            select_group(conn, name)?.into()
+        } else {
+            debug!("No initial group specified");
+            None
+        };
+
+        Ok((capabilities, group))
+    }
+
+    /// Returns true if credentials are configured
+    pub(crate) fn has_auth(&self) -> bool {
+        self.sasl.is_some() || self.authinfo.is_some() || self.authinfo_generic.is_some()
+    }
+
+    /// Send `MODE READER` if configured or required by the server
+    ///
+    /// `capabilities` holds the capabilities if they are still valid and is cleared if the mode
+    /// is switched.
+    fn init_mode_reader(
+        &self,
+        conn: &mut NntpConnection,
+        capabilities: &mut Option<Capabilities>,
+    ) -> Result<()> {
+        if self.mode_reader == Some(false) {
+            return Ok(());
+        }
+
+        let current = match (self.mode_reader, capabilities.take()) {
+            (Some(true), _) => None,
+            (_, Some(capabilities)) => Some(capabilities),
+            (_, None) => {
+                debug!("Retrieving capabilities...");
+                Some(get_capabilities(conn)?)
+            }
+        };
+
+        if current.as_ref().is_none_or(needs_mode_reader) {
+            debug!("Switching to reader mode");
+            mode_reader(conn)?;
+        } else {
+            *capabilities = current;
+        }
+        Ok(())
+    }
+
+    /// Authenticate if credentials are configured
+    ///
+    /// `capabilities` holds the capabilities if they are still valid and is cleared once
+    /// authenticated.
+    fn init_auth(
+        &self,
+        conn: &mut NntpConnection,
+        capabilities: &mut Option<Capabilities>,
+    ) -> Result<()> {
+        if self.sasl.is_some() || self.authinfo.is_some() {
+            let current = match capabilities.take() {
+                Some(capabilities) => Ok(capabilities),
+                None => {
+                    debug!("Retrieving capabilities...");
+                    get_capabilities(conn)
+                }
+            };
+            let method = match &self.sasl {
+                Some((mechanism, _, _)) => AuthMethod::Sasl(*mechanism),
+                None => AuthMethod::UserPass,
+            };
+            match current {
+                Ok(current) => {
+                    check_auth_supported(&current, method, conn.is_tls())?;
+                    *capabilities = Some(current);
+                }
+                // The server won't say what it supports until we authenticate, so try anyway
+                Err(Error::AccessDenied { .. }) => {
+                    debug!("Capabilities are unavailable before authenticating")
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        self.send_credentials(conn, capabilities)
+    }
+
+    /// Authenticate with the configured credentials without checking the capabilities first
+    fn send_credentials(
+        &self,
+        conn: &mut NntpConnection,
+        capabilities: &mut Option<Capabilities>,
+    ) -> Result<()> {
+        if let Some((mechanism, username, password)) = &self.sasl {
+            if *mechanism == SaslMechanism::Plain && !conn.is_tls() {
+                warn!("TLS is not enabled, credentials will be sent in the clear!");
+            }
+            debug!("Authenticating with AUTHINFO SASL {}", mechanism);
+            authenticate_sasl(conn, *mechanism, username, password)?;
+            *capabilities = None;
+        } else if let Some(provider) = &self.authinfo {
+            let (username, password) = provider.credentials()?;
+            if !conn.is_tls() {
+                warn!("TLS is not enabled, credentials will be sent in the clear!");
+            }
+            debug!("Authenticating with AUTHINFO USER/PASS");
+            authenticate(conn, username, password)?;
+            *capabilities = None;
+        } else if let Some(args) = &self.authinfo_generic {
+            debug!("Authenticating with AUTHINFO GENERIC");
+            authenticate_generic(conn, args)?;
+            *capabilities = None;
+        }
+
+        Ok(())
+    }
+}
+
+impl RawResponse {}
+
+/// A function invoked by the client to recover from `480`, `401`, and `483` responses
+///
+/// See [`ClientConfig::on_auth_or_mode_required`]
+pub type RecoveryFn = dyn Fn(&mut NntpClient) -> Result<()> + Send + Sync;
+
+#[derive(Clone)]
+pub(crate) struct RecoveryHandler(Arc<RecoveryFn>);
+
+impl fmt::Debug for RecoveryHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecoveryHandler").finish()
+    }
+}
+
+/// A function returning the username and password sent via AUTHINFO USER/PASS
+///
+/// See [`ClientConfig::authinfo_provider`]
+pub type CredentialsFn = dyn Fn() -> Result<(String, String)> + Send + Sync;
+
+#[derive(Clone)]
+pub(crate) struct CredentialsProvider(Arc<CredentialsFn>);
+
+impl CredentialsProvider {
+    pub(crate) fn credentials(&self) -> Result<(String, String)> {
+        (self.0)()
+    }
+}
+
+impl fmt::Debug for CredentialsProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CredentialsProvider").finish()
+    }
+}
+
+/// Returns true if the response code indicates that the command may succeed once the client
+/// authenticates, switches modes, or enables TLS
+fn needs_recovery(code: ResponseCode) -> bool {
+    matches!(
+        code,
+        ResponseCode::Known(Kind::AuthenticationRequired)
+            | ResponseCode::Known(Kind::WrongMode)
+            | ResponseCode::Known(Kind::SecureConnectionRequired)
+    )
+}
+
+/// Returns true if the error was caused by the server closing or resetting the connection
+fn is_connection_dropped(e: &crate::raw::error::Error) -> bool {
+    use std::io::ErrorKind;
+
+    match e {
+        crate::raw::error::Error::Io(e) => matches!(
+            e.kind(),
+            ErrorKind::ConnectionReset
+                | ErrorKind::ConnectionAborted
+                | ErrorKind::BrokenPipe
+                | ErrorKind::UnexpectedEof
+        ),
+        crate::raw::error::Error::ConnectionClosed { .. } => true,
+        _ => false,
+    }
+}
+
+/// Returns false for commands that must not be sent twice as they have side effects, and for
+/// commands that refer to the current article, which doesn't survive a reconnect
+fn is_retryable(command: &impl NntpCommand) -> bool {
+    let encoded = command.encode();
+    let mut words = encoded.split(|b| *b == b' ').filter(|w| !w.is_empty());
+    let name = words.next().unwrap_or_default().to_ascii_uppercase();
+    let args = words.count();
+    match name.as_slice() {
+        b"POST" | b"IHAVE" | b"NEXT" | b"LAST" => false,
+        b"ARTICLE" | b"HEAD" | b"BODY" | b"STAT" | b"OVER" | b"XOVER" => args > 0,
+        // n.b. the first argument is the header field
+        b"HDR" | b"XHDR" => args > 1,
+        _ => true,
+    }
+}
+
+/// The order in which `MODE READER` and `AUTHINFO` are sent while connecting
+///
+/// `STARTTLS` is always negotiated first and `COMPRESS` is always enabled last. Set via
+/// [`ClientConfig::handshake_order`].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum HandshakeOrder {
+    /// Switch to reader mode, then authenticate (the default)
+    ///
+    /// Per [RFC 4643](https://tools.ietf.org/html/rfc4643#section-2.2) clients should switch
+    /// modes before authenticating, as a mode switch may discard the authentication state and
+    /// servers often only advertise `AUTHINFO` in reader mode.
+    #[default]
+    ModeReaderFirst,
+    /// Authenticate, then switch to reader mode
+    ///
+    /// For servers that reject `MODE READER` (e.g. with `480`) until the client has
+    /// authenticated.
+    AuthFirst,
+}
+
+/// How the client handles [`Unknown`](ResponseCode::Unknown) response codes
+///
+/// Some servers return nonstandard codes for successful commands. Set via
+/// [`ClientConfig::unknown_code_policy`].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum UnknownCodePolicy {
+    /// Unknown response codes are always treated as errors
+    #[default]
+    Strict,
+    /// Unknown response codes in the `2xx` range are treated as success
+    Lenient,
+}
+
+impl UnknownCodePolicy {
+    /// Returns true if the policy treats the response code as a success
+    fn accepts(&self, code: ResponseCode) -> bool {
+        match (self, code) {
+            (UnknownCodePolicy::Lenient, ResponseCode::Unknown(code)) => (200..300).contains(&code),
+            _ => false,
+        }
+    }
+
+    /// Returns true if the response should be read as a multi-line response when expecting `kind`
+    pub(crate) fn is_multiline(&self, code: ResponseCode, kind: Kind) -> bool {
+        code.is_multiline() || (kind.is_multiline() && self.accepts(code))
+    }
+
+    /// Return the response if it is of the expected kind or is accepted by the policy
+    ///
+    /// Accepted responses have their code replaced by the expected kind so that they can be
+    /// parsed into typed responses.
+    pub(crate) fn check(&self, mut resp: RawResponse, kind: Kind) -> Result<RawResponse> {
+        if resp.code() == ResponseCode::Known(kind) {
+            Ok(resp)
+        } else if self.accepts(resp.code()) {
+            debug!("Treating unknown response code {} as {}", resp.code(), kind);
+            resp.code = ResponseCode::Known(kind);
+            Ok(resp)
+        } else {
+            Err(Error::unexpected_code(kind, resp))
+        }
+    }
+}
+
+/// The times of recent reconnects
+#[derive(Clone, Debug, Default)]
+struct ReconnectHistory(VecDeque<Instant>);
+
+impl ReconnectHistory {
+    /// Record a reconnect attempt unless it would exceed the provided rate limit
+    fn record(&mut self, limit: Option<(u32, Duration)>, now: Instant) -> Result<()> {
+        let (max, per) = match limit {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+
+        while let Some(oldest) = self.0.front() {
+            if now.duration_since(*oldest) >= per {
+                self.0.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        if self.0.len() >= max as usize {
+            warn!("Exceeded {} reconnects in {:?}", max, per);
+            return Err(Error::ReconnectRateLimited { max, per });
+        }
+
+        self.0.push_back(now);
+        Ok(())
+    }
+}
+
+/// An entry in the [`ArticleCache`]
+#[derive(Clone, Debug)]
+enum CachedArticle {
+    Article(BinaryArticle),
+    Head(Head),
+}
+
+/// A least recently used cache of articles retrieved by message ID
+///
+/// Eviction scans every entry, which is fine for the modest capacities this is intended for.
+#[derive(Clone, Debug, Default)]
+struct ArticleCache {
+    capacity: usize,
+    /// Entries along with the tick at which they were last used
+    entries: HashMap<String, (u64, CachedArticle)>,
+    tick: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl ArticleCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            ..Default::default()
+        }
+    }
+
+    fn article(&mut self, message_id: &str) -> Option<BinaryArticle> {
+        self.get(message_id, |cached| match cached {
+            CachedArticle::Article(article) => Some(article.clone()),
+            CachedArticle::Head(_) => None,
+        })
+    }
+
+    fn head(&mut self, message_id: &str) -> Option<Head> {
+        self.get(message_id, |cached| match cached {
+            CachedArticle::Article(article) => Some(Head {
+                number: article.number,
+                message_id: article.message_id.clone(),
+                headers: article.headers.clone(),
+            }),
+            CachedArticle::Head(head) => Some(head.clone()),
+        })
+    }
+
+    /// Look up an entry, marking it as recently used if `f` accepts it
+    fn get<T>(
+        &mut self,
+        message_id: &str,
+        f: impl FnOnce(&CachedArticle) -> Option<T>,
+    ) -> Option<T> {
+        if self.capacity == 0 {
+            return None;
+        }
+
+        self.tick += 1;
+        let tick = self.tick;
+        let found = self.entries.get_mut(message_id).and_then(|(used, cached)| {
+            let found = f(cached)?;
+            *used = tick;
+            Some(found)
+        });
+
+        match found {
+            Some(_) => self.hits += 1,
+            None => self.misses += 1,
+        }
+        found
+    }
+
+    fn insert(&mut self, message_id: String, cached: CachedArticle) {
+        if self.capacity == 0 {
+            return;
+        }
+        // Don't replace a full article with just its headers
+        if let (Some((_, CachedArticle::Article(_))), CachedArticle::Head(_)) =
+            (self.entries.get(&message_id), &cached)
+        {
+            return;
+        }
+
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&message_id) {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (used, _))| *used)
+                .map(|(id, _)| id.clone());
+            if let Some(oldest) = oldest {
+                trace!("Evicting {} from the article cache", oldest);
+                self.entries.remove(&oldest);
+            }
+        }
+
+        self.tick += 1;
+        self.entries.insert(message_id, (self.tick, cached));
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear()
+    }
+
+    fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            len: self.entries.len(),
+            capacity: self.capacity,
+        }
+    }
+}
+
+/// Perform an AUTHINFO USER/PASS exchange
+fn authenticate(
+    conn: &mut NntpConnection,
+    username: impl AsRef<str>,
+    password: impl AsRef<str>,
+) -> Result<()> {
+    debug!("Sending AUTHINFO USER");
+    let user_resp = conn.send_then_read(&cmd::AuthInfo::User(username.as_ref().to_string()))?;
+    authinfo_user_response(user_resp)?;
+
+    debug!("Sending AUTHINFO PASS");
+    let pass_resp = conn.command(&cmd::AuthInfo::Pass(password.as_ref().to_string()))?;
+    authinfo_pass_response(pass_resp)?;
+    debug!("Successfully authenticated");
+
+    Ok(())
+}
+
+/// Perform an AUTHINFO GENERIC exchange
+fn authenticate_generic(conn: &mut NntpConnection, args: impl AsRef<str>) -> Result<()> {
+    let resp = conn.command(&cmd::AuthInfo::Generic(args.as_ref().to_string()))?;
+    if resp.code() != ResponseCode::Known(Kind::AuthenticationAccepted) {
+        return Err(Error::Failure {
+            code: resp.code,
+            resp,
+            msg: Some("AUTHINFO GENERIC failed".to_string()),
+        });
+    }
+    debug!("Successfully authenticated");
+
+    Ok(())
+}
+
+/// An `AUTHINFO` variant used to authenticate
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum AuthMethod {
+    UserPass,
+    Sasl(SaslMechanism),
+}
+
+/// Check that the server advertises the authentication method before sending any credentials
+///
+/// Per [RFC 4643](https://tools.ietf.org/html/rfc4643#section-2.1) the `AUTHINFO` capability
+/// lists the available methods, and servers may only offer them once TLS is active. In that case
+/// [`Error::AuthRequiresTls`] is returned so the password is never sent in the clear.
+pub(crate) fn check_auth_supported(
+    capabilities: &Capabilities,
+    method: AuthMethod,
+    is_tls: bool,
+) -> Result<()> {
+    let has_arg = |name: &str, arg: &str| {
+        capabilities
+            .get(name)
+            .and_then(|cap| cap.args.as_ref())
+            .is_some_and(|args| args.contains(arg))
+    };
+
+    let (supported, name) = match method {
+        AuthMethod::UserPass => (has_arg("AUTHINFO", "USER"), "AUTHINFO USER".to_string()),
+        AuthMethod::Sasl(mechanism) => (
+            has_arg("AUTHINFO", "SASL")
+                && capabilities
+                    .sasl_mechanisms()
+                    .iter()
+                    .any(|m| m == mechanism.name()),
+            format!("AUTHINFO SASL {}", mechanism),
+        ),
+    };
+
+    if supported {
+        Ok(())
+    } else if !is_tls && capabilities.get("STARTTLS").is_some() {
+        Err(Error::AuthRequiresTls { method: name })
+    } else {
+        Err(Error::AuthNotSupported { method: name })
+    }
+}
+
+/// Check that the server asked for a password in response to `AUTHINFO USER`
+pub(crate) fn authinfo_user_response(exchange: Exchange) -> Result<()> {
+    match exchange {
+        Exchange::Continue(_) => Ok(()),
+        Exchange::Complete(resp) => Err(Error::Failure {
+            code: resp.code,
+            resp,
+            msg: Some("AUTHINFO USER failed".to_string()),
+        }),
+    }
+}
+
+/// Check that the server accepted the password sent via `AUTHINFO PASS`
+pub(crate) fn authinfo_pass_response(resp: RawResponse) -> Result<()> {
+    if resp.code() != ResponseCode::Known(Kind::AuthenticationAccepted) {
+        return Err(Error::Failure {
+            code: resp.code,
+            resp,
+            msg: Some("AUTHINFO PASS failed".to_string()),
+        });
+    }
+    Ok(())
+}
+
+/// Perform an AUTHINFO SASL exchange
+fn authenticate_sasl(
+    conn: &mut NntpConnection,
+    mechanism: SaslMechanism,
+    username: impl AsRef<str>,
+    password: impl AsRef<str>,
+) -> Result<()> {
+    let sasl_failure = |resp: RawResponse| Error::Failure {
+        code: resp.code,
+        resp,
+        msg: Some(format!("AUTHINFO SASL {} failed", mechanism)),
+    };
+
+    match mechanism {
+        SaslMechanism::ScramSha256 => {
+            let mut scram = ScramSha256::new(username, password);
+
+            debug!("Sending AUTHINFO SASL {} client-first message", mechanism);
+            let resp = match conn.send_then_read(&cmd::AuthInfo::Sasl {
+                mechanism: mechanism.name().to_string(),
+                initial_response: Some(base64::encode(scram.client_first())),
+            })? {
+                Exchange::Continue(resp) => resp,
+                Exchange::Complete(resp) => return Err(sasl_failure(resp)),
+            };
+
+            debug!("Sending SASL client-final message");
+            let client_final = scram.client_final(&sasl_data(&resp)?)?;
+            match conn.send_then_read(&cmd::SaslResponse(base64::encode(client_final)))? {
+                Exchange::Complete(resp)
+                    if resp.code() == ResponseCode::Known(Kind::AuthenticationAcceptedWithData) =>
+                {
+                    scram.verify_server_final(&sasl_data(&resp)?)?;
+                }
+                // Some servers send the server-final message as a challenge and expect
+                // an empty response
+                Exchange::Continue(resp) => {
+                    scram.verify_server_final(&sasl_data(&resp)?)?;
+                    let resp = conn.command(&cmd::SaslResponse("=".to_string()))?;
+                    if resp.code() != ResponseCode::Known(Kind::AuthenticationAccepted) {
+                        return Err(sasl_failure(resp));
+                    }
+                }
+                exchange => return Err(sasl_failure(exchange.into_response())),
+            }
+        }
+        SaslMechanism::Plain => {
+            debug!("Sending AUTHINFO SASL {} initial response", mechanism);
+            let initial_response = plain_initial_response(username, password);
+            let resp = conn.command(&cmd::AuthInfo::Sasl {
+                mechanism: mechanism.name().to_string(),
+                initial_response: Some(base64::encode(initial_response)),
+            })?;
+            if resp.code() != ResponseCode::Known(Kind::AuthenticationAccepted) {
+                return Err(sasl_failure(resp));
+            }
+        }
+        SaslMechanism::CramMd5 => {
+            debug!("Sending AUTHINFO SASL {}", mechanism);
+            let resp = match conn.send_then_read(&cmd::AuthInfo::Sasl {
+                mechanism: mechanism.name().to_string(),
+                initial_response: None,
+            })? {
+                Exchange::Continue(resp) => resp,
+                Exchange::Complete(resp) => return Err(sasl_failure(resp)),
+            };
+
+            debug!("Sending CRAM-MD5 response");
+            let response = cram_md5_response(username, password, sasl_data(&resp)?);
+            let resp = conn.command(&cmd::SaslResponse(base64::encode(response)))?;
+            if resp.code() != ResponseCode::Known(Kind::AuthenticationAccepted) {
+                return Err(sasl_failure(resp));
+            }
+        }
+    }
+    debug!("Successfully authenticated");
+
+    Ok(())
+}
+
+/// Decode the base64 data returned with a SASL response
+fn sasl_data(resp: &RawResponse) -> Result<String> {
+    let data = String::from_utf8_lossy(resp.first_line_without_code());
+    let decoded = base64::decode(data.trim()).map_err(|_| Error::sasl("Invalid base64 data"))?;
+
+    String::from_utf8(decoded).map_err(|_| Error::sasl("SASL data is not UTF-8"))
+}
+
+/// A single command line sent via [`NntpClient::raw_command`]
+pub(crate) struct RawLine<'a>(&'a str);
+
+impl<'a> RawLine<'a> {
+    /// Validate that the line does not contain a line terminator
+    pub(crate) fn new(line: &'a str) -> Result<Self> {
+        if line.contains(['\r', '\n']) {
+            return Err(Error::InvalidCommand(format!(
+                "{:?} contains a line terminator",
+                line
+            )));
+        }
+        Ok(Self(line))
+    }
+}
+
+impl fmt::Display for RawLine<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl NntpCommand for RawLine<'_> {}
+
+/// Parse the response to `NEXT` or `LAST`
+pub(crate) fn navigate_response(resp: RawResponse) -> Result<Stat> {
+    match resp.code() {
+        ResponseCode::Known(Kind::ArticleExists) => Stat::try_from(&resp),
+        ResponseCode::Known(Kind::NoNextArticle) => Err(Error::NoNextArticle { resp }),
+        ResponseCode::Known(Kind::NoPreviousArticle) => Err(Error::NoPreviousArticle { resp }),
+        _ => Err(Error::unexpected_code(Kind::ArticleExists, resp)),
+    }
+}
+
+/// Extract the server software from a greeting such as
+/// `200 news.example.com InterNetNews NNRP server INN 2.6.3 ready`
+pub(crate) fn greeting_software(greeting: &RawResponse) -> Option<String> {
+    let text = String::from_utf8_lossy(greeting.first_line_without_code());
+    let mut tokens = text.trim().splitn(2, char::is_whitespace);
+
+    let hostname = tokens.next()?;
+    let looks_like_hostname = hostname.contains('.')
+        && hostname
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-');
+    if !looks_like_hostname {
+        return None;
+    }
+
+    let software = tokens.next()?.trim();
+    (!software.is_empty()).then(|| software.to_string())
+}
+
+/// Parse the response to `STAT`
+pub(crate) fn stat_response(id: ArticleId, resp: RawResponse) -> Result<Stat> {
+    match resp.code() {
+        ResponseCode::Known(Kind::ArticleExists) => resp.borrow().try_into(),
+        code @ ResponseCode::Known(Kind::NoArticleWithMessageId)
+        | code @ ResponseCode::Known(Kind::InvalidCurrentArticleNumber)
+        | code @ ResponseCode::Known(Kind::NoArticleWithNumber) => {
+            Err(Error::NoSuchArticle { id, code, resp })
+        }
+        _ => Err(Error::unexpected_code(Kind::ArticleExists, resp)),
+    }
+}
+
+/// Validate an optional wildmat, returning it in the form used by commands
+pub(crate) fn validate_wildmat(wildmat: Option<&str>) -> Result<Option<String>> {
+    wildmat
+        .map(Wildmat::from)
+        .map(|wildmat| wildmat.validate().map(|_| wildmat.into_string()))
+        .transpose()
+}
+
+/// The article that becomes current when a group is selected
+pub(crate) fn first_article(group: &Group) -> Option<ArticleNumber> {
+    group.article_range().map(|range| *range.start())
+}
+
+/// Remember the posting statuses of listed groups, updating the selected group if included
+pub(crate) fn record_posting_statuses(
+    statuses: &mut HashMap<String, GroupStatus>,
+    mut selected: Option<&mut Group>,
+    groups: &[ActiveGroup],
+) {
+    for active in groups {
+        if let Some(group) = selected.as_mut().filter(|g| g.name == active.name) {
+            group.posting_status = Some(active.status.clone());
+        }
+        statuses.insert(active.name.clone(), active.status.clone());
+    }
+}
+
+/// Returns true if the server must be switched to reader mode
+pub(crate) fn needs_mode_reader(capabilities: &Capabilities) -> bool {
+    capabilities.get("MODE-READER").is_some() && capabilities.get("READER").is_none()
+}
+
+fn mode_reader(conn: &mut NntpConnection) -> Result<()> {
+    let resp = conn.command(&cmd::ModeReader)?;
+    mode_reader_response(resp)
+}
+
+/// Parse the response to `MODE READER`
+pub(crate) fn mode_reader_response(resp: RawResponse) -> Result<()> {
+    let msg = match resp.code() {
+        ResponseCode::Known(Kind::PostingAllowed)
+        | ResponseCode::Known(Kind::PostingProhibited) => return Ok(()),
+        ResponseCode::Known(Kind::AuthenticationRequired) => "MODE READER requires authentication",
+        ResponseCode::Known(Kind::PermanentlyUnavailable) => "Reader mode is not available",
+        _ => "MODE READER failed",
+    };
+
+    Err(Error::Failure {
+        code: resp.code(),
+        resp,
+        msg: Some(msg.to_string()),
+    })
+}
+
+fn get_capabilities(conn: &mut NntpConnection) -> Result<Capabilities> {
+    let resp = conn.command(&cmd::Capabilities)?;
+    capabilities_response(resp)
+}
+
+/// Parse the response to `CAPABILITIES`
+pub(crate) fn capabilities_response(resp: RawResponse) -> Result<Capabilities> {
+    match resp.code() {
+        ResponseCode::Known(Kind::Capabilities) => Capabilities::try_from(&resp),
+        ResponseCode::Known(Kind::PermanentlyUnavailable) => Err(Error::AccessDenied { resp }),
+        _ => Err(Error::unexpected_code(Kind::Capabilities, resp)),
+    }
+}
+
+/// Return [`Error::ServiceUnavailable`] if the server discontinued the session with a `400`,
+/// recording the response in `discontinued`
+pub(crate) fn check_service_available(
+    discontinued: &mut Option<RawResponse>,
+    resp: RawResponse,
+) -> Result<RawResponse> {
+    if resp.code() != ResponseCode::Known(Kind::TemporarilyUnavailable) {
+        return Ok(resp);
+    }
+
+    warn!(
+        "Server discontinued the session -- {}",
+        resp.first_line_to_utf8_lossy().trim_end()
+    );
+    *discontinued = Some(resp.clone());
+    Err(Error::ServiceUnavailable { resp })
+}
+
+/// Fail fast with [`Error::ServiceUnavailable`] once the server discontinued the session
+pub(crate) fn check_discontinued(discontinued: &Option<RawResponse>) -> Result<()> {
+    match discontinued {
+        Some(resp) => Err(Error::ServiceUnavailable { resp: resp.clone() }),
+        None => Ok(()),
+    }
+}
+
+/// Return [`Error::AccessDenied`] if the server responded with `502` or
+/// [`Error::SecureConnectionRequired`] if it responded with `483`
+///
+/// The client authenticates while connecting, so a `502` afterwards won't be resolved by
+/// authenticating again.
+fn check_access(resp: RawResponse) -> Result<RawResponse> {
+    match resp.code() {
+        ResponseCode::Known(Kind::PermanentlyUnavailable) => Err(Error::AccessDenied { resp }),
+        ResponseCode::Known(Kind::SecureConnectionRequired) => {
+            Err(Error::SecureConnectionRequired { resp })
+        }
+        _ => Ok(resp),
+    }
+}
+
+/// Returns true if the server responded with `502`, which some servers send until the client
+/// has authenticated
+pub(crate) fn is_access_denied(e: &Error) -> bool {
+    match e {
+        Error::AccessDenied { .. } => true,
+        Error::Failure { code, .. } => *code == ResponseCode::Known(Kind::PermanentlyUnavailable),
+        _ => false,
+    }
+}
+
+fn select_group(conn: &mut NntpConnection, group: impl AsRef<str>) -> Result<Group> {
+    let resp = conn.command(&cmd::Group(group.as_ref().to_string()))?;
+    group_response(resp)
+}
+
+/// Parse the response to `GROUP`
+pub(crate) fn group_response(resp: RawResponse) -> Result<Group> {
+    match resp.code() {
+        ResponseCode::Known(Kind::GroupSelected) => Group::try_from(&resp),
+        _ => Err(Error::unexpected_code(Kind::GroupSelected, resp)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use crate::test_support::{MockScript, DEFAULT_GREETING};
+
+    const CAPABILITIES: &[u8] = b"101 Capability list:\r\nVERSION 2\r\nREADER\r\n.\r\n";
+
+    fn connect(addr: SocketAddr, config: &mut ClientConfig) -> NntpClient {
+        config
+            .connection_config(
+                ConnectionConfig::new()
+                    .read_timeout(Some(Duration::from_secs(5)))
+                    .to_owned(),
+            )
+            .connect(addr)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_article() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect(
+                "ARTICLE <a@example.com>",
+                b"220 0 <a@example.com>\r\nSubject: hi\r\n\r\n..leading dot\r\nbody\r\n.\r\n",
+            )
+            .expect("GROUP misc.test", b"211 2 1 2 misc.test\r\n")
+            .expect(
+                "ARTICLE 2",
+                b"220 2 <b@example.com>\r\nSubject: hi\r\n\r\nbody\r\n.\r\n",
+            )
+            .start()
+            .unwrap();
+
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+
+        // Article numbers are rejected without contacting the server if no group is selected
+        match client.article(2) {
+            Err(Error::NoGroupSelected(ArticleId::Number(2))) => {}
+            res => panic!("Unexpected result {:?}", res),
+        }
+
+        let article = client.article("<a@example.com>").unwrap();
+        assert_eq!(article.message_id(), "<a@example.com>");
+        assert_eq!(
+            article.headers().header("Subject").unwrap().content,
+            vec!["hi"]
+        );
+        assert_eq!(article.body(), b".leading dot\r\nbody\r\n");
+        assert_eq!(article.lines_len(), 2);
+
+        // Groups selected via a raw command are tracked too
+        client.command(cmd::Group("misc.test".to_string())).unwrap();
+        let article = client.article(2).unwrap();
+        assert_eq!(article.number(), 2);
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_head_and_body() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect(
+                "HEAD <a@example.com>",
+                b"221 0 <a@example.com>\r\nSubject: hello world\r\n.\r\n",
+            )
+            .expect(
+                "BODY <a@example.com>",
+                b"222 0 <a@example.com>\r\n..leading dot\r\nbody\r\n.\r\n",
+            )
+            .expect("HEAD <b@example.com>", b"430 No such article\r\n")
+            .start()
+            .unwrap();
+
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+
+        assert!(matches!(
+            client.body(3),
+            Err(Error::NoGroupSelected(ArticleId::Number(3)))
+        ));
+        assert!(matches!(
+            client.head(ArticleId::Current),
+            Err(Error::NoGroupSelected(ArticleId::Current))
+        ));
+
+        let head = client.head("<a@example.com>").unwrap();
+        assert_eq!(head.message_id, "<a@example.com>");
+        assert_eq!(
+            head.headers.header("Subject").unwrap().content,
+            vec!["hello world"]
+        );
+
+        let body = client.body("<a@example.com>").unwrap();
+        assert_eq!(body.body(), b".leading dot\r\nbody\r\n");
+        assert_eq!(body.unterminated().count(), 2);
+
+        match client.head("<b@example.com>") {
+            Err(Error::UnexpectedCode { expected, got, .. }) => {
+                assert_eq!(expected, ResponseCode::Known(Kind::Head));
+                assert_eq!(got, ResponseCode::Known(Kind::NoArticleWithMessageId))
+            }
+            res => panic!("Unexpected result {:?}", res),
+        }
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_stat() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("STAT <a@example.com>", b"223 0 <a@example.com>\r\n")
+            .expect("STAT <b@example.com>", b"430 No such article\r\n")
+            .expect("GROUP misc.test", b"211 2 1 2 misc.test\r\n")
+            .expect("STAT 2", b"223 2 <c@example.com>\r\n")
+            .expect("STAT 3", b"423 No article with that number\r\n")
+            .start()
+            .unwrap();
+
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+
+        let stat = client.stat("<a@example.com>").unwrap();
+        assert_eq!(stat.number(), 0);
+        assert_eq!(stat.message_id(), Some("<a@example.com>"));
+
+        match client.stat("<b@example.com>") {
+            Err(Error::NoSuchArticle { id, code, .. }) => {
+                assert_eq!(id, ArticleId::from("<b@example.com>"));
+                assert_eq!(code, ResponseCode::Known(Kind::NoArticleWithMessageId));
+            }
+            res => panic!("Unexpected result {:?}", res),
+        }
+
+        client.select_group("misc.test").unwrap();
+        let stat = client.stat(2).unwrap();
+        assert_eq!(stat.number(), 2);
+        assert_eq!(stat.message_id(), Some("<c@example.com>"));
+        assert!(matches!(
+            client.stat(3),
+            Err(Error::NoSuchArticle {
+                id: ArticleId::Number(3),
+                ..
+            })
+        ));
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_posting_status() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("GROUP misc.test", b"211 20 1 20 misc.test\r\n")
+            .expect(
+                "LIST ACTIVE misc.*",
+                b"215 list of newsgroups follows\r\nmisc.test 20 1 y\r\nmisc.ro 5 1 n\r\n.\r\n",
+            )
+            .expect("GROUP misc.ro", b"211 5 1 5 misc.ro\r\n")
+            .start()
+            .unwrap();
+
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+        // GROUP responses don't include the status
+        let group = client.select_group("misc.test").unwrap();
+        assert_eq!(group.posting_status(), None);
+        assert_eq!(client.posting_status("misc.test"), None);
+
+        client.list_active(Some("misc.*")).unwrap();
+        assert_eq!(
+            client.group().unwrap().posting_status(),
+            Some(&GroupStatus::PostingAllowed)
+        );
+        assert_eq!(
+            client.posting_status("misc.ro"),
+            Some(&GroupStatus::NoPosting)
+        );
+
+        let group = client.select_group("misc.ro").unwrap();
+        assert_eq!(group.posting_status(), Some(&GroupStatus::NoPosting));
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_list_active() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect(
+                "LIST ACTIVE misc.*",
+                b"215 list of newsgroups follows\r\nmisc.test 20 10 y\r\nmisc.empty 0 1 n\r\n.\r\n",
+            )
+            .expect("LIST ACTIVE", b"215 list of newsgroups follows\r\n.\r\n")
+            .expect(
+                "NEWGROUPS 201015 000000 GMT",
+                b"231 list of new newsgroups follows\r\nmisc.new 1 1 m\r\n.\r\n",
+            )
+            .start()
+            .unwrap();
+
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+
+        let groups = client.list_active(Some("misc.*")).unwrap();
+        assert_eq!(
+            groups.iter().map(|g| g.name.as_str()).collect::<Vec<_>>(),
+            vec!["misc.test", "misc.empty"]
+        );
+        assert_eq!(groups[0].status, GroupStatus::PostingAllowed);
+        assert!(groups[1].is_empty());
+        assert_eq!(groups[1].status, GroupStatus::NoPosting);
+
+        assert!(client.list_active(None).unwrap().is_empty());
+
+        let since = NewsDateTime::new(2020, 10, 15, 0, 0, 0).unwrap();
+        let groups = client.newgroups(since.two_digit_year(true)).unwrap();
+        assert_eq!(groups[0].name, "misc.new");
+        assert_eq!(groups[0].status, GroupStatus::Moderated);
+
+        // Malformed wildmats are never sent
+        assert!(matches!(
+            client.list_active(Some("misc.*\r\nQUIT")),
+            Err(Error::InvalidWildmat(_))
+        ));
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_list_newsgroups() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect(
+                "LIST NEWSGROUPS misc.*",
+                b"215 descriptions follow\r\nmisc.test\tGeneral testing\r\nmisc.empty\r\n.\r\n",
+            )
+            .start()
+            .unwrap();
+
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+
+        let descriptions = client
+            .list_newsgroups(Some(Wildmat::prefix("misc.").as_str()))
+            .unwrap();
+        assert_eq!(
+            descriptions
+                .iter()
+                .map(|d| (d.name.as_str(), d.description.as_str()))
+                .collect::<Vec<_>>(),
+            vec![("misc.test", "General testing"), ("misc.empty", "")]
+        );
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_next_and_last() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("GROUP misc.test", b"211 3 1 5 misc.test\r\n")
+            .expect("NEXT", b"223 3 <three@example.com> retrieved\r\n")
+            .expect("NEXT", b"223 5 <five@example.com> retrieved\r\n")
+            .expect("NEXT", b"421 No next article in this group\r\n")
+            .expect("LAST", b"223 3 <three@example.com> retrieved\r\n")
+            .expect("LAST", b"223 1 <one@example.com> retrieved\r\n")
+            .expect("LAST", b"422 No previous article in this group\r\n")
+            .start()
+            .unwrap();
+
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+        assert!(matches!(
+            client.next(),
+            Err(Error::NoGroupSelected(ArticleId::Current))
+        ));
+
+        client.select_group("misc.test").unwrap();
+        assert_eq!(client.current_article(), Some(1));
+
+        let stat = client.next().unwrap();
+        assert_eq!(stat.message_id(), Some("<three@example.com>"));
+        assert_eq!(client.current_article(), Some(3));
+        client.next().unwrap();
+        assert!(matches!(client.next(), Err(Error::NoNextArticle { .. })));
+        assert_eq!(client.current_article(), Some(5));
+
+        client.last().unwrap();
+        assert_eq!(client.last().unwrap().number(), 1);
+        assert!(matches!(
+            client.last(),
+            Err(Error::NoPreviousArticle { .. })
+        ));
+        assert_eq!(client.current_article(), Some(1));
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_current_article_follows_retrieval() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("GROUP misc.test", b"211 3 1 5 misc.test\r\n")
+            .expect("STAT 3", b"223 3 <three@example.com>\r\n")
+            .expect("STAT <five@example.com>", b"223 0 <five@example.com>\r\n")
+            .expect("STAT 4", b"423 No article with that number\r\n")
+            .expect("STAT", b"223 3 <three@example.com>\r\n")
+            .start()
+            .unwrap();
+
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+        client.select_group("misc.test").unwrap();
+        assert_eq!(client.current_article(), Some(1));
+
+        client.stat(3).unwrap();
+        assert_eq!(client.current_article(), Some(3));
+        // Neither retrieval by message ID nor a failed retrieval move the current article
+        client.stat("<five@example.com>").unwrap();
+        client.stat(4).unwrap_err();
+        assert_eq!(client.current_article(), Some(3));
+
+        let stat = client.stat(ArticleId::Current).unwrap();
+        assert_eq!(stat.message_id(), Some("<three@example.com>"));
+        assert_eq!(client.current_article(), Some(3));
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_hdr() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("GROUP misc.test", b"211 3 1 3 misc.test\r\n")
+            .expect("XHDR References 1-", b"221 References follow\r\n1 \r\n2 (none)\r\n3 <a@example.com> <b@example.com>\r\n.\r\n")
+            .expect("CAPABILITIES", b"101 Capability list:\r\nVERSION 2\r\nREADER\r\nHDR\r\n.\r\n")
+            .expect("HDR Subject 2-3", b"225 Headers follow\r\n2 Hello\r\n3 \r\n.\r\n")
+            .start()
+            .unwrap();
+
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+        client.select_group("misc.test").unwrap();
+
+        let references = client.hdr("References", 1..).unwrap();
+        assert_eq!(
+            references,
+            vec![
+                (1, Some(vec![])),
+                (2, None),
+                (3, Some(b"<a@example.com> <b@example.com>".to_vec()))
+            ]
+        );
+
+        client.update_capabilities().unwrap();
+        let subjects = client.hdr("Subject", 2..=3).unwrap();
+        assert_eq!(
+            subjects,
+            vec![(2, Some(b"Hello".to_vec())), (3, Some(vec![]))]
+        );
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_xpat() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("GROUP misc.test", b"211 3 1 3 misc.test\r\n")
+            .expect(
+                "XPAT Subject 1-3 *rust* *nntp*",
+                b"221 Header follows\r\n1 Learning rust\r\n3 nntp in rust\r\n.\r\n",
+            )
+            .expect("XPAT From 1- *@example.com", b"500 What?\r\n")
+            .start()
+            .unwrap();
+
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+        client.select_group("misc.test").unwrap();
+
+        let matches = client
+            .xpat("Subject", 1..=3, &["*rust*", "*nntp*"])
+            .unwrap();
+        assert_eq!(
+            matches
+                .iter()
+                .map(|(number, _)| *number)
+                .collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+        assert_eq!(matches[0].1.as_deref(), Some(&b"Learning rust"[..]));
+
+        // XPAT has no current-article form
+        assert!(matches!(
+            client.xpat("Subject", ArticleRange::Current, &["*rust*"]),
+            Err(Error::InvalidCommand(_))
+        ));
+        assert!(matches!(
+            client.xpat::<&str>("Subject", 1..=3, &[]),
+            Err(Error::InvalidCommand(_))
+        ));
+
+        match client.xpat("From", 1.., &["*@example.com"]) {
+            Err(Error::Unsupported { command, resp }) => {
+                assert_eq!(command, "XPAT");
+                assert!(resp.is_some());
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_newnews() {
+        let since = NewsDateTime::new(2020, 10, 15, 0, 0, 0).unwrap();
+
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .start()
+            .unwrap();
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+        match client.newnews("misc.*", since) {
+            Err(Error::Unsupported { command, resp }) => {
+                assert_eq!(command, "NEWNEWS");
+                assert!(resp.is_none());
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+        drop(client);
+        server.finish().unwrap();
+
+        let server = MockScript::new()
+            .expect(
+                "CAPABILITIES",
+                b"101 Capability list:\r\nVERSION 2\r\nREADER\r\nNEWNEWS\r\n.\r\n",
+            )
+            .expect(
+                "NEWNEWS misc.* 20201015 000000 GMT",
+                b"230 list of new articles follows\r\n<a@example.com>\r\n<b@example.com>\r\n.\r\n",
+            )
+            .expect(
+                "NEWNEWS alt.* 20201015 000000 GMT",
+                b"230 list of new articles follows\r\n<a@example.com>\r\nnot-an-id\r\n.\r\n",
+            )
+            .start()
+            .unwrap();
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+        assert_eq!(
+            client.newnews("misc.*", since).unwrap(),
+            vec!["<a@example.com>", "<b@example.com>"]
+        );
+        match client.newnews("alt.*", since) {
+            Err(Error::Deserialization(msg)) => assert!(msg.contains("`not-an-id`"), "{}", msg),
+            other => panic!("Unexpected result {:?}", other),
+        }
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_listgroup() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect(
+                "LISTGROUP misc.test",
+                b"211 3 1 5 misc.test list follows\r\n1\r\n3\r\n5\r\n.\r\n",
+            )
+            .expect(
+                "LISTGROUP misc.test 4-",
+                b"211 3 1 5 misc.test list follows\r\n5\r\n.\r\n",
+            )
+            .expect("LISTGROUP alt.nope", b"411 No such newsgroup\r\n")
+            .expect(
+                "LISTGROUP",
+                b"211 3 1 5 misc.test list follows\r\n1\r\n3x\r\n.\r\n",
+            )
+            .start()
+            .unwrap();
+
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+
+        let (group, numbers) = client.listgroup(Some("misc.test"), None).unwrap();
+        assert_eq!(group.name, "misc.test");
+        assert_eq!(numbers, vec![1, 3, 5]);
+        assert_eq!(client.group(), Some(&group));
+
+        // The range is sent with the selected group
+        let (_, numbers) = client.listgroup(None, Some((4..).into())).unwrap();
+        assert_eq!(numbers, vec![5]);
+
+        // LISTGROUP has no current-article form
+        assert!(matches!(
+            client.listgroup(Some("misc.test"), Some(ArticleRange::Current)),
+            Err(Error::InvalidCommand(_))
+        ));
+
+        match client.listgroup(Some("alt.nope"), None) {
+            Err(Error::NoSuchNewsgroup { .. }) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+        assert_eq!(client.group().unwrap().name, "misc.test");
+
+        match client.listgroup(None, None) {
+            Err(Error::Deserialization(msg)) => assert!(msg.contains("`3x`"), "{}", msg),
+            other => panic!("Unexpected result {:?}", other),
+        }
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_overview_by_id() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect(
+                "CAPABILITIES",
+                b"101 Capability list:\r\nVERSION 2\r\nREADER\r\nOVER MSGID\r\n.\r\n",
+            )
+            .expect(
+                "OVER <2@example.com>",
+                b"224 Overview information follows\r\n\
+                  0\tsecond\tb@example.com\tdate\t<2@example.com>\t<1@example.com>\t200\t4\r\n\
+                  .\r\n",
+            )
+            .expect("OVER <3@example.com>", b"430 No such article\r\n")
+            .start()
+            .unwrap();
+
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+        let id = |s: &str| MessageId::new(s).unwrap();
+
+        // Only OVER MSGID accepts message-ids
+        assert!(matches!(
+            client.overview_by_id(&id("<2@example.com>")),
+            Err(Error::Unsupported { resp: None, .. })
+        ));
+        client.update_capabilities().unwrap();
+
+        let overview = client
+            .overview_by_id(&id("<2@example.com>"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(overview.number, 0);
+        assert_eq!(overview.references, "<1@example.com>");
+        assert!(client
+            .overview_by_id(&id("<3@example.com>"))
+            .unwrap()
+            .is_none());
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_overviews() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("GROUP misc.test", b"211 2 1 2 misc.test\r\n")
+            .expect(
+                "XOVER 1-",
+                b"224 Overview information follows\r\n\
+                  1\tfirst\ta@example.com\tdate\t<1@example.com>\t\t100\t2\r\n\
+                  2\tsecond\tb@example.com\tdate\t<2@example.com>\t<1@example.com>\t200\t4\t\
+                  Xref: example.com misc.test:2\r\n\
+                  .\r\n",
+            )
+            .expect(
+                "LIST OVERVIEW.FMT",
+                b"215 Order of fields in overview database.\r\n\
+                  Subject:\r\nFrom:\r\nDate:\r\nMessage-ID:\r\nReferences:\r\n\
+                  :bytes\r\n:lines\r\nNewsgroups:full\r\n.\r\n",
+            )
+            .expect(
+                "XOVER 2",
+                b"224 Overview information follows\r\n\
+                  2\ts\tf\td\t<2@example.com>\t\t200\t4\tNewsgroups: misc.test\r\n.\r\n",
+            )
+            .expect(
+                "CAPABILITIES",
+                b"101 Capability list:\r\nVERSION 2\r\nOVER\r\n.\r\n",
+            )
+            .expect("OVER 1-2", b"224 Overview information follows\r\n.\r\n")
+            .start()
+            .unwrap();
+
+        let mut client = connect(
+            server.addr(),
+            ClientConfig::default().group(Some("misc.test")),
+        );
+
+        let overviews = client.overviews(1..).unwrap();
+        assert_eq!(overviews.len(), 2);
+        assert_eq!(overviews[0].number, 1);
+        assert_eq!(overviews[0].subject, "first");
+        assert_eq!(overviews[0].bytes, Some(100));
+        assert_eq!(overviews[1].references, "<1@example.com>");
+        assert_eq!(overviews[1].lines, Some(4));
+        assert_eq!(
+            overviews[1].extra("Xref"),
+            Some(&b"example.com misc.test:2"[..])
+        );
+
+        // Additional fields are aligned with the overview format once it is known
+        let fmt = client.list_overview_fmt().unwrap();
+        assert_eq!(fmt.field_index("Newsgroups"), Some(7));
+        assert_eq!(client.overview_method().format, Some(fmt));
+        let overviews = client.overviews(2).unwrap();
+        assert_eq!(overviews[0].extra("Newsgroups"), Some(&b"misc.test"[..]));
+
+        // OVER is used once it is advertised
+        client.update_capabilities().unwrap();
+        assert!(client.overviews(1..=2).unwrap().is_empty());
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_hdr_current() {
+        let server = MockScript::new()
+            .expect(
+                "CAPABILITIES",
+                b"101 Capability list:\r\nVERSION 2\r\nREADER\r\nHDR\r\n.\r\n",
+            )
+            .expect("GROUP misc.test", b"211 2 1 2 misc.test\r\n")
+            .expect("HDR Subject", b"225 Headers follow\r\n1 Hello\r\n.\r\n")
+            .expect("HDR X-Missing", b"225 Headers follow\r\n1 \r\n.\r\n")
+            .expect("HDR Subject", b"420 No current article selected\r\n")
+            .start()
+            .unwrap();
+
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+        assert!(matches!(
+            client.hdr_current("Subject"),
+            Err(Error::NoGroupSelected(ArticleId::Current))
+        ));
+
+        client.select_group("misc.test").unwrap();
+        assert_eq!(
+            client.hdr_current("Subject").unwrap(),
+            Some(b"Hello".to_vec())
+        );
+        assert_eq!(client.hdr_current("X-Missing").unwrap(), None);
+        assert!(matches!(
+            client.hdr_current("Subject"),
+            Err(Error::NoSuchArticle {
+                id: ArticleId::Current,
+                ..
+            })
+        ));
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_legacy_fallback() {
+        let server = MockScript::new()
+            .expect(
+                "CAPABILITIES",
+                b"101 Capability list:\r\nVERSION 2\r\nREADER\r\nOVER\r\nHDR\r\n.\r\n",
+            )
+            .expect("OVER 1-2", b"500 What?\r\n")
+            .expect(
+                "XOVER 1-2",
+                b"224 Overview information follows\r\n\
+                  1\ts\tf\td\t<1@example.com>\t\t100\t2\r\n.\r\n",
+            )
+            .expect("XOVER 3", b"224 Overview information follows\r\n.\r\n")
+            .expect("HDR Subject 1", b"500 What?\r\n")
+            .expect("XHDR Subject 1", b"221 Headers follow\r\n1 Hello\r\n.\r\n")
+            .expect("XHDR Subject 2", b"221 Headers follow\r\n.\r\n")
+            .start()
+            .unwrap();
+
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+        assert_eq!(client.overview_command(), "OVER");
+        assert!(!client.overview_method().fallback);
+        assert_eq!(client.header_command(), "HDR");
+
+        assert_eq!(client.overviews(1..=2).unwrap()[0].number, 1);
+        assert_eq!(client.overview_command(), "XOVER");
+        assert_eq!(
+            client.overview_method(),
+            OverviewMethod {
+                command: "XOVER",
+                fallback: true,
+                format: None,
+            }
+        );
+        // The legacy command is used for the rest of the session
+        assert!(client.overviews(3).unwrap().is_empty());
+
+        assert_eq!(
+            client.hdr("Subject", 1).unwrap(),
+            vec![(1, Some(b"Hello".to_vec()))]
+        );
+        assert_eq!(client.header_command(), "XHDR");
+        assert!(client.hdr("Subject", 2).unwrap().is_empty());
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_overviews_iter() {
+        let xover: &[u8] = b"224 Overview information follows\r\n\
+              1\tfirst\ta@example.com\tdate\t<1@example.com>\t\t100\t2\r\n\
+              bogus\tsecond\r\n\
+              3\tthird\tc@example.com\tdate\t<3@example.com>\t\t300\t6\r\n\
+              .\r\n";
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("GROUP misc.test", b"211 3 1 3 misc.test\r\n")
+            .expect("XOVER 1-", xover)
+            .expect("XOVER 5", b"423 No articles in that range\r\n")
+            .expect("XOVER 1-", xover)
+            .start()
+            .unwrap();
+
+        let mut client = connect(
+            server.addr(),
+            ClientConfig::default().group(Some("misc.test")),
+        );
+
+        // A malformed line doesn't end iteration
+        let overviews = client.overviews_iter(1..).collect::<Vec<_>>();
+        assert_eq!(overviews.len(), 3);
+        assert_eq!(overviews[0].as_ref().unwrap().subject, "first");
+        assert!(overviews[1].is_err());
+        assert_eq!(overviews[2].as_ref().unwrap().number, 3);
+
+        let mut iter = client.overviews_iter(5);
+        assert!(matches!(
+            iter.next(),
+            Some(Err(Error::UnexpectedCode { .. }))
+        ));
+        assert!(iter.next().is_none());
+        drop(iter);
+
+        // The connection is left mid-response if the iterator is dropped early
+        let first = client.overviews_iter(1..).next().unwrap().unwrap();
+        assert_eq!(first.number, 1);
+        assert!(matches!(
+            client.date(),
+            Err(Error::Connection(crate::raw::error::Error::Desynchronized))
+        ));
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_list_active_iter() {
+        let active: &[u8] = b"215 List of newsgroups follows\r\n\
+              comp.lang.rust 300 1 y\r\n\
+              comp.broken\r\n\
+              comp.lang.c 10 5 n\r\n\
+              .\r\n";
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("LIST ACTIVE comp.*", active)
+            .expect("LIST ACTIVE misc.*", b"502 Access denied\r\n")
+            .expect("LIST ACTIVE", active)
+            .start()
+            .unwrap();
+
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+
+        // A malformed line doesn't end iteration
+        let groups = client.list_active_iter(Some("comp.*")).collect::<Vec<_>>();
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[0].as_ref().unwrap().name, "comp.lang.rust");
+        assert!(groups[1].is_err());
+        assert_eq!(groups[2].as_ref().unwrap().status, GroupStatus::NoPosting);
+
+        let mut iter = client.list_active_iter(Some("comp.* misc.*"));
+        assert!(matches!(iter.next(), Some(Err(Error::InvalidWildmat(_)))));
+        assert!(iter.next().is_none());
+        drop(iter);
+
+        // Errors are mapped as for buffered commands
+        assert!(matches!(
+            client.list_active_iter(Some("misc.*")).next(),
+            Some(Err(Error::AccessDenied { .. }))
+        ));
+
+        // The connection is left mid-response if the iterator is dropped early
+        let first = client.list_active_iter(None).next().unwrap().unwrap();
+        assert_eq!(first.high, 300);
+        assert!(matches!(
+            client.date(),
+            Err(Error::Connection(crate::raw::error::Error::Desynchronized))
+        ));
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_post() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect(
+                "CAPABILITIES",
+                b"101 Capability list:\r\nVERSION 2\r\nREADER\r\nPOST\r\n.\r\n",
+            )
+            .expect("POST", b"340 Input article; end with <CR-LF>.<CR-LF>\r\n")
+            .expect("Subject: hi", b"")
+            .expect("", b"")
+            .expect("..leading dot", b"")
+            .expect(".", b"240 Article received OK\r\n")
+            .expect("POST", b"440 Posting not permitted\r\n")
+            .start()
+            .unwrap();
+
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+        let article = b"Subject: hi\r\n\r\n.leading dot\r\n";
+
+        // POST is not advertised
+        assert!(matches!(
+            client.post(article),
+            Err(Error::PostingNotPermitted { resp: None })
+        ));
+
+        client.update_capabilities().unwrap();
+        client.post(article).unwrap();
+
+        match client.post(article) {
+            Err(Error::PostingNotPermitted { resp: Some(resp) }) => {
+                assert_eq!(resp.code(), ResponseCode::Known(Kind::PostingNotPermitted))
+            }
+            res => panic!("Unexpected result {:?}", res),
+        }
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_ihave() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("IHAVE <a@example.com>", b"335 Send it\r\n")
+            .expect("Subject: hi", b"")
+            .expect("", b"")
+            .expect("..leading dot", b"")
+            .expect(".", b"235 Article transferred OK\r\n")
+            .expect("IHAVE <a@example.com>", b"435 Duplicate\r\n")
+            .expect("IHAVE <b@example.com>", b"436 Retry later\r\n")
+            .expect("IHAVE <c@example.com>", b"335 Send it\r\n")
+            .expect("Subject: hi", b"")
+            .expect("", b"")
+            .expect("..leading dot", b"")
+            .expect(".", b"437 Rejected\r\n")
+            .start()
+            .unwrap();
+
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+        let article = b"Subject: hi\r\n\r\n.leading dot\r\n";
+
+        let outcomes = [
+            "<a@example.com>",
+            "<a@example.com>",
+            "<b@example.com>",
+            "<c@example.com>",
+        ]
+        .iter()
+        .map(|id| client.ihave(id, article).unwrap())
+        .collect::<Vec<_>>();
+        assert_eq!(
+            outcomes,
+            vec![
+                IHaveOutcome::Accepted,
+                IHaveOutcome::NotWanted,
+                IHaveOutcome::RetryLater,
+                IHaveOutcome::Rejected
+            ]
+        );
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_date() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("DATE", b"111 20201015043840\r\n")
+            .expect("DATE", b"111 2020-10-15\r\n")
+            .start()
+            .unwrap();
+
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+        assert_eq!(client.greeting().first_line(), DEFAULT_GREETING);
+        assert!(client.posting_allowed());
+
+        assert_eq!(client.date().unwrap().to_string(), "2020-10-15T04:38:40Z");
+        assert!(matches!(client.date(), Err(Error::Deserialization(_))));
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_connect_to() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .start()
+            .unwrap();
+
+        let client = ClientConfig::default().connect_to(server.addr()).unwrap();
+        assert_eq!(client.connection_info().peer_addr, Some(server.addr()));
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_help() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect(
+                "HELP",
+                b"100 Help text follows\r\n  ARTICLE [message-id|number]\r\n  XFOO\r\n.\r\n",
+            )
+            .expect("HELP", b"100 Help text follows\r\n.\r\n")
+            .start()
+            .unwrap();
+
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+        assert_eq!(
+            client.help().unwrap(),
+            "  ARTICLE [message-id|number]\n  XFOO"
+        );
+        assert_eq!(client.help().unwrap(), "");
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_list_raw() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect(
+                "LIST HEADERS MSGID",
+                b"215 Metadata items follow\r\n:\r\n:bytes\r\n.\r\n",
+            )
+            .expect("LIST XFOO", b"503 Data item not stored\r\n")
+            .start()
+            .unwrap();
+
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+        for (keyword, args) in [("MOTD\r\nQUIT", &[][..]), ("HEADERS", &["MSG ID"][..])] {
+            assert!(matches!(
+                client.list_raw(keyword, args),
+                Err(Error::InvalidCommand(_))
+            ));
+        }
+
+        let resp = client.list_raw("HEADERS", &["MSGID"]).unwrap();
+        assert_eq!(
+            resp.data_blocks().unwrap().to_utf8_lines_lossy(),
+            vec![":", ":bytes"]
+        );
+        assert!(matches!(
+            client.list_raw("XFOO", &[]),
+            Err(Error::UnexpectedCode { .. })
+        ));
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_list_headers() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect(
+                "LIST HEADERS",
+                b"215 Headers supported:\r\nSubject\r\n:lines\r\n:\r\n.\r\n",
+            )
+            .start()
+            .unwrap();
+
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+        assert_eq!(
+            client.list_headers().unwrap(),
+            vec!["Subject", ":lines", ":"]
+        );
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_byte_counters() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("HELP", b"100 Help\r\n..x\r\n.\r\n")
+            .start()
+            .unwrap();
+
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+        assert_eq!(
+            client.bytes_read(),
+            (DEFAULT_GREETING.len() + CAPABILITIES.len()) as u64
+        );
+        assert_eq!(client.bytes_written(), b"CAPABILITIES\r\n".len() as u64);
+
+        client.reset_counters();
+        assert_eq!(client.help().unwrap(), ".x");
+        // Dot-stuffing is counted as it was sent on the wire
+        assert_eq!(client.bytes_read(), 18);
+        assert_eq!(client.bytes_written(), 6);
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_iter_articles() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("GROUP misc.test", b"211 3 2 5 misc.test\r\n")
+            .expect(
+                "ARTICLE 2",
+                b"220 2 <b@example.com>\r\nSubject: hi\r\n\r\nbody\r\n.\r\n",
+            )
+            .expect("ARTICLE 3", b"423 No article with that number\r\n")
+            .expect("ARTICLE 4", b"423 No article with that number\r\n")
+            .expect(
+                "ARTICLE 5",
+                b"220 5 <e@example.com>\r\nSubject: hi\r\n\r\nbody\r\n.\r\n",
+            )
+            .start()
+            .unwrap();
+
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+        assert!(matches!(
+            client.iter_articles(1..).collect::<Vec<_>>().as_slice(),
+            [Err(Error::NoGroupSelected(ArticleId::Number(1)))]
+        ));
+
+        client.select_group("misc.test").unwrap();
+        let numbers = client
+            .iter_articles(1..)
+            .map(|article| article.unwrap().number())
+            .collect::<Vec<_>>();
+        assert_eq!(numbers, vec![2, 5]);
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_keepalive_if_needed() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("DATE", b"111 20201015043840\r\n")
+            .start()
+            .unwrap();
+
+        let mut client = connect(
+            server.addr(),
+            ClientConfig::default().keepalive_interval(Some(Duration::from_millis(200))),
+        );
+        assert!(!client.keepalive_if_needed().unwrap());
+
+        thread::sleep(Duration::from_millis(250));
+        assert!(client.keepalive_if_needed().unwrap());
+        assert!(!client.keepalive_if_needed().unwrap());
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_articles_by_ids() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect(
+                "ARTICLE <a@example.com>",
+                b"220 0 <a@example.com>\r\nSubject: a\r\n\r\nbody\r\n.\r\n",
+            )
+            .expect("ARTICLE <b@example.com>", b"430 No such article\r\n")
+            .expect(
+                "ARTICLE <c@example.com>",
+                b"220 0 <c@example.com>\r\nSubject: c\r\n\r\nbody\r\n.\r\n",
+            )
+            .start()
+            .unwrap();
+
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+        let articles =
+            client.articles_by_ids(&["<a@example.com>", "<b@example.com>", "<c@example.com>"]);
+
+        // A missing article doesn't abort the rest of the batch
+        assert_eq!(articles.len(), 3);
+        assert_eq!(
+            articles[0].as_ref().unwrap().message_id(),
+            "<a@example.com>"
+        );
+        assert!(matches!(
+            articles[1],
+            Err(Error::UnexpectedCode {
+                got: ResponseCode::Known(Kind::NoArticleWithMessageId),
+                ..
+            })
+        ));
+        assert_eq!(
+            articles[2].as_ref().unwrap().message_id(),
+            "<c@example.com>"
+        );
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_heads_by_id() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect(
+                "HEAD <a@example.com>",
+                b"221 0 <a@example.com>\r\nSubject: first\r\n.\r\n",
+            )
+            .expect("HEAD <b@example.com>", b"430 No such article\r\n")
+            .start()
+            .unwrap();
+
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+        let a = MessageId::new("<a@example.com>").unwrap();
+        let b = MessageId::new("<b@example.com>").unwrap();
+        let heads = client.heads_by_id(&[a.clone(), b.clone(), a.clone()]);
+
+        // Duplicates are only retrieved once
+        assert_eq!(heads.len(), 2);
+        assert_eq!(heads[0].0, a);
+        assert_eq!(
+            heads[0].1.as_ref().unwrap().get("Subject"),
+            Some(&b"first"[..])
+        );
+        assert_eq!(heads[1].0, b);
+        assert!(matches!(
+            heads[1].1,
+            Err(Error::UnexpectedCode {
+                got: ResponseCode::Known(Kind::NoArticleWithMessageId),
+                ..
+            })
+        ));
+
+        drop(client);
+        server.finish().unwrap();
+
+        // The client reconnects before falling back if pipelining failed mid-response
+        let long_subject = format!(
+            "221 0 <a@example.com>\r\nSubject: {}\r\n.\r\n",
+            "a".repeat(100)
+        );
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("HEAD <a@example.com>", long_subject)
+            .expect("HEAD <b@example.com>", b"430 No such article\r\n")
+            .reconnect()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect(
+                "HEAD <a@example.com>",
+                b"221 0 <a@example.com>\r\nSubject: first\r\n.\r\n",
+            )
+            .expect("HEAD <b@example.com>", b"430 No such article\r\n")
+            .start()
+            .unwrap();
+
+        let mut client = ClientConfig::default()
+            .connection_config(
+                ConnectionConfig::new()
+                    .read_timeout(Some(Duration::from_secs(5)))
+                    .max_line_bytes(Some(64))
+                    .to_owned(),
+            )
+            .connect(server.addr())
+            .unwrap();
+        let heads = client.heads_by_id(&[a.clone(), b.clone()]);
+        assert_eq!(
+            heads[0].1.as_ref().unwrap().get("Subject"),
+            Some(&b"first"[..])
+        );
+        assert!(heads[1].1.is_err());
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_is_healthy() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("DATE", b"111 20201015043840\r\n")
+            .expect("DATE", b"400 Service discontinued\r\n")
+            .start()
+            .unwrap();
+
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+        assert!(client.is_healthy());
+        assert!(!client.is_healthy());
+
+        // The server discontinued the session, so it isn't contacted again
+        assert!(!client.is_healthy());
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_close() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("QUIT", b"205 bye\r\n")
+            .start()
+            .unwrap();
+        let client = connect(server.addr(), &mut ClientConfig::default());
+        let resp = client.close().unwrap();
+        assert_eq!(resp.code(), ResponseCode::Known(Kind::ConnectionClosing));
+        server.finish().unwrap();
+
+        // Dropping the client sends QUIT without waiting for the response
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("QUIT", b"205 bye\r\n")
+            .start()
+            .unwrap();
+        drop(connect(server.addr(), &mut ClientConfig::default()));
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_abort() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut writer = stream;
+            writer.write_all(b"200 ready\r\n").unwrap();
+
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            assert_eq!(line, "CAPABILITIES\r\n");
+            writer.write_all(CAPABILITIES).unwrap();
+
+            // The client hangs up without sending QUIT
+            line.clear();
+            assert_eq!(reader.read_line(&mut line).unwrap(), 0);
+        });
+
+        connect(addr, &mut ClientConfig::default()).abort();
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_command_with_cancel() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect(
+                "ARTICLE <a@example.com>",
+                b"220 0 <a@example.com>\r\nSubject: hi\r\n\r\n..leading dot\r\n.\r\n",
+            )
+            .expect(
+                "ARTICLE <b@example.com>",
+                b"220 0 <b@example.com>\r\nSubject: hi\r\n\r\nstop here\r\nmore\r\n.\r\n",
+            )
+            .start()
+            .unwrap();
+
+        // Cancel once the server sends a particular line
+        let cancel = Arc::new(AtomicBool::new(false));
+        let flag = cancel.clone();
+        let mut client = ClientConfig::default()
+            .connection_config(
+                ConnectionConfig::new()
+                    .read_timeout(Some(Duration::from_secs(5)))
+                    .trace_hook(Arc::new(move |_, bytes| {
+                        if bytes.windows(9).any(|w| w == b"stop here") {
+                            flag.store(true, Ordering::SeqCst);
+                        }
+                    }))
+                    .to_owned(),
+            )
+            .connect(server.addr())
+            .unwrap();
+
+        let resp = client
+            .command_with_cancel(cmd::Article::MessageId("<a@example.com>".into()), &cancel)
+            .unwrap();
+        let data_blocks = resp.data_blocks().unwrap();
+        assert_eq!(
+            data_blocks.payload(),
+            b"Subject: hi\r\n\r\n.leading dot\r\n.\r\n"
+        );
+        assert_eq!(data_blocks.line(2), Some(&b".leading dot\r\n"[..]));
+        assert_eq!(data_blocks.unterminated().count(), 3);
+
+        assert!(matches!(
+            client.command_with_cancel(cmd::Article::MessageId("<b@example.com>".into()), &cancel),
+            Err(Error::Cancelled)
+        ));
+        // The rest of the article was never read
+        assert!(matches!(
+            client.date(),
+            Err(Error::Connection(crate::raw::error::Error::Desynchronized))
+        ));
+        // Nothing is sent if the flag is already set
+        assert!(matches!(
+            client.command_with_cancel(cmd::Date, &cancel),
+            Err(Error::Cancelled)
+        ));
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_connect_unix() {
+        let path = std::env::temp_dir().join(format!("brokaw-test-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("DATE", b"111 20200101123456\r\n")
+            .start_unix(&path)
+            .unwrap();
+
+        let mut client = ClientConfig::default()
+            .connection_config(
+                ConnectionConfig::new()
+                    .read_timeout(Some(Duration::from_secs(5)))
+                    .to_owned(),
+            )
+            .connect_unix(&path)
+            .unwrap();
+        assert!(client.capabilities().get("READER").is_some());
+        assert!(!client.connection_info().is_tls());
+        assert_eq!(client.connection_info().peer_addr, None);
+        client.date().unwrap();
+
+        // Timeouts apply to the socket just as they do to TCP streams
+        assert_eq!(
+            client.conn.read_timeout().unwrap(),
+            Some(Duration::from_secs(5))
+        );
+        {
+            let _guard = client.with_timeout(Duration::from_secs(1)).unwrap();
+        }
+        assert_eq!(
+            client.conn.read_timeout().unwrap(),
+            Some(Duration::from_secs(5))
+        );
+
+        client.abort();
+        server.finish().unwrap();
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_with_timeout() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("DATE", b"111 20201015043840\r\n")
+            .expect("DATE", b"111 2020-10-15\r\n")
+            .start()
+            .unwrap();
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+        let configured = Some(Duration::from_secs(5));
+        assert_eq!(client.conn().read_timeout().unwrap(), configured);
+
+        {
+            let mut guard = client.with_timeout(Duration::from_secs(60)).unwrap();
+            assert!(guard.date().is_ok());
+            assert_eq!(
+                guard.conn().read_timeout().unwrap(),
+                Some(Duration::from_secs(60))
+            );
+            // The previous timeout is restored even if the command fails
+            assert!(guard.date().is_err());
+        }
+        assert_eq!(client.conn().read_timeout().unwrap(), configured);
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_article_in() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("GROUP misc.test", b"211 2 1 2 misc.test\r\n")
+            .expect(
+                "ARTICLE 1",
+                b"220 1 <a@example.com>\r\nSubject: a\r\n\r\nbody\r\n.\r\n",
+            )
+            .expect(
+                "ARTICLE 2",
+                b"220 2 <b@example.com>\r\nSubject: b\r\n\r\nbody\r\n.\r\n",
+            )
+            .expect("GROUP alt.nope", b"411 No such group\r\n")
+            .start()
+            .unwrap();
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+
+        let article = client.article_in("misc.test", 1).unwrap();
+        assert_eq!(article.number(), 1);
+        // The group is already selected so GROUP isn't sent again
+        let article = client.article_in("misc.test", 2).unwrap();
+        assert_eq!(article.number(), 2);
+        assert_eq!(client.current_article(), Some(2));
+
+        match client.article_in("alt.nope", 1) {
+            Err(Error::NoSuchNewsgroup { .. }) => {}
+            res => panic!("Unexpected result {:?}", res.map(|_| ())),
+        }
+        assert_eq!(client.group().unwrap().name, "misc.test");
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_article_cache() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect(
+                "ARTICLE <a@example.com>",
+                b"220 0 <a@example.com>\r\nSubject: a\r\n\r\nbody\r\n.\r\n",
+            )
+            .expect(
+                "HEAD <b@example.com>",
+                b"221 0 <b@example.com>\r\nSubject: b\r\n.\r\n",
+            )
+            .expect(
+                "ARTICLE <b@example.com>",
+                b"220 0 <b@example.com>\r\nSubject: b\r\n\r\nbody\r\n.\r\n",
+            )
+            .expect("GROUP misc.test", b"211 2 1 2 misc.test\r\n")
+            .expect(
+                "ARTICLE 1",
+                b"220 1 <a@example.com>\r\nSubject: a\r\n\r\nbody\r\n.\r\n",
+            )
+            .expect(
+                "ARTICLE <a@example.com>",
+                b"220 0 <a@example.com>\r\nSubject: a\r\n\r\nbody\r\n.\r\n",
+            )
+            .start()
+            .unwrap();
+        let mut client = connect(server.addr(), ClientConfig::default().article_cache(1));
+
+        let article = client.article("<a@example.com>").unwrap();
+        assert_eq!(client.article("<a@example.com>").unwrap(), article);
+        let head = client.head("<a@example.com>").unwrap();
+        assert_eq!(head.headers.get("Subject"), Some(&b"a"[..]));
+        assert_eq!(
+            client.cache_stats(),
+            CacheStats {
+                hits: 2,
+                misses: 1,
+                len: 1,
+                capacity: 1,
+            }
+        );
+
+        // Cached headers don't satisfy a request for the full article, and evict the article
+        client.head("<b@example.com>").unwrap();
+        client.head("<b@example.com>").unwrap();
+        client.article("<b@example.com>").unwrap();
+        assert_eq!(client.cache_stats().len, 1);
+
+        // Articles retrieved by number aren't cached
+        client.select_group("misc.test").unwrap();
+        client.article(1).unwrap();
+        client.clear_cache();
+        client.article("<a@example.com>").unwrap();
+        assert_eq!(client.cache_stats().hits, 3);
+        drop(client);
+        server.finish().unwrap();
+
+        // The cache is disabled by default
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect(
+                "HEAD <b@example.com>",
+                b"221 0 <b@example.com>\r\nSubject: b\r\n.\r\n",
+            )
+            .expect(
+                "HEAD <b@example.com>",
+                b"221 0 <b@example.com>\r\nSubject: b\r\n.\r\n",
+            )
+            .start()
+            .unwrap();
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+        client.head("<b@example.com>").unwrap();
+        client.head("<b@example.com>").unwrap();
+        assert_eq!(client.cache_stats(), CacheStats::default());
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_articles() {
+        let article = |number: u8| {
+            format!(
+                "220 {} <{}@example.com>\r\nSubject: hi\r\n\r\nbody\r\n.\r\n",
+                number, number
+            )
+        };
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("GROUP misc.test", b"211 3 1 3 misc.test\r\n")
+            .expect("ARTICLE 1", article(1))
+            .expect("ARTICLE 2", b"423 No article with that number\r\n")
+            .expect("ARTICLE 3", article(3))
+            // The connection drops part way through the first batch
+            .expect("ARTICLE 1", article(1))
+            .expect("ARTICLE 2", b"")
+            .disconnect()
+            .start()
+            .unwrap();
+
+        let mut client = ClientConfig::default()
+            .group(Some("misc.test"))
+            .connection_config(
+                ConnectionConfig::new()
+                    .read_timeout(Some(Duration::from_secs(5)))
+                    .max_pipeline_depth(2)
+                    .to_owned(),
+            )
+            .connect(server.addr())
+            .unwrap();
+
+        let articles = client.articles(1..=3).unwrap();
+        assert_eq!(
+            articles.iter().map(|(n, _)| *n).collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+        assert_eq!(articles[1].1.message_id(), "<3@example.com>");
+        assert_eq!(client.current_article(), Some(3));
+
+        match client.articles(1..=3) {
+            Err(Error::IncompleteArticles { articles, source }) => {
+                assert_eq!(articles.len(), 1);
+                assert_eq!(articles[0].0, 1);
+                assert!(matches!(*source, Error::Connection(_)), "{:?}", source);
+            }
+            res => panic!("Unexpected result {:?}", res.map(|_| ())),
+        }
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_article_to_writer() {
+        /// A writer that fails once it has received `capacity` bytes
+        struct Limited {
+            written: Vec<u8>,
+            capacity: usize,
+        }
+
+        impl Write for Limited {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                if self.written.len() + buf.len() > self.capacity {
+                    return Err(std::io::Error::other("disk full"));
+                }
+                self.written.extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let article = b"220 3000234 <45223423@example.com>\r\n\
+            Subject: binary\r\n\
+            \r\n\
+            ..dotted\r\n\
+            data\r\n\
+            .\r\n";
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("ARTICLE <45223423@example.com>", article)
+            .expect("ARTICLE <45223423@example.com>", article)
+            .start()
+            .unwrap();
+
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+
+        let mut out = Vec::new();
+        let stat = client
+            .article_to_writer("<45223423@example.com>", &mut out)
+            .unwrap();
+        assert_eq!(stat.number, 3000234);
+        assert_eq!(stat.message_id(), Some("<45223423@example.com>"));
+        assert_eq!(out, b"Subject: binary\r\n\r\n.dotted\r\ndata\r\n");
+
+        let mut out = Limited {
+            written: Vec::new(),
+            capacity: 20,
+        };
+        assert!(matches!(
+            client.article_to_writer("<45223423@example.com>", &mut out),
+            Err(Error::Output(_))
+        ));
+        assert_eq!(out.written, b"Subject: binary\r\n\r\n");
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_enforce_capabilities() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("DATE", b"111 20201015043840\r\n")
+            .start()
+            .unwrap();
+
+        let mut client = connect(
+            server.addr(),
+            ClientConfig::default().enforce_capabilities(true),
+        );
+        match client.command(cmd::Over::Current) {
+            Err(Error::CapabilityMissing { capability }) => assert_eq!(capability, "OVER"),
+            res => panic!("Unexpected result {:?}", res),
+        }
+        // READER is advertised
+        assert_eq!(
+            client.command(cmd::Date).unwrap().code(),
+            ResponseCode::Known(Kind::Date)
+        );
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_lazy_capabilities() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("DATE", b"111 20201015043840\r\n")
+            .expect("DATE", b"111 20201015043840\r\n")
+            .start()
+            .unwrap();
+
+        let mut client = connect(
+            server.addr(),
+            ClientConfig::default()
+                .mode_reader(false)
+                .lazy_capabilities(true)
+                .enforce_capabilities(true),
+        );
+        assert!(client.capabilities().get("READER").is_none());
+
+        // DATE requires READER, so the capabilities are retrieved before it is sent
+        client.command(cmd::Date).unwrap();
+        assert!(client.capabilities().get("READER").is_some());
+
+        // They are only retrieved once
+        client.try_capabilities().unwrap();
+        client.command(cmd::Date).unwrap();
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_raw_command() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("XFEATURE COMPRESS GZIP", b"290 feature enabled\r\n")
+            .start()
+            .unwrap();
+
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+
+        assert!(matches!(
+            client.raw_command("DATE\r\nQUIT"),
+            Err(Error::InvalidCommand(_))
+        ));
+        // Arguments of typed commands are checked as well
+        assert!(matches!(
+            client.select_group("misc.test\r\nQUIT"),
+            Err(Error::InvalidCommand(_))
+        ));
+        let resp = client.raw_command("XFEATURE COMPRESS GZIP").unwrap();
+        assert_eq!(resp.code(), ResponseCode::from(290));
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_starttls() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .start()
+            .unwrap();
+        let err = ClientConfig::default()
+            .starttls("localhost".to_string())
+            .unwrap()
+            .connect(server.addr())
+            .unwrap_err();
+        match err {
+            Error::Unsupported { command, .. } => assert_eq!(command, "STARTTLS"),
+            e => panic!("Unexpected error {:?}", e),
+        }
+        server.finish().unwrap();
+
+        let server = MockScript::new()
+            .expect(
+                "CAPABILITIES",
+                b"101 Capability list:\r\nVERSION 2\r\nREADER\r\nSTARTTLS\r\n.\r\n",
+            )
+            .expect("STARTTLS", b"580 Can not initiate TLS negotiation\r\n")
+            .start()
+            .unwrap();
+        let err = ClientConfig::default()
+            .starttls("localhost".to_string())
+            .unwrap()
+            .connect(server.addr())
+            .unwrap_err();
+        match err {
+            Error::Connection(crate::raw::error::Error::StartTlsRejected(resp)) => {
+                assert_eq!(resp.code(), ResponseCode::Known(Kind::TlsNegotiationFailed))
+            }
+            e => panic!("Unexpected error {:?}", e),
+        }
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_secure_connection_required() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("GROUP misc.secret", b"483 Secure connection required\r\n")
+            .start()
+            .unwrap();
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+        match client.select_group("misc.secret") {
+            Err(Error::SecureConnectionRequired { resp }) => {
+                assert_eq!(
+                    resp.code(),
+                    ResponseCode::Known(Kind::SecureConnectionRequired)
+                )
+            }
+            res => panic!("Unexpected result {:?}", res),
+        }
+        drop(client);
+        server.finish().unwrap();
+
+        // With auto_starttls the connection is only upgraded once TLS is required
+        let server = MockScript::new()
+            .expect(
+                "CAPABILITIES",
+                b"101 Capability list:\r\nVERSION 2\r\nREADER\r\nSTARTTLS\r\n.\r\n",
+            )
+            .expect("GROUP misc.secret", b"483 Secure connection required\r\n")
+            .expect("STARTTLS", b"580 Can not initiate TLS negotiation\r\n")
+            .start()
+            .unwrap();
+        let mut client = connect(
+            server.addr(),
+            ClientConfig::default()
+                .starttls("localhost".to_string())
+                .unwrap()
+                .auto_starttls(true),
+        );
+        match client.select_group("misc.secret") {
+            Err(Error::Connection(crate::raw::error::Error::StartTlsRejected(_))) => {}
+            res => panic!("Unexpected result {:?}", res),
+        }
+        drop(client);
+        server.finish().unwrap();
+
+        // With credentials the connection is upgraded before authenticating regardless
+        let server = MockScript::new()
+            .expect(
+                "CAPABILITIES",
+                b"101 Capability list:\r\nVERSION 2\r\nREADER\r\nSTARTTLS\r\n.\r\n",
+            )
+            .expect("STARTTLS", b"580 Can not initiate TLS negotiation\r\n")
+            .start()
+            .unwrap();
+        let res = ClientConfig::default()
+            .starttls("localhost".to_string())
+            .unwrap()
+            .auto_starttls(true)
+            .authinfo_user_pass("alice", "secret")
+            .connect(server.addr());
+        match res {
+            Err(Error::Connection(crate::raw::error::Error::StartTlsRejected(_))) => {}
+            res => panic!("Unexpected result {:?}", res.map(|_| ())),
+        }
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_compress() {
+        // Servers that do not advertise compression are used uncompressed
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .start()
+            .unwrap();
+        let mut client = connect(server.addr(), ClientConfig::default().compress(true));
+        assert!(!client.conn().is_compressed());
+        drop(client);
+        server.finish().unwrap();
+
+        let server = MockScript::new()
+            .expect(
+                "CAPABILITIES",
+                b"101 Capability list:\r\nVERSION 2\r\nREADER\r\nCOMPRESS DEFLATE\r\n.\r\n",
+            )
+            .expect(
+                "COMPRESS DEFLATE",
+                b"403 Unable to activate compression\r\n",
+            )
+            .start()
+            .unwrap();
+        let err = ClientConfig::default()
+            .compress(true)
+            .connect(server.addr())
+            .unwrap_err();
+        match err {
+            Error::Connection(crate::raw::error::Error::CompressRejected(resp)) => {
+                assert_eq!(resp.code(), ResponseCode::Known(Kind::InternalError))
+            }
+            e => panic!("Unexpected error {:?}", e),
+        }
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_mode_reader() {
+        const TRANSIT: &[u8] =
+            b"101 Capability list:\r\nVERSION 2\r\nIHAVE\r\nMODE-READER\r\n.\r\n";
+
+        let server = MockScript::new()
+            .expect("CAPABILITIES", TRANSIT)
+            .expect("MODE READER", b"200 Reader mode, posting permitted\r\n")
+            .expect("CAPABILITIES", CAPABILITIES)
+            .start()
+            .unwrap();
+        let client = connect(server.addr(), &mut ClientConfig::default());
+        assert!(client.capabilities().get("READER").is_some());
+        drop(client);
+        server.finish().unwrap();
+
+        // Reader mode can be disabled
+        let server = MockScript::new()
+            .expect("CAPABILITIES", TRANSIT)
+            .start()
+            .unwrap();
+        let client = connect(server.addr(), ClientConfig::default().mode_reader(false));
+        assert!(client.capabilities().get("READER").is_none());
+        drop(client);
+        server.finish().unwrap();
+
+        // ...or forced without checking capabilities first
+        let server = MockScript::new()
+            .expect("MODE READER", b"201 Reader mode, posting prohibited\r\n")
+            .expect("CAPABILITIES", CAPABILITIES)
+            .start()
+            .unwrap();
+        connect(server.addr(), ClientConfig::default().mode_reader(true));
+        server.finish().unwrap();
+
+        let server = MockScript::new()
+            .expect("CAPABILITIES", TRANSIT)
+            .expect("MODE READER", b"502 Transit service only\r\n")
+            .start()
+            .unwrap();
+        let err = ClientConfig::default().connect(server.addr()).unwrap_err();
+        match err {
+            Error::Failure { code, msg, .. } => {
+                assert_eq!(code, ResponseCode::Known(Kind::PermanentlyUnavailable));
+                assert_eq!(msg.as_deref(), Some("Reader mode is not available"));
+            }
+            e => panic!("Unexpected error {:?}", e),
+        }
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_handshake_order() {
+        const TRANSIT: &[u8] =
+            b"101 Capability list:\r\nVERSION 2\r\nMODE-READER\r\nAUTHINFO USER\r\n.\r\n";
+
+        // By default the mode is switched before authenticating
+        let server = MockScript::new()
+            .expect("CAPABILITIES", TRANSIT)
+            .expect("MODE READER", b"200 Reader mode, posting permitted\r\n")
+            .expect(
+                "CAPABILITIES",
+                b"101 Capability list:\r\nVERSION 2\r\nREADER\r\nAUTHINFO USER\r\n.\r\n",
+            )
+            .expect("AUTHINFO USER alice", b"381 Password required\r\n")
+            .expect("AUTHINFO PASS hunter2", b"281 Authentication accepted\r\n")
+            .expect("CAPABILITIES", CAPABILITIES)
+            .start()
+            .unwrap();
+        connect(
+            server.addr(),
+            ClientConfig::default().authinfo_user_pass("alice", "hunter2"),
+        );
+        server.finish().unwrap();
+
+        let server = MockScript::new()
+            .expect("CAPABILITIES", TRANSIT)
+            .expect("AUTHINFO USER alice", b"381 Password required\r\n")
+            .expect("AUTHINFO PASS hunter2", b"281 Authentication accepted\r\n")
+            // The capabilities are retrieved again after each step
+            .expect(
+                "CAPABILITIES",
+                b"101 Capability list:\r\nVERSION 2\r\nMODE-READER\r\n.\r\n",
+            )
+            .expect("MODE READER", b"200 Reader mode, posting permitted\r\n")
+            .expect("CAPABILITIES", CAPABILITIES)
+            .start()
+            .unwrap();
+        let client = connect(
+            server.addr(),
+            ClientConfig::default()
+                .authinfo_user_pass("alice", "hunter2")
+                .handshake_order(HandshakeOrder::AuthFirst),
+        );
+        assert!(client.capabilities().get("READER").is_some());
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_authinfo_generic() {
+        let server = MockScript::new()
+            .expect(
+                "AUTHINFO GENERIC authsrv -u alice",
+                b"281 Authentication accepted\r\n",
+            )
+            .expect("CAPABILITIES", CAPABILITIES)
+            .start()
+            .unwrap();
+        let client = connect(
+            server.addr(),
+            ClientConfig::default()
+                .mode_reader(false)
+                .authinfo_generic("authsrv -u alice"),
+        );
+        assert!(client.capabilities().get("READER").is_some());
+        drop(client);
+        server.finish().unwrap();
+
+        let server = MockScript::new()
+            .expect(
+                "AUTHINFO GENERIC authsrv -u alice",
+                b"502 Authentication failed\r\n",
+            )
+            .start()
+            .unwrap();
+        let res = ClientConfig::default()
+            .mode_reader(false)
+            .authinfo_generic("authsrv -u alice")
+            .connect(server.addr());
+        match res {
+            Err(Error::Failure { code, msg, .. }) => {
+                assert_eq!(code, ResponseCode::Known(Kind::PermanentlyUnavailable));
+                assert_eq!(msg.as_deref(), Some("AUTHINFO GENERIC failed"));
+            }
+            res => panic!("Unexpected result {:?}", res.map(|_| ())),
+        }
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_authinfo_provider() {
+        const AUTH_CAPABILITIES: &[u8] =
+            b"101 Capability list:\r\nVERSION 2\r\nREADER\r\nAUTHINFO USER\r\n.\r\n";
+
+        let mut script = MockScript::new();
+        for password in &["secret-1", "secret-2"] {
+            if *password == "secret-2" {
+                script.reconnect();
+            }
+            script
+                .expect("CAPABILITIES", AUTH_CAPABILITIES)
+                .expect("AUTHINFO USER alice", b"381 Password required\r\n")
+                .expect(
+                    format!("AUTHINFO PASS {}", password),
+                    b"281 Authentication accepted\r\n",
+                )
+                .expect("CAPABILITIES", CAPABILITIES);
+        }
+        let server = script.start().unwrap();
+
+        // The provider is invoked again on reconnect, picking up the rotated password
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = calls.clone();
+        let mut client = connect(
+            server.addr(),
+            ClientConfig::default().authinfo_provider(Arc::new(move || {
+                let n = counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+                Ok(("alice".to_string(), format!("secret-{}", n)))
+            })),
+        );
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        client.reconnect().unwrap();
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_access_denied_before_auth() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", b"502 Authentication required\r\n")
+            .expect("AUTHINFO USER alice", b"381 Password required\r\n")
+            .expect("AUTHINFO PASS hunter2", b"281 Authentication accepted\r\n")
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("DATE", b"502 Access denied\r\n")
+            .expect("HELP", b"502 Access denied\r\n")
+            .start()
+            .unwrap();
+        let mut client = connect(
+            server.addr(),
+            ClientConfig::default().authinfo_user_pass("alice", "hunter2"),
+        );
+        assert!(client.capabilities().get("READER").is_some());
+        match client.date() {
+            Err(Error::AccessDenied { resp }) => {
+                assert_eq!(
+                    resp.code(),
+                    ResponseCode::Known(Kind::PermanentlyUnavailable)
+                )
+            }
+            res => panic!("Unexpected result {:?}", res),
+        }
+        // Raw commands return the response as-is
+        let resp = client.raw_command("HELP").unwrap();
+        assert_eq!(
+            resp.code(),
+            ResponseCode::Known(Kind::PermanentlyUnavailable)
+        );
+        drop(client);
+        server.finish().unwrap();
+
+        // Without credentials there's nothing to retry
+        let server = MockScript::new()
+            .expect("CAPABILITIES", b"502 Authentication required\r\n")
+            .start()
+            .unwrap();
+        let res = ClientConfig::default().connect(server.addr());
+        assert!(matches!(res, Err(Error::AccessDenied { .. })));
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_auth_or_mode_required_handler() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("GROUP misc.test", b"480 Authentication required\r\n")
+            .expect("AUTHINFO USER alice", b"281 Authentication accepted\r\n")
+            .expect("GROUP misc.test", b"211 2 1 2 misc.test\r\n")
+            .expect("GROUP alt.test", b"480 Authentication required\r\n")
+            .expect("AUTHINFO USER alice", b"480 Authentication required\r\n")
+            .start()
+            .unwrap();
+
+        let mut client = connect(
+            server.addr(),
+            ClientConfig::default().on_auth_or_mode_required(Some(Box::new(|client| {
+                client
+                    .command(cmd::AuthInfo::User("alice".to_string()))?
+                    .fail_unless(Kind::AuthenticationAccepted)?;
+                Ok(())
+            }))),
+        );
+
+        let group = client.select_group("misc.test").unwrap();
+        assert_eq!(group.name, "misc.test");
+
+        // The handler is not re-entered when its own command fails
+        match client.select_group("alt.test") {
+            Err(Error::UnexpectedCode { got, .. }) => {
+                assert_eq!(got, ResponseCode::Known(Kind::AuthenticationRequired))
+            }
+            res => panic!("Unexpected result {:?}", res),
+        }
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_reselect_group() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("GROUP misc.test", b"211 3 1 5 misc.test\r\n")
+            .expect("GROUP misc.test", b"211 4 1 6 misc.test\r\n")
+            .expect("GROUP alt.test", b"211 0 0 0 alt.test\r\n")
+            .expect("GROUP alt.test", b"211 2 7 8 alt.test\r\n")
+            .start()
+            .unwrap();
+
+        let mut client = connect(
+            server.addr(),
+            ClientConfig::default().skip_redundant_group_select(true),
+        );
+        assert!(client
+            .reselect_group(WhenUnselected::SelectLast)
+            .unwrap()
+            .is_none());
+        assert!(matches!(
+            client.reselect_group(WhenUnselected::Fail),
+            Err(Error::NoGroupSelected(ArticleId::Current))
+        ));
+        assert_eq!(client.last_group_name(), None);
+
+        assert!(!client.select_group_status("misc.test").unwrap().is_cached());
+        assert_eq!(client.last_group_name(), Some("misc.test"));
+
+        // GROUP is sent even though the group is already selected
+        let group = client
+            .reselect_group(WhenUnselected::Fail)
+            .unwrap()
+            .unwrap();
+        assert_eq!(group.high, 6);
+        assert_eq!(client.group(), Some(&group));
+
+        let selection = client.select_group_status("misc.test").unwrap();
+        assert_eq!(selection, GroupSelection::Cached(group));
+
+        // The current article is reset to the first article of the refreshed group
+        client.select_group("alt.test").unwrap();
+        assert_eq!(client.current_article(), None);
+        let group = client
+            .reselect_group(WhenUnselected::SelectLast)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            (group.name.as_str(), group.low, group.high),
+            ("alt.test", 7, 8)
+        );
+        assert_eq!(client.group(), Some(&group));
+        assert_eq!(client.current_article(), Some(7));
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_group_stats() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("GROUP alt.test", b"211 2 7 8 alt.test\r\n")
+            .expect("GROUP misc.test", b"211 3 1 5 misc.test\r\n")
+            .expect("NEXT", b"223 3 <c@example.com>\r\n")
+            .expect("GROUP alt.test", b"211 2 7 8 alt.test\r\n")
+            .expect("GROUP misc.test", b"211 4 1 6 misc.test\r\n")
+            .expect("STAT 3", b"223 3 <c@example.com>\r\n")
+            .expect("GROUP misc.test", b"211 4 1 6 misc.test\r\n")
+            .expect("STAT 3", b"423 No such article\r\n")
+            .start()
+            .unwrap();
+
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+
+        // Without a selected group the polled group stays selected
+        let stats = client.group_stats("alt.test").unwrap();
+        assert_eq!((stats.number, stats.low, stats.high), (2, 7, 8));
+        assert_eq!(client.group(), Some(&stats));
+
+        client.select_group("misc.test").unwrap();
+        client.next().unwrap();
+        assert_eq!(client.current_article(), Some(3));
+
+        let stats = client.group_stats("alt.test").unwrap();
+        assert_eq!(stats.name, "alt.test");
+        // The previous group is re-selected with fresh water marks and the article is restored
+        assert_eq!(client.group().unwrap().name, "misc.test");
+        assert_eq!(client.group().unwrap().high, 6);
+        assert_eq!(client.current_article(), Some(3));
+        assert_eq!(client.last_group_name(), Some("misc.test"));
+
+        // Polling the selected group still restores the article, unless it has been removed
+        client.group_stats("misc.test").unwrap();
+        assert_eq!(client.current_article(), Some(1));
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_greeting_software() {
+        let software = |line: &[u8]| {
+            greeting_software(&RawResponse {
+                code: ResponseCode::Known(Kind::PostingAllowed),
+                first_line: line.to_vec(),
+                data_blocks: None,
+            })
+        };
+
+        assert_eq!(
+            software(b"200 news.example.com InterNetNews NNRP server INN 2.6.3 ready\r\n")
+                .as_deref(),
+            Some("InterNetNews NNRP server INN 2.6.3 ready")
+        );
+        assert_eq!(
+            software(b"201 nntp.example.org Caf\xe9 NNTP\r\n").as_deref(),
+            Some("Caf\u{fffd} NNTP")
+        );
+        assert_eq!(software(b"200 ready\r\n"), None);
+        assert_eq!(software(b"200 news.example.com\r\n"), None);
+        assert_eq!(software(b"200 Welcome to Example News!\r\n"), None);
+        assert_eq!(software(b"200"), None);
+    }
+
+    #[test]
+    fn test_is_retryable() {
+        assert!(is_retryable(&cmd::Date));
+        assert!(is_retryable(&cmd::Article::Number(5)));
+        assert!(is_retryable(&cmd::Over::Articles(ArticleRange::from(
+            1..=5
+        ))));
+        assert!(is_retryable(&cmd::Hdr::MessageId {
+            field: "Subject".to_string(),
+            id: "<a@example.com>".to_string()
+        }));
+
+        assert!(!is_retryable(&cmd::Post));
+        assert!(!is_retryable(&cmd::Next));
+        assert!(!is_retryable(&cmd::Last));
+        assert!(!is_retryable(&cmd::Article::Current));
+        assert!(!is_retryable(&cmd::Stat::Current));
+        assert!(!is_retryable(&cmd::Over::Current));
+        assert!(!is_retryable(&cmd::Hdr::Current {
+            field: "Subject".to_string()
+        }));
+        assert!(!is_retryable(&RawLine::new("xover").unwrap()));
+    }
+
+    #[test]
+    fn test_auto_reconnect() {
+        // The server hangs up instead of responding to DATE and then to IHAVE
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("GROUP misc.test", b"211 2 1 2 misc.test\r\n")
+            .expect("DATE", b"")
+            .reconnect()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("GROUP misc.test", b"211 2 1 2 misc.test\r\n")
+            .expect("DATE", b"111 20201015043840\r\n")
+            .expect("IHAVE <a@example.com>", b"")
+            .disconnect()
+            .start()
+            .unwrap();
+
+        let mut client = connect(
+            server.addr(),
+            ClientConfig::default()
+                .group(Some("misc.test"))
+                .auto_reconnect(1),
+        );
+
+        assert_eq!(client.date().unwrap().to_string(), "2020-10-15T04:38:40Z");
+        assert_eq!(client.group().unwrap().name, "misc.test");
+
+        // IHAVE is never retried
+        assert!(matches!(
+            client.ihave("<a@example.com>", b"Subject: hi\r\n\r\nbody\r\n"),
+            Err(Error::Connection(_))
+        ));
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_service_unavailable() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("DATE", b"400 Idle timeout\r\n")
+            .reconnect()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("DATE", b"111 20201015043840\r\n")
+            .expect("IHAVE <a@example.com>", b"400 Shutting down\r\n")
+            .reconnect()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("DATE", b"111 20201015043840\r\n")
+            .start()
+            .unwrap();
+
+        let mut client = connect(server.addr(), ClientConfig::default().auto_reconnect(1));
+
+        // The 400 triggers a reconnect and the command is retried
+        assert_eq!(client.date().unwrap().to_string(), "2020-10-15T04:38:40Z");
+
+        // IHAVE is never retried so the error is surfaced
+        match client.ihave("<a@example.com>", b"Subject: hi\r\n\r\nbody\r\n") {
+            Err(Error::ServiceUnavailable { resp }) => {
+                assert_eq!(resp.first_line(), b"400 Shutting down\r\n")
+            }
+            res => panic!("Unexpected result {:?}", res),
+        }
+        // The connection is not reused
+        assert!(!client.is_healthy());
+
+        // The next retryable command reconnects
+        assert_eq!(client.date().unwrap().to_string(), "2020-10-15T04:38:40Z");
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_service_unavailable_streaming_and_post() {
+        let is_unavailable = |res: Result<_>| matches!(res, Err(Error::ServiceUnavailable { .. }));
+
+        // A 400 in response to the article is detected while streaming
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("ARTICLE <a@example.com>", b"400 Shutting down\r\n")
+            .start()
+            .unwrap();
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+        // The second attempt fails without being sent
+        for _ in 0..2 {
+            assert!(is_unavailable(
+                client
+                    .article_to_writer("<a@example.com>", &mut Vec::new())
+                    .map(|_| ())
+            ));
+        }
+        drop(client);
+        server.finish().unwrap();
+
+        // As is one in response to the article sent after the 340
+        let article = b"Subject: hi\r\n\r\nbody\r\n";
+        let server = MockScript::new()
+            .expect(
+                "CAPABILITIES",
+                b"101 Capability list:\r\nVERSION 2\r\nREADER\r\nPOST\r\n.\r\n",
+            )
+            .expect("POST", b"340 Send article\r\n")
+            .expect_data(&article[..], b"400 Shutting down\r\n")
+            .start()
+            .unwrap();
+        let mut client = connect(server.addr(), &mut ClientConfig::default());
+        assert!(is_unavailable(client.post(&article[..])));
+        assert!(is_unavailable(client.date().map(|_| ())));
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_check_auth_supported() {
+        let capabilities = |lines: &[&str]| {
+            let resp = RawResponse {
+                code: ResponseCode::Known(Kind::Capabilities),
+                first_line: b"101 Capability list:\r\n".to_vec(),
+                data_blocks: Some(DataBlocks::from_lines(lines)),
+            };
+            Capabilities::try_from(&resp).unwrap()
+        };
+        let sasl = AuthMethod::Sasl(SaslMechanism::ScramSha256);
+
+        let caps = capabilities(&["VERSION 2", "AUTHINFO USER SASL", "SASL SCRAM-SHA-256"]);
+        assert!(check_auth_supported(&caps, AuthMethod::UserPass, false).is_ok());
+        assert!(check_auth_supported(&caps, sasl, false).is_ok());
+
+        let caps = capabilities(&["VERSION 2", "AUTHINFO SASL", "SASL PLAIN"]);
+        assert!(matches!(
+            check_auth_supported(&caps, AuthMethod::UserPass, false),
+            Err(Error::AuthNotSupported { .. })
+        ));
+        assert!(matches!(
+            check_auth_supported(&caps, sasl, false),
+            Err(Error::AuthNotSupported { .. })
+        ));
+
+        // Servers may withhold AUTHINFO until TLS is active
+        let caps = capabilities(&["VERSION 2", "STARTTLS"]);
+        match check_auth_supported(&caps, AuthMethod::UserPass, false) {
+            Err(Error::AuthRequiresTls { method }) => assert_eq!(method, "AUTHINFO USER"),
+            res => panic!("Unexpected result {:?}", res),
+        }
+        assert!(matches!(
+            check_auth_supported(&caps, AuthMethod::UserPass, true),
+            Err(Error::AuthNotSupported { .. })
+        ));
+    }
+
+    #[test]
+    fn test_unknown_code_policy() {
+        let strict = UnknownCodePolicy::Strict;
+        assert!(!strict.accepts(ResponseCode::Unknown(299)));
+
+        let lenient = UnknownCodePolicy::Lenient;
+        assert!(lenient.accepts(ResponseCode::Unknown(299)));
+        assert!(!lenient.accepts(ResponseCode::Unknown(499)));
+        assert!(!lenient.accepts(ResponseCode::Known(Kind::NoSuchNewsgroup)));
+    }
+
+    #[test]
+    fn test_needs_recovery() {
+        assert!(needs_recovery(Kind::AuthenticationRequired.into()));
+        assert!(needs_recovery(Kind::WrongMode.into()));
+        assert!(needs_recovery(Kind::SecureConnectionRequired.into()));
+        assert!(!needs_recovery(Kind::NoSuchNewsgroup.into()));
+        assert!(!needs_recovery(ResponseCode::Unknown(480 + 100)));
+    }
+
+    #[test]
+    fn test_reconnect_rate_limit() {
+        let limit = Some((2, Duration::from_secs(60)));
+        let start = Instant::now();
+        let mut history = ReconnectHistory::default();
+
+        assert!(history.record(limit, start).is_ok());
+        assert!(history
+            .record(limit, start + Duration::from_secs(10))
+            .is_ok());
+        assert!(matches!(
+            history.record(limit, start + Duration::from_secs(20)),
+            Err(Error::ReconnectRateLimited { max: 2, .. })
+        ));
+
+        // the first reconnect has aged out of the window
+        assert!(history
+            .record(limit, start + Duration::from_secs(61))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_no_reconnect_rate_limit() {
+        let start = Instant::now();
+        let mut history = ReconnectHistory::default();
+
+        (0..100).for_each(|_| assert!(history.record(None, start).is_ok()));
     }
 }
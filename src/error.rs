@@ -1,4 +1,5 @@
 use std::str::Utf8Error;
+use std::time::Duration;
 
 use crate::types::prelude::*;
 
@@ -7,10 +8,9 @@ use crate::types::prelude::*;
 pub enum Error {
     /// This error indicates an application layer failure.
     ///
-    /// For example, asking for a non-existent group will return
-    /// [`NoSuchNewsGroup`](`crate::types::prelude::Kind::NoSuchNewsgroup`) (code 411),
-    /// which is not a protocol error.
-    #[error("Server returned {code:?} -- {msg:?}")]
+    /// For example, an `AUTHINFO` exchange that the server rejects. Responses that simply don't
+    /// match the code a command expects are returned as [`UnexpectedCode`](Self::UnexpectedCode).
+    #[error("Server returned {code} -- {msg:?}")]
     Failure {
         /// The response code
         code: ResponseCode,
@@ -19,25 +19,263 @@ pub enum Error {
         /// An error message associated with the response
         msg: Option<String>,
     },
+    /// The server responded with a different code than the command expects
+    ///
+    /// For example, an `ARTICLE` command rejected with a `500` returns `expected` 220
+    /// (`Article`) and `got` 500 (`UnknownCommand`).
+    #[error("Expected {expected} but the server returned {got}")]
+    UnexpectedCode {
+        /// The response code the command expects on success
+        expected: ResponseCode,
+        /// The response code returned by the server
+        got: ResponseCode,
+        /// The raw response
+        resp: RawResponse,
+    },
+    /// The server is in the wrong mode to process the command (response code 401)
+    ///
+    /// Per [RFC 3977](https://tools.ietf.org/html/rfc3977#section-3.2.1) the response includes
+    /// the label of the capability that must be used to change modes
+    /// (e.g. `401 MODE-READER`).
+    #[error("Server is in the wrong mode -- required capability {required_capability:?}")]
+    WrongMode {
+        /// The label of the capability needed to switch modes, if the server provided one
+        required_capability: Option<String>,
+        /// The raw response
+        resp: RawResponse,
+    },
+    /// The server returned a `GROUP` response with nonsensical water marks or article counts
+    #[error("Malformed group response for {} -- {msg}", group.name)]
+    MalformedGroupResponse {
+        /// The group parsed from the response
+        group: Group,
+        /// A description of the inconsistency
+        msg: String,
+    },
+    /// The client reconnected too many times within the configured window
+    ///
+    /// See [`ClientConfig::reconnect_rate_limit`](crate::client::ClientConfig::reconnect_rate_limit)
+    #[error("Exceeded {max} reconnects within {per:?}")]
+    ReconnectRateLimited {
+        /// The maximum number of reconnects allowed within the window
+        max: u32,
+        /// The length of the window
+        per: Duration,
+    },
+    /// The server is discontinuing the session (response code 400)
+    ///
+    /// Servers send a `400` in response to any command when they are shutting down the session
+    /// (e.g. after a timeout or when overloaded). The connection can no longer be used, so
+    /// subsequent commands fail with this error without being sent until the client
+    /// [reconnects](crate::client::NntpClient::reconnect). This also applies to the
+    /// `AsyncNntpClient`, which can't reconnect.
+    #[error("Service unavailable -- {}", resp.first_line_to_utf8_lossy().trim_end())]
+    ServiceUnavailable {
+        /// The raw response
+        resp: RawResponse,
+    },
+    /// The server denied access (response code 502)
+    ///
+    /// Some servers deny every command, including `CAPABILITIES`, until the client has
+    /// authenticated. If credentials are configured and a `502` is received while connecting
+    /// before authenticating, the client authenticates and retries, so this is only returned
+    /// if access is still denied or no credentials are configured.
+    #[error("Access denied -- {}", resp.first_line_to_utf8_lossy().trim_end())]
+    AccessDenied {
+        /// The raw response
+        resp: RawResponse,
+    },
+    /// The command may only be sent over a secure connection (response code 483)
+    ///
+    /// Reconnect with TLS, or see
+    /// [`ClientConfig::auto_starttls`](crate::client::ClientConfig::auto_starttls) to upgrade the
+    /// connection automatically.
+    #[error("Secure connection required -- {}", resp.first_line_to_utf8_lossy().trim_end())]
+    SecureConnectionRequired {
+        /// The raw response
+        resp: RawResponse,
+    },
+    /// The newsgroup does not exist (response code 411)
+    ///
+    /// This is returned when selecting a group with `GROUP` or `LISTGROUP`. The previously
+    /// selected group, if any, remains selected.
+    #[error("No such newsgroup -- {}", resp.first_line_to_utf8_lossy().trim_end())]
+    NoSuchNewsgroup {
+        /// The raw response
+        resp: RawResponse,
+    },
+    /// The requested article does not exist
+    ///
+    /// This is returned for `430` (no article with that message ID), `423` (no article with that
+    /// number), and `420` (no current article) responses.
+    #[error("No such article {id:?} -- {code}")]
+    NoSuchArticle {
+        /// The ID of the article that was requested
+        id: ArticleId,
+        /// The response code
+        code: ResponseCode,
+        /// The raw response
+        resp: RawResponse,
+    },
+    /// Retrieving a range of articles failed part way through
+    ///
+    /// See [`NntpClient::articles`](crate::client::NntpClient::articles)
+    #[error("Failed after retrieving {} articles -- {source}", articles.len())]
+    IncompleteArticles {
+        /// The articles that were retrieved before the failure, along with their numbers
+        articles: Vec<(ArticleNumber, BinaryArticle)>,
+        /// The error that stopped the retrieval
+        #[source]
+        source: Box<Error>,
+    },
+    /// There is no article after the current article in the selected group (response code 421)
+    #[error("No next article in the selected group")]
+    NoNextArticle {
+        /// The raw response
+        resp: RawResponse,
+    },
+    /// There is no article before the current article in the selected group (response code 422)
+    #[error("No previous article in the selected group")]
+    NoPreviousArticle {
+        /// The raw response
+        resp: RawResponse,
+    },
+    /// An article number was used without selecting a group
+    ///
+    /// Article numbers are relative to the selected group, so sending them without one would
+    /// only result in a `412` from the server.
+    #[error("No group selected -- cannot retrieve article {0:?}")]
+    NoGroupSelected(ArticleId),
+    /// The server does not permit posting
+    ///
+    /// This is returned if the server does not advertise the `POST` capability (in which case
+    /// `resp` is `None`) or rejects the `POST` command with a `440`.
+    #[error("Posting is not permitted")]
+    PostingNotPermitted {
+        /// The raw response, if the server rejected the command
+        resp: Option<RawResponse>,
+    },
+    /// The server does not support the command
+    ///
+    /// This is returned if a command that requires a capability is used when the server does not
+    /// advertise it (in which case `resp` is `None`), or if the server responds to a
+    /// non-standard command with `500` or `501`.
+    #[error("{command} is not supported by the server")]
+    Unsupported {
+        /// The command that is not supported
+        command: String,
+        /// The raw response, if the server rejected the command
+        resp: Option<RawResponse>,
+    },
+    /// The server does not advertise the capability a command requires
+    ///
+    /// This is only returned when
+    /// [`ClientConfig::enforce_capabilities`](crate::client::ClientConfig::enforce_capabilities)
+    /// is enabled, in which case the command is not sent.
+    #[error("The server does not advertise the {capability} capability")]
+    CapabilityMissing {
+        /// The label of the missing capability (e.g. `OVER`)
+        capability: String,
+    },
+    /// The server does not advertise the configured authentication method
+    ///
+    /// The credentials were not sent.
+    #[error("{method} is not supported by the server")]
+    AuthNotSupported {
+        /// The `AUTHINFO` variant that was configured (e.g. `AUTHINFO USER`)
+        method: String,
+    },
+    /// The server only offers the configured authentication method over TLS
+    ///
+    /// This is returned when the connection is in plain text and the server advertises
+    /// `STARTTLS` but not the method, as it will likely be offered once TLS is active
+    /// (see [`ClientConfig::starttls`](crate::client::ClientConfig::starttls)).
+    /// The credentials were not sent.
+    #[error("{method} requires TLS")]
+    AuthRequiresTls {
+        /// The `AUTHINFO` variant that was configured (e.g. `AUTHINFO USER`)
+        method: String,
+    },
+    /// An article could not be built because it is invalid
+    ///
+    /// See [`ArticleBuilder`](crate::posting::ArticleBuilder)
+    #[error("Invalid article -- {0}")]
+    InvalidArticle(String),
+    /// A message-id is malformed
+    ///
+    /// See [`MessageId`](crate::types::MessageId)
+    #[error("Invalid message-id -- {0}")]
+    InvalidMessageId(String),
+    /// A wildmat is malformed
+    ///
+    /// See [`Wildmat`](crate::types::Wildmat)
+    #[error("Invalid wildmat -- {0}")]
+    InvalidWildmat(String),
+    /// Writing to a caller provided writer failed
+    ///
+    /// This is distinct from I/O errors on the connection, which are returned as
+    /// [`Connection`](Self::Connection) errors.
+    /// See [`NntpClient::article_to_writer`](crate::client::NntpClient::article_to_writer)
+    #[error("Failed to write output -- {0}")]
+    Output(#[source] std::io::Error),
+    /// A command could not be sent because it is invalid
+    ///
+    /// For example, an argument contains a CR or LF or the command line exceeds
+    /// [`MAX_COMMAND_LINE_LEN`](crate::types::command::MAX_COMMAND_LINE_LEN).
+    /// Nothing was sent to the server.
+    #[error("Invalid command -- {0}")]
+    InvalidCommand(String),
+    /// The caller cancelled the command before its response was fully read
+    ///
+    /// See [`NntpClient::command_with_cancel`](crate::client::NntpClient::command_with_cancel).
+    /// The connection is left mid-response, so the client must be dropped or reconnected.
+    #[error("Command was cancelled")]
+    Cancelled,
     #[error(transparent)]
     /// An error raised by the underlying connection
     ///
     /// This is usually of an I/O error or a TLS error
-    Connection(#[from] crate::raw::error::Error),
+    Connection(crate::raw::error::Error),
     /// An error deserializing a [RawResponse] into a concrete type
     #[error("{0}")]
     Deserialization(String),
+    /// An error raised during a SASL exchange
+    ///
+    /// For example, the server sent a malformed challenge or could not prove that it knows
+    /// the client's credentials
+    #[error("SASL error -- {0}")]
+    Sasl(String),
     /// An error deserializing bytes as UTF-8
     #[error("{0}")]
     Utf8(#[from] Utf8Error),
 }
 
 impl Error {
-    pub(crate) fn failure(resp: RawResponse) -> Self {
-        Error::Failure {
-            code: resp.code(),
+    /// The error for a response that doesn't match the `expected` code
+    ///
+    /// A `401` is still returned as [`WrongMode`](Self::WrongMode) as it carries the capability
+    /// needed to recover, and a `411` as [`NoSuchNewsgroup`](Self::NoSuchNewsgroup).
+    pub(crate) fn unexpected_code(expected: impl Into<ResponseCode>, resp: RawResponse) -> Self {
+        match resp.code() {
+            ResponseCode::Known(Kind::WrongMode) => Error::wrong_mode(resp),
+            ResponseCode::Known(Kind::NoSuchNewsgroup) => Error::NoSuchNewsgroup { resp },
+            got => Error::UnexpectedCode {
+                expected: expected.into(),
+                got,
+                resp,
+            },
+        }
+    }
+
+    pub(crate) fn wrong_mode(resp: RawResponse) -> Self {
+        let required_capability = String::from_utf8_lossy(resp.first_line_without_code())
+            .split_whitespace()
+            .next()
+            .map(ToString::to_string);
+
+        Error::WrongMode {
+            required_capability,
             resp,
-            msg: None,
         }
     }
 
@@ -45,6 +283,14 @@ impl Error {
         Error::Deserialization(msg.as_ref().to_string())
     }
 
+    pub(crate) fn sasl(msg: impl AsRef<str>) -> Self {
+        Error::Sasl(msg.as_ref().to_string())
+    }
+
+    pub(crate) fn invalid_article(msg: impl AsRef<str>) -> Self {
+        Error::InvalidArticle(msg.as_ref().to_string())
+    }
+
     pub(crate) fn missing_field(name: impl AsRef<str>) -> Self {
         Error::Deserialization(format!("Missing field `{}`", name.as_ref()))
     }
@@ -62,5 +308,69 @@ impl Error {
     }
 }
 
+impl From<crate::raw::error::Error> for Error {
+    fn from(e: crate::raw::error::Error) -> Self {
+        match e {
+            crate::raw::error::Error::InvalidCommand(msg) => Error::InvalidCommand(msg),
+            e => Error::Connection(e),
+        }
+    }
+}
+
 /// A result type returned by the library
 pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrong_mode() {
+        let resp = RawResponse {
+            code: ResponseCode::Known(Kind::WrongMode),
+            first_line: b"401 MODE-READER you must send MODE READER first\r\n".to_vec(),
+            data_blocks: None,
+        };
+
+        match Error::unexpected_code(Kind::GroupSelected, resp) {
+            Error::WrongMode {
+                required_capability,
+                ..
+            } => assert_eq!(required_capability, Some("MODE-READER".to_string())),
+            e => panic!("Unexpected error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_unexpected_code() {
+        let resp = RawResponse {
+            code: ResponseCode::Known(Kind::UnknownCommand),
+            first_line: b"500 What?\r\n".to_vec(),
+            data_blocks: None,
+        };
+
+        match Error::unexpected_code(Kind::GroupSelected, resp) {
+            Error::UnexpectedCode { expected, got, .. } => {
+                assert_eq!(expected, ResponseCode::Known(Kind::GroupSelected));
+                assert_eq!(got, ResponseCode::Known(Kind::UnknownCommand));
+            }
+            e => panic!("Unexpected error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_no_such_newsgroup() {
+        let resp = RawResponse {
+            code: ResponseCode::Known(Kind::NoSuchNewsgroup),
+            first_line: b"411 No such group\r\n".to_vec(),
+            data_blocks: None,
+        };
+
+        match Error::unexpected_code(Kind::GroupSelected, resp) {
+            Error::NoSuchNewsgroup { resp } => {
+                assert_eq!(resp.code(), ResponseCode::Known(Kind::NoSuchNewsgroup))
+            }
+            e => panic!("Unexpected error {:?}", e),
+        }
+    }
+}
@@ -17,6 +17,9 @@
 //! 2. The [`NntpConnection`] provides a lower-level abstraction that
 //! only provides validation that messages adhere to NNTP's wire format.
 //!
+//! Enabling the `async` feature adds `AsyncNntpClient` and `AsyncNntpConnection`, which
+//! mirror the above on top of [tokio](https://tokio.rs).
+//!
 //! Brokaw additionally provides strongly typed [commands](types::command),
 //! [responses](types::response), and the [`NntpCommand`](types::NntpCommand)
 //! trait for implementing your own strongly typed commands.
@@ -28,24 +31,47 @@
 #[cfg(doctest)]
 doc_comment::doctest!("../README.md");
 
+/// The high-level async client, enabled by the `async` feature
+#[cfg(feature = "async")]
+pub mod async_client;
+
 /// The high-level client and configuration API
 pub mod client;
 
 /// Error and Result types returned by the Brokaw
 pub mod error;
 
+/// Building and posting articles
+pub mod posting;
+
 /// Low level connection/stream APIs
 ///
 /// These deal with raw NNTP connections and byte responses.
 /// Consider using the higher level [`client`] APIs unless you have special requirements
 pub mod raw;
 
+/// SASL mechanisms for `AUTHINFO SASL`
+pub mod sasl;
+
+/// A scripted mock server for tests, enabled by the `test-support` feature
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support;
+
 /// Typed commands, responses, and response codes
 pub mod types;
 
+#[cfg(feature = "async")]
+#[doc(inline)]
+pub use async_client::AsyncNntpClient;
 #[doc(inline)]
-pub use client::{ClientConfig, NntpClient};
+pub use client::{
+    ActiveGroupIter, ArticleIter, CacheStats, ClientConfig, GroupSelection, NntpClient,
+    OverviewIter, OverviewMethod, ParsedLineIter, TimeoutGuard, WhenUnselected,
+};
 #[doc(inline)]
 pub use raw::connection::{ConnectionConfig, NntpConnection};
+#[cfg(feature = "async")]
+#[doc(inline)]
+pub use raw::AsyncNntpConnection;
 #[doc(inline)]
 pub use raw::Compression;
@@ -0,0 +1,601 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+
+use crate::client::NntpClient;
+use crate::error::{Error, Result};
+use crate::types::MessageId;
+
+/// The maximum length of a line, excluding the CRLF
+///
+/// Per [RFC 5322](https://tools.ietf.org/html/rfc5322#section-2.1.1)
+const MAX_LINE_LENGTH: usize = 998;
+
+/// The length that header lines are folded to where possible, excluding the CRLF
+///
+/// Per [RFC 5322](https://tools.ietf.org/html/rfc5322#section-2.1.1)
+const FOLD_LENGTH: usize = 78;
+
+/// A builder for articles to be sent via `POST`
+///
+/// The `From`, `Newsgroups`, and `Subject` headers are required.
+/// If the `Date` or `Message-ID` headers are not provided they will be generated when the
+/// article is built.
+///
+/// Articles are validated when they are built to catch the most common reasons servers reject
+/// posts:
+///
+/// * Header names must be printable ASCII and may not contain colons
+/// * Header values may not contain CR or LF characters
+/// * Lines may not exceed 998 bytes
+///
+/// Headers longer than 78 characters (e.g. a long `References` header) are folded at whitespace,
+/// so only a header containing a run of more than 998 bytes without whitespace is rejected.
+///
+/// Line endings in the body are normalized to CRLF. Dot-stuffing is performed by the connection
+/// when the article is sent, so the body should NOT be dot-stuffed.
+///
+/// ## Example
+///
+/// ```
+/// use brokaw::posting::ArticleBuilder;
+///
+/// # fn main() -> brokaw::error::Result<()> {
+/// let article = ArticleBuilder::new()
+///     .from("Alice <alice@example.com>")
+///     .newsgroups(&["misc.test"])
+///     .subject("Hello")
+///     .body("Hello world!\n")
+///     .build()?;
+///
+/// assert!(article.ends_with(b"\r\n\r\nHello world!\r\n"));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ArticleBuilder {
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    message_id_domain: Option<String>,
+}
+
+impl ArticleBuilder {
+    /// Create a new builder
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set the `From` header
+    pub fn from(&mut self, from: impl AsRef<str>) -> &mut Self {
+        self.header("From", from)
+    }
+
+    /// Set the `Newsgroups` header
+    pub fn newsgroups(&mut self, groups: &[impl AsRef<str>]) -> &mut Self {
+        let groups = groups
+            .iter()
+            .map(|g| g.as_ref().trim())
+            .collect::<Vec<_>>()
+            .join(",");
+        self.header("Newsgroups", groups)
+    }
+
+    /// Set the `Subject` header
+    pub fn subject(&mut self, subject: impl AsRef<str>) -> &mut Self {
+        self.header("Subject", subject)
+    }
+
+    /// Set the `Message-ID` header
+    ///
+    /// If this is not set a Message-ID will be generated using the
+    /// [`message_id_domain`](Self::message_id_domain)
+    pub fn message_id(&mut self, id: impl AsRef<str>) -> &mut Self {
+        self.header("Message-ID", id)
+    }
+
+    /// Set the domain used to generate a Message-ID
+    ///
+    /// If this is not set, the domain of the `From` address is used
+    pub fn message_id_domain(&mut self, domain: Option<impl AsRef<str>>) -> &mut Self {
+        self.message_id_domain = domain.map(|d| d.as_ref().to_string());
+        self
+    }
+
+    /// Set the `Date` header
+    ///
+    /// If this is not set the current time will be used
+    pub fn date(&mut self, date: SystemTime) -> &mut Self {
+        self.header("Date", format_date(date))
+    }
+
+    /// Set the `References` header
+    pub fn references(&mut self, ids: &[impl AsRef<str>]) -> &mut Self {
+        let ids = ids
+            .iter()
+            .map(|id| id.as_ref().trim())
+            .collect::<Vec<_>>()
+            .join(" ");
+        self.header("References", ids)
+    }
+
+    /// Set a header, replacing any existing header with the same (case-insensitive) name
+    pub fn header(&mut self, name: impl AsRef<str>, value: impl AsRef<str>) -> &mut Self {
+        let (name, value) = (name.as_ref(), value.as_ref());
+        match self
+            .headers
+            .iter_mut()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        {
+            Some(header) => header.1 = value.to_string(),
+            None => self.headers.push((name.to_string(), value.to_string())),
+        }
+        self
+    }
+
+    /// Set the body of the article
+    pub fn body(&mut self, body: impl AsRef<[u8]>) -> &mut Self {
+        self.body = body.as_ref().to_vec();
+        self
+    }
+
+    /// Validate the article and return the bytes that should be sent to the server
+    pub fn build(&self) -> Result<Vec<u8>> {
+        for required in &["From", "Newsgroups", "Subject"] {
+            match self.get(required) {
+                Some(value) if !value.trim().is_empty() => {}
+                _ => {
+                    return Err(Error::invalid_article(format!(
+                        "Missing required header `{}`",
+                        required
+                    )))
+                }
+            }
+        }
+
+        if let Some(group) = self
+            .get("Newsgroups")
+            .into_iter()
+            .flat_map(|groups| groups.split(','))
+            .find(|g| g.is_empty() || g.contains(char::is_whitespace))
+        {
+            return Err(Error::invalid_article(format!(
+                "Invalid newsgroup name `{}`",
+                group
+            )));
+        }
+
+        let mut headers = self.headers.clone();
+        if self.get("Date").is_none() {
+            headers.push(("Date".to_string(), format_date(SystemTime::now())));
+        }
+        match self.get("Message-ID") {
+            Some(id) if !is_message_id(id) => {
+                return Err(Error::invalid_article(format!(
+                    "Invalid Message-ID `{}`",
+                    id
+                )))
+            }
+            Some(_) => {}
+            None => {
+                let domain = self
+                    .message_id_domain
+                    .as_deref()
+                    .or_else(|| self.get("From").and_then(address_domain))
+                    .ok_or_else(|| {
+                        Error::invalid_article("No domain available to generate a Message-ID")
+                    })?;
+                headers.push(("Message-ID".to_string(), generate_message_id(domain)));
+            }
+        }
+
+        let mut article = Vec::with_capacity(self.body.len() + 512);
+        for (name, value) in headers.iter() {
+            validate_header(name, value)?;
+            article.extend_from_slice(&fold_header(name, value)?);
+        }
+        article.extend_from_slice(b"\r\n");
+
+        let body = normalize_line_endings(&self.body);
+        if let Some(n) = body
+            .split(|&b| b == b'\n')
+            .position(|line| line.len() > MAX_LINE_LENGTH + 1)
+        {
+            return Err(Error::invalid_article(format!(
+                "Line {} of the body exceeds {} bytes",
+                n + 1,
+                MAX_LINE_LENGTH
+            )));
+        }
+        article.extend_from_slice(&body);
+
+        Ok(article)
+    }
+
+    /// Build the article and post it with the provided client
+    ///
+    /// If the article has neither a `User-Agent` nor an `X-Newsreader` header, the client's
+    /// [user agent](crate::client::ClientConfig::user_agent) is added.
+    pub fn post_with(&self, client: &mut NntpClient) -> Result<()> {
+        let article = self.build_with_user_agent(client.config().default_user_agent())?;
+        client.post(article)
+    }
+
+    fn build_with_user_agent(&self, user_agent: Option<&str>) -> Result<Vec<u8>> {
+        match user_agent {
+            Some(ua) if self.get("User-Agent").is_none() && self.get("X-Newsreader").is_none() => {
+                self.clone().header("User-Agent", ua).build()
+            }
+            _ => self.build(),
+        }
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// The outcome of offering an article to a server via
+/// [`NntpClient::ihave`](crate::client::NntpClient::ihave)
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum IHaveOutcome {
+    /// The article was transferred successfully (`235`)
+    Accepted,
+    /// The server does not want the article, e.g. because it already has it (`435`)
+    ///
+    /// The article is not sent.
+    NotWanted,
+    /// The transfer failed and should be retried later (`436`)
+    RetryLater,
+    /// The article was rejected and should not be offered again (`437`)
+    Rejected,
+}
+
+fn validate_header(name: &str, value: &str) -> Result<()> {
+    if name.is_empty() || !name.bytes().all(|b| (33..=126).contains(&b) && b != b':') {
+        return Err(Error::invalid_article(format!(
+            "Invalid header name `{}`",
+            name
+        )));
+    }
+    if value.contains(['\r', '\n']) {
+        return Err(Error::invalid_article(format!(
+            "Header `{}` contains a CR or LF",
+            name
+        )));
+    }
+    Ok(())
+}
+
+/// Format a header as `Name: value\r\n`, folding it before whitespace to keep the lines within
+/// [`FOLD_LENGTH`] where possible
+///
+/// Returns an error if a line still exceeds [`MAX_LINE_LENGTH`].
+fn fold_header(name: &str, value: &str) -> Result<Vec<u8>> {
+    let mut folded = Vec::with_capacity(name.len() + value.len() + 8);
+    folded.extend_from_slice(name.as_bytes());
+    folded.extend_from_slice(b": ");
+    let mut line_len = folded.len();
+
+    // Each word after the first keeps the whitespace that precedes it, so folding only inserts
+    // a CRLF and unfolding restores the original value
+    let value = value.as_bytes();
+    let is_wsp = |b: &u8| *b == b' ' || *b == b'\t';
+    let mut words = Vec::new();
+    let mut start = 0;
+    for i in 1..value.len() {
+        if is_wsp(&value[i]) && !is_wsp(&value[i - 1]) {
+            words.push(&value[start..i]);
+            start = i;
+        }
+    }
+    words.push(&value[start..]);
+
+    for (i, word) in words.into_iter().enumerate() {
+        let foldable = i > 0 && !word.iter().all(is_wsp);
+        if foldable && line_len + word.len() > FOLD_LENGTH {
+            folded.extend_from_slice(b"\r\n");
+            line_len = 0;
+        }
+        if line_len + word.len() > MAX_LINE_LENGTH {
+            return Err(Error::invalid_article(format!(
+                "Header `{}` exceeds {} bytes",
+                name, MAX_LINE_LENGTH
+            )));
+        }
+        folded.extend_from_slice(word);
+        line_len += word.len();
+    }
+    folded.extend_from_slice(b"\r\n");
+
+    Ok(folded)
+}
+
+/// Convert bare CRs and LFs into CRLFs and ensure the body ends with a CRLF
+fn normalize_line_endings(body: &[u8]) -> Vec<u8> {
+    let mut normalized = Vec::with_capacity(body.len() + 2);
+    let mut iter = body.iter().peekable();
+    while let Some(&byte) = iter.next() {
+        match byte {
+            b'\r' => {
+                iter.next_if_eq(&&b'\n');
+                normalized.extend_from_slice(b"\r\n");
+            }
+            b'\n' => normalized.extend_from_slice(b"\r\n"),
+            b => normalized.push(b),
+        }
+    }
+    if !normalized.is_empty() && !normalized.ends_with(b"\r\n") {
+        normalized.extend_from_slice(b"\r\n");
+    }
+    normalized
+}
+
+/// Returns true if the id is a valid [`MessageId`] of the form `<left@right>`
+fn is_message_id(id: &str) -> bool {
+    MessageId::new(id).is_ok()
+        && id[1..id.len() - 1]
+            .split_once('@')
+            .is_some_and(|(left, right)| !left.is_empty() && !right.is_empty())
+}
+
+/// Extract the domain from an address such as `Alice <alice@example.com>`
+fn address_domain(from: &str) -> Option<&str> {
+    let (_, rest) = from.rsplit_once('@')?;
+    let domain = rest
+        .split(|c: char| c == '>' || c == ')' || c.is_whitespace())
+        .next()?;
+    Some(domain).filter(|d| !d.is_empty())
+}
+
+/// Generate a unique Message-ID for the given domain
+///
+/// The ID is made from the current time and a random string, e.g. `<1602720000.Dk3jX0aVbQ@example.com>`
+pub fn generate_message_id(domain: impl AsRef<str>) -> String {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let unique = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(16)
+        .map(char::from)
+        .collect::<String>();
+
+    format!("<{}.{}@{}>", timestamp, unique, domain.as_ref())
+}
+
+/// Format a time as an [RFC 5322](https://tools.ietf.org/html/rfc5322#section-3.3) date in UTC
+///
+/// e.g. `Thu, 15 Oct 2020 00:00:00 +0000`
+pub fn format_date(time: SystemTime) -> String {
+    const DAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let (days, secs_of_day) = (secs / 86400, secs % 86400);
+
+    // Convert days since the epoch to a civil date
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{}, {:02} {} {} {:02}:{:02}:{:02} +0000",
+        DAYS[(days % 7) as usize],
+        day,
+        MONTHS[(month - 1) as usize],
+        year,
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    fn builder() -> ArticleBuilder {
+        ArticleBuilder::new()
+            .from("Alice <alice@example.com>")
+            .newsgroups(&["misc.test", "alt.test"])
+            .subject("Hello")
+            .date(UNIX_EPOCH + Duration::from_secs(1_602_720_000))
+            .message_id("<1234@example.com>")
+            .body("Hello\nworld\n")
+            .to_owned()
+    }
+
+    #[test]
+    fn test_build() {
+        let article = builder().build().unwrap();
+        assert_eq!(
+            String::from_utf8(article).unwrap(),
+            "From: Alice <alice@example.com>\r\n\
+             Newsgroups: misc.test,alt.test\r\n\
+             Subject: Hello\r\n\
+             Date: Thu, 15 Oct 2020 00:00:00 +0000\r\n\
+             Message-ID: <1234@example.com>\r\n\
+             \r\n\
+             Hello\r\n\
+             world\r\n"
+        );
+    }
+
+    #[test]
+    fn test_user_agent() {
+        let has_header = |article: Vec<u8>, header: &str| {
+            String::from_utf8(article)
+                .unwrap()
+                .lines()
+                .any(|line| line == header)
+        };
+
+        let article = builder().build_with_user_agent(Some("test/1.0")).unwrap();
+        assert!(has_header(article, "User-Agent: test/1.0"));
+
+        let article = builder().build_with_user_agent(None).unwrap();
+        assert_eq!(article, builder().build().unwrap());
+
+        // Per-article headers take precedence
+        for name in &["user-agent", "X-Newsreader"] {
+            let article = builder()
+                .header(name, "custom/2.0")
+                .build_with_user_agent(Some("test/1.0"))
+                .unwrap();
+            assert!(has_header(
+                article.clone(),
+                &format!("{}: custom/2.0", name)
+            ));
+            assert!(!has_header(article, "User-Agent: test/1.0"));
+        }
+    }
+
+    #[test]
+    fn test_missing_required_headers() {
+        let mut builder = builder();
+        builder.header("Subject", "");
+        assert!(matches!(builder.build(), Err(Error::InvalidArticle(_))));
+
+        let builder = ArticleBuilder::new()
+            .from("alice@example.com")
+            .subject("Hello")
+            .to_owned();
+        assert!(matches!(builder.build(), Err(Error::InvalidArticle(_))));
+    }
+
+    #[test]
+    fn test_invalid_headers() {
+        assert!(builder().header("X Bad", "value").build().is_err());
+        assert!(builder().header("X-Bad:", "value").build().is_err());
+        assert!(builder().header("X-Bad", "a\r\nb").build().is_err());
+        assert!(builder()
+            .header("X-Long", "a".repeat(MAX_LINE_LENGTH))
+            .build()
+            .is_err());
+        assert!(builder().message_id("1234@example.com").build().is_err());
+        assert!(builder().message_id("<1234>").build().is_err());
+        assert!(builder().message_id("<12 34@example.com>").build().is_err());
+        assert!(builder()
+            .newsgroups(&["misc.test", "alt test"])
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_fold_header() {
+        let ids = (0..20)
+            .map(|i| format!("<{}@example.com>", i))
+            .collect::<Vec<_>>();
+        let article = builder().references(&ids).build().unwrap();
+        let article = String::from_utf8(article).unwrap();
+        let (headers, _) = article.split_once("\r\n\r\n").unwrap();
+
+        let references = headers
+            .split("\r\n")
+            .skip_while(|line| !line.starts_with("References:"))
+            .take_while(|line| line.starts_with("References:") || line.starts_with(' '))
+            .collect::<Vec<_>>();
+        assert!(references.len() > 1);
+        assert!(references.iter().all(|line| line.len() <= FOLD_LENGTH));
+        assert_eq!(
+            references.concat(),
+            format!("References: {}", ids.join(" "))
+        );
+
+        // Short headers and trailing whitespace aren't folded
+        assert_eq!(
+            fold_header("Subject", "Hi  ").unwrap(),
+            b"Subject: Hi  \r\n"
+        );
+        // A single word can't be folded
+        let word = "a".repeat(100);
+        assert_eq!(
+            fold_header("X-Long", &word).unwrap(),
+            format!("X-Long: {}\r\n", word).into_bytes()
+        );
+        assert!(fold_header("X-Long", &format!("a {}", "b".repeat(MAX_LINE_LENGTH))).is_err());
+    }
+
+    #[test]
+    fn test_long_body_line() {
+        let line = "a".repeat(MAX_LINE_LENGTH);
+        assert!(builder().body(&line).build().is_ok());
+        assert!(builder().body(format!("{}a\r\n", line)).build().is_err());
+    }
+
+    #[test]
+    fn test_normalize_line_endings() {
+        assert_eq!(
+            normalize_line_endings(b"a\nb\r\nc\rd"),
+            b"a\r\nb\r\nc\r\nd\r\n".to_vec()
+        );
+        assert_eq!(normalize_line_endings(b""), b"".to_vec());
+    }
+
+    #[test]
+    fn test_generated_headers() {
+        let article = ArticleBuilder::new()
+            .from("Alice <alice@example.com>")
+            .newsgroups(&["misc.test"])
+            .subject("Hello")
+            .build()
+            .unwrap();
+        let article = String::from_utf8(article).unwrap();
+
+        assert!(article.contains("\r\nDate: "));
+        let id = article
+            .lines()
+            .find_map(|l| l.strip_prefix("Message-ID: "))
+            .unwrap();
+        assert!(is_message_id(id));
+        assert!(id.ends_with("@example.com>"));
+
+        let article = ArticleBuilder::new()
+            .from("alice")
+            .newsgroups(&["misc.test"])
+            .subject("Hello")
+            .build();
+        assert!(article.is_err());
+    }
+
+    #[test]
+    fn test_address_domain() {
+        assert_eq!(
+            address_domain("Alice <alice@example.com>"),
+            Some("example.com")
+        );
+        assert_eq!(
+            address_domain("alice@example.com (Alice)"),
+            Some("example.com")
+        );
+        assert_eq!(address_domain("alice"), None);
+    }
+
+    #[test]
+    fn test_format_date() {
+        assert_eq!(format_date(UNIX_EPOCH), "Thu, 01 Jan 1970 00:00:00 +0000");
+        assert_eq!(
+            format_date(UNIX_EPOCH + Duration::from_secs(951_827_696)),
+            "Tue, 29 Feb 2000 12:34:56 +0000"
+        );
+    }
+}
@@ -0,0 +1,246 @@
+use std::fmt;
+use std::io::{self, ErrorKind};
+use std::net::{IpAddr, Ipv6Addr, SocketAddr, SocketAddrV6, ToSocketAddrs};
+use std::vec;
+
+use crate::raw::error::Result;
+
+/// A server address given as a string, e.g. `news.example.com:563` or `[2001:db8::1]:563`
+///
+/// Unlike a plain `&str`, this understands bracketed IPv6 literals with or without a port, and
+/// IPv6 zone ids (e.g. `fe80::1%eth0`). The zone id is kept for the socket connection but
+/// stripped, together with the brackets, from the [TLS domain](Self::tls_domain).
+///
+/// `ServerAddr` implements [`ToSocketAddrs`], so it can be passed to
+/// [`NntpConnection::connect`](super::NntpConnection::connect) and
+/// [`ClientConfig::connect`](crate::ClientConfig::connect).
+///
+/// ```
+/// use brokaw::raw::ServerAddr;
+///
+/// let addr = ServerAddr::parse("[2001:db8::1]:563", 119).unwrap();
+/// assert_eq!((addr.host(), addr.port()), ("2001:db8::1", 563));
+///
+/// let addr = ServerAddr::parse("fe80::1%eth0", 119).unwrap();
+/// assert_eq!((addr.host(), addr.port()), ("fe80::1%eth0", 119));
+/// assert_eq!(addr.tls_domain(), "fe80::1");
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ServerAddr {
+    host: String,
+    port: u16,
+}
+
+impl ServerAddr {
+    /// Parse a host with an optional port, using `default_port` if there is none
+    ///
+    /// Accepts hostnames, IPv4 addresses, and IPv6 addresses that are either bracketed
+    /// (`[::1]`, `[::1]:119`) or bare (`::1`, which can't carry a port).
+    pub fn parse(s: &str, default_port: u16) -> Result<Self> {
+        let invalid =
+            |msg: &str| io::Error::new(ErrorKind::InvalidInput, format!("{} {:?}", msg, s));
+
+        let (host, port) = if let Some(rest) = s.strip_prefix('[') {
+            let end = rest
+                .find(']')
+                .ok_or_else(|| invalid("Unterminated bracket in address"))?;
+            let port = match &rest[end + 1..] {
+                "" => None,
+                port => Some(
+                    port.strip_prefix(':')
+                        .ok_or_else(|| invalid("Unexpected characters after bracket in address"))?,
+                ),
+            };
+            (&rest[..end], port)
+        } else {
+            match s.rsplit_once(':') {
+                // More than one colon means a bare IPv6 address
+                Some((host, port)) if !host.contains(':') => (host, Some(port)),
+                _ => (s, None),
+            }
+        };
+
+        if host.is_empty() {
+            return Err(invalid("Missing host in address").into());
+        }
+        let port = match port {
+            Some(port) => port
+                .parse()
+                .map_err(|_| invalid("Invalid port in address"))?,
+            None => default_port,
+        };
+
+        Ok(Self {
+            host: host.to_string(),
+            port,
+        })
+    }
+
+    /// The host without brackets, including the zone id if any
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// The port to connect to
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// The host as it should be used to validate the server's certificate
+    ///
+    /// This is the host without the brackets and zone id of an IPv6 literal, as certificates
+    /// never contain either.
+    pub fn tls_domain(&self) -> &str {
+        tls_domain(&self.host)
+    }
+
+    /// The host as an IP address without its zone id, or `None` if it is a hostname
+    pub(crate) fn ip(&self) -> Option<IpAddr> {
+        self.tls_domain().parse().ok()
+    }
+}
+
+impl fmt::Display for ServerAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.host.contains(':') {
+            write!(f, "[{}]:{}", self.host, self.port)
+        } else {
+            write!(f, "{}:{}", self.host, self.port)
+        }
+    }
+}
+
+impl From<SocketAddr> for ServerAddr {
+    fn from(addr: SocketAddr) -> Self {
+        let host = match addr {
+            SocketAddr::V4(addr) => addr.ip().to_string(),
+            SocketAddr::V6(addr) if addr.scope_id() != 0 => {
+                format!("{}%{}", addr.ip(), addr.scope_id())
+            }
+            SocketAddr::V6(addr) => addr.ip().to_string(),
+        };
+        Self {
+            host,
+            port: addr.port(),
+        }
+    }
+}
+
+impl ToSocketAddrs for ServerAddr {
+    type Iter = vec::IntoIter<SocketAddr>;
+
+    fn to_socket_addrs(&self) -> io::Result<Self::Iter> {
+        // Numeric zone ids are handled here, interface names are left to the system resolver
+        if let Some((ip, zone)) = self.host.split_once('%') {
+            if let (Ok(ip), Ok(scope_id)) = (ip.parse::<Ipv6Addr>(), zone.parse()) {
+                let addr = SocketAddrV6::new(ip, self.port, 0, scope_id);
+                return Ok(vec![addr.into()].into_iter());
+            }
+        }
+        (self.host.as_str(), self.port).to_socket_addrs()
+    }
+}
+
+/// Strip the brackets and zone id from an IPv6 literal so it can be used as a TLS domain
+///
+/// Certificates never contain either, e.g. both `[fe80::1%eth0]` and `fe80::1%eth0` become
+/// `fe80::1`. Other hosts are returned unchanged.
+pub(crate) fn tls_domain(host: &str) -> &str {
+    let host = host
+        .strip_prefix('[')
+        .and_then(|h| h.strip_suffix(']'))
+        .unwrap_or(host);
+    match host.split_once('%') {
+        Some((ip, _zone)) if ip.contains(':') => ip,
+        _ => host,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let parse = |s| ServerAddr::parse(s, 119).map(|a| (a.host, a.port));
+
+        assert_eq!(
+            parse("news.example.com").unwrap(),
+            ("news.example.com".into(), 119)
+        );
+        assert_eq!(parse("192.0.2.1:563").unwrap(), ("192.0.2.1".into(), 563));
+        assert_eq!(
+            parse("[2001:db8::1]:563").unwrap(),
+            ("2001:db8::1".into(), 563)
+        );
+        assert_eq!(parse("[2001:db8::1]").unwrap(), ("2001:db8::1".into(), 119));
+        assert_eq!(parse("2001:db8::1").unwrap(), ("2001:db8::1".into(), 119));
+        assert_eq!(
+            parse("[fe80::1%eth0]:563").unwrap(),
+            ("fe80::1%eth0".into(), 563)
+        );
+
+        for invalid in &[
+            "",
+            "[2001:db8::1",
+            "[2001:db8::1]563",
+            "[]:119",
+            "host:port",
+        ] {
+            assert!(parse(invalid).is_err(), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_socket_and_tls_domain() {
+        let addr = ServerAddr::parse("[2001:db8::1]:563", 119).unwrap();
+        assert_eq!(addr.tls_domain(), "2001:db8::1");
+        assert_eq!(
+            addr.to_socket_addrs().unwrap().collect::<Vec<_>>(),
+            vec!["[2001:db8::1]:563".parse::<SocketAddr>().unwrap()]
+        );
+        assert_eq!(addr.to_string(), "[2001:db8::1]:563");
+
+        // The zone id is kept for the socket but not for the certificate
+        let addr = ServerAddr::parse("[fe80::1%3]:563", 119).unwrap();
+        assert_eq!(addr.tls_domain(), "fe80::1");
+        match addr.to_socket_addrs().unwrap().next() {
+            Some(SocketAddr::V6(addr)) => {
+                assert_eq!(*addr.ip(), "fe80::1".parse::<Ipv6Addr>().unwrap());
+                assert_eq!((addr.port(), addr.scope_id()), (563, 3));
+            }
+            addr => panic!("Unexpected address {:?}", addr),
+        }
+        assert_eq!(
+            ServerAddr::parse("fe80::1%eth0", 119).unwrap().tls_domain(),
+            "fe80::1"
+        );
+    }
+
+    #[test]
+    fn test_from_socket_addr() {
+        let addr = ServerAddr::from("192.0.2.1:119".parse::<SocketAddr>().unwrap());
+        assert_eq!(addr.to_string(), "192.0.2.1:119");
+
+        let addr = ServerAddr::from(SocketAddr::from(SocketAddrV6::new(
+            "fe80::1".parse().unwrap(),
+            563,
+            0,
+            2,
+        )));
+        assert_eq!(addr.to_string(), "[fe80::1%2]:563");
+        assert_eq!(addr.tls_domain(), "fe80::1");
+        assert_eq!(
+            addr.to_socket_addrs().unwrap().next().unwrap(),
+            SocketAddrV6::new("fe80::1".parse().unwrap(), 563, 0, 2).into()
+        );
+    }
+
+    #[test]
+    fn test_tls_domain() {
+        assert_eq!(tls_domain("news.example.com"), "news.example.com");
+        assert_eq!(tls_domain("[2001:db8::1]"), "2001:db8::1");
+        assert_eq!(tls_domain("[fe80::1%eth0]"), "fe80::1");
+        assert_eq!(tls_domain("fe80::1%25eth0"), "fe80::1");
+    }
+}
@@ -0,0 +1,542 @@
+use std::future::Future;
+use std::io;
+use std::io::ErrorKind;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use log::*;
+use tokio::io::{
+    AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader, ReadBuf,
+};
+use tokio::net::{TcpStream, ToSocketAddrs};
+
+use crate::raw::connection::{AddressFamily, ConnectionConfig};
+use crate::raw::error::{Error, Result};
+use crate::raw::response::{
+    check_line_complete, check_line_length, frame_data_block_line, line_budget, min_budget,
+    parse_response_code, response_budget, DataBlocks, Exchange, RawResponse,
+};
+use crate::types::command::{check_wire_line, raw_max_line_len, to_wire_line, NntpCommand};
+use crate::types::prelude::*;
+
+/// An async raw connection to an NNTP Server
+///
+/// This is the async counterpart of the [`NntpConnection`](super::NntpConnection) and is built on
+/// [`tokio`]. Responses are framed by the same logic, so the two connections return identical
+/// [`RawResponse`]s.
+///
+/// The following [`ConnectionConfig`] settings are supported:
+///
+/// * TLS via [`tls_config`](ConnectionConfig::tls_config)
+/// * Read and write timeouts, which require the tokio runtime's timer to be enabled
+/// * Buffer sizes, the [maximum response size](ConnectionConfig::max_response_bytes), and the
+///   [maximum line length](ConnectionConfig::max_line_bytes)
+///
+/// `XFEATURE COMPRESS GZIP` [compression](ConnectionConfig::compression) is not supported and is
+/// ignored.
+///
+/// ## Example: Getting Capabilities
+///
+/// ```no_run
+/// use brokaw::ConnectionConfig;
+/// use brokaw::raw::AsyncNntpConnection;
+/// use brokaw::types::command as cmd;
+/// use brokaw::types::prelude::*;
+///
+/// async fn capabilities() -> Result<(), Box<dyn std::error::Error>> {
+///     let (mut conn, _greeting) =
+///         AsyncNntpConnection::connect(("news.mozilla.org", 119), ConnectionConfig::default())
+///             .await?;
+///     let resp = conn.command(&cmd::Capabilities).await?;
+///     assert_eq!(resp.code(), ResponseCode::Known(Kind::Capabilities));
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct AsyncNntpConnection {
+    stream: BufReader<AsyncNntpStream>,
+    peer_addr: SocketAddr,
+    first_line_buf: Vec<u8>,
+    data_blocks_buf: Vec<u8>,
+    config: ConnectionConfig,
+    mid_response: bool,
+    tls_domain: Option<String>,
+}
+
+impl AsyncNntpConnection {
+    /// Connect to an NNTP server
+    ///
+    /// [Proxies](ConnectionConfig::proxy) are not supported and return an
+    /// [`Unsupported`](ErrorKind::Unsupported) I/O error.
+    pub async fn connect(
+        addr: impl ToSocketAddrs,
+        config: ConnectionConfig,
+    ) -> Result<(Self, RawResponse)> {
+        if config.proxy.is_some() {
+            return Err(io::Error::new(
+                ErrorKind::Unsupported,
+                "SOCKS5 proxies are not supported by the async connection",
+            )
+            .into());
+        }
+
+        trace!("Opening TcpStream...");
+        let tcp_stream = connect_tcp(addr, config.connect_timeout, config.address_family).await?;
+        let peer_addr = tcp_stream.peer_addr()?;
+
+        let stream = if let Some(tls_config) = config.tls_config.as_ref() {
+            trace!("Wrapping TcpStream w/ TlsConnector");
+            let connector = tokio_native_tls::TlsConnector::from(tls_config.connector().clone());
+            AsyncNntpStream::Tls(connector.connect(tls_config.domain(), tcp_stream).await?)
+        } else {
+            trace!("No TLS config providing, continuing with plain text");
+            AsyncNntpStream::Tcp(tcp_stream)
+        };
+
+        let mut conn = Self {
+            stream: BufReader::with_capacity(config.read_buf_size, stream),
+            peer_addr,
+            first_line_buf: Vec::with_capacity(config.first_line_buf_size),
+            data_blocks_buf: Vec::with_capacity(config.data_blocks_buf_size),
+            tls_domain: config
+                .tls_config
+                .as_ref()
+                .map(|tls| tls.domain().to_string()),
+            config,
+            mid_response: false,
+        };
+
+        let initial_resp = conn.read_response_auto().await?;
+
+        Ok((conn, initial_resp))
+    }
+
+    /// Create an AsyncNntpConnection with the default configuration
+    pub async fn with_defaults(addr: impl ToSocketAddrs) -> Result<(Self, RawResponse)> {
+        Self::connect(addr, Default::default()).await
+    }
+
+    /// Send a command to the server and read the response
+    pub async fn command<C: NntpCommand>(&mut self, command: &C) -> Result<RawResponse> {
+        self.send_then_read(command)
+            .await
+            .map(Exchange::into_response)
+    }
+
+    /// Send a command and read the response, which may prompt the client to continue
+    ///
+    /// See [`NntpConnection::send_then_read`](crate::raw::NntpConnection::send_then_read)
+    pub async fn send_then_read<C: NntpCommand>(&mut self, command: &C) -> Result<Exchange> {
+        self.send(command).await?;
+        let resp = self
+            .read_response_with(|code| !code.is_success_so_far() && code.is_multiline())
+            .await?;
+        Ok(Exchange::from(resp))
+    }
+
+    /// Send a command and specify whether the response is multiline
+    pub async fn command_multiline<C: NntpCommand>(
+        &mut self,
+        command: &C,
+        is_multiline: bool,
+    ) -> Result<RawResponse> {
+        self.send(command).await?;
+        self.read_response(Some(is_multiline)).await
+    }
+
+    /// Send a command to the server, returning the number of bytes written
+    ///
+    /// The caller is responsible for reading the response
+    pub async fn send<C: NntpCommand>(&mut self, command: &C) -> Result<usize> {
+        self.send_line(command.to_wire_bytes(), command.max_line_len())
+            .await
+    }
+
+    /// Send a raw command to the server, returning the number of bytes written
+    ///
+    /// See [`NntpConnection::send_bytes`](super::NntpConnection::send_bytes).
+    pub async fn send_bytes(&mut self, command: impl AsRef<[u8]>) -> Result<usize> {
+        let command = command.as_ref();
+        self.send_line(to_wire_line(command), raw_max_line_len(command))
+            .await
+    }
+
+    /// Send a CRLF terminated command line of at most `max_len` octets
+    async fn send_line(&mut self, bytes: Vec<u8>, max_len: usize) -> Result<usize> {
+        if !self.is_at_command_boundary() {
+            return Err(Error::Desynchronized);
+        }
+        check_wire_line(&bytes, max_len).map_err(Error::InvalidCommand)?;
+
+        let writer = self.stream.get_mut();
+        let res = with_timeout(self.config.write_timeout, async {
+            writer.write_all(&bytes).await?;
+            writer.flush().await
+        })
+        .await;
+
+        match res {
+            Err(Error::Timeout { .. }) => {
+                self.mid_response = true;
+                Err(Error::Timeout { partial: None })
+            }
+            res => res.map(|_| bytes.len()),
+        }
+    }
+
+    /// Read any data from the stream into a RawResponse
+    ///
+    /// See [`NntpConnection::read_response_auto`](super::NntpConnection::read_response_auto).
+    pub async fn read_response_auto(&mut self) -> Result<RawResponse> {
+        self.read_response(None).await
+    }
+
+    /// Read an NNTP response from the connection
+    ///
+    /// See [`NntpConnection::read_response`](super::NntpConnection::read_response).
+    pub async fn read_response(&mut self, is_multiline: Option<bool>) -> Result<RawResponse> {
+        self.read_response_with(|code| is_multiline == Some(true) || code.is_multiline())
+            .await
+    }
+
+    /// Read an NNTP response, using `is_multiline` to decide from the response code whether
+    /// to read data blocks
+    pub(crate) async fn read_response_with(
+        &mut self,
+        is_multiline: impl FnOnce(ResponseCode) -> bool,
+    ) -> Result<RawResponse> {
+        self.mid_response = true;
+        self.first_line_buf.truncate(0);
+        self.data_blocks_buf.truncate(0);
+
+        let read_timeout = self.config.read_timeout;
+        let max_line_bytes = self.config.max_line_bytes;
+        let bytes_read = with_timeout(
+            read_timeout,
+            (&mut self.stream)
+                .take(line_budget(max_line_bytes).unwrap_or(u64::MAX))
+                .read_until(b'\n', &mut self.first_line_buf),
+        )
+        .await?;
+        check_line_length(bytes_read, max_line_bytes)?;
+        check_line_complete(&self.first_line_buf, bytes_read)?;
+        let resp_code =
+            parse_response_code(&self.first_line_buf, self.config.lenient_line_endings)?;
+
+        let data_blocks = if is_multiline(resp_code) {
+            trace!("Parsing data blocks for response {}", resp_code);
+            let mut line_boundaries = Vec::with_capacity(10);
+
+            let limit = self.config.max_response_bytes;
+            loop {
+                let budget = min_budget(
+                    response_budget(limit, self.data_blocks_buf.len()),
+                    line_budget(max_line_bytes),
+                );
+                let res = with_timeout(
+                    read_timeout,
+                    (&mut self.stream)
+                        .take(budget.unwrap_or(u64::MAX))
+                        .read_until(b'\n', &mut self.data_blocks_buf),
+                )
+                .await;
+
+                if let Err(Error::Timeout { .. }) = res {
+                    // Hand back every line that was completely read before the timeout
+                    let end = line_boundaries.last().map(|(_, end)| *end).unwrap_or(0);
+                    let partial = RawResponse {
+                        code: resp_code,
+                        first_line: self.first_line_buf.clone(),
+                        data_blocks: Some(DataBlocks {
+                            payload: self.data_blocks_buf[..end].to_vec(),
+                            line_boundaries,
+                        }),
+                    };
+                    return Err(Error::Timeout {
+                        partial: Some(partial),
+                    });
+                }
+                let line_len = res?;
+
+                if let Some(limit) = limit.filter(|limit| self.data_blocks_buf.len() > *limit) {
+                    return Err(Error::ResponseTooLarge { limit });
+                }
+                check_line_length(line_len, max_line_bytes)?;
+                let read_head = line_boundaries.last().map(|(_, end)| *end).unwrap_or(0);
+                check_line_complete(
+                    &self.data_blocks_buf[read_head..],
+                    self.first_line_buf.len() + self.data_blocks_buf.len(),
+                )?;
+
+                if frame_data_block_line(
+                    &mut self.data_blocks_buf,
+                    &mut line_boundaries,
+                    self.config.lenient_line_endings,
+                )? {
+                    break;
+                }
+            }
+
+            Some(DataBlocks {
+                payload: self.data_blocks_buf.clone(),
+                line_boundaries,
+            })
+        } else {
+            None
+        };
+
+        let resp = RawResponse {
+            code: resp_code,
+            first_line: self.first_line_buf.clone(),
+            data_blocks,
+        };
+
+        self.reset_buffers();
+        self.mid_response = false;
+
+        Ok(resp)
+    }
+
+    /// Returns true if the previous response was completely read
+    ///
+    /// See [`NntpConnection::is_at_command_boundary`](super::NntpConnection::is_at_command_boundary).
+    pub fn is_at_command_boundary(&self) -> bool {
+        !self.mid_response
+    }
+
+    /// Reset the connection's buffers to their initial size
+    fn reset_buffers(&mut self) {
+        self.first_line_buf
+            .truncate(self.config.first_line_buf_size);
+        self.first_line_buf.shrink_to_fit();
+
+        self.data_blocks_buf
+            .truncate(self.config.data_blocks_buf_size);
+        self.data_blocks_buf.shrink_to_fit();
+    }
+
+    /// Get a ref to the underlying AsyncNntpStream
+    pub fn stream(&self) -> &BufReader<AsyncNntpStream> {
+        &self.stream
+    }
+
+    /// Get a mutable ref to the underlying AsyncNntpStream
+    pub fn stream_mut(&mut self) -> &mut BufReader<AsyncNntpStream> {
+        &mut self.stream
+    }
+
+    /// Get the configuration of the connection
+    pub fn config(&self) -> &ConnectionConfig {
+        &self.config
+    }
+
+    /// The address of the server the connection was established with
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.peer_addr
+    }
+
+    /// Returns true if the connection is using TLS
+    pub fn is_tls(&self) -> bool {
+        self.stream.get_ref().is_tls()
+    }
+
+    /// The domain used to validate the server's certificate if TLS is in use
+    pub fn tls_domain(&self) -> Option<&str> {
+        self.tls_domain.as_deref()
+    }
+}
+
+/// Open a TCP connection to the first reachable address of `addr`
+///
+/// See [`ConnectionConfig::connect_timeout`]
+async fn connect_tcp(
+    addr: impl ToSocketAddrs,
+    timeout: Option<Duration>,
+    family: AddressFamily,
+) -> Result<TcpStream> {
+    let addrs = family.select(tokio::net::lookup_host(addr).await?.collect())?;
+    let timeout = match timeout {
+        Some(timeout) => timeout,
+        None => return Ok(TcpStream::connect(&addrs[..]).await?),
+    };
+
+    let mut last_err = None;
+    for addr in &addrs {
+        match tokio::time::timeout(timeout, TcpStream::connect(addr)).await {
+            Ok(Ok(stream)) => return Ok(stream),
+            Ok(Err(e)) => {
+                debug!("Failed to connect to {} -- {}", addr, e);
+                last_err = Some(e);
+            }
+            Err(_elapsed) => debug!("Timed out connecting to {}", addr),
+        }
+    }
+
+    match last_err {
+        Some(e) => Err(e.into()),
+        None if addrs.is_empty() => {
+            Err(io::Error::new(ErrorKind::InvalidInput, "Address did not resolve").into())
+        }
+        None => Err(Error::ConnectTimeout { addrs, timeout }),
+    }
+}
+
+/// Await `fut`, failing with [`Error::Timeout`] if it does not complete within `dur`
+async fn with_timeout<T>(
+    dur: Option<Duration>,
+    fut: impl Future<Output = io::Result<T>>,
+) -> Result<T> {
+    match dur {
+        Some(dur) => match tokio::time::timeout(dur, fut).await {
+            Ok(res) => Ok(res?),
+            Err(_elapsed) => Err(Error::Timeout { partial: None }),
+        },
+        None => Ok(fut.await?),
+    }
+}
+
+/// A raw NNTP session used by the [`AsyncNntpConnection`]
+#[derive(Debug)]
+pub enum AsyncNntpStream {
+    /// A stream using TLS
+    Tls(tokio_native_tls::TlsStream<TcpStream>),
+    /// A plain text stream
+    Tcp(TcpStream),
+}
+
+impl AsyncNntpStream {
+    /// Returns true if the stream is using TLS
+    pub fn is_tls(&self) -> bool {
+        matches!(self, AsyncNntpStream::Tls(_))
+    }
+}
+
+impl AsyncRead for AsyncNntpStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            AsyncNntpStream::Tls(s) => Pin::new(s).poll_read(cx, buf),
+            AsyncNntpStream::Tcp(s) => Pin::new(s).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for AsyncNntpStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            AsyncNntpStream::Tls(s) => Pin::new(s).poll_write(cx, buf),
+            AsyncNntpStream::Tcp(s) => Pin::new(s).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            AsyncNntpStream::Tls(s) => Pin::new(s).poll_flush(cx),
+            AsyncNntpStream::Tcp(s) => Pin::new(s).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            AsyncNntpStream::Tls(s) => Pin::new(s).poll_shutdown(cx),
+            AsyncNntpStream::Tcp(s) => Pin::new(s).poll_shutdown(cx),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::{BufRead, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    #[tokio::test]
+    async fn test_command() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut stream = std::io::BufReader::new(stream);
+            stream.get_mut().write_all(b"200 ready\r\n").unwrap();
+
+            let mut line = String::new();
+            stream.read_line(&mut line).unwrap();
+            assert_eq!(line, "OVER 1-2\r\n");
+            stream
+                .get_mut()
+                .write_all(b"224 Overview follows\r\n1\tfirst\r\n..2\tsecond\r\n.\r\n")
+                .unwrap();
+        });
+
+        let (mut conn, greeting) = AsyncNntpConnection::with_defaults(addr).await.unwrap();
+        assert_eq!(greeting.code(), ResponseCode::Known(Kind::PostingAllowed));
+        assert_eq!(conn.peer_addr(), addr);
+        assert!(!conn.is_tls());
+
+        conn.send_bytes("OVER 1-2").await.unwrap();
+        let resp = conn.read_response_auto().await.unwrap();
+        assert_eq!(resp.code(), ResponseCode::Known(Kind::Overview));
+        assert_eq!(
+            resp.data_blocks().unwrap().lines().collect::<Vec<_>>(),
+            vec![&b"1\tfirst\r\n"[..], b".2\tsecond\r\n", b".\r\n"]
+        );
+        assert!(conn.is_at_command_boundary());
+
+        server.join().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_timeout_partial_data_blocks() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream
+                .write_all(b"200 ready\r\n224 Overview follows\r\n1\tfirst\r\n2\tsec")
+                .unwrap();
+            thread::sleep(Duration::from_millis(500));
+        });
+
+        let (mut conn, _) = AsyncNntpConnection::connect(
+            addr,
+            ConnectionConfig::new()
+                .read_timeout(Some(Duration::from_millis(100)))
+                .to_owned(),
+        )
+        .await
+        .unwrap();
+
+        match conn.read_response_auto().await {
+            Err(Error::Timeout {
+                partial: Some(partial),
+            }) => {
+                let data_blocks = partial.data_blocks().unwrap();
+                assert_eq!(
+                    data_blocks.lines().collect::<Vec<_>>(),
+                    vec![b"1\tfirst\r\n"]
+                );
+            }
+            res => panic!("Unexpected result {:?}", res),
+        }
+        assert!(!conn.is_at_command_boundary());
+        assert!(matches!(
+            conn.send_bytes("DATE").await,
+            Err(Error::Desynchronized)
+        ));
+
+        server.join().unwrap();
+    }
+}
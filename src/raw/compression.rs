@@ -1,6 +1,7 @@
-use std::io::{BufRead, BufReader, Read};
+use std::io::{BufRead, BufReader, Read, Write};
 
 use flate2::bufread::ZlibDecoder;
+use flate2::{Compress, Decompress, FlushCompress, FlushDecompress, Status};
 use std::io;
 
 /// A type of compression enabled on the server
@@ -56,6 +57,107 @@ impl<S: BufRead> BufRead for Decoder<S> {
     }
 }
 
+/// The size of the buffer used to read compressed data from the inner stream
+const DEFLATE_BUF_SIZE: usize = 8 * 1024;
+
+/// A stream compressed in both directions with raw deflate per
+/// [RFC 8054](https://tools.ietf.org/html/rfc8054)
+///
+/// Writes are compressed as they are made and a sync flush is performed whenever the stream is
+/// flushed, so that the server can decompress every command as soon as it is sent.
+#[derive(Debug)]
+pub struct DeflateStream<S> {
+    inner: S,
+    compress: Compress,
+    decompress: Decompress,
+    in_buf: Vec<u8>,
+    in_pos: usize,
+    out_buf: Vec<u8>,
+}
+
+impl<S> DeflateStream<S> {
+    /// Wrap a stream, `buffered` contains any compressed bytes already read from it
+    pub(crate) fn new(inner: S, buffered: Vec<u8>) -> Self {
+        Self {
+            inner,
+            compress: Compress::new(flate2::Compression::default(), false),
+            decompress: Decompress::new(false),
+            in_buf: buffered,
+            in_pos: 0,
+            out_buf: Vec::new(),
+        }
+    }
+
+    /// The wrapped stream
+    pub(crate) fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Compress `input` into the output buffer
+    fn compress(&mut self, mut input: &[u8], flush: FlushCompress) -> io::Result<()> {
+        loop {
+            self.out_buf.reserve(input.len() + 64);
+            let before = self.compress.total_in();
+            self.compress
+                .compress_vec(input, &mut self.out_buf, flush)
+                .map_err(io::Error::other)?;
+            input = &input[(self.compress.total_in() - before) as usize..];
+
+            // If the output buffer has room to spare, all of the input has been compressed
+            if self.out_buf.len() < self.out_buf.capacity() {
+                return Ok(());
+            }
+        }
+    }
+}
+
+impl<S: Read> Read for DeflateStream<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            if self.in_pos == self.in_buf.len() {
+                self.in_buf.resize(DEFLATE_BUF_SIZE, 0);
+                let n = self.inner.read(&mut self.in_buf)?;
+                self.in_buf.truncate(n);
+                self.in_pos = 0;
+                if n == 0 {
+                    return Ok(0);
+                }
+            }
+
+            let before_in = self.decompress.total_in();
+            let before_out = self.decompress.total_out();
+            let status = self
+                .decompress
+                .decompress(&self.in_buf[self.in_pos..], buf, FlushDecompress::Sync)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            self.in_pos += (self.decompress.total_in() - before_in) as usize;
+            let produced = (self.decompress.total_out() - before_out) as usize;
+
+            if produced > 0 || status == Status::StreamEnd {
+                return Ok(produced);
+            }
+        }
+    }
+}
+
+impl<S: Write> Write for DeflateStream<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.compress(buf, FlushCompress::None)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.compress(&[], FlushCompress::Sync)?;
+        self.inner.write_all(&self.out_buf)?;
+        self.out_buf.clear();
+        self.inner.flush()
+    }
+}
+
 /*
     In theory if we wanted to implement extensible compression we could replace Decoder and
     Compression objects w/ traits. That said it didn't seem necessary given the slow moving
@@ -74,6 +176,28 @@ mod tests {
         assert!(!Compression::XFeature.use_decoder("224 xover information follows [COMPRESS=GZIP]"))
     }
 
+    #[test]
+    fn test_deflate_stream() {
+        let mut stream = DeflateStream::new(Vec::new(), Vec::new());
+        stream.write_all(b"ARTICLE 1\r\n").unwrap();
+        stream.flush().unwrap();
+        stream.write_all(&[b'x'; 100_000]).unwrap();
+        stream.flush().unwrap();
+
+        // Reading what was written back produces the original bytes
+        let compressed = stream.get_ref().clone();
+        let mut reader = BufReader::with_capacity(
+            7,
+            DeflateStream::new(&compressed[3..], compressed[..3].to_vec()),
+        );
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(line, "ARTICLE 1\r\n");
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, vec![b'x'; 100_000]);
+    }
+
     #[test]
     fn test_compressed() {
         let compressed_resp = include_bytes!(concat!(
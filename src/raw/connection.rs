@@ -1,45 +1,132 @@
 use std::fmt;
 use std::io;
-use std::io::{ErrorKind, Write};
-use std::net::{TcpStream, ToSocketAddrs};
-use std::str::FromStr;
-use std::time::Duration;
+use std::io::{BufRead, ErrorKind, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+#[cfg(unix)]
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use log::*;
-use native_tls::TlsConnector;
-
-use crate::raw::compression::{Compression, Decoder};
-use crate::raw::error::Result;
-use crate::raw::parse::{is_end_of_datablock, parse_data_block_line, parse_first_line};
-use crate::raw::response::{DataBlocks, RawResponse};
-use crate::raw::stream::NntpStream;
-use crate::types::command::NntpCommand;
+use native_tls::{Identity, Protocol, TlsConnector};
+
+use crate::raw::addr::{tls_domain, ServerAddr};
+use crate::raw::compression::{Compression, Decoder, DeflateStream};
+use crate::raw::error::{Error, Result};
+use crate::raw::parse::{is_end_of_datablock, parse_data_block_line};
+use crate::raw::proxy::ProxyConfig;
+use crate::raw::response::{
+    check_line_complete, check_line_length, line_budget, min_budget, read_data_blocks,
+    read_initial_response, read_line, response_budget, DataBlocks, Exchange, RawResponse,
+};
+use crate::raw::stream::{NntpStream, Transport};
+use crate::raw::trace::{TraceFn, TraceHook, Traced};
+use crate::types::command::{
+    check_wire_line, raw_max_line_len, strip_crlf, to_wire_line, CompressDeflate, NntpCommand,
+    StartTls,
+};
 use crate::types::prelude::*;
 
 /// TLS configuration for an [`NntpConnection`]
+///
+/// The `Debug` output includes the domain and the certificate validation settings, but only
+/// whether a client [`Identity`] is configured rather than the identity itself.
 #[derive(Clone)]
 pub struct TlsConfig {
     connector: TlsConnector,
     domain: String,
+    options: TlsOptions,
 }
 
 impl TlsConfig {
     /// Create a `TlsConfig` for use with [`NntpConnections`](NntpConnection)
     ///
     /// The `domain` will be passed to [`TlsConnector::connect`] for certificate validation
-    /// during any TLS handshakes.
+    /// during any TLS handshakes. Brackets and zone ids are stripped from IPv6 literals
+    /// (e.g. `[fe80::1%eth0]` becomes `fe80::1`) as certificates never contain them.
+    ///
+    /// Note that calling any of the setters (e.g. [`min_protocol_version`](Self::min_protocol_version))
+    /// replaces `connector` with one built from the system defaults and the configured options.
     pub fn new(domain: String, connector: TlsConnector) -> Self {
-        Self { connector, domain }
+        Self {
+            connector,
+            domain: tls_domain(&domain).to_string(),
+            options: Default::default(),
+        }
     }
 
     /// Create a `TlsConfig` with the system default TLS settings
     ///
-    /// The `domain` will be used to validate server certs during any TLS handshakes.
+    /// The `domain` will be used to validate server certs during any TLS handshakes, see
+    /// [`new`](Self::new).
     pub fn default_connector(domain: impl AsRef<str>) -> Result<Self> {
-        let connector = TlsConnector::new()?;
+        Self::from_options(domain, Default::default())
+    }
+
+    /// Create a `TlsConfig` that presents a client certificate during the TLS handshake
+    ///
+    /// This is needed for servers that require mutual TLS. An [`Identity`] can be loaded from a
+    /// PKCS #12 archive with [`Identity::from_pkcs12`].
+    pub fn with_identity(domain: impl AsRef<str>, identity: Identity) -> Result<Self> {
+        Self::from_options(
+            domain,
+            TlsOptions {
+                identity: Some(identity),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Set the minimum supported TLS protocol version
+    ///
+    /// Defaults to TLS 1.0, `None` uses the lowest version supported by the system.
+    /// Some servers reject TLS 1.0 and 1.1, in which case this should be set to
+    /// [`Protocol::Tlsv12`].
+    pub fn min_protocol_version(&mut self, version: Option<Protocol>) -> Result<&mut Self> {
+        self.options.min_protocol_version = version;
+        self.connector = self.options.build()?;
+        Ok(self)
+    }
+
+    /// Accept invalid server certificates
+    ///
+    /// # Warning
+    ///
+    /// **This is dangerous.** Any certificate will be trusted, including expired, self-signed,
+    /// and forged ones, which exposes the connection to man-in-the-middle attacks.
+    /// This should only be used for testing (e.g. against a local server with a self-signed
+    /// certificate).
+    pub fn danger_accept_invalid_certs(&mut self, accept: bool) -> Result<&mut Self> {
+        if accept {
+            warn!("Certificate validation is disabled for {}", self.domain);
+        }
+        self.options.accept_invalid_certs = accept;
+        self.connector = self.options.build()?;
+        Ok(self)
+    }
+
+    /// Accept server certificates that do not match the domain
+    ///
+    /// # Warning
+    ///
+    /// **This is dangerous.** A valid certificate for *any* domain will be trusted, which exposes
+    /// the connection to man-in-the-middle attacks. This should only be used for testing.
+    pub fn danger_accept_invalid_hostnames(&mut self, accept: bool) -> Result<&mut Self> {
+        if accept {
+            warn!("Hostname validation is disabled for {}", self.domain);
+        }
+        self.options.accept_invalid_hostnames = accept;
+        self.connector = self.options.build()?;
+        Ok(self)
+    }
+
+    fn from_options(domain: impl AsRef<str>, options: TlsOptions) -> Result<Self> {
         Ok(Self {
-            connector,
-            domain: domain.as_ref().to_string(),
+            connector: options.build()?,
+            domain: tls_domain(domain.as_ref()).to_string(),
+            options,
         })
     }
 
@@ -47,16 +134,64 @@ impl TlsConfig {
     pub fn connector(&self) -> &TlsConnector {
         &self.connector
     }
+
+    /// The domain used to validate server certificates
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
 }
 
 impl fmt::Debug for TlsConfig {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("TlsConfig")
             .field("domain", &self.domain)
+            .field("has_identity", &self.options.identity.is_some())
+            .field("min_protocol_version", &self.options.min_protocol_version)
+            .field("accept_invalid_certs", &self.options.accept_invalid_certs)
+            .field(
+                "accept_invalid_hostnames",
+                &self.options.accept_invalid_hostnames,
+            )
             .finish()
     }
 }
 
+/// The settings used to build the [`TlsConnector`] for a [`TlsConfig`]
+#[derive(Clone)]
+struct TlsOptions {
+    identity: Option<Identity>,
+    min_protocol_version: Option<Protocol>,
+    accept_invalid_certs: bool,
+    accept_invalid_hostnames: bool,
+}
+
+impl Default for TlsOptions {
+    fn default() -> Self {
+        // n.b. this matches the defaults of `TlsConnector::new`
+        Self {
+            identity: None,
+            min_protocol_version: Some(Protocol::Tlsv10),
+            accept_invalid_certs: false,
+            accept_invalid_hostnames: false,
+        }
+    }
+}
+
+impl TlsOptions {
+    fn build(&self) -> Result<TlsConnector> {
+        let mut builder = TlsConnector::builder();
+        if let Some(identity) = &self.identity {
+            builder.identity(identity.clone());
+        }
+        builder
+            .min_protocol_version(self.min_protocol_version)
+            .danger_accept_invalid_certs(self.accept_invalid_certs)
+            .danger_accept_invalid_hostnames(self.accept_invalid_hostnames);
+
+        Ok(builder.build()?)
+    }
+}
+
 /// A raw connection to an NNTP Server
 ///
 /// `NntpConnection` essentially wraps a stream. It is responsible for serializing commands
@@ -128,11 +263,106 @@ impl fmt::Debug for TlsConfig {
 #[derive(Debug)]
 pub struct NntpConnection {
     stream: BufNntpStream,
+    peer_addr: Option<SocketAddr>,
+    /// The destination of a connection whose host was resolved by a proxy
+    proxied_host: Option<ServerAddr>,
     first_line_buf: Vec<u8>,
     data_blocks_buf: Vec<u8>,
     config: ConnectionConfig,
+    mid_response: bool,
+    generation: u64,
+    write_buf: Vec<u8>,
+    /// The number of queued commands whose responses have not been read
+    pending_responses: usize,
+    /// Whether a SASL exchange is in progress, for redacting its continuations from the trace
+    sasl_exchange: bool,
+    tls_domain: Option<String>,
+    last_sent: Instant,
+    bytes_read: u64,
+    bytes_written: u64,
+}
+
+/// Open a TCP connection to the first reachable address of `addr` through `proxy`
+///
+/// The returned address is that of the server rather than the proxy.
+fn connect_via_proxy(
+    proxy: &ProxyConfig,
+    addrs: &[SocketAddr],
+    connect_timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+    write_timeout: Option<Duration>,
+) -> Result<(TcpStream, SocketAddr)> {
+    let mut last_err = None;
+    for &dest in addrs {
+        let server = ServerAddr::from(dest);
+        match proxy.connect(&server, connect_timeout, read_timeout, write_timeout) {
+            Ok(stream) => return Ok((stream, dest)),
+            Err(e) => {
+                debug!("Failed to connect to {} via proxy -- {}", dest, e);
+                last_err = Some(e);
+            }
+        }
+    }
+
+    Err(last_err.unwrap_or_else(|| {
+        io::Error::new(ErrorKind::InvalidInput, "Address did not resolve").into()
+    }))
+}
+
+/// Open a TCP connection to the first reachable address of `addr`
+///
+/// Without a timeout this is equivalent to [`TcpStream::connect`]. Otherwise each resolved
+/// address is tried in turn for at most `timeout`, and [`Error::ConnectTimeout`] is returned if
+/// every attempt timed out.
+pub(crate) fn connect_tcp(
+    addr: impl ToSocketAddrs,
+    timeout: Option<Duration>,
+) -> Result<TcpStream> {
+    let timeout = match timeout {
+        Some(timeout) => timeout,
+        None => return Ok(TcpStream::connect(addr)?),
+    };
+
+    let addrs = addr.to_socket_addrs()?.collect::<Vec<_>>();
+    let mut last_err = None;
+    for addr in &addrs {
+        match TcpStream::connect_timeout(addr, timeout) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => {
+                debug!("Failed to connect to {} -- {}", addr, e);
+                // n.b. keep a non-timeout error (e.g. connection refused) as it's more specific
+                if last_err.as_ref().is_none_or(is_timeout) {
+                    last_err = Some(e);
+                }
+            }
+        }
+    }
+
+    match last_err {
+        Some(e) if is_timeout(&e) => Err(Error::ConnectTimeout { addrs, timeout }),
+        Some(e) => Err(e.into()),
+        None => Err(io::Error::new(ErrorKind::InvalidInput, "Address did not resolve").into()),
+    }
 }
 
+/// The default size of the buffer used to read from the socket
+///
+/// See [`ConnectionConfig::read_buf_size`]
+pub const DEFAULT_READ_BUF_SIZE: usize = 8 * 1024;
+
+/// The default number of commands written at once by [`NntpConnection::pipeline`]
+///
+/// See [`ConnectionConfig::max_pipeline_depth`]
+pub const DEFAULT_MAX_PIPELINE_DEPTH: usize = 32;
+
+/// The default maximum length of a single response line
+///
+/// See [`ConnectionConfig::max_line_bytes`]
+pub const DEFAULT_MAX_LINE_BYTES: usize = 64 * 1024;
+
+/// Queued commands are written once this many bytes are buffered
+const WRITE_BUF_FLUSH_THRESHOLD: usize = 16 * 1024;
+
 impl NntpConnection {
     /// Connect to an NNTP server
     pub fn connect(
@@ -143,17 +373,83 @@ impl NntpConnection {
             compression: _,
             tls_config,
             read_timeout,
-            write_timeout: _,
-            first_line_buf_size,
-            data_blocks_buf_size,
+            write_timeout,
+            first_line_buf_size: _,
+            data_blocks_buf_size: _,
+            read_buf_size: _,
+            max_response_bytes: _,
+            max_line_bytes: _,
+            ambiguous_response_timeout: _,
+            proxy,
+            connect_timeout,
+            address_family,
+            lenient_line_endings: _,
+            trace_hook: _,
+            redact_passwords: _,
+            max_pipeline_depth: _,
         } = config.clone();
 
-        trace!("Opening TcpStream...");
-        let tcp_stream = TcpStream::connect(&addr)?;
+        let addrs = address_family.select(addr.to_socket_addrs()?.collect())?;
+        let (tcp_stream, peer_addr) = match &proxy {
+            Some(proxy) => {
+                connect_via_proxy(proxy, &addrs, connect_timeout, read_timeout, write_timeout)?
+            }
+            None => {
+                trace!("Opening TcpStream...");
+                let tcp_stream = connect_tcp(&addrs[..], connect_timeout)?;
+
+                tcp_stream.set_read_timeout(read_timeout)?;
+                tcp_stream.set_write_timeout(write_timeout)?;
+                let peer_addr = tcp_stream.peer_addr()?;
+                (tcp_stream, peer_addr)
+            }
+        };
+
+        Self::start(tcp_stream, Some(peer_addr), config, tls_config)
+    }
+
+    /// Connect to an NNTP server, leaving the resolution of the host to the proxy if there is one
+    ///
+    /// Without a [proxy](ConnectionConfig::proxy) this is equivalent to
+    /// [`connect`](Self::connect). Otherwise the host is sent to the proxy as-is, which is
+    /// required for hosts that only the proxy can resolve (e.g. Tor onion services). The
+    /// [address family](ConnectionConfig::address_family) preference can't be applied to the
+    /// addresses the proxy resolves, the connection has no [`peer_addr`](Self::peer_addr), and a
+    /// [reconnect](Self::reconnect) asks the proxy to resolve the host again. IP addresses are
+    /// handled exactly as by [`connect`](Self::connect).
+    pub fn connect_host(
+        addr: &ServerAddr,
+        config: ConnectionConfig,
+    ) -> Result<(Self, RawResponse)> {
+        let proxy = match config.proxy.clone() {
+            Some(proxy) if addr.ip().is_none() => proxy,
+            _ => return Self::connect(addr, config),
+        };
 
-        tcp_stream.set_read_timeout(read_timeout)?;
+        let tcp_stream = proxy.connect(
+            addr,
+            config.connect_timeout,
+            config.read_timeout,
+            config.write_timeout,
+        )?;
+        let tls_config = config.tls_config.clone();
+        let (mut conn, initial_resp) = Self::start(tcp_stream, None, config, tls_config)?;
+        conn.proxied_host = Some(addr.clone());
+
+        Ok((conn, initial_resp))
+    }
 
-        let nntp_stream = if let Some(TlsConfig { connector, domain }) = tls_config.as_ref() {
+    /// Negotiate TLS if configured and read the greeting
+    fn start(
+        tcp_stream: TcpStream,
+        peer_addr: Option<SocketAddr>,
+        config: ConnectionConfig,
+        tls_config: Option<TlsConfig>,
+    ) -> Result<(Self, RawResponse)> {
+        let nntp_stream = if let Some(TlsConfig {
+            connector, domain, ..
+        }) = tls_config.as_ref()
+        {
             trace!("Wrapping TcpStream w/ TlsConnector");
             connector.connect(domain, tcp_stream)?.into()
         } else {
@@ -161,21 +457,248 @@ impl NntpConnection {
             tcp_stream.into()
         };
 
-        let first_line_buf = Vec::with_capacity(first_line_buf_size);
-        let data_blocks_buf = Vec::with_capacity(data_blocks_buf_size);
+        let tls_domain = tls_config.map(|tls| tls.domain);
+        let mut conn = Self::new(nntp_stream, peer_addr, config, tls_domain);
+        let initial_resp = conn.read_response_auto()?;
 
-        let mut conn = Self {
-            stream: io::BufReader::new(nntp_stream),
-            first_line_buf,
-            data_blocks_buf,
-            config,
-        };
+        Ok((conn, initial_resp))
+    }
 
+    /// Connect to an NNTP server listening on a Unix domain socket
+    ///
+    /// The read and write timeouts are applied to the socket. TLS doesn't apply to a local
+    /// socket, so a [`TlsConfig`] is ignored with a warning, as is a [`ProxyConfig`].
+    /// [`reconnect`](Self::reconnect) is unsupported and the connection has no
+    /// [`peer_addr`](Self::peer_addr).
+    #[cfg(unix)]
+    pub fn connect_unix(
+        path: impl AsRef<Path>,
+        config: ConnectionConfig,
+    ) -> Result<(Self, RawResponse)> {
+        if config.tls_config.is_some() {
+            warn!("Ignoring the TLS config, TLS is not used over Unix domain sockets");
+        }
+        if config.proxy.is_some() {
+            warn!("Ignoring the proxy config for a Unix domain socket");
+        }
+
+        let path = path.as_ref();
+        trace!("Opening UnixStream to {}...", path.display());
+        let unix_stream = UnixStream::connect(path)?;
+        unix_stream.set_read_timeout(config.read_timeout)?;
+        unix_stream.set_write_timeout(config.write_timeout)?;
+
+        let mut conn = Self::new(unix_stream.into(), None, config, None);
         let initial_resp = conn.read_response_auto()?;
 
         Ok((conn, initial_resp))
     }
 
+    /// Use a stream that was established elsewhere (e.g. an SSH tunnel or an in-memory pipe)
+    /// with the default configuration
+    ///
+    /// If `expect_greeting` is true the server's greeting is read and returned, otherwise the
+    /// stream is assumed to be positioned at a command boundary (e.g. a proxy that already
+    /// consumed the greeting).
+    ///
+    /// As the stream is opaque, the read and write timeouts and TLS configuration are not
+    /// applied to it, [`starttls`](Self::starttls) and [`reconnect`](Self::reconnect) are
+    /// unsupported, and the connection has no [`peer_addr`](Self::peer_addr).
+    /// See [`ConnectionConfig::connect_stream`] to use a different configuration.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use brokaw::raw::NntpConnection;
+    ///
+    /// let stream = Cursor::new(b"200 ready\r\n".to_vec());
+    /// let (conn, greeting) = NntpConnection::from_stream(stream, true).unwrap();
+    /// assert_eq!(greeting.unwrap().first_line(), b"200 ready\r\n");
+    /// ```
+    pub fn from_stream(
+        stream: impl Transport + 'static,
+        expect_greeting: bool,
+    ) -> Result<(Self, Option<RawResponse>)> {
+        ConnectionConfig::default().connect_stream(stream, expect_greeting)
+    }
+
+    /// Wrap an established stream
+    fn new(
+        stream: NntpStream,
+        peer_addr: Option<SocketAddr>,
+        config: ConnectionConfig,
+        tls_domain: Option<String>,
+    ) -> Self {
+        Self {
+            stream: io::BufReader::with_capacity(config.read_buf_size, stream),
+            peer_addr,
+            proxied_host: None,
+            first_line_buf: Vec::with_capacity(config.first_line_buf_size),
+            data_blocks_buf: Vec::with_capacity(config.data_blocks_buf_size),
+            config,
+            mid_response: false,
+            generation: 0,
+            write_buf: Vec::new(),
+            pending_responses: 0,
+            sasl_exchange: false,
+            last_sent: Instant::now(),
+            tls_domain,
+            bytes_read: 0,
+            bytes_written: 0,
+        }
+    }
+
+    /// Re-establish the connection in place, returning the new greeting
+    ///
+    /// The current socket is closed and a new one (including any TLS handshake) is opened to
+    /// the same [address](Self::peer_addr) using the provided configuration. The address is not
+    /// re-resolved, except by the proxy for connections established with
+    /// [`connect_host`](Self::connect_host).
+    ///
+    /// A successful reconnect leaves the connection at a
+    /// [command boundary](Self::is_at_command_boundary) and increments the
+    /// [generation](Self::generation). Note that any session state on the server (e.g. the
+    /// selected group or authentication) is lost. If the reconnect fails, the connection is left
+    /// unchanged.
+    pub fn reconnect(&mut self, config: ConnectionConfig) -> Result<RawResponse> {
+        let (conn, greeting) = match (&self.proxied_host, self.peer_addr) {
+            (Some(host), _) => {
+                trace!("Reconnecting to {} via proxy...", host);
+                Self::connect_host(host, config)?
+            }
+            (None, Some(peer_addr)) => {
+                trace!("Reconnecting to {}...", peer_addr);
+                Self::connect(peer_addr, config)?
+            }
+            (None, None) => {
+                return Err(io::Error::new(
+                    ErrorKind::Unsupported,
+                    "A connection created from a stream cannot be reconnected",
+                )
+                .into())
+            }
+        };
+        let generation = self.generation + 1;
+        let (bytes_read, bytes_written) = (self.bytes_read, self.bytes_written);
+
+        *self = conn;
+        self.generation = generation;
+        self.bytes_read += bytes_read;
+        self.bytes_written += bytes_written;
+
+        Ok(greeting)
+    }
+
+    /// The number of times the connection has been [re-established](Self::reconnect)
+    ///
+    /// This can be used to detect that session state (e.g. the selected group) was lost since
+    /// it was last observed.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// The number of bytes received from the server
+    ///
+    /// Bytes are counted after TLS decryption and `COMPRESS DEFLATE` decompression, i.e. they are
+    /// the NNTP protocol bytes including response lines, CRLFs and dot-stuffing. Responses
+    /// compressed via [`Compression::XFeature`] are counted after decompression as well.
+    ///
+    /// The count is kept across [reconnects](Self::reconnect) and can be cleared with
+    /// [`reset_counters`](Self::reset_counters).
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    /// The number of bytes sent to the server
+    ///
+    /// Like [`bytes_read`](Self::bytes_read), this counts protocol bytes before compression
+    /// and encryption. A write that fails is not counted.
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
+    /// Reset [`bytes_read`](Self::bytes_read) and [`bytes_written`](Self::bytes_written) to zero
+    pub fn reset_counters(&mut self) {
+        self.bytes_read = 0;
+        self.bytes_written = 0;
+    }
+
+    /// Upgrade a plain text connection to TLS via `STARTTLS`
+    ///
+    /// The server must respond with [`ContinueWithTls`](Kind::ContinueWithTls) (code 382),
+    /// after which the TLS handshake is performed over the existing socket. Otherwise,
+    /// [`Error::StartTlsRejected`] is returned and the connection remains in plain text.
+    ///
+    /// Any data that was buffered before the handshake is discarded as it was not protected
+    /// by TLS and may have been injected by an attacker.
+    ///
+    /// Note that the server's capabilities change after upgrading, so callers should retrieve
+    /// them again. [`Error::AlreadyTls`] is returned if the connection already uses TLS.
+    pub fn starttls(&mut self, config: TlsConfig) -> Result<()> {
+        let tcp_stream = match self.stream.get_ref().try_clone_tcp() {
+            Some(stream) => stream?,
+            None => return Err(Error::AlreadyTls),
+        };
+
+        let resp = self.command(&StartTls)?;
+        if resp.code() != ResponseCode::Known(Kind::ContinueWithTls) {
+            return Err(Error::StartTlsRejected(resp));
+        }
+
+        // Unread bytes are expected while the responses to pipelined commands are outstanding
+        if self.pending_responses == 0 && !self.stream.buffer().is_empty() {
+            warn!(
+                "Discarding {} bytes received before the TLS handshake",
+                self.stream.buffer().len()
+            );
+        }
+
+        trace!("Upgrading TcpStream w/ TlsConnector");
+        let TlsConfig {
+            connector, domain, ..
+        } = config;
+        let tls_stream = connector.connect(&domain, tcp_stream)?;
+
+        // n.b. replacing the reader drops any buffered plain text
+        self.stream = io::BufReader::with_capacity(self.config.read_buf_size, tls_stream.into());
+        self.tls_domain = Some(domain);
+
+        Ok(())
+    }
+
+    /// Compress the connection in both directions via `COMPRESS DEFLATE`
+    /// ([RFC 8054](https://tools.ietf.org/html/rfc8054))
+    ///
+    /// The server must respond with [`CompressionActive`](Kind::CompressionActive) (code 206),
+    /// after which every command and response is compressed with raw deflate. Otherwise,
+    /// [`Error::CompressRejected`] is returned and the connection remains uncompressed.
+    ///
+    /// Responses are decompressed before they are framed, so multi-line responses are read as
+    /// usual. [`Error::AlreadyCompressed`] is returned if the connection is already compressed.
+    pub fn compress_deflate(&mut self) -> Result<()> {
+        if self.is_compressed() {
+            return Err(Error::AlreadyCompressed);
+        }
+
+        let resp = self.command(&CompressDeflate)?;
+        if resp.code() != ResponseCode::Known(Kind::CompressionActive) {
+            return Err(Error::CompressRejected(resp));
+        }
+
+        trace!("Wrapping stream w/ deflate");
+        // The server starts compressing immediately, so anything already buffered is compressed
+        let buffered = self.stream.buffer().to_vec();
+        // n.b. the placeholder is dropped as soon as it is replaced
+        let placeholder = NntpStream::Custom(Box::new(io::Cursor::new(Vec::new())));
+        let stream =
+            std::mem::replace(&mut self.stream, io::BufReader::new(placeholder)).into_inner();
+        self.stream = io::BufReader::with_capacity(
+            self.config.read_buf_size,
+            NntpStream::Deflate(Box::new(DeflateStream::new(stream, buffered))),
+        );
+
+        Ok(())
+    }
+
     /// Create an NntpConnection with the default configuration
     pub fn with_defaults(addr: impl ToSocketAddrs) -> Result<(Self, RawResponse)> {
         Self::connect(addr, Default::default())
@@ -187,10 +710,23 @@ impl NntpConnection {
     /// 1. Block while reading the response
     /// 2. Parse the response
     /// 2. This function *may* allocate depending on the size of the response
+    ///
+    /// Continuation prompts (`3xx`) are returned like any other response, use
+    /// [`send_then_read`](Self::send_then_read) for commands that may prompt for more input.
     pub fn command<C: NntpCommand>(&mut self, command: &C) -> Result<RawResponse> {
+        self.send_then_read(command).map(Exchange::into_response)
+    }
+
+    /// Send a command and read the response, which may prompt the client to continue
+    ///
+    /// `3xx` responses are returned as [`Exchange::Continue`] and are never read as multi-line.
+    /// The caller decides how to continue, e.g. by sending an article via
+    /// [`send_data_blocks`](Self::send_data_blocks) or a password via another command.
+    pub fn send_then_read<C: NntpCommand>(&mut self, command: &C) -> Result<Exchange> {
         self.send(command)?;
-        let resp = self.read_response_auto()?;
-        Ok(resp)
+        let resp =
+            self.read_response_with(|code| !code.is_success_so_far() && code.is_multiline())?;
+        Ok(Exchange::from(resp))
     }
 
     /// Send a command and specify whether the response is multiline
@@ -208,7 +744,8 @@ impl NntpConnection {
     ///
     /// The caller is responsible for reading the response
     pub fn send<C: NntpCommand>(&mut self, command: &C) -> Result<usize> {
-        let bytes = self.send_bytes(command.encode())?;
+        let bytes = self.queue(command)?;
+        self.flush_commands()?;
         Ok(bytes)
     }
 
@@ -219,15 +756,165 @@ impl NntpConnection {
     ///
     /// * The caller is responsible for reading the response
     /// * The command SHOULD NOT include the CRLF terminator
+    ///
+    /// An [`Error::Desynchronized`] is returned if the previous response was not fully read and
+    /// an [`Error::InvalidCommand`] if the command contains a CR or LF or is too long.
     pub fn send_bytes(&mut self, command: impl AsRef<[u8]>) -> Result<usize> {
-        let writer = self.stream.get_mut();
-        // Write the command and terminal char
-        let bytes = writer.write(command.as_ref())? + writer.write(b"\r\n")?;
-        // Flush the buffer
-        writer.flush()?;
+        let bytes = self.queue_bytes(command)?;
+        self.flush_commands()?;
         Ok(bytes)
     }
 
+    /// Queue a command to be sent to the server, returning the number of bytes queued
+    ///
+    /// Queued commands are buffered so that several pipelined commands can be written with a
+    /// single syscall. They are written when [`flush_commands`](Self::flush_commands) is called,
+    /// when a response is read, or when the buffer grows large.
+    ///
+    /// The caller is responsible for reading a response for every queued command.
+    pub fn queue<C: NntpCommand>(&mut self, command: &C) -> Result<usize> {
+        self.queue_line(&command.to_wire_bytes(), command.max_line_len())
+    }
+
+    /// Queue a raw command to be sent to the server, returning the number of bytes queued
+    ///
+    /// See [`queue`](Self::queue) and [`send_bytes`](Self::send_bytes).
+    pub fn queue_bytes(&mut self, command: impl AsRef<[u8]>) -> Result<usize> {
+        let command = command.as_ref();
+        self.queue_line(&to_wire_line(command), raw_max_line_len(command))
+    }
+
+    /// Queue a CRLF terminated command line of at most `max_len` octets
+    fn queue_line(&mut self, line: &[u8], max_len: usize) -> Result<usize> {
+        if !self.is_at_command_boundary() {
+            return Err(Error::Desynchronized);
+        }
+        check_wire_line(line, max_len).map_err(Error::InvalidCommand)?;
+        self.write_buf.extend_from_slice(line);
+        self.pending_responses += 1;
+
+        if self.write_buf.len() >= WRITE_BUF_FLUSH_THRESHOLD {
+            self.flush_commands()?;
+        }
+
+        Ok(line.len())
+    }
+
+    /// Send several commands at once and read their responses
+    ///
+    /// All of the commands are written before any responses are read, saving a round trip per
+    /// command (e.g. when retrieving thousands of `HEAD`s). The returned responses are in the
+    /// same order as the commands, i.e. the `n`th response belongs to the `n`th command.
+    ///
+    /// Commands that require an intermediate response (`POST`, `IHAVE`, `AUTHINFO`) or that
+    /// change the state of the stream (`STARTTLS`, `COMPRESS`, `MODE`) cannot be pipelined, and
+    /// [`Error::CannotPipeline`] is returned without sending anything if one is included.
+    /// Likewise, [`Error::InvalidCommand`] is returned if any of the commands is invalid.
+    ///
+    /// Responses are read as in [`read_response_auto`](Self::read_response_auto), so commands
+    /// whose responses cannot be framed by their code alone (e.g. `LISTGROUP`) should be
+    /// [queued](Self::queue) and read manually instead. If reading a response fails, the
+    /// error is returned and the remaining responses are left unread.
+    ///
+    /// At most [`max_pipeline_depth`](ConnectionConfig::max_pipeline_depth) commands are written
+    /// before their responses are read, so longer pipelines take one round trip per chunk. If
+    /// reading fails, the commands in later chunks are not sent.
+    pub fn pipeline(&mut self, commands: &[&dyn NntpCommand]) -> Result<Vec<RawResponse>> {
+        let encoded = commands
+            .iter()
+            .map(|c| (c.to_wire_bytes(), c.max_line_len()))
+            .collect::<Vec<_>>();
+        for (line, max_len) in &encoded {
+            check_wire_line(line, *max_len).map_err(Error::InvalidCommand)?;
+        }
+        if let Some(command) = encoded
+            .iter()
+            .map(|(l, _)| strip_crlf(l))
+            .find(|c| !is_pipelinable(c))
+        {
+            let name = command.split(|b| *b == b' ').next().unwrap_or_default();
+            return Err(Error::CannotPipeline(
+                String::from_utf8_lossy(name).to_uppercase(),
+            ));
+        }
+
+        let mut resps = Vec::with_capacity(encoded.len());
+        for chunk in encoded.chunks(self.config.max_pipeline_depth) {
+            for (line, max_len) in chunk {
+                self.queue_line(line, *max_len)?;
+            }
+            self.flush_commands()?;
+
+            for _ in chunk {
+                resps.push(self.read_response_auto()?);
+            }
+        }
+
+        Ok(resps)
+    }
+
+    /// Write any [queued](Self::queue) commands to the server
+    ///
+    /// If the write times out, an [`Error::Timeout`] is returned and the connection is no longer
+    /// at a [command boundary](Self::is_at_command_boundary) as part of a command may have been
+    /// sent.
+    pub fn flush_commands(&mut self) -> Result<()> {
+        if self.write_buf.is_empty() {
+            return Ok(());
+        }
+        trace!("Flushing {} bytes of queued commands", self.write_buf.len());
+        if let Some(hook) = &self.config.trace_hook {
+            hook.sent(
+                &self.write_buf,
+                self.config.redact_passwords,
+                &mut self.sasl_exchange,
+            );
+        }
+        let writer = self.stream.get_mut();
+        let res = writer
+            .write_all(&self.write_buf)
+            .and_then(|_| writer.flush());
+        if res.is_ok() {
+            self.bytes_written += self.write_buf.len() as u64;
+        }
+        self.write_buf.clear();
+        self.last_sent = Instant::now();
+        match res {
+            Err(e) if is_timeout(&e) => {
+                self.mid_response = true;
+                Err(Error::Timeout { partial: None })
+            }
+            res => Ok(res?),
+        }
+    }
+
+    /// When a command (or data block) was last written to the server
+    ///
+    /// This is the time the connection was established if nothing has been sent yet.
+    pub(crate) fn last_sent(&self) -> Instant {
+        self.last_sent
+    }
+
+    /// Returns true if there are [queued](Self::queue) commands that have not been written
+    pub fn has_queued_commands(&self) -> bool {
+        !self.write_buf.is_empty()
+    }
+
+    /// Send a multi-line data block to the server (e.g. the article for `POST`)
+    ///
+    /// The data is dot-stuffed, terminated with a CRLF if necessary, and followed by the
+    /// end of data block marker (`.\r\n`), so it should be passed as-is.
+    /// Returns the number of bytes written.
+    pub fn send_data_blocks(&mut self, data: impl AsRef<[u8]>) -> Result<usize> {
+        if !self.is_at_command_boundary() {
+            return Err(Error::Desynchronized);
+        }
+        let encoded = encode_data_blocks(data.as_ref());
+        self.write_buf.extend_from_slice(&encoded);
+        self.flush_commands()?;
+        Ok(encoded.len())
+    }
+
     /// Read any data from the stream into a RawResponse
     ///
     /// This function attempts to automatically determine if the response is muliti-line based
@@ -242,44 +929,129 @@ impl NntpConnection {
 
     /// Read an NNTP response from the connection
     ///
+    /// # Timeouts
+    ///
+    /// If the read times out, an [`Error::Timeout`] is returned. When the timeout occurs while
+    /// reading data blocks, the error will contain every complete line that was received before
+    /// the timeout. Note that the rest of the response may still be in flight, so the connection
+    /// should not be reused for subsequent commands.
+    ///
     /// # Multiline Responses
     ///
     /// If `is_multiline` is set to None then the connection use [`ResponseCode::is_multiline`]
     /// to determine if it should expect a multiline response.
     /// This behavior can be overridden by manually specifying `Some(true)` or `Some(false)`
+    ///
+    /// # Command Boundaries
+    ///
+    /// If this returns an error before the response was completely read, the connection is no
+    /// longer at a [command boundary](Self::is_at_command_boundary) and will refuse to send
+    /// further commands.
     pub fn read_response(&mut self, is_multiline: Option<bool>) -> Result<RawResponse> {
+        self.read_response_with(|code| is_multiline == Some(true) || code.is_multiline())
+    }
+
+    /// Read an NNTP response, using `is_multiline` to decide from the response code whether
+    /// to read data blocks
+    pub(crate) fn read_response_with(
+        &mut self,
+        is_multiline: impl FnOnce(ResponseCode) -> bool,
+    ) -> Result<RawResponse> {
+        self.flush_commands()?;
+        self.mid_response = true;
+        self.pending_responses = self.pending_responses.saturating_sub(1);
         self.first_line_buf.truncate(0);
         self.data_blocks_buf.truncate(0);
-        let resp_code = read_initial_response(&mut self.stream, &mut self.first_line_buf)?;
-
-        let data_blocks = match (is_multiline, resp_code.is_multiline()) {
-            // Check for data blocks if the caller tells us to OR the kind is multiline
-            (Some(true), _) | (_, true) => {
-                trace!("Parsing data blocks for response {}", u16::from(resp_code));
-
-                // FIXME(ops): Consider pre-allocating this buffer
-                let mut line_boundaries = Vec::with_capacity(10);
-
-                let mut stream = match self.config.compression {
-                    Some(c) if c.use_decoder(&self.first_line_buf) => {
-                        trace!("Compression enabled, wrapping stream with decoder");
-                        c.decoder(&mut self.stream)
-                    }
-                    _ => {
-                        trace!("Using passthrough decoder");
-                        Decoder::Passthrough(&mut self.stream)
-                    }
-                };
-
-                read_data_blocks(&mut stream, &mut self.data_blocks_buf, &mut line_boundaries)?;
-
-                Some(DataBlocks {
-                    payload: self.data_blocks_buf.clone(),
-                    line_boundaries,
-                })
+        let resp_code = read_initial_response(
+            &mut Traced::new(&mut self.stream, self.config.trace_hook.as_ref()),
+            &mut self.first_line_buf,
+            self.config.lenient_line_endings,
+            self.config.max_line_bytes,
+        );
+        self.bytes_read += self.first_line_buf.len() as u64;
+        let resp_code = resp_code.map_err(|e| match e {
+            Error::Io(e) if is_timeout(&e) => Error::Timeout { partial: None },
+            e => e,
+        })?;
+        // A SASL exchange continues for as long as the server responds with 383
+        self.sasl_exchange &= u16::from(resp_code) == 383;
+
+        // Check for data blocks if the caller tells us to OR the kind is multiline
+        let multiline = is_multiline(resp_code);
+        if multiline && !resp_code.is_multiline() && !self.await_data_blocks()? {
+            debug!("No data blocks followed {}", resp_code);
+            let resp = RawResponse {
+                code: resp_code,
+                first_line: self.first_line_buf.clone(),
+                data_blocks: None,
+            };
+            // n.b. the connection is left mid-response as the data blocks may just be late
+            return Err(Error::MalformedResponse { resp });
+        }
+
+        let data_blocks = if multiline {
+            trace!("Parsing data blocks for response {}", resp_code);
+
+            // FIXME(ops): Consider pre-allocating this buffer
+            let mut line_boundaries = Vec::with_capacity(10);
+
+            let mut stream = match self.config.compression {
+                Some(c) if c.use_decoder(&self.first_line_buf) => {
+                    trace!("Compression enabled, wrapping stream with decoder");
+                    c.decoder(&mut self.stream)
+                }
+                _ => {
+                    trace!("Using passthrough decoder");
+                    Decoder::Passthrough(&mut self.stream)
+                }
+            };
+
+            let res = read_data_blocks(
+                &mut Traced::new(&mut stream, self.config.trace_hook.as_ref()),
+                &mut self.data_blocks_buf,
+                &mut line_boundaries,
+                self.config.max_response_bytes,
+                self.config.max_line_bytes,
+                self.config.lenient_line_endings,
+            );
+            // n.b. if reading fails dot-stuffing has already been undone, so this may be short
+            let bytes_read = match &res {
+                Ok(bytes_read) => *bytes_read,
+                Err(_) => self.data_blocks_buf.len(),
+            };
+            self.bytes_read += bytes_read as u64;
+
+            match res {
+                Err(Error::Io(e)) if is_timeout(&e) => {
+                    // Hand back every line that was completely read before the timeout
+                    let end = line_boundaries.last().map(|(_, end)| *end).unwrap_or(0);
+                    let partial = RawResponse {
+                        code: resp_code,
+                        first_line: self.first_line_buf.clone(),
+                        data_blocks: Some(DataBlocks {
+                            payload: self.data_blocks_buf[..end].to_vec(),
+                            line_boundaries,
+                        }),
+                    };
+                    return Err(Error::Timeout {
+                        partial: Some(partial),
+                    });
+                }
+                Err(Error::ConnectionClosed { bytes_read }) => {
+                    return Err(Error::ConnectionClosed {
+                        bytes_read: bytes_read + self.first_line_buf.len(),
+                    });
+                }
+                Err(e) => return Err(e),
+                Ok(_) => {}
             }
-            (Some(false), _) => None, // The caller says not to look for data blocks
-            _ => None,
+
+            Some(DataBlocks {
+                payload: self.data_blocks_buf.clone(),
+                line_boundaries,
+            })
+        } else {
+            None
         };
 
         let resp = RawResponse {
@@ -289,46 +1061,366 @@ impl NntpConnection {
         };
 
         self.reset_buffers();
+        self.mid_response = false;
+
+        // Unread bytes are expected while the responses to pipelined commands are outstanding
+        if self.pending_responses == 0 && !self.stream.buffer().is_empty() {
+            warn!(
+                "{} unread bytes remain after response {}, the response may have been misframed",
+                self.stream.buffer().len(),
+                resp.code
+            );
+        }
 
         Ok(resp)
     }
 
-    /// Reset the connection's buffers to their initial size
+    /// Read an NNTP response and check the number of lines in its data blocks
     ///
-    /// This should be run after reading responses to prevent the buffers from growing unbounded
-    fn reset_buffers(&mut self) {
-        // Honestly we should probably just use the bytes create, it seems better suited to
-        //  what we want in this layer
-        self.first_line_buf
-            .truncate(self.config.first_line_buf_size);
-        self.first_line_buf.shrink_to_fit();
-
-        self.data_blocks_buf
-            .truncate(self.config.data_blocks_buf_size);
-        self.data_blocks_buf.shrink_to_fit();
-    }
+    /// This can be used to detect truncated transfers when the number of lines is known ahead
+    /// of time (e.g. an `OVER` for a range of articles listed by `LISTGROUP`).
+    /// See [`ExpectedLines`] for details on how mismatches are handled.
+    pub fn read_response_expecting(
+        &mut self,
+        is_multiline: Option<bool>,
+        expected: ExpectedLines,
+    ) -> Result<RawResponse> {
+        let resp = self.read_response(is_multiline)?;
+        // n.b. the terminating `.` line is not counted
+        let actual = resp
+            .data_blocks()
+            .map(|db| db.lines_len().saturating_sub(1))
+            .unwrap_or(0);
 
-    /// Get a ref to the underlying NntpStream
-    pub fn stream(&self) -> &io::BufReader<NntpStream> {
-        &self.stream
+        expected.check(actual, || Some(resp.clone()))?;
+        Ok(resp)
     }
 
-    /// Get a mutable ref to the underlying NntpStream
+    /// Read the first line of a response, returning a reader for the data blocks (if any)
     ///
-    /// This can be useful if you want to handle response parsing and/or control buffering
-    pub fn stream_mut(&mut self) -> &mut io::BufReader<NntpStream> {
-        &mut self.stream
-    }
+    /// Unlike [`read_response`](Self::read_response), the data blocks are not buffered.
+    /// Instead, they can be read one line at a time with the returned [`DataBlockReader`],
+    /// allowing each line to be processed as soon as it arrives and bounding the memory used to a
+    /// single line. `is_multiline` has the same meaning as in `read_response`.
+    ///
+    /// The returned [`RawResponse`] never contains data blocks. The data blocks must be read to
+    /// completion before another command can be sent.
+    pub fn read_response_streaming(
+        &mut self,
+        is_multiline: Option<bool>,
+    ) -> Result<(RawResponse, Option<DataBlockReader<'_>>)> {
+        self.flush_commands()?;
+        self.mid_response = true;
+        self.pending_responses = self.pending_responses.saturating_sub(1);
+        self.first_line_buf.truncate(0);
+        let resp_code = read_initial_response(
+            &mut Traced::new(&mut self.stream, self.config.trace_hook.as_ref()),
+            &mut self.first_line_buf,
+            self.config.lenient_line_endings,
+            self.config.max_line_bytes,
+        );
+        self.bytes_read += self.first_line_buf.len() as u64;
+        let resp_code = resp_code.map_err(|e| match e {
+            Error::Io(e) if is_timeout(&e) => Error::Timeout { partial: None },
+            e => e,
+        })?;
+        // A SASL exchange continues for as long as the server responds with 383
+        self.sasl_exchange &= u16::from(resp_code) == 383;
 
-    /// Get the configuration of the connection
-    pub fn config(&self) -> &ConnectionConfig {
+        let resp = RawResponse {
+            code: resp_code,
+            first_line: self.first_line_buf.clone(),
+            data_blocks: None,
+        };
+
+        if is_multiline != Some(true) && !resp_code.is_multiline() {
+            self.mid_response = false;
+            return Ok((resp, None));
+        }
+
+        Ok((resp, Some(self.data_block_reader())))
+    }
+
+    /// Stream the data blocks following the status line that was just read
+    ///
+    /// This allows a response whose status line was read as a single-line response (e.g. by
+    /// [`read_response_with`](Self::read_response_with)) to be continued with a
+    /// [`DataBlockReader`]. The connection is no longer at a command boundary until the reader
+    /// has read the terminating `.` line.
+    pub(crate) fn data_block_reader(&mut self) -> DataBlockReader<'_> {
+        self.mid_response = true;
+        let stream = match self.config.compression {
+            Some(c) if c.use_decoder(&self.first_line_buf) => c.decoder(&mut self.stream),
+            _ => Decoder::Passthrough(&mut self.stream),
+        };
+
+        DataBlockReader {
+            stream,
+            mid_response: &mut self.mid_response,
+            total_bytes_read: &mut self.bytes_read,
+            line: Vec::with_capacity(self.config.first_line_buf_size),
+            lines_read: 0,
+            bytes_read: self.first_line_buf.len(),
+            expected: None,
+            lenient: self.config.lenient_line_endings,
+            max_line_bytes: self.config.max_line_bytes,
+            trace_hook: self.config.trace_hook.as_ref(),
+            done: false,
+        }
+    }
+
+    /// Returns true if the previous response was completely read
+    ///
+    /// A connection that is not at a command boundary cannot be used to send further commands as
+    /// the remainder of the previous response would be read as the response to the next command.
+    /// This happens when reading a response fails part way through (e.g. a [timeout](Error::Timeout))
+    /// or when a write times out.
+    pub fn is_at_command_boundary(&self) -> bool {
+        !self.mid_response
+    }
+
+    /// Discard the rest of a partially read multi-line response
+    ///
+    /// Lines are read and discarded until the terminating `.` line, after which the connection
+    /// is back at a [command boundary](Self::is_at_command_boundary). This can be used to recover
+    /// after a [timeout](Error::Timeout) while reading data blocks or after dropping a
+    /// [`DataBlockReader`] early. Nothing is read if the connection is already at a command
+    /// boundary.
+    ///
+    /// At most [`max_response_bytes`](ConnectionConfig::max_response_bytes) are discarded,
+    /// after which [`Error::ResponseTooLarge`] is returned. Note that this cannot recover from
+    /// a failure before the first line of a response was read (as it is unknown whether data
+    /// blocks follow) or from a failed write.
+    pub fn resync(&mut self) -> Result<()> {
+        if self.is_at_command_boundary() {
+            return Ok(());
+        }
+        trace!("Discarding the remainder of a multi-line response...");
+
+        let limit = self.config.max_response_bytes;
+        let max_line_bytes = self.config.max_line_bytes;
+        let mut line = Vec::with_capacity(self.config.first_line_buf_size);
+        let mut discarded = 0;
+        loop {
+            line.truncate(0);
+            let budget = min_budget(
+                response_budget(limit, discarded),
+                line_budget(max_line_bytes),
+            );
+            let res = read_line(&mut self.stream, &mut line, budget);
+            let bytes_read = res.map_err(|e| match is_timeout(&e) {
+                true => Error::Timeout { partial: None },
+                false => e.into(),
+            })?;
+            discarded += bytes_read;
+            self.bytes_read += bytes_read as u64;
+            if let Some(hook) = &self.config.trace_hook {
+                hook.received(&line);
+            }
+
+            match limit {
+                Some(limit) if discarded > limit => return Err(Error::ResponseTooLarge { limit }),
+                _ => {}
+            }
+            check_line_length(bytes_read, max_line_bytes)?;
+            check_line_complete(&line, discarded)?;
+
+            let lenient = self.config.lenient_line_endings;
+            if matches!(parse_data_block_line(&line, lenient), Ok((_, line)) if is_end_of_datablock(line))
+            {
+                debug!("Discarded {} bytes to resynchronize", discarded);
+                self.mid_response = false;
+                return Ok(());
+            }
+        }
+    }
+
+    /// Reset the connection's buffers to their initial size
+    ///
+    /// This should be run after reading responses to prevent the buffers from growing unbounded
+    fn reset_buffers(&mut self) {
+        // Honestly we should probably just use the bytes create, it seems better suited to
+        //  what we want in this layer
+        self.first_line_buf
+            .truncate(self.config.first_line_buf_size);
+        self.first_line_buf.shrink_to_fit();
+
+        self.data_blocks_buf
+            .truncate(self.config.data_blocks_buf_size);
+        self.data_blocks_buf.shrink_to_fit();
+    }
+
+    /// Get a ref to the underlying NntpStream
+    pub fn stream(&self) -> &io::BufReader<NntpStream> {
+        &self.stream
+    }
+
+    /// Get a mutable ref to the underlying NntpStream
+    ///
+    /// This can be useful if you want to handle response parsing and/or control buffering
+    pub fn stream_mut(&mut self) -> &mut io::BufReader<NntpStream> {
+        &mut self.stream
+    }
+
+    /// Get the configuration of the connection
+    pub fn config(&self) -> &ConnectionConfig {
         &self.config
     }
+
+    /// The address of the server the connection was established with
+    ///
+    /// This is `None` for connections created with [`from_stream`](Self::from_stream) or
+    /// [`connect_unix`](Self::connect_unix), and for hosts resolved by a proxy.
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        self.peer_addr
+    }
+
+    /// The read timeout of the underlying socket
+    ///
+    /// An error is returned for connections created with [`from_stream`](Self::from_stream).
+    pub fn read_timeout(&self) -> Result<Option<Duration>> {
+        Ok(self.stream.get_ref().read_timeout()?)
+    }
+
+    /// Set the read timeout of the underlying socket
+    ///
+    /// Unlike [`ConnectionConfig::read_timeout`] this only applies to the current socket, a
+    /// [reconnect](Self::reconnect) uses the configured timeout. An error is returned for
+    /// connections created with [`from_stream`](Self::from_stream).
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) -> Result<()> {
+        Ok(self.stream.get_ref().set_read_timeout(timeout)?)
+    }
+
+    /// Wait for the data blocks of a response whose code doesn't imply any, returning false if
+    /// nothing arrived within the [ambiguous response
+    /// timeout](ConnectionConfig::ambiguous_response_timeout)
+    fn await_data_blocks(&mut self) -> Result<bool> {
+        let timeout = match self.config.ambiguous_response_timeout {
+            Some(timeout) if self.stream.buffer().is_empty() => timeout,
+            _ => return Ok(true),
+        };
+        let previous = match self.read_timeout() {
+            Ok(previous) => previous,
+            // Custom streams can only be read until something arrives
+            Err(_) => return Ok(true),
+        };
+
+        self.set_read_timeout(Some(previous.map_or(timeout, |p| p.min(timeout))))?;
+        let res = self.stream.fill_buf().map(|_| ());
+        self.set_read_timeout(previous)?;
+        match res {
+            Err(e) if is_timeout(&e) => Ok(false),
+            // n.b. an EOF is reported while reading the data blocks
+            res => res.map(|_| true).map_err(Error::from),
+        }
+    }
+
+    /// Returns true if the connection is using TLS
+    pub fn is_tls(&self) -> bool {
+        self.stream.get_ref().is_tls()
+    }
+
+    /// Returns true if the connection is [compressed](Self::compress_deflate)
+    pub fn is_compressed(&self) -> bool {
+        self.stream.get_ref().is_compressed()
+    }
+
+    /// The domain used to validate the server's certificate if TLS is in use
+    ///
+    /// This is set by [`connect`](Self::connect) and [`starttls`](Self::starttls).
+    pub fn tls_domain(&self) -> Option<&str> {
+        self.tls_domain.as_deref()
+    }
 }
 
 /// A buffered NntpStream
 pub type BufNntpStream = io::BufReader<NntpStream>;
 
+/// Reads the data blocks of a multi-line response one line at a time
+///
+/// Created by [`NntpConnection::read_response_streaming`]. Only a single line is buffered at a
+/// time. If the reader is dropped before the terminating `.` line has been read, the connection
+/// will no longer be at a [command boundary](NntpConnection::is_at_command_boundary).
+#[derive(Debug)]
+pub struct DataBlockReader<'a> {
+    stream: Decoder<&'a mut BufNntpStream>,
+    mid_response: &'a mut bool,
+    total_bytes_read: &'a mut u64,
+    line: Vec<u8>,
+    lines_read: usize,
+    bytes_read: usize,
+    expected: Option<ExpectedLines>,
+    lenient: bool,
+    max_line_bytes: Option<usize>,
+    trace_hook: Option<&'a TraceHook>,
+    done: bool,
+}
+
+impl DataBlockReader<'_> {
+    /// Check the number of lines once the data blocks have been read
+    ///
+    /// If the check is strict, the final call to [`next_line`](Self::next_line) will return an
+    /// [`Error::LineCountMismatch`] rather than `None`.
+    pub fn expect_lines(&mut self, expected: Option<ExpectedLines>) -> &mut Self {
+        self.expected = expected;
+        self
+    }
+
+    /// The number of lines that have been read, not including the terminating `.` line
+    pub fn lines_read(&self) -> usize {
+        self.lines_read
+    }
+
+    /// Read the next line of the data blocks
+    ///
+    /// Lines are returned WITHOUT the CRLF terminator. `None` is returned once the final line
+    /// containing only `.` has been read.
+    pub fn next_line(&mut self) -> Result<Option<&[u8]>> {
+        if self.done {
+            return Ok(None);
+        }
+
+        self.line.truncate(0);
+        let budget = line_budget(self.max_line_bytes);
+        let bytes_read = read_line(&mut self.stream, &mut self.line, budget).map_err(|e| {
+            if is_timeout(&e) {
+                Error::Timeout { partial: None }
+            } else {
+                e.into()
+            }
+        })?;
+        self.bytes_read += bytes_read;
+        *self.total_bytes_read += bytes_read as u64;
+        if let Some(hook) = self.trace_hook {
+            hook.received(&self.line);
+        }
+        check_line_length(bytes_read, self.max_line_bytes)?;
+        check_line_complete(&self.line, self.bytes_read)?;
+
+        let (_empty, line) = parse_data_block_line(&self.line, self.lenient).map_err(|_e| {
+            io::Error::new(ErrorKind::InvalidData, "Failed to parse data block line")
+        })?;
+
+        if is_end_of_datablock(line) {
+            self.done = true;
+            *self.mid_response = false;
+            if let Some(expected) = self.expected {
+                expected.check(self.lines_read, || None)?;
+            }
+            Ok(None)
+        } else {
+            self.lines_read += 1;
+            // Undo the dot-stuffing performed by the server
+            Ok(Some(line.strip_prefix(b".").unwrap_or(line)))
+        }
+    }
+
+    /// Returns true if the terminating `.` line has been read
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+}
+
 /// A builder for [`NntpConnection`]
 #[derive(Clone, Debug)]
 pub struct ConnectionConfig {
@@ -338,6 +1430,72 @@ pub struct ConnectionConfig {
     pub(crate) write_timeout: Option<Duration>,
     pub(crate) first_line_buf_size: usize,
     pub(crate) data_blocks_buf_size: usize,
+    pub(crate) read_buf_size: usize,
+    pub(crate) max_response_bytes: Option<usize>,
+    pub(crate) max_line_bytes: Option<usize>,
+    pub(crate) ambiguous_response_timeout: Option<Duration>,
+    pub(crate) proxy: Option<ProxyConfig>,
+    pub(crate) connect_timeout: Option<Duration>,
+    pub(crate) address_family: AddressFamily,
+    pub(crate) lenient_line_endings: bool,
+    pub(crate) trace_hook: Option<TraceHook>,
+    pub(crate) redact_passwords: bool,
+    pub(crate) max_pipeline_depth: usize,
+}
+
+/// Which addresses are used when a hostname resolves to both IPv4 and IPv6 addresses
+///
+/// Addresses are tried in turn until a connection is established.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum AddressFamily {
+    /// Try the addresses in the order they were resolved (the default)
+    #[default]
+    Any,
+    /// Only use IPv4 addresses
+    V4Only,
+    /// Only use IPv6 addresses
+    V6Only,
+    /// Try IPv4 addresses before IPv6 addresses
+    PreferV4,
+    /// Try IPv6 addresses before IPv4 addresses
+    PreferV6,
+}
+
+impl AddressFamily {
+    /// Filter and order resolved addresses according to the preference
+    ///
+    /// Returns [`Error::NoMatchingAddress`] if addresses were resolved but none match.
+    pub(crate) fn select(self, resolved: Vec<SocketAddr>) -> Result<Vec<SocketAddr>> {
+        let mut addrs = match self {
+            AddressFamily::V4Only => resolved
+                .iter()
+                .copied()
+                .filter(SocketAddr::is_ipv4)
+                .collect(),
+            AddressFamily::V6Only => resolved
+                .iter()
+                .copied()
+                .filter(SocketAddr::is_ipv6)
+                .collect(),
+            _ => resolved.clone(),
+        };
+
+        match self {
+            // n.b. sorting is stable so the resolver's order is kept within each family
+            AddressFamily::PreferV4 => addrs.sort_by_key(SocketAddr::is_ipv6),
+            AddressFamily::PreferV6 => addrs.sort_by_key(SocketAddr::is_ipv4),
+            _ => {}
+        }
+
+        if addrs.is_empty() && !resolved.is_empty() {
+            return Err(Error::NoMatchingAddress {
+                family: self,
+                resolved,
+            });
+        }
+
+        Ok(addrs)
+    }
 }
 
 impl Default for ConnectionConfig {
@@ -349,6 +1507,17 @@ impl Default for ConnectionConfig {
             write_timeout: None,
             first_line_buf_size: 128,
             data_blocks_buf_size: 16 * 1024,
+            read_buf_size: DEFAULT_READ_BUF_SIZE,
+            max_response_bytes: None,
+            max_line_bytes: Some(DEFAULT_MAX_LINE_BYTES),
+            ambiguous_response_timeout: None,
+            proxy: None,
+            connect_timeout: None,
+            address_family: AddressFamily::Any,
+            lenient_line_endings: false,
+            trace_hook: None,
+            redact_passwords: false,
+            max_pipeline_depth: DEFAULT_MAX_PIPELINE_DEPTH,
         }
     }
 }
@@ -386,6 +1555,35 @@ impl ConnectionConfig {
         self
     }
 
+    /// Set the write timeout on the socket
+    ///
+    /// This applies to plain text and TLS connections alike.
+    pub fn write_timeout(&mut self, dur: Option<Duration>) -> &mut Self {
+        self.write_timeout = dur;
+        self
+    }
+
+    /// Set the timeout for establishing the TCP connection
+    ///
+    /// Each address the server's name resolves to is tried in turn for at most `dur`, so a
+    /// hostname with several records fails over to the next address. If every attempt times out,
+    /// an [`Error::ConnectTimeout`] is returned. Without a timeout, connecting to an unreachable
+    /// server blocks for the OS default.
+    pub fn connect_timeout(&mut self, dur: Option<Duration>) -> &mut Self {
+        self.connect_timeout = dur;
+        self
+    }
+
+    /// Choose which of the server's resolved addresses are tried, and in which order
+    ///
+    /// This also applies to connections through a [proxy](Self::proxy), whose destination is
+    /// resolved locally, but not to hostnames that are left to the proxy to resolve with
+    /// [`NntpConnection::connect_host`]. Defaults to [`AddressFamily::Any`].
+    pub fn address_family(&mut self, pref: AddressFamily) -> &mut Self {
+        self.address_family = pref;
+        self
+    }
+
     /// Set the size of the buffer used to read the first line
     pub fn first_line_buf_size(&mut self, s: usize) -> &mut Self {
         self.first_line_buf_size = s;
@@ -398,82 +1596,1441 @@ impl ConnectionConfig {
         self
     }
 
+    /// Set the size of the buffer used to read from the socket
+    ///
+    /// Larger buffers reduce the number of syscalls when reading large responses (e.g. binary
+    /// articles or overviews). Defaults to [`DEFAULT_READ_BUF_SIZE`] (8 KiB).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes` is zero.
+    pub fn read_buf_size(&mut self, bytes: usize) -> &mut Self {
+        assert!(bytes > 0, "The read buffer size must be greater than zero");
+        self.read_buf_size = bytes;
+        self
+    }
+
+    /// Limit the number of commands [pipelined](NntpConnection::pipeline) before reading their
+    /// responses
+    ///
+    /// Longer pipelines are written in chunks of `depth` commands, reading every response in a
+    /// chunk before writing the next. While the client is writing it doesn't read, so the
+    /// server's responses pile up in its send buffer and then in the client's receive buffer.
+    /// Once both are full the server stops reading commands, the client blocks writing them, and
+    /// neither makes progress until one of them times out or disconnects. How many commands it
+    /// takes to get there depends on the size of the responses and of the socket buffers, so
+    /// keep this modest: a handful of commands in flight already hides most of the round trip
+    /// latency.
+    ///
+    /// Defaults to [`DEFAULT_MAX_PIPELINE_DEPTH`] (32).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `depth` is zero.
+    pub fn max_pipeline_depth(&mut self, depth: usize) -> &mut Self {
+        assert!(depth > 0, "The pipeline depth must be greater than zero");
+        self.max_pipeline_depth = depth;
+        self
+    }
+
+    /// Limit the size of the data blocks of a multi-line response
+    ///
+    /// Reading fails with [`Error::ResponseTooLarge`] as soon as the data blocks exceed `limit`
+    /// bytes, protecting against servers that send unbounded responses. The limit is checked as
+    /// the data arrives, so no more than `limit` bytes (plus one) are buffered.
+    ///
+    /// Responses are unbounded by default. This does not apply to
+    /// [streamed](NntpConnection::read_response_streaming) responses.
+    pub fn max_response_bytes(&mut self, limit: Option<usize>) -> &mut Self {
+        self.max_response_bytes = limit;
+        self
+    }
+
+    /// Limit the length of a single response line, including its terminator
+    ///
+    /// Reading fails with [`Error::LineTooLong`] as soon as a line exceeds `limit` bytes, so a
+    /// server (or corrupt stream) sending a line without a terminator cannot grow the buffers
+    /// without bound. [RFC 3977](https://tools.ietf.org/html/rfc3977#section-3.1.1) caps lines
+    /// at 998 octets plus the CRLF, but some servers exceed that, so the default is a generous
+    /// [`DEFAULT_MAX_LINE_BYTES`] (64 KiB). This complements
+    /// [`max_response_bytes`](Self::max_response_bytes) and also applies to
+    /// [streamed](NntpConnection::read_response_streaming) responses.
+    pub fn max_line_bytes(&mut self, limit: Option<usize>) -> &mut Self {
+        self.max_line_bytes = limit;
+        self
+    }
+
+    /// Limit how long to wait for data blocks that the response code doesn't imply
+    ///
+    /// Whether a response has data blocks is usually determined by its code
+    /// (see [`Kind::is_multiline`]), but a few codes depend on the command, e.g. `211` is only
+    /// followed by data blocks in response to `LISTGROUP`. Some servers send only the first line
+    /// anyway, which would otherwise leave the client waiting for a terminator that never comes.
+    ///
+    /// If nothing follows the first line of such a response within `timeout` (or the
+    /// [read timeout](Self::read_timeout), if that is shorter), reading fails with
+    /// [`Error::MalformedResponse`]. As the data blocks may still arrive later, the connection is
+    /// then no longer at a [command boundary](NntpConnection::is_at_command_boundary) and must be
+    /// [reconnected](NntpConnection::reconnect).
+    ///
+    /// Defaults to `None`, which waits indefinitely. This is not applied to
+    /// [custom streams](NntpConnection::from_stream), whose timeouts can't be changed.
+    pub fn ambiguous_response_timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+        self.ambiguous_response_timeout = timeout;
+        self
+    }
+
+    /// Accept a lone LF as a line terminator in responses
+    ///
+    /// NNTP lines are terminated by a CRLF, but some misconfigured servers and proxies send bare
+    /// LFs, which otherwise fail to parse. When enabled, a line ending with a lone `\n` is accepted,
+    /// including the `.\n` line terminating the data blocks.
+    ///
+    /// Lines are kept as they were received, so the lines returned by [`DataBlocks::lines`] and
+    /// [`DataBlocks::payload`] may end with a lone LF rather than a CRLF. Disabled by default.
+    pub fn lenient_line_endings(&mut self, lenient: bool) -> &mut Self {
+        self.lenient_line_endings = lenient;
+        self
+    }
+
+    /// Invoke `hook` with every chunk of bytes sent to and received from the server
+    ///
+    /// This is intended for debugging and for recording sessions, where the exact bytes
+    /// exchanged are needed rather than log output. Commands are traced as they are flushed and
+    /// responses as they are read (at most one line per call). Bytes are traced after TLS and
+    /// compression have been removed.
+    ///
+    /// The hook is invoked synchronously, so it should return quickly. Passwords are traced
+    /// as-is unless [`redact_passwords`](Self::redact_passwords) is enabled.
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use brokaw::raw::connection::ConnectionConfig;
+    /// use brokaw::raw::trace::Direction;
+    ///
+    /// let config = ConnectionConfig::new()
+    ///     .trace_hook(Arc::new(|direction, bytes: &[u8]| match direction {
+    ///         Direction::Send => eprint!("> {}", String::from_utf8_lossy(bytes)),
+    ///         Direction::Recv => eprint!("< {}", String::from_utf8_lossy(bytes)),
+    ///     }))
+    ///     .redact_passwords(true)
+    ///     .to_owned();
+    /// ```
+    pub fn trace_hook(&mut self, hook: Arc<TraceFn>) -> &mut Self {
+        self.trace_hook = Some(TraceHook(hook));
+        self
+    }
+
+    /// Replace the credentials in authentication commands passed to the
+    /// [trace hook](Self::trace_hook) with `********`
+    ///
+    /// This covers the password of `AUTHINFO PASS`, the initial response of `AUTHINFO SASL` and
+    /// every line sent in reply to the server's SASL challenges, and the arguments following the
+    /// authenticator of `AUTHINFO GENERIC`. Only the trace is redacted, the credentials are still
+    /// sent to the server. Disabled by default.
+    pub fn redact_passwords(&mut self, redact: bool) -> &mut Self {
+        self.redact_passwords = redact;
+        self
+    }
+
+    /// Route the connection through a SOCKS5 proxy
+    ///
+    /// TLS, if configured, is negotiated with the server over the proxied connection.
+    /// Use [`NntpConnection::connect_host`] to have the proxy resolve the server's host, the
+    /// address passed to [`NntpConnection::connect`] is resolved locally.
+    pub fn proxy(&mut self, proxy: Option<ProxyConfig>) -> &mut Self {
+        self.proxy = proxy;
+        self
+    }
+
     /// Create a connection from the config
     pub fn connect(&self, addr: impl ToSocketAddrs) -> Result<(NntpConnection, RawResponse)> {
         NntpConnection::connect(addr, self.clone())
     }
+
+    /// Create a connection from the config, leaving the resolution of the host to the proxy
+    ///
+    /// See [`NntpConnection::connect_host`].
+    pub fn connect_host(&self, addr: &ServerAddr) -> Result<(NntpConnection, RawResponse)> {
+        NntpConnection::connect_host(addr, self.clone())
+    }
+
+    /// Create a connection to a Unix domain socket from the config
+    ///
+    /// See [`NntpConnection::connect_unix`].
+    #[cfg(unix)]
+    pub fn connect_unix(&self, path: impl AsRef<Path>) -> Result<(NntpConnection, RawResponse)> {
+        NntpConnection::connect_unix(path, self.clone())
+    }
+
+    /// Create a connection from a stream that was established elsewhere
+    ///
+    /// See [`NntpConnection::from_stream`].
+    pub fn connect_stream(
+        &self,
+        stream: impl Transport + 'static,
+        expect_greeting: bool,
+    ) -> Result<(NntpConnection, Option<RawResponse>)> {
+        let stream = NntpStream::Custom(Box::new(stream));
+        let mut conn = NntpConnection::new(stream, None, self.clone(), None);
+        let greeting = match expect_greeting {
+            true => Some(conn.read_response_auto()?),
+            false => None,
+        };
+
+        Ok((conn, greeting))
+    }
 }
 
-/// Read the initial response from a stream
+/// The number of lines a multi-line response is expected to contain
 ///
-/// Per [RFC 3977](https://tools.ietf.org/html/rfc3977#section-3.1) the initial response
-/// should not exceed 512 bytes
-fn read_initial_response<S: io::BufRead>(
-    stream: &mut S,
-    buffer: &mut Vec<u8>,
-) -> Result<ResponseCode> {
-    stream.read_until(b'\n', buffer)?;
-    let (_initial_line_buffer, resp) = parse_first_line(&buffer).map_err(|_e| {
-        io::Error::new(
-            ErrorKind::InvalidData,
-            "Failed to parse first line of response",
-        )
-    })?;
+/// A response is considered a mismatch if the number of lines (excluding the terminating `.`)
+/// differs from `count` by more than `tolerance`. Mismatches are logged as warnings unless
+/// `strict` is set, in which case an [`Error::LineCountMismatch`] is returned.
+///
+/// A tolerance is useful because articles may be expired or cancelled between the time the
+/// count was determined and the time the response was requested.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ExpectedLines {
+    /// The expected number of lines
+    pub count: usize,
+    /// The allowed difference between the expected and actual number of lines
+    pub tolerance: usize,
+    /// Return an error rather than logging a warning on mismatch
+    pub strict: bool,
+}
+
+impl ExpectedLines {
+    /// Expect exactly `count` lines, logging a warning on mismatch
+    pub fn new(count: usize) -> Self {
+        Self {
+            count,
+            tolerance: 0,
+            strict: false,
+        }
+    }
+
+    fn check(&self, actual: usize, resp: impl FnOnce() -> Option<RawResponse>) -> Result<()> {
+        if actual.abs_diff(self.count) <= self.tolerance {
+            Ok(())
+        } else if self.strict {
+            Err(Error::LineCountMismatch {
+                expected: self.count,
+                actual,
+                resp: resp(),
+            })
+        } else {
+            warn!("Expected {} lines but received {}", self.count, actual);
+            Ok(())
+        }
+    }
+}
+
+/// Returns true if the error was caused by a socket timeout
+///
+/// Depending on the platform, timeouts are reported as either `WouldBlock` or `TimedOut`
+fn is_timeout(e: &io::Error) -> bool {
+    matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut)
+}
 
-    // This made it past the parser -> infallible
-    let code_str = std::str::from_utf8(resp.code).unwrap();
-    // All three digit integers will fit w/in u16 -> also infallible
-    let code_u16 = u16::from_str(code_str).unwrap();
+/// Returns false if the command cannot be [pipelined](NntpConnection::pipeline)
+fn is_pipelinable(command: &[u8]) -> bool {
+    const NOT_PIPELINABLE: &[&[u8]] = &[
+        b"AUTHINFO",
+        b"COMPRESS",
+        b"IHAVE",
+        b"MODE",
+        b"POST",
+        b"STARTTLS",
+    ];
 
-    Ok(code_u16.into())
+    let name = command.split(|b| *b == b' ').next().unwrap_or_default();
+    !NOT_PIPELINABLE
+        .iter()
+        .any(|keyword| name.eq_ignore_ascii_case(keyword))
 }
 
-/// Read multi-line data block portion from a stream
+/// Encode data for transmission as a multi-line data block
 ///
-/// * The data will be read line-by-line into the provided `buffer`
-/// * The `line_boundaries` vector will contain a list two-tuples containing the start and ending
-///   of every line within the `buffer`
-/// * Note that depending on the command the total data size may be on the order of several megabytes!
-fn read_data_blocks<S: io::BufRead>(
-    stream: &mut S,
-    buffer: &mut Vec<u8>,
-    line_boundaries: &mut Vec<(usize, usize)>,
-) -> Result<()> {
-    let mut read_head = 0;
-    trace!("Reading data blocks...");
-
-    // n.b. - icky imperative style so that we have zero allocations outside of the reader
-    loop {
-        // n.b. - read_until will _append_ data from the current end of the vector
-        let bytes_read = stream.read_until(b'\n', buffer)?;
-
-        let (_empty, line) = parse_data_block_line(&buffer[read_head..]).map_err(|e| {
-            trace!("parse_data_block_line failed -- {:?}", e);
-            io::Error::new(
-                ErrorKind::InvalidData,
-                format!(
-                    "Failed to parse line {} of data blocks",
-                    line_boundaries.len() + 1
-                ),
+/// Per [RFC 3977](https://tools.ietf.org/html/rfc3977#section-3.1.1) lines beginning with a `.`
+/// are "dot-stuffed" by prepending another `.`, and the block is terminated by a line containing
+/// a single `.`.
+fn encode_data_blocks(data: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(data.len() + 5);
+    let mut line_start = true;
+    for &byte in data {
+        if line_start && byte == b'.' {
+            encoded.push(b'.');
+        }
+        encoded.push(byte);
+        line_start = byte == b'\n';
+    }
+    if !data.is_empty() && !data.ends_with(b"\r\n") {
+        encoded.extend_from_slice(b"\r\n");
+    }
+    encoded.extend_from_slice(b".\r\n");
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Read;
+    use std::net::TcpListener;
+
+    use crate::raw::error::Error;
+    use crate::test_support::{MockScript, DEFAULT_GREETING};
+    use crate::types::command::MAX_COMMAND_LINE_LEN;
+    use std::thread;
+
+    #[test]
+    fn test_address_family() {
+        let v4: SocketAddr = "192.0.2.1:119".parse().unwrap();
+        let v6: SocketAddr = "[2001:db8::1]:119".parse().unwrap();
+        let v4_2: SocketAddr = "192.0.2.2:119".parse().unwrap();
+        let resolved = vec![v6, v4, v4_2];
+
+        let select = |family: AddressFamily| family.select(resolved.clone()).unwrap();
+        assert_eq!(select(AddressFamily::Any), vec![v6, v4, v4_2]);
+        assert_eq!(select(AddressFamily::V4Only), vec![v4, v4_2]);
+        assert_eq!(select(AddressFamily::V6Only), vec![v6]);
+        assert_eq!(select(AddressFamily::PreferV4), vec![v4, v4_2, v6]);
+        assert_eq!(select(AddressFamily::PreferV6), vec![v6, v4, v4_2]);
+
+        match AddressFamily::V6Only.select(vec![v4]) {
+            Err(Error::NoMatchingAddress { family, resolved }) => {
+                assert_eq!(family, AddressFamily::V6Only);
+                assert_eq!(resolved, vec![v4]);
+            }
+            res => panic!("Unexpected result {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_connect_timeout() {
+        let server = MockScript::new().start().unwrap();
+
+        let config = ConnectionConfig::new()
+            .connect_timeout(Some(Duration::from_secs(5)))
+            .to_owned();
+        let addr = server.addr();
+        let (conn, greeting) = NntpConnection::connect(addr, config.clone()).unwrap();
+        assert_eq!(greeting.first_line(), DEFAULT_GREETING);
+        // The listener is dropped once the server finishes, so the second connection is refused
+        drop(conn);
+        server.finish().unwrap();
+
+        // A refused connection is reported as such rather than as a timeout
+        match NntpConnection::connect(addr, config) {
+            Err(Error::Io(e)) => assert_eq!(e.kind(), ErrorKind::ConnectionRefused),
+            res => panic!("Unexpected result {:?}", res.map(|(_, greeting)| greeting)),
+        }
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_connect_timeout_stalled() {
+        use std::os::raw::c_int;
+        use std::os::unix::io::AsRawFd;
+
+        extern "C" {
+            fn listen(fd: c_int, backlog: c_int) -> c_int;
+        }
+
+        // A server that never accepts, with room for a single pending connection, so that the
+        // handshake of any further connection stalls
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        // n.b. listening again on a listening socket only updates its backlog
+        assert_eq!(unsafe { listen(listener.as_raw_fd(), 0) }, 0);
+        let addr = listener.local_addr().unwrap();
+        let _pending = TcpStream::connect(addr).unwrap();
+
+        let timeout = Duration::from_millis(200);
+        let config = ConnectionConfig::new()
+            .connect_timeout(Some(timeout))
+            .to_owned();
+        match NntpConnection::connect(addr, config) {
+            Err(Error::ConnectTimeout {
+                addrs,
+                timeout: got,
+            }) => {
+                assert_eq!(addrs, vec![addr]);
+                assert_eq!(got, timeout);
+            }
+            res => panic!("Unexpected result {:?}", res.map(|(_, greeting)| greeting)),
+        }
+    }
+
+    #[test]
+    fn test_ambiguous_response_timeout() {
+        use crate::test_support::MockScript;
+        use crate::types::command as cmd;
+
+        let server = MockScript::new()
+            // The data blocks are missing
+            .expect("LISTGROUP misc.test", b"211 2 1 2 misc.test\r\n")
+            .start()
+            .unwrap();
+
+        let (mut conn, _) = ConnectionConfig::new()
+            .read_timeout(Some(Duration::from_secs(5)))
+            .ambiguous_response_timeout(Some(Duration::from_millis(100)))
+            .connect(server.addr())
+            .unwrap();
+        let listgroup = cmd::ListGroup {
+            group: Some("misc.test".to_string()),
+            range: None,
+        };
+        let is_multiline =
+            |code: ResponseCode| code.is_multiline() || code == Kind::GroupSelected.into();
+
+        conn.send(&listgroup).unwrap();
+        match conn.read_response_with(is_multiline) {
+            Err(Error::MalformedResponse { resp }) => {
+                assert_eq!(resp.first_line(), b"211 2 1 2 misc.test\r\n")
+            }
+            res => panic!("Unexpected result {:?}", res),
+        }
+        // The data blocks might still arrive, so the connection can't be reused
+        assert!(!conn.is_at_command_boundary());
+        assert_eq!(conn.read_timeout().unwrap(), Some(Duration::from_secs(5)));
+        assert!(matches!(
+            conn.command(&cmd::Date),
+            Err(Error::Desynchronized)
+        ));
+
+        drop(conn);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_read_buf_size() {
+        ConnectionConfig::new().read_buf_size(0);
+    }
+
+    #[test]
+    fn test_tls_config_ipv6_domain() {
+        let config = TlsConfig::default_connector("[fe80::1%eth0]").unwrap();
+        assert_eq!(config.domain(), "fe80::1");
+        let config = TlsConfig::new("[2001:db8::1]".to_string(), TlsConnector::new().unwrap());
+        assert_eq!(config.domain(), "2001:db8::1");
+    }
+
+    #[test]
+    fn test_tls_config_options() {
+        let mut config = TlsConfig::default_connector("news.example.com").unwrap();
+        assert!(format!("{:?}", config).contains("Tlsv10"));
+
+        config.min_protocol_version(Some(Protocol::Tlsv12)).unwrap();
+        assert_eq!(config.domain(), "news.example.com");
+        assert!(format!("{:?}", config).contains("Tlsv12"));
+        assert!(!config.options.accept_invalid_certs);
+
+        config
+            .danger_accept_invalid_certs(true)
+            .unwrap()
+            .danger_accept_invalid_hostnames(true)
+            .unwrap();
+        assert!(config.options.accept_invalid_certs);
+        assert!(config.options.accept_invalid_hostnames);
+        // Setters keep the options that were previously set
+        assert!(format!("{:?}", config).contains("Tlsv12"));
+    }
+
+    #[test]
+    fn test_compress_deflate() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream.write_all(b"200 ready\r\n").unwrap();
+
+            let mut reader = io::BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            assert_eq!(line, "COMPRESS DEFLATE\r\n");
+            stream.write_all(b"206 Compression active\r\n").unwrap();
+
+            let mut stream = io::BufReader::new(DeflateStream::new(stream, Vec::new()));
+            line.clear();
+            stream.read_line(&mut line).unwrap();
+            assert_eq!(line, "CAPABILITIES\r\n");
+            stream
+                .get_mut()
+                .write_all(b"101 Capability list:\r\nVERSION 2\r\nREADER\r\n.\r\n")
+                .unwrap();
+            stream.get_mut().flush().unwrap();
+        });
+
+        let (mut conn, _) = NntpConnection::with_defaults(addr).unwrap();
+        assert!(!conn.is_compressed());
+        conn.compress_deflate().unwrap();
+        assert!(conn.is_compressed());
+
+        let resp = conn.command(&crate::types::command::Capabilities).unwrap();
+        assert_eq!(resp.code(), ResponseCode::Known(Kind::Capabilities));
+        assert_eq!(resp.data_blocks().unwrap().unterminated().count(), 2);
+
+        assert!(matches!(
+            conn.compress_deflate(),
+            Err(Error::AlreadyCompressed)
+        ));
+
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_write_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let (done_tx, done_rx) = std::sync::mpsc::channel::<()>();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream.write_all(b"200 ready\r\n").unwrap();
+            // Never read so that the socket buffers fill up
+            done_rx.recv().unwrap();
+        });
+
+        let (mut conn, _) = NntpConnection::connect(
+            addr,
+            ConnectionConfig::new()
+                .write_timeout(Some(Duration::from_millis(100)))
+                .to_owned(),
+        )
+        .unwrap();
+
+        let article = vec![b'x'; 64 * 1024 * 1024];
+        match conn.send_data_blocks(&article) {
+            Err(Error::Timeout { partial: None }) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+        assert!(!conn.is_at_command_boundary());
+
+        done_tx.send(()).unwrap();
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_slow_greeting() {
+        let greeting = b"200 news.example.com ready\r\n";
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream.set_nodelay(true).unwrap();
+            for byte in greeting.iter() {
+                stream.write_all(&[*byte]).unwrap();
+                thread::sleep(Duration::from_millis(5));
+            }
+        });
+
+        let (conn, resp) = NntpConnection::connect(
+            addr,
+            ConnectionConfig::new()
+                .read_timeout(Some(Duration::from_secs(5)))
+                .to_owned(),
+        )
+        .unwrap();
+
+        assert_eq!(resp.code(), ResponseCode::Known(Kind::PostingAllowed));
+        assert_eq!(resp.first_line(), &greeting[..]);
+        assert_eq!(conn.peer_addr(), Some(addr));
+        assert!(!conn.is_tls());
+
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_timeout_partial_data_blocks() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let (done_tx, done_rx) = std::sync::mpsc::channel::<()>();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream
+                .write_all(b"200 ready\r\n224 Overview follows\r\n1\tfirst\r\n2\tsec")
+                .unwrap();
+            // Hold the connection open without finishing the line
+            done_rx.recv().unwrap();
+        });
+
+        let (mut conn, _) = NntpConnection::connect(
+            addr,
+            ConnectionConfig::new()
+                .read_timeout(Some(Duration::from_millis(100)))
+                .to_owned(),
+        )
+        .unwrap();
+
+        match conn.read_response_auto() {
+            Err(Error::Timeout {
+                partial: Some(partial),
+            }) => {
+                assert_eq!(partial.code(), ResponseCode::Known(Kind::Overview));
+                let data_blocks = partial.data_blocks().unwrap();
+                assert_eq!(
+                    data_blocks.lines().collect::<Vec<_>>(),
+                    vec![b"1\tfirst\r\n"]
+                );
+            }
+            res => panic!("Unexpected result {:?}", res),
+        }
+        assert!(!conn.is_at_command_boundary());
+
+        done_tx.send(()).unwrap();
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_command_boundary_after_error_response() {
+        let server = MockScript::new()
+            .expect("ARTICLE 1", b"423 No article with that number\r\n")
+            .expect(
+                "ARTICLE 1",
+                b"221 0 <id@example.com>\r\nSubject: test\r\n.\r\n",
             )
-        })?;
-        // we keep track of line boundaries rather than slices as borrowck won't allow
-        // us to reuse the buffer AND keep track of sub-slices within it
-        line_boundaries.push((read_head, read_head + bytes_read));
+            .expect("STAT", b"223 0 <id@example.com>\r\n")
+            .start()
+            .unwrap();
 
-        // n.b. we use bytes read rather than the length of line so that we don't drop the
-        // terminators
-        read_head += bytes_read;
+        let (mut conn, _) = NntpConnection::connect(
+            server.addr(),
+            ConnectionConfig::new()
+                .read_timeout(Some(Duration::from_secs(5)))
+                .to_owned(),
+        )
+        .unwrap();
+        assert!(conn.is_at_command_boundary());
 
-        if is_end_of_datablock(line) {
-            trace!(
-                "Read {} bytes of data across {} lines",
-                read_head,
-                line_boundaries.len()
-            );
-            break;
+        let article = crate::types::command::Article::Number(1);
+        let resp = conn.command(&article).unwrap();
+        assert_eq!(resp.code(), ResponseCode::Known(Kind::NoArticleWithNumber));
+        assert!(conn.is_at_command_boundary());
+
+        // The wrong kind of response still has its data blocks consumed
+        let resp = conn.command(&article).unwrap();
+        assert_eq!(resp.code(), ResponseCode::Known(Kind::Head));
+        assert!(conn.is_at_command_boundary());
+
+        let resp = conn.command(&crate::types::command::Stat::Current).unwrap();
+        assert_eq!(resp.code(), ResponseCode::Known(Kind::ArticleExists));
+
+        drop(conn);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_desynchronized_after_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream
+                .write_all(b"200 ready\r\n224 Overview follows\r\n1\tfirst\r\n")
+                .unwrap();
+            thread::sleep(Duration::from_millis(500));
+        });
+
+        let (mut conn, _) = NntpConnection::connect(
+            addr,
+            ConnectionConfig::new()
+                .read_timeout(Some(Duration::from_millis(100)))
+                .to_owned(),
+        )
+        .unwrap();
+
+        assert!(matches!(
+            conn.read_response_auto(),
+            Err(Error::Timeout { .. })
+        ));
+        assert!(!conn.is_at_command_boundary());
+        assert!(matches!(
+            conn.send(&crate::types::command::Quit),
+            Err(Error::Desynchronized)
+        ));
+
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_first_line_and_data_blocks_in_one_read() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            // The greeting and both responses are delivered in a single write
+            stream
+                .write_all(
+                    b"200 ready\r\n\
+                    215 Newsgroups follow\r\nline1\r\n.\r\n\
+                    215 Newsgroups follow\r\n.\r\n",
+                )
+                .unwrap();
+        });
+
+        let (mut conn, greeting) = NntpConnection::connect(
+            addr,
+            ConnectionConfig::new()
+                .read_timeout(Some(Duration::from_secs(5)))
+                .to_owned(),
+        )
+        .unwrap();
+        assert_eq!(greeting.first_line(), b"200 ready\r\n");
+
+        let resp = conn.read_response_auto().unwrap();
+        assert_eq!(resp.code(), ResponseCode::Known(Kind::List));
+        assert_eq!(resp.first_line(), b"215 Newsgroups follow\r\n");
+        assert_eq!(
+            resp.data_blocks()
+                .unwrap()
+                .unterminated()
+                .collect::<Vec<_>>(),
+            vec![b"line1"]
+        );
+
+        let resp = conn.read_response_auto().unwrap();
+        assert_eq!(resp.code(), ResponseCode::Known(Kind::List));
+        assert_eq!(resp.data_blocks().unwrap().unterminated().count(), 0);
+        assert!(conn.is_at_command_boundary());
+
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_dot_stuffed_data_blocks() {
+        let resp = b"222 0 <a@example.com>\r\n..leading\r\n...\r\nend.\r\n.\r\n";
+        let server = MockScript::new()
+            .expect("BODY <a@example.com>", resp)
+            .expect("BODY <a@example.com>", resp)
+            .start()
+            .unwrap();
+
+        let (mut conn, _) = NntpConnection::connect(
+            server.addr(),
+            ConnectionConfig::new()
+                .read_timeout(Some(Duration::from_secs(5)))
+                .to_owned(),
+        )
+        .unwrap();
+
+        let expected: Vec<&[u8]> = vec![b".leading", b"..", b"end."];
+
+        let body = crate::types::command::Body::MessageId("<a@example.com>".to_string());
+        let resp = conn.command(&body).unwrap();
+        let data_blocks = resp.data_blocks().unwrap();
+        assert_eq!(data_blocks.unterminated().collect::<Vec<_>>(), expected);
+        assert_eq!(data_blocks.payload(), b".leading\r\n..\r\nend.\r\n.\r\n");
+
+        conn.send(&body).unwrap();
+        let (_resp, reader) = conn.read_response_streaming(None).unwrap();
+        let mut reader = reader.unwrap();
+        let mut lines = Vec::new();
+        while let Some(line) = reader.next_line().unwrap() {
+            lines.push(line.to_vec());
         }
+        assert_eq!(lines, expected);
+
+        drop(conn);
+        server.finish().unwrap();
     }
 
-    Ok(())
+    #[test]
+    fn test_lenient_line_endings() {
+        let resp = b"215 Newsgroups follow\nmisc.test 3 1 y\r\n..dot 2 1 n\n.\n";
+        let server = MockScript::new()
+            .greeting(b"200 ready\n")
+            .reconnect()
+            .expect("LIST ACTIVE", resp)
+            .expect("LIST ACTIVE", resp)
+            .start()
+            .unwrap();
+
+        // Bare LFs are rejected by default
+        let res = NntpConnection::connect(
+            server.addr(),
+            ConnectionConfig::new()
+                .read_timeout(Some(Duration::from_secs(5)))
+                .to_owned(),
+        );
+        assert!(matches!(res, Err(Error::Io(_))), "{:?}", res.map(|_| ()));
+
+        let (mut conn, greeting) = NntpConnection::connect(
+            server.addr(),
+            ConnectionConfig::new()
+                .read_timeout(Some(Duration::from_secs(5)))
+                .lenient_line_endings(true)
+                .to_owned(),
+        )
+        .unwrap();
+        assert_eq!(greeting.code(), ResponseCode::Known(Kind::PostingAllowed));
+
+        let expected: Vec<&[u8]> = vec![b"misc.test 3 1 y", b".dot 2 1 n"];
+
+        let list = crate::types::command::List::Active { wildmat: None };
+        let resp = conn.command(&list).unwrap();
+        assert_eq!(resp.code(), ResponseCode::Known(Kind::List));
+        let data_blocks = resp.data_blocks().unwrap();
+        assert_eq!(data_blocks.unterminated().collect::<Vec<_>>(), expected);
+        // Lines are kept as they were received
+        assert_eq!(data_blocks.payload(), b"misc.test 3 1 y\r\n.dot 2 1 n\n.\n");
+        assert_eq!(resp.data_line_count(), 2);
+
+        conn.send(&list).unwrap();
+        let (_resp, reader) = conn.read_response_streaming(None).unwrap();
+        let mut reader = reader.unwrap();
+        let mut lines = Vec::new();
+        while let Some(line) = reader.next_line().unwrap() {
+            lines.push(line.to_vec());
+        }
+        assert_eq!(lines, expected);
+        assert!(conn.is_at_command_boundary());
+
+        drop(conn);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_max_line_bytes() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream.write_all(b"200 ready\r\n").unwrap();
+            stream.write_all(b"222 0 <a@example.com>\r\n").unwrap();
+            // The client hangs up before reading the whole line
+            let _ = stream.write_all(&vec![b'x'; 1024 * 1024]);
+        });
+
+        let (mut conn, _) = NntpConnection::connect(
+            addr,
+            ConnectionConfig::new()
+                .read_timeout(Some(Duration::from_secs(5)))
+                .max_line_bytes(Some(64 * 1024))
+                .to_owned(),
+        )
+        .unwrap();
+
+        let (_resp, reader) = conn.read_response_streaming(None).unwrap();
+        match reader.unwrap().next_line() {
+            Err(Error::LineTooLong { limit }) => assert_eq!(limit, 64 * 1024),
+            res => panic!("Unexpected result {:?}", res),
+        }
+        assert!(!conn.is_at_command_boundary());
+
+        drop(conn);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_max_response_bytes() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream.write_all(b"200 ready\r\n").unwrap();
+            stream
+                .write_all(b"222 0 <a@example.com>\r\nshort\r\n.\r\n")
+                .unwrap();
+            stream.write_all(b"222 0 <a@example.com>\r\n").unwrap();
+            // A single line without a terminator must not be buffered in its entirety
+            stream.write_all(&[b'x'; 1024]).unwrap();
+        });
+
+        let (mut conn, _) = NntpConnection::connect(
+            addr,
+            ConnectionConfig::new()
+                .read_timeout(Some(Duration::from_secs(5)))
+                .max_response_bytes(Some(32))
+                .to_owned(),
+        )
+        .unwrap();
+
+        let resp = conn.read_response_auto().unwrap();
+        assert_eq!(resp.data_blocks().unwrap().payload(), b"short\r\n.\r\n");
+
+        match conn.read_response_auto() {
+            Err(Error::ResponseTooLarge { limit: 32 }) => {}
+            res => panic!("Unexpected result {:?}", res),
+        }
+        assert!(!conn.is_at_command_boundary());
+
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_streaming_data_blocks() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, rx) = std::sync::mpsc::channel();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream
+                .write_all(b"200 ready\r\n224 Overview follows\r\n1\tfirst\r\n")
+                .unwrap();
+            // Hold the rest of the response until the client has consumed the first line
+            rx.recv().unwrap();
+            stream.write_all(b"2\tsecond\r\n.\r\n").unwrap();
+        });
+
+        let (mut conn, _) = NntpConnection::connect(
+            addr,
+            ConnectionConfig::new()
+                .read_timeout(Some(Duration::from_secs(5)))
+                .to_owned(),
+        )
+        .unwrap();
+
+        let (resp, reader) = conn.read_response_streaming(None).unwrap();
+        assert_eq!(resp.code(), ResponseCode::Known(Kind::Overview));
+        assert!(resp.data_blocks().is_none());
+
+        let mut reader = reader.unwrap();
+        assert_eq!(reader.next_line().unwrap(), Some(&b"1\tfirst"[..]));
+        tx.send(()).unwrap();
+        assert_eq!(reader.next_line().unwrap(), Some(&b"2\tsecond"[..]));
+        assert!(!reader.is_done());
+        assert_eq!(reader.next_line().unwrap(), None);
+        assert!(reader.is_done());
+        assert_eq!(reader.next_line().unwrap(), None);
+        assert!(conn.is_at_command_boundary());
+
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_expected_lines() {
+        let expected = ExpectedLines::new(10);
+        assert!(expected.check(10, || None).is_ok());
+        // Non-strict mismatches are only logged
+        assert!(expected.check(5, || None).is_ok());
+
+        let strict = ExpectedLines {
+            tolerance: 1,
+            strict: true,
+            ..expected
+        };
+        assert!(strict.check(9, || None).is_ok());
+        assert!(strict.check(11, || None).is_ok());
+        assert!(matches!(
+            strict.check(8, || None),
+            Err(Error::LineCountMismatch {
+                expected: 10,
+                actual: 8,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_read_response_expecting() {
+        let resp = b"224 Overview follows\r\n1\tfirst\r\n.\r\n";
+        let server = MockScript::new()
+            .expect("OVER 1", resp)
+            .expect("OVER 1", resp)
+            .start()
+            .unwrap();
+
+        let (mut conn, _) = NntpConnection::connect(
+            server.addr(),
+            ConnectionConfig::new()
+                .read_timeout(Some(Duration::from_secs(5)))
+                .to_owned(),
+        )
+        .unwrap();
+
+        let expected = ExpectedLines {
+            strict: true,
+            ..ExpectedLines::new(1)
+        };
+        conn.send_bytes("OVER 1").unwrap();
+        assert!(conn.read_response_expecting(None, expected).is_ok());
+
+        let expected = ExpectedLines {
+            strict: true,
+            ..ExpectedLines::new(2)
+        };
+        conn.send_bytes("OVER 1").unwrap();
+        match conn.read_response_expecting(None, expected) {
+            Err(Error::LineCountMismatch {
+                expected: 2,
+                actual: 1,
+                resp: Some(resp),
+            }) => assert_eq!(resp.code(), ResponseCode::Known(Kind::Overview)),
+            res => panic!("Unexpected result {:?}", res),
+        }
+        assert!(conn.is_at_command_boundary());
+
+        drop(conn);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_reconnect() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream
+                .write_all(b"200 first\r\n224 Overview follows\r\n1\tfirst\r\n")
+                .unwrap();
+            let (mut stream2, _) = listener.accept().unwrap();
+            stream2.write_all(b"201 second\r\n").unwrap();
+            drop(stream);
+        });
+
+        let config = ConnectionConfig::new()
+            .read_timeout(Some(Duration::from_millis(500)))
+            .to_owned();
+        let (mut conn, _) = NntpConnection::connect(addr, config.clone()).unwrap();
+        assert_eq!(conn.generation(), 0);
+
+        // Poison the connection with a partially read response
+        assert!(conn.read_response_auto().is_err());
+        assert!(!conn.is_at_command_boundary());
+
+        let greeting = conn.reconnect(config).unwrap();
+        assert_eq!(greeting.first_line(), b"201 second\r\n");
+        assert_eq!(conn.generation(), 1);
+        assert!(conn.is_at_command_boundary());
+        assert_eq!(conn.peer_addr(), Some(addr));
+
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_queued_commands() {
+        let server = MockScript::new()
+            .expect("STAT 1", b"223 1 <a@example>\r\n")
+            .expect("STAT 2", b"223 2 <b@example>\r\n")
+            .expect("STAT 3", b"423 No article\r\n")
+            .start()
+            .unwrap();
+
+        let (mut conn, _) = NntpConnection::connect(
+            server.addr(),
+            ConnectionConfig::new()
+                .read_timeout(Some(Duration::from_secs(5)))
+                .to_owned(),
+        )
+        .unwrap();
+
+        for n in 1..=3 {
+            assert_eq!(conn.queue_bytes(format!("STAT {}", n)).unwrap(), 8);
+        }
+        assert!(conn.has_queued_commands());
+
+        // Reading the first response flushes all of the queued commands
+        let mut codes = vec![conn.read_response_auto().unwrap().code()];
+        assert!(!conn.has_queued_commands());
+        codes.extend((0..2).map(|_| conn.read_response_auto().unwrap().code()));
+        assert_eq!(
+            codes,
+            vec![
+                ResponseCode::Known(Kind::ArticleExists),
+                ResponseCode::Known(Kind::ArticleExists),
+                ResponseCode::Known(Kind::NoArticleWithNumber),
+            ]
+        );
+
+        drop(conn);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_invalid_command() {
+        use crate::types::command as cmd;
+
+        // None of the invalid commands reach the server
+        let server = MockScript::new()
+            .expect("DATE", b"111 20201015043840\r\n")
+            .start()
+            .unwrap();
+
+        let (mut conn, _) = NntpConnection::connect(
+            server.addr(),
+            ConnectionConfig::new()
+                .read_timeout(Some(Duration::from_secs(5)))
+                .to_owned(),
+        )
+        .unwrap();
+
+        let too_long = format!("GROUP {}", "a".repeat(MAX_COMMAND_LINE_LEN - 7));
+        for command in &["GROUP misc.test\r\nQUIT", "GROUP misc\ntest", &too_long] {
+            match conn.send_bytes(command) {
+                Err(Error::InvalidCommand(_)) => {}
+                res => panic!("Unexpected result {:?}", res),
+            }
+        }
+        match conn.pipeline(&[&cmd::Date, &cmd::Group("misc.test\r\n".to_string())]) {
+            Err(Error::InvalidCommand(_)) => {}
+            res => panic!("Unexpected result {:?}", res),
+        }
+        assert!(!conn.has_queued_commands());
+
+        // Overriding `to_wire_bytes` can't smuggle a line without its terminator
+        struct Unterminated;
+        impl fmt::Display for Unterminated {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "DATE")
+            }
+        }
+        impl NntpCommand for Unterminated {
+            fn to_wire_bytes(&self) -> Vec<u8> {
+                b"DATE".to_vec()
+            }
+        }
+        match conn.send(&Unterminated) {
+            Err(Error::InvalidCommand(_)) => {}
+            res => panic!("Unexpected result {:?}", res),
+        }
+
+        // The limit includes the CRLF
+        let longest = format!("GROUP {}", "a".repeat(MAX_COMMAND_LINE_LEN - 8));
+        conn.queue_bytes(&longest).unwrap();
+        conn.write_buf.clear();
+
+        conn.command(&cmd::Date).unwrap();
+
+        drop(conn);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_pipeline() {
+        use crate::types::command as cmd;
+
+        let server = MockScript::new()
+            .expect("STAT 1", b"223 1 <a@example>\r\n")
+            .expect("HEAD 2", b"221 2 <b@example>\r\nSubject: hi\r\n.\r\n")
+            .expect("DATE", b"111 20201015043840\r\n")
+            .start()
+            .unwrap();
+
+        let (mut conn, _) = NntpConnection::connect(
+            server.addr(),
+            ConnectionConfig::new()
+                .read_timeout(Some(Duration::from_secs(5)))
+                .to_owned(),
+        )
+        .unwrap();
+
+        match conn.pipeline(&[&cmd::Stat::Number(1), &cmd::Post]) {
+            Err(Error::CannotPipeline(name)) => assert_eq!(name, "POST"),
+            res => panic!("Unexpected result {:?}", res),
+        }
+        assert!(!conn.has_queued_commands());
+
+        let resps = conn
+            .pipeline(&[&cmd::Stat::Number(1), &cmd::Head::Number(2), &cmd::Date])
+            .unwrap();
+        assert_eq!(
+            resps.iter().map(RawResponse::code).collect::<Vec<_>>(),
+            vec![
+                ResponseCode::Known(Kind::ArticleExists),
+                ResponseCode::Known(Kind::Head),
+                ResponseCode::Known(Kind::Date),
+            ]
+        );
+        assert_eq!(resps[1].data_blocks().unwrap().lines_len(), 2);
+        // Every response was read, so unread bytes would indicate misframing again
+        assert_eq!(conn.pending_responses, 0);
+
+        drop(conn);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_pipeline_depth() {
+        use crate::types::command as cmd;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream.write_all(b"200 ready\r\n").unwrap();
+
+            let mut reader = io::BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            for expected in &["STAT 1\r\n", "STAT 2\r\n"] {
+                line.clear();
+                reader.read_line(&mut line).unwrap();
+                assert_eq!(&line, expected);
+            }
+            // The third command is only sent once the first chunk has been answered
+            assert!(reader.buffer().is_empty());
+            stream
+                .set_read_timeout(Some(Duration::from_millis(100)))
+                .unwrap();
+            assert!(stream.peek(&mut [0]).is_err());
+            stream.set_read_timeout(None).unwrap();
+            stream
+                .write_all(b"223 1 <a@example>\r\n223 2 <b@example>\r\n")
+                .unwrap();
+
+            line.clear();
+            reader.read_line(&mut line).unwrap();
+            assert_eq!(line, "STAT 3\r\n");
+            stream.write_all(b"223 3 <c@example>\r\n").unwrap();
+        });
+
+        let (mut conn, _) = NntpConnection::connect(
+            addr,
+            ConnectionConfig::new()
+                .read_timeout(Some(Duration::from_secs(5)))
+                .max_pipeline_depth(2)
+                .to_owned(),
+        )
+        .unwrap();
+
+        let resps = conn
+            .pipeline(&[
+                &cmd::Stat::Number(1),
+                &cmd::Stat::Number(2),
+                &cmd::Stat::Number(3),
+            ])
+            .unwrap();
+        assert_eq!(resps.len(), 3);
+        assert_eq!(resps[2].first_line(), b"223 3 <c@example>\r\n");
+
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_encode_data_blocks() {
+        assert_eq!(
+            encode_data_blocks(b"Subject: hi\r\n\r\n.leading dot\r\n..two\r\nend.\r\n"),
+            b"Subject: hi\r\n\r\n..leading dot\r\n...two\r\nend.\r\n.\r\n".to_vec()
+        );
+        assert_eq!(encode_data_blocks(b".\r\n"), b"..\r\n.\r\n".to_vec());
+        assert_eq!(encode_data_blocks(b"no crlf"), b"no crlf\r\n.\r\n".to_vec());
+        assert_eq!(encode_data_blocks(b""), b".\r\n".to_vec());
+    }
+
+    #[test]
+    fn test_resync() {
+        let session = b"224 Overview follows\r\n1\tfirst\r\n2\tsecond\r\n..\r\n.\r\n\
+            205 Bye\r\n";
+        let (mut conn, _) =
+            NntpConnection::from_stream(io::Cursor::new(session.to_vec()), false).unwrap();
+
+        // Abandon the data blocks after the first line
+        let (_, reader) = conn.read_response_streaming(None).unwrap();
+        assert_eq!(reader.unwrap().next_line().unwrap(), Some(&b"1\tfirst"[..]));
+        assert!(!conn.is_at_command_boundary());
+
+        conn.resync().unwrap();
+        assert!(conn.is_at_command_boundary());
+        assert_eq!(
+            conn.read_response_auto().unwrap().first_line(),
+            b"205 Bye\r\n"
+        );
+        // There is nothing to discard at a command boundary
+        conn.resync().unwrap();
+
+        // Discarding stops at the response size limit
+        let (mut conn, _) = ConnectionConfig::new()
+            .max_response_bytes(Some(16))
+            .connect_stream(io::Cursor::new(session.to_vec()), false)
+            .unwrap();
+        let (_, reader) = conn.read_response_streaming(None).unwrap();
+        drop(reader);
+        assert!(matches!(
+            conn.resync(),
+            Err(Error::ResponseTooLarge { limit: 16 })
+        ));
+    }
+
+    #[test]
+    fn test_desynced_response() {
+        // The end of an abandoned article arrives in place of a response
+        let session = b"tail of an article\r\n.\r\n205 Bye\r\n";
+        let (mut conn, _) =
+            NntpConnection::from_stream(io::Cursor::new(session.to_vec()), false).unwrap();
+
+        assert!(matches!(
+            conn.read_response_auto(),
+            Err(Error::DesyncedResponse { raw }) if raw == b"tail of an article\r\n"
+        ));
+        assert!(!conn.is_at_command_boundary());
+        assert!(matches!(
+            conn.send(&crate::types::command::Quit),
+            Err(Error::Desynchronized)
+        ));
+
+        conn.resync().unwrap();
+        assert_eq!(
+            conn.read_response_auto().unwrap().first_line(),
+            b"205 Bye\r\n"
+        );
+    }
+
+    #[test]
+    fn test_status_line_without_text() {
+        let session = b"200\r\n205\r\n";
+        let (mut conn, greeting) =
+            NntpConnection::from_stream(io::Cursor::new(session.to_vec()), true).unwrap();
+        assert_eq!(greeting.unwrap().code(), ResponseCode::from(200));
+
+        let resp = conn.read_response_auto().unwrap();
+        assert_eq!(resp.code(), ResponseCode::from(205));
+        assert_eq!(resp.first_line(), b"205\r\n");
+    }
+
+    #[test]
+    fn test_from_stream() {
+        use std::sync::{Arc, Mutex};
+
+        /// Replays canned responses and records everything written to it
+        struct Replay {
+            input: io::Cursor<Vec<u8>>,
+            output: Arc<Mutex<Vec<u8>>>,
+        }
+
+        impl Read for Replay {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                self.input.read(buf)
+            }
+        }
+
+        impl Write for Replay {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.output.lock().unwrap().write(buf)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let stream = Replay {
+            input: io::Cursor::new(b"200 ready\r\n111 20201231000000\r\n".to_vec()),
+            output: output.clone(),
+        };
+
+        let (mut conn, greeting) = NntpConnection::from_stream(stream, true).unwrap();
+        assert_eq!(greeting.unwrap().first_line(), b"200 ready\r\n");
+        assert!(!conn.is_tls());
+
+        let resp = conn.command(&crate::types::command::Date).unwrap();
+        assert_eq!(resp.code(), ResponseCode::Known(Kind::Date));
+        assert_eq!(&*output.lock().unwrap(), b"DATE\r\n");
+
+        assert!(matches!(
+            conn.reconnect(ConnectionConfig::default()),
+            Err(Error::Io(e)) if e.kind() == ErrorKind::Unsupported
+        ));
+
+        // Without a greeting the first response read is that of the first command
+        let (mut conn, greeting) =
+            NntpConnection::from_stream(io::Cursor::new(b"205 bye\r\n".to_vec()), false).unwrap();
+        assert!(greeting.is_none());
+        assert_eq!(
+            conn.read_response_auto().unwrap().code(),
+            ResponseCode::Known(Kind::ConnectionClosing)
+        );
+    }
+
+    #[test]
+    fn test_trace_hook() {
+        use std::sync::Mutex;
+
+        use crate::raw::trace::Direction;
+        use crate::types::command::{AuthInfo, Capabilities, SaslResponse};
+
+        /// Replays canned responses and discards everything written to it
+        struct Canned(io::Cursor<Vec<u8>>);
+
+        impl Read for Canned {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                self.0.read(buf)
+            }
+        }
+
+        impl Write for Canned {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let input = b"200 ready\r\n\
+            281 Authentication accepted\r\n\
+            383 cj1meWtv\r\n\
+            281 Authentication accepted\r\n\
+            101 Capability list:\r\nVERSION 2\r\n..stuffed\r\n.\r\n";
+        let trace = Arc::new(Mutex::new((Vec::new(), Vec::new())));
+        let hook = {
+            let trace = trace.clone();
+            Arc::new(move |direction, bytes: &[u8]| {
+                let mut trace = trace.lock().unwrap();
+                match direction {
+                    Direction::Send => trace.0.extend_from_slice(bytes),
+                    Direction::Recv => trace.1.extend_from_slice(bytes),
+                }
+            })
+        };
+
+        let (mut conn, _) = ConnectionConfig::new()
+            .trace_hook(hook)
+            .redact_passwords(true)
+            .connect_stream(Canned(io::Cursor::new(input.to_vec())), true)
+            .unwrap();
+        conn.command(&AuthInfo::Pass("hunter2".to_string()))
+            .unwrap();
+        conn.command(&AuthInfo::Sasl {
+            mechanism: "SCRAM-SHA-256".to_string(),
+            initial_response: Some("bj1hbGljZQ==".to_string()),
+        })
+        .unwrap();
+        conn.command(&SaslResponse("Yz1iaXdz".to_string())).unwrap();
+        let resp = conn.command(&Capabilities).unwrap();
+        assert_eq!(resp.data_blocks().unwrap().lines_len(), 3);
+
+        let trace = trace.lock().unwrap();
+        // The SASL exchange ends with the 281 so CAPABILITIES is traced as-is
+        assert_eq!(
+            trace.0,
+            b"AUTHINFO PASS ********\r\n\
+            AUTHINFO SASL SCRAM-SHA-256 ********\r\n\
+            ********\r\n\
+            CAPABILITIES\r\n"
+        );
+        // The exact bytes are traced, including dot-stuffing
+        assert_eq!(trace.1, input);
+    }
+
+    #[test]
+    fn test_closed_before_greeting() {
+        let server = MockScript::new()
+            .greeting(b"")
+            .disconnect()
+            .start()
+            .unwrap();
+
+        let err = NntpConnection::with_defaults(server.addr()).unwrap_err();
+        assert!(
+            matches!(err, Error::ConnectionClosed { bytes_read: 0 }),
+            "Unexpected error {:?}",
+            err
+        );
+
+        server.finish().unwrap();
+    }
 }
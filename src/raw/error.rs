@@ -1,4 +1,8 @@
-use std::net::TcpStream;
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+
+use crate::raw::connection::AddressFamily;
+use crate::raw::response::RawResponse;
 
 /// Low level API Errors
 ///
@@ -16,6 +20,140 @@ pub enum Error {
     /// The TLS Handshake has failed
     #[error("TLS Handshake Error -- {0}")]
     TlsHandshake(#[from] native_tls::HandshakeError<TcpStream>),
+    /// The server refused to begin a TLS negotiation in response to `STARTTLS`
+    #[error("STARTTLS was rejected -- {}", .0.code())]
+    StartTlsRejected(RawResponse),
+    /// `STARTTLS` was attempted on a connection that is already using TLS
+    #[error("The connection is already using TLS")]
+    AlreadyTls,
+    /// The server refused to compress the connection in response to `COMPRESS DEFLATE`
+    #[error("COMPRESS DEFLATE was rejected -- {}", .0.code())]
+    CompressRejected(RawResponse),
+    /// `COMPRESS DEFLATE` was attempted on a connection that is already compressed
+    #[error("The connection is already compressed")]
+    AlreadyCompressed,
+    /// The server closed the connection before a complete response was received
+    ///
+    /// This is returned if the connection reaches EOF before the end of the first line or
+    /// the terminating `.` of the data blocks. The connection must be re-established.
+    #[error("Connection closed by the server after reading {bytes_read} bytes of the response")]
+    ConnectionClosed {
+        /// The number of bytes of the response that were read before the connection was closed
+        bytes_read: usize,
+    },
+    /// A read from or a write to the server timed out
+    ///
+    /// If the timeout occurred while reading the data blocks of a multi-line response,
+    /// `partial` will contain the response with every line that was _completely_ read
+    /// before the timeout, allowing callers to salvage and resume from the progress made.
+    #[error("Timed out")]
+    Timeout {
+        /// The portion of the response that was read before the timeout
+        partial: Option<RawResponse>,
+    },
+    /// Every address of the server timed out while connecting
+    ///
+    /// See [`ConnectionConfig::connect_timeout`](crate::raw::connection::ConnectionConfig::connect_timeout)
+    #[error("Timed out connecting to {addrs:?} after {timeout:?}")]
+    ConnectTimeout {
+        /// The addresses that were tried
+        addrs: Vec<SocketAddr>,
+        /// The timeout for each address
+        timeout: Duration,
+    },
+    /// None of the server's addresses match the configured address family
+    ///
+    /// See [`ConnectionConfig::address_family`](crate::raw::connection::ConnectionConfig::address_family)
+    #[error("None of the resolved addresses {resolved:?} match {family:?}")]
+    NoMatchingAddress {
+        /// The configured address family
+        family: AddressFamily,
+        /// The addresses the server resolved to
+        resolved: Vec<SocketAddr>,
+    },
+    /// A multi-line response contained a different number of lines than expected
+    ///
+    /// This is only returned when the [`ExpectedLines`](crate::raw::connection::ExpectedLines)
+    /// check is strict. The response was read completely so the connection can still be used.
+    #[error("Expected {expected} lines but received {actual}")]
+    LineCountMismatch {
+        /// The expected number of lines
+        expected: usize,
+        /// The number of lines that were received
+        actual: usize,
+        /// The response, if it was buffered
+        resp: Option<RawResponse>,
+    },
+    /// A multi-line response exceeded the configured maximum size
+    ///
+    /// See [`ConnectionConfig::max_response_bytes`](crate::raw::connection::ConnectionConfig::max_response_bytes).
+    /// Reading stops as soon as the limit is exceeded, so the rest of the response is left
+    /// unread and the connection is no longer at a command boundary.
+    #[error("Response exceeded the limit of {limit} bytes")]
+    ResponseTooLarge {
+        /// The maximum number of bytes allowed in the data blocks of a response
+        limit: usize,
+    },
+    /// A single line of a response exceeded the configured maximum length
+    ///
+    /// See [`ConnectionConfig::max_line_bytes`](crate::raw::connection::ConnectionConfig::max_line_bytes).
+    /// As with [`ResponseTooLarge`](Self::ResponseTooLarge), the rest of the response is left
+    /// unread.
+    #[error("Response line exceeded the limit of {limit} bytes")]
+    LineTooLong {
+        /// The maximum number of bytes allowed in a line, including the terminator
+        limit: usize,
+    },
+    /// The framing of a response contradicts what its command requires
+    ///
+    /// For example, a `211` in response to `LISTGROUP` must be followed by data blocks, but some
+    /// servers send only the single line `GROUP` would return. See
+    /// [`ConnectionConfig::ambiguous_response_timeout`](crate::raw::connection::ConnectionConfig::ambiguous_response_timeout).
+    /// The response contains just the first line. As the rest of the response may still arrive,
+    /// the connection is no longer at a command boundary.
+    #[error("Response {} was not followed by the expected data blocks", .resp.code())]
+    MalformedResponse {
+        /// The response as far as it was read
+        resp: RawResponse,
+    },
+    /// A command that cannot be pipelined was passed to
+    /// [`NntpConnection::pipeline`](crate::raw::connection::NntpConnection::pipeline)
+    ///
+    /// Commands such as `POST` and `AUTHINFO` require an intermediate response before the
+    /// exchange can continue. Nothing was sent to the server.
+    #[error("{0} cannot be pipelined")]
+    CannotPipeline(String),
+    /// The SOCKS5 proxy could not connect to the server
+    ///
+    /// See [`ProxyConfig`](crate::raw::proxy::ProxyConfig)
+    #[error("Proxy error -- {0}")]
+    Proxy(String),
+    /// A command could not be sent because it contains a CR or LF or is too long
+    ///
+    /// Nothing was sent to the server.
+    /// See [`MAX_COMMAND_LINE_LEN`](crate::types::command::MAX_COMMAND_LINE_LEN)
+    #[error("Invalid command -- {0}")]
+    InvalidCommand(String),
+    /// The previous response was not completely read
+    ///
+    /// Sending another command would cause the remainder of the previous response to be read
+    /// as the response to the new command, so the connection refuses to send it.
+    /// The connection must be re-established.
+    #[error("Connection is not at a command boundary, the previous response was not fully read")]
+    Desynchronized,
+    /// The first line of a response did not start with a status code
+    ///
+    /// Every response begins with three digits followed by a space or the end of the line, so
+    /// this usually means that the tail of a previous response (e.g. one abandoned after a
+    /// timeout) was read in place of the response to the latest command. The connection is left
+    /// mid-response, so further commands fail with [`Desynchronized`](Self::Desynchronized)
+    /// until it is [resynchronized](crate::raw::connection::NntpConnection::resync) or
+    /// re-established.
+    #[error("Response does not start with a status code, the connection is out of sync")]
+    DesyncedResponse {
+        /// The offending line
+        raw: Vec<u8>,
+    },
     /// The server returned data that could not be parsed
     ///
     /// This likely indicates that either a bug in Brokaw's response parser,
@@ -1,3 +1,10 @@
+/// Parsing of server addresses
+pub mod addr;
+
+/// Async raw connection implementation
+#[cfg(feature = "async")]
+pub mod async_connection;
+
 /// Raw connection implementation
 pub mod connection;
 
@@ -11,19 +18,34 @@ pub mod error;
 /// * Any function that begins with `parse_` will fail if the provided buffer is not consumed.
 pub(crate) mod parse;
 
+/// SOCKS5 proxy support
+pub mod proxy;
+
 /// Raw NNTP response types
 pub mod response;
 
 /// Raw TCP stream implementation
 pub(crate) mod stream;
 
+/// Tracing of the raw bytes exchanged with the server
+pub mod trace;
+
 #[doc(inline)]
-pub use connection::{NntpConnection, TlsConfig};
+pub use addr::ServerAddr;
+#[cfg(feature = "async")]
 #[doc(inline)]
-pub use response::{DataBlocks, RawResponse};
+pub use async_connection::{AsyncNntpConnection, AsyncNntpStream};
+#[doc(inline)]
+pub use connection::{AddressFamily, DataBlockReader, NntpConnection, TlsConfig};
+#[doc(inline)]
+pub use proxy::ProxyConfig;
+#[doc(inline)]
+pub use response::{DataBlocks, Exchange, RawResponse};
 
 #[doc(inline)]
-pub use stream::NntpStream;
+pub use stream::{NntpStream, Transport};
+#[doc(inline)]
+pub use trace::{Direction, TraceFn};
 
 pub(crate) mod compression;
 
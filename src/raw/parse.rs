@@ -1,9 +1,9 @@
 use std::convert::TryInto;
 
-use nom::bytes::complete::take_until;
-use nom::character::complete::{crlf, one_of};
-use nom::combinator::all_consuming;
-use nom::sequence::{terminated, tuple};
+use nom::bytes::complete::{tag, take_until};
+use nom::character::complete::one_of;
+use nom::combinator::{all_consuming, opt};
+use nom::sequence::{preceded, terminated, tuple};
 use nom::IResult;
 
 /// The first line of an NNTP response
@@ -24,11 +24,24 @@ fn one_of_digit(b: &[u8]) -> IResult<&[u8], char> {
     one_of("0123456789")(b)
 }
 
+/// The line terminator to expect for a line read up to and including an LF
+///
+/// If `lenient`, a line ending with a lone LF is terminated by it rather than by a CRLF.
+fn line_terminator(b: &[u8], lenient: bool) -> &'static str {
+    if lenient && b.ends_with(b"\n") && !b.ends_with(b"\r\n") {
+        "\n"
+    } else {
+        "\r\n"
+    }
+}
+
 /// Takes a line from the input buffer
 ///
-/// A "line" is a sequence of bytes terminated by a CRLF (`\r\n`) sequence.
-fn take_line(b: &[u8]) -> IResult<&[u8], &[u8]> {
-    let (rest, line) = terminated(take_until("\r\n"), crlf)(b)?;
+/// A "line" is a sequence of bytes terminated by a CRLF (`\r\n`) sequence, or a lone LF
+/// if `lenient`.
+fn take_line(b: &[u8], lenient: bool) -> IResult<&[u8], &[u8]> {
+    let terminator = line_terminator(b, lenient);
+    let (rest, line) = terminated(take_until(terminator), tag(terminator))(b)?;
 
     Ok((rest, line))
 }
@@ -53,16 +66,21 @@ pub(crate) fn is_end_of_datablock(b: &[u8]) -> bool {
 ///
 /// Per [RFC 3977](https://tools.ietf.org/html/rfc3977#section-3.2), the first line of an
 /// NNTP response consists of a three-digit response code, a single space, and then
-/// some text terminated with a CRLF.
-pub(crate) fn parse_first_line(b: &[u8]) -> IResult<&[u8], InitialResponseLine<'_>> {
+/// some text terminated with a CRLF. Some servers omit the space and text entirely (e.g. `205`),
+/// in which case `data` is empty. If `lenient`, a lone LF is accepted as well.
+pub(crate) fn parse_first_line(b: &[u8], lenient: bool) -> IResult<&[u8], InitialResponseLine<'_>> {
+    let terminator = line_terminator(b, lenient);
     let res = all_consuming(tuple((
         take_response_code,
-        nom::character::complete::char(' '),
-        take_until("\r\n"),
-        crlf,
+        opt(preceded(
+            nom::character::complete::char(' '),
+            take_until(terminator),
+        )),
+        tag(terminator),
     )))(b)?;
 
-    let (rest, (code, _, data, _crlf)) = res;
+    let (rest, (code, data, _crlf)) = res;
+    let data = data.unwrap_or_default();
     let code = code
         .try_into()
         .expect("Code should be three bytes, there is likely a bug in the parser.");
@@ -78,8 +96,10 @@ pub(crate) fn parse_first_line(b: &[u8]) -> IResult<&[u8], InitialResponseLine<'
 }
 
 /// Parse a data block line from the buffer
-pub(crate) fn parse_data_block_line(b: &[u8]) -> IResult<&[u8], &[u8]> {
-    all_consuming(take_line)(b)
+///
+/// If `lenient`, the line may be terminated by a lone LF rather than a CRLF.
+pub(crate) fn parse_data_block_line(b: &[u8], lenient: bool) -> IResult<&[u8], &[u8]> {
+    all_consuming(|b| take_line(b, lenient))(b)
 }
 
 #[cfg(test)]
@@ -99,7 +119,7 @@ mod tests {
 
         #[test]
         fn happy_path() {
-            let (_remainder, raw_response) = parse_first_line(MOTD).unwrap();
+            let (_remainder, raw_response) = parse_first_line(MOTD, false).unwrap();
             let expected_resp = InitialResponseLine {
                 code: b"200",
                 data: &b"news.example.com InterNetNews server INN 2.5.5 ready (transit mode)"[..],
@@ -112,7 +132,29 @@ mod tests {
         fn test_remaining_data() {
             let data = [MOTD, &b"SOME MORE DATA\r\n"[..]].concat();
 
-            assert!(parse_first_line(&data).is_err());
+            assert!(parse_first_line(&data, false).is_err());
+        }
+
+        #[test]
+        fn test_lone_lf() {
+            let motd = b"200 news.example.com ready\n";
+            assert!(parse_first_line(motd, false).is_err());
+
+            let (_remainder, raw_response) = parse_first_line(motd, true).unwrap();
+            assert_eq!(raw_response.code, b"200");
+            assert_eq!(raw_response.data, b"news.example.com ready");
+        }
+
+        #[test]
+        fn test_no_text() {
+            let (_remainder, raw_response) = parse_first_line(b"205\r\n", false).unwrap();
+            assert_eq!(raw_response.code, b"205");
+            assert_eq!(raw_response.data, b"");
+
+            let (_remainder, raw_response) = parse_first_line(b"205\n", true).unwrap();
+            assert_eq!(raw_response.data, b"");
+
+            assert!(parse_first_line(b"205-bye\r\n", false).is_err());
         }
     }
 
@@ -121,7 +163,8 @@ mod tests {
 
         #[test]
         fn happy_path() {
-            assert_eq!(take_line(MOTD), Ok((&b""[..], MOTD_NO_CRLF)));
+            assert_eq!(take_line(MOTD, false), Ok((&b""[..], MOTD_NO_CRLF)));
+            assert_eq!(take_line(MOTD, true), Ok((&b""[..], MOTD_NO_CRLF)));
         }
 
         #[test]
@@ -131,7 +174,7 @@ mod tests {
                 "/tests/xover_gzip_header"
             ));
 
-            let (rest, data) = take_line(header).unwrap();
+            let (rest, data) = take_line(header, false).unwrap();
             assert_eq!(rest.len(), 0);
             assert_eq!(data, &header[..header.len() - 2]);
         }
@@ -143,9 +186,18 @@ mod tests {
         #[test]
         fn happy_path() {
             let msg = b"101 Capability list:\r\n";
-            let (_remainder, block) = parse_data_block_line(msg).unwrap();
+            let (_remainder, block) = parse_data_block_line(msg, false).unwrap();
             assert_eq!(block, b"101 Capability list:")
         }
+
+        #[test]
+        fn test_lone_lf() {
+            assert!(parse_data_block_line(b".\n", false).is_err());
+            assert_eq!(parse_data_block_line(b".\n", true).unwrap().1, b".");
+            // A CR that isn't followed by the LF is part of the line
+            assert_eq!(parse_data_block_line(b"a\rb\n", true).unwrap().1, b"a\rb");
+            assert_eq!(parse_data_block_line(b"ab\r\n", true).unwrap().1, b"ab");
+        }
     }
 
     mod test_parse_response_code {
@@ -0,0 +1,365 @@
+use std::fmt;
+use std::io::{Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpStream};
+use std::time::Duration;
+
+use log::*;
+
+use crate::raw::addr::ServerAddr;
+use crate::raw::connection::connect_tcp;
+use crate::raw::error::{Error, Result};
+
+const SOCKS_VERSION: u8 = 5;
+const AUTH_NONE: u8 = 0x00;
+const AUTH_USER_PASS: u8 = 0x02;
+const AUTH_NO_ACCEPTABLE: u8 = 0xff;
+const USER_PASS_VERSION: u8 = 1;
+const CMD_CONNECT: u8 = 0x01;
+const ATYP_IPV4: u8 = 0x01;
+const ATYP_DOMAIN: u8 = 0x03;
+const ATYP_IPV6: u8 = 0x04;
+
+/// A [SOCKS5](https://tools.ietf.org/html/rfc1928) proxy that connections are routed through
+///
+/// The destination address is sent to the proxy, which opens the TCP connection to the server.
+/// TLS, if configured, is negotiated end-to-end with the server over the proxied stream.
+///
+/// Hostnames passed to [`NntpConnection::connect_host`](super::NntpConnection::connect_host)
+/// are sent to the proxy as-is and resolved by it, so this works for hosts that only the proxy
+/// can resolve (e.g. Tor onion services). [`NntpConnection::connect`](super::NntpConnection::connect)
+/// resolves the destination locally.
+///
+/// ```
+/// use brokaw::raw::ProxyConfig;
+///
+/// let proxy = ProxyConfig::socks5("127.0.0.1:9050".parse().unwrap())
+///     .credentials("user", "hunter2")
+///     .to_owned();
+/// ```
+#[derive(Clone)]
+pub struct ProxyConfig {
+    addr: SocketAddr,
+    credentials: Option<(String, String)>,
+}
+
+impl ProxyConfig {
+    /// Connect through the SOCKS5 proxy listening on `addr`
+    pub fn socks5(addr: SocketAddr) -> Self {
+        Self {
+            addr,
+            credentials: None,
+        }
+    }
+
+    /// Authenticate with the proxy via [username/password](https://tools.ietf.org/html/rfc1929)
+    ///
+    /// Both must be at most 255 bytes long.
+    pub fn credentials(
+        &mut self,
+        username: impl AsRef<str>,
+        password: impl AsRef<str>,
+    ) -> &mut Self {
+        self.credentials = Some((username.as_ref().to_string(), password.as_ref().to_string()));
+        self
+    }
+
+    /// The address of the proxy
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Open a TCP connection to `dest` through the proxy
+    ///
+    /// IP addresses are sent as such, anything else is sent as a domain name for the proxy to
+    /// resolve. The read and write timeouts are applied to the returned stream before the
+    /// handshake with the proxy, the connect timeout applies to the connection to the proxy.
+    pub(crate) fn connect(
+        &self,
+        dest: &ServerAddr,
+        connect_timeout: Option<Duration>,
+        read_timeout: Option<Duration>,
+        write_timeout: Option<Duration>,
+    ) -> Result<TcpStream> {
+        let host = dest.host();
+        let mut request = vec![SOCKS_VERSION, CMD_CONNECT, 0];
+        // n.b. zone ids are meaningless to the proxy
+        match dest.ip() {
+            Some(IpAddr::V4(ip)) => {
+                request.push(ATYP_IPV4);
+                request.extend_from_slice(&ip.octets());
+            }
+            Some(IpAddr::V6(ip)) => {
+                request.push(ATYP_IPV6);
+                request.extend_from_slice(&ip.octets());
+            }
+            None if host.is_empty() || host.len() > 255 => {
+                return Err(Error::Proxy(format!(
+                    "Invalid destination host {:?}, it must be 1-255 bytes long",
+                    host
+                )));
+            }
+            None => {
+                request.push(ATYP_DOMAIN);
+                request.push(host.len() as u8);
+                request.extend_from_slice(host.as_bytes());
+            }
+        }
+        request.extend_from_slice(&dest.port().to_be_bytes());
+
+        trace!("Connecting to SOCKS5 proxy {}...", self.addr);
+        let mut stream = connect_tcp(self.addr, connect_timeout)?;
+        stream.set_read_timeout(read_timeout)?;
+        stream.set_write_timeout(write_timeout)?;
+
+        self.negotiate_auth(&mut stream)?;
+
+        trace!("Asking SOCKS5 proxy to connect to {}", dest);
+        stream.write_all(&request)?;
+
+        let mut reply = [0; 4];
+        stream.read_exact(&mut reply)?;
+        if reply[0] != SOCKS_VERSION {
+            return Err(Error::Proxy(format!(
+                "Unexpected SOCKS version {}",
+                reply[0]
+            )));
+        }
+        if reply[1] != 0 {
+            return Err(Error::Proxy(format!(
+                "Connection to {} failed -- {}",
+                dest,
+                reply_message(reply[1])
+            )));
+        }
+
+        // The bound address isn't needed but must be consumed before the NNTP session starts
+        let addr_len = match reply[3] {
+            ATYP_IPV4 => 4,
+            ATYP_IPV6 => 16,
+            ATYP_DOMAIN => {
+                let mut len = [0; 1];
+                stream.read_exact(&mut len)?;
+                len[0] as usize
+            }
+            atyp => return Err(Error::Proxy(format!("Unknown address type {}", atyp))),
+        };
+        let mut bound = vec![0; addr_len + 2];
+        stream.read_exact(&mut bound)?;
+
+        debug!("Connected to {} via SOCKS5 proxy {}", dest, self.addr);
+        Ok(stream)
+    }
+
+    /// Select an authentication method and authenticate if required
+    fn negotiate_auth(&self, stream: &mut TcpStream) -> Result<()> {
+        let greeting: &[u8] = match self.credentials {
+            Some(_) => &[SOCKS_VERSION, 2, AUTH_NONE, AUTH_USER_PASS],
+            None => &[SOCKS_VERSION, 1, AUTH_NONE],
+        };
+        stream.write_all(greeting)?;
+
+        let mut choice = [0; 2];
+        stream.read_exact(&mut choice)?;
+        match (choice[1], &self.credentials) {
+            (AUTH_NONE, _) => Ok(()),
+            (AUTH_USER_PASS, Some((username, password))) => {
+                if username.len() > 255 || password.len() > 255 {
+                    return Err(Error::Proxy(
+                        "Username and password must be at most 255 bytes".to_string(),
+                    ));
+                }
+
+                let mut request = vec![USER_PASS_VERSION, username.len() as u8];
+                request.extend_from_slice(username.as_bytes());
+                request.push(password.len() as u8);
+                request.extend_from_slice(password.as_bytes());
+                stream.write_all(&request)?;
+
+                let mut status = [0; 2];
+                stream.read_exact(&mut status)?;
+                if status[1] != 0 {
+                    return Err(Error::Proxy("Authentication failed".to_string()));
+                }
+                Ok(())
+            }
+            (AUTH_NO_ACCEPTABLE, _) => Err(Error::Proxy(
+                "No acceptable authentication method".to_string(),
+            )),
+            (method, _) => Err(Error::Proxy(format!(
+                "Unexpected authentication method {}",
+                method
+            ))),
+        }
+    }
+}
+
+impl fmt::Debug for ProxyConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProxyConfig")
+            .field("addr", &self.addr)
+            .field(
+                "username",
+                &self.credentials.as_ref().map(|(username, _)| username),
+            )
+            .finish()
+    }
+}
+
+/// The reply field of a SOCKS5 reply
+///
+/// Per [RFC 1928](https://tools.ietf.org/html/rfc1928#section-6)
+fn reply_message(rep: u8) -> &'static str {
+    match rep {
+        0x01 => "general SOCKS server failure",
+        0x02 => "connection not allowed by ruleset",
+        0x03 => "network unreachable",
+        0x04 => "host unreachable",
+        0x05 => "connection refused",
+        0x06 => "TTL expired",
+        0x07 => "command not supported",
+        0x08 => "address type not supported",
+        _ => "unknown error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::net::TcpListener;
+    use std::thread;
+
+    use crate::raw::connection::{AddressFamily, ConnectionConfig, NntpConnection};
+
+    fn read_exact(stream: &mut TcpStream, len: usize) -> Vec<u8> {
+        let mut buf = vec![0; len];
+        stream.read_exact(&mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_socks5() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let proxy_addr = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+
+            assert_eq!(
+                read_exact(&mut stream, 4),
+                [5, 2, AUTH_NONE, AUTH_USER_PASS]
+            );
+            stream.write_all(&[5, AUTH_USER_PASS]).unwrap();
+
+            assert_eq!(read_exact(&mut stream, 6), b"\x01\x04user");
+            assert_eq!(read_exact(&mut stream, 8), b"\x07hunter2");
+            stream.write_all(&[1, 0]).unwrap();
+
+            assert_eq!(
+                read_exact(&mut stream, 10),
+                [5, CMD_CONNECT, 0, ATYP_IPV4, 192, 0, 2, 1, 0, 119]
+            );
+            stream
+                .write_all(&[5, 0, 0, ATYP_IPV4, 10, 0, 0, 1, 0xc3, 0x50])
+                .unwrap();
+
+            // From here on the proxy relays the NNTP session
+            stream.write_all(b"200 ready\r\n").unwrap();
+        });
+
+        let config = ConnectionConfig::new()
+            .proxy(Some(
+                ProxyConfig::socks5(proxy_addr)
+                    .credentials("user", "hunter2")
+                    .to_owned(),
+            ))
+            .to_owned();
+        let (conn, greeting) = NntpConnection::connect("192.0.2.1:119", config).unwrap();
+
+        assert_eq!(greeting.first_line(), b"200 ready\r\n");
+        // The peer is the destination rather than the proxy
+        assert_eq!(
+            conn.peer_addr(),
+            Some("192.0.2.1:119".parse::<SocketAddr>().unwrap())
+        );
+
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_socks5_remote_dns() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let proxy_addr = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            // The host is sent to the proxy again on reconnect
+            for _ in 0..2 {
+                let (mut stream, _) = listener.accept().unwrap();
+
+                assert_eq!(read_exact(&mut stream, 3), [5, 1, AUTH_NONE]);
+                stream.write_all(&[5, AUTH_NONE]).unwrap();
+
+                assert_eq!(
+                    read_exact(&mut stream, 5),
+                    [5, CMD_CONNECT, 0, ATYP_DOMAIN, 13]
+                );
+                assert_eq!(read_exact(&mut stream, 15), b"example.onion\x02\x33");
+                stream
+                    .write_all(&[5, 0, 0, ATYP_IPV4, 10, 0, 0, 1, 0xc3, 0x50])
+                    .unwrap();
+                stream.write_all(b"200 ready\r\n").unwrap();
+            }
+        });
+
+        let config = ConnectionConfig::new()
+            .proxy(Some(ProxyConfig::socks5(proxy_addr)))
+            .to_owned();
+        let dest = ServerAddr::parse("example.onion:563", 119).unwrap();
+        let (mut conn, greeting) = NntpConnection::connect_host(&dest, config.clone()).unwrap();
+        assert_eq!(greeting.first_line(), b"200 ready\r\n");
+
+        let greeting = conn.reconnect(config).unwrap();
+        assert_eq!(greeting.first_line(), b"200 ready\r\n");
+        assert_eq!(conn.generation(), 1);
+
+        server.join().unwrap();
+
+        // IP addresses are subject to the address family preference rather than sent as-is
+        let config = ConnectionConfig::new()
+            .proxy(Some(ProxyConfig::socks5(proxy_addr)))
+            .address_family(AddressFamily::V6Only)
+            .to_owned();
+        let dest = ServerAddr::parse("192.0.2.1", 119).unwrap();
+        assert!(matches!(
+            NntpConnection::connect_host(&dest, config),
+            Err(Error::NoMatchingAddress { .. })
+        ));
+    }
+
+    #[test]
+    fn test_socks5_connection_refused() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let proxy_addr = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+
+            assert_eq!(read_exact(&mut stream, 3), [5, 1, AUTH_NONE]);
+            stream.write_all(&[5, AUTH_NONE]).unwrap();
+
+            read_exact(&mut stream, 10);
+            stream
+                .write_all(&[5, 0x05, 0, ATYP_IPV4, 0, 0, 0, 0, 0, 0])
+                .unwrap();
+        });
+
+        let dest = ServerAddr::parse("192.0.2.1", 119).unwrap();
+        let res = ProxyConfig::socks5(proxy_addr).connect(&dest, None, None, None);
+        match res {
+            Err(Error::Proxy(msg)) => assert!(msg.contains("connection refused"), "{}", msg),
+            res => panic!("Unexpected result {:?}", res),
+        }
+
+        server.join().unwrap();
+    }
+}
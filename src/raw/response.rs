@@ -1,7 +1,14 @@
 use std::borrow::Cow;
-use std::str::{from_utf8, from_utf8_unchecked};
+use std::fmt;
+use std::io;
+use std::io::{BufRead, ErrorKind, Read};
+use std::str::{from_utf8, from_utf8_unchecked, FromStr};
+
+use log::*;
 
 use crate::error::Error;
+use crate::raw::error::{Error as RawError, Result as RawResult};
+use crate::raw::parse::{is_end_of_datablock, parse_data_block_line, parse_first_line};
 
 use crate::types::response_code::ResponseCode;
 
@@ -9,6 +16,9 @@ use crate::types::response_code::ResponseCode;
 ///
 /// 1. The contents are guaranteed to be represent a syntactically valid NNTP response
 /// 2. The contents ARE NOT guaranteed to be UTF-8 as the NNTP does not require contents be UTF-8.
+///
+/// Responses can also be read from any other source (e.g. fixtures or captured sessions) with
+/// [`RawResponse::parse`].
 #[derive(Clone, Debug)]
 pub struct RawResponse {
     pub(crate) code: ResponseCode,
@@ -17,6 +27,60 @@ pub struct RawResponse {
 }
 
 impl RawResponse {
+    /// Read and frame a single response from `reader`
+    ///
+    /// The response is framed exactly as it would be by an
+    /// [`NntpConnection`](super::connection::NntpConnection): data blocks are read if
+    /// [`ResponseCode::is_multiline`] returns true for the response code, and dot-stuffing is
+    /// undone. Anything following the response is left unread, so consecutive calls read
+    /// consecutive responses.
+    ///
+    /// Returns [`ConnectionClosed`](RawError::ConnectionClosed) if `reader` reaches EOF before
+    /// the response is complete.
+    ///
+    /// ```
+    /// use brokaw::raw::RawResponse;
+    ///
+    /// let mut session = &b"215 Newsgroups follow\r\nmisc.test 3 1 y\r\n.\r\n"[..];
+    /// let resp = RawResponse::parse(&mut session).unwrap();
+    /// assert_eq!(resp.data_blocks().unwrap().unterminated().count(), 1);
+    /// ```
+    pub fn parse(reader: &mut impl BufRead) -> RawResult<RawResponse> {
+        let mut first_line = Vec::new();
+        let code = read_initial_response(reader, &mut first_line, false, None)?;
+
+        let data_blocks = if code.is_multiline() {
+            let mut payload = Vec::new();
+            let mut line_boundaries = Vec::new();
+            read_data_blocks(
+                reader,
+                &mut payload,
+                &mut line_boundaries,
+                None,
+                None,
+                false,
+            )
+            .map_err(|e| match e {
+                RawError::ConnectionClosed { bytes_read } => RawError::ConnectionClosed {
+                    bytes_read: bytes_read + first_line.len(),
+                },
+                e => e,
+            })?;
+            Some(DataBlocks {
+                payload,
+                line_boundaries,
+            })
+        } else {
+            None
+        };
+
+        Ok(RawResponse {
+            code,
+            first_line,
+            data_blocks,
+        })
+    }
+
     /// The response code
     pub fn code(&self) -> ResponseCode {
         self.code
@@ -32,21 +96,51 @@ impl RawResponse {
         self.data_blocks.as_ref()
     }
 
+    /// The number of bytes in the response
+    ///
+    /// This is the length of the first line plus the [payload](DataBlocks::payload_len) of the
+    /// data blocks, if any. As the payload has been unstuffed, this may be slightly less than the
+    /// number of bytes sent by the server.
+    pub fn total_len(&self) -> usize {
+        self.first_line.len() + self.data_blocks.as_ref().map_or(0, DataBlocks::payload_len)
+    }
+
+    /// The number of lines in the data blocks, or 0 if there are none
+    ///
+    /// As with [`DataBlocks::unterminated`], the final `.` line is not counted.
+    pub fn data_line_count(&self) -> usize {
+        let data_blocks = match &self.data_blocks {
+            Some(data_blocks) => data_blocks,
+            None => return 0,
+        };
+
+        let terminated = data_blocks
+            .line_boundaries
+            .last()
+            .is_some_and(|&(start, end)| {
+                is_end_of_datablock(strip_terminator(&data_blocks.payload[start..end]))
+            });
+        data_blocks.lines_len() - terminated as usize
+    }
+
     /// Return the first line of the response
     pub fn first_line(&self) -> &[u8] {
         &self.first_line
     }
 
     /// Return the first line of the response without the response code
+    ///
+    /// An empty slice is returned if the line is too short to contain anything after the code.
     pub fn first_line_without_code(&self) -> &[u8] {
-        // n.b. this should be infallible barring bugs in the response parsing layer
-        &self.first_line[4..]
+        // n.b. a misbehaving server may send a bare code so this must not panic
+        self.first_line.get(4..).unwrap_or_default()
     }
 
     /// Converts a response into an error if it does not match the provided status
     pub fn fail_unless(self, desired: impl Into<ResponseCode>) -> Result<RawResponse, Error> {
-        if self.code() != desired.into() {
-            Err(Error::failure(self))
+        let desired = desired.into();
+        if self.code() != desired {
+            Err(Error::unexpected_code(desired, self))
         } else {
             Ok(self)
         }
@@ -69,9 +163,78 @@ impl RawResponse {
     }
 }
 
+/// A response that either completes a command or prompts the client to continue
+///
+/// Per [RFC 3977](https://tools.ietf.org/html/rfc3977#section-3.2) a `3xx` response means the
+/// command was accepted so far and the server is waiting for more input, e.g. the article for
+/// `POST` (`340`) and `IHAVE` (`335`), the password for `AUTHINFO USER` (`381`), or the next
+/// step of a SASL exchange (`383`).
+///
+/// See [`NntpConnection::send_then_read`](super::connection::NntpConnection::send_then_read)
+#[derive(Clone, Debug)]
+pub enum Exchange {
+    /// The server sent a `3xx` and is waiting for the client to continue
+    Continue(RawResponse),
+    /// The server sent a final response
+    Complete(RawResponse),
+}
+
+impl Exchange {
+    /// Returns true if the server is waiting for the client to continue
+    pub fn is_continue(&self) -> bool {
+        matches!(self, Exchange::Continue(_))
+    }
+
+    /// Return the underlying response
+    pub fn into_response(self) -> RawResponse {
+        match self {
+            Exchange::Continue(resp) | Exchange::Complete(resp) => resp,
+        }
+    }
+}
+
+impl From<RawResponse> for Exchange {
+    fn from(resp: RawResponse) -> Self {
+        if resp.code().is_success_so_far() {
+            Exchange::Continue(resp)
+        } else {
+            Exchange::Complete(resp)
+        }
+    }
+}
+
+/// Displays the response code and the (lossy) remainder of the first line
+///
+/// If the response has data blocks, the number of lines (excluding the terminating `.`) is
+/// appended, e.g. `211 (GroupSelected) 3 1 5 misc.test (+3 data lines)`.
+impl fmt::Display for RawResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code)?;
+
+        let rest = String::from_utf8_lossy(self.first_line_without_code());
+        let rest = rest.trim_end();
+        if !rest.is_empty() {
+            write!(f, " {}", rest)?;
+        }
+
+        if let Some(data_blocks) = &self.data_blocks {
+            write!(
+                f,
+                " (+{} data lines)",
+                data_blocks.lines_len().saturating_sub(1)
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
 /// The [Multi-line Data Blocks](https://tools.ietf.org/html/rfc3977#section-3.1.1)
 /// portion of an NNTP response
 ///
+/// The data blocks are stored as they were sent by the server except that
+/// [dot-stuffing](https://tools.ietf.org/html/rfc3977#section-3.1.1) has been undone
+/// (e.g. a line sent as `..foo` is stored as `.foo`). The terminating `.` line is kept.
 ///
 /// # Usage
 ///
@@ -79,6 +242,12 @@ impl RawResponse {
 /// * [`DataBlocks::lines`](Self::lines) returns an iterator over the lines within the block
 /// * [`DataBlocks::unterminated`](Self::unterminated) returns an iterator over the lines with the
 /// CRLF terminator and the final `.` line of the response stripped
+/// * [`DataBlocks::lines_without_terminator`](Self::lines_without_terminator) returns an
+///   iterator over the lines with the CRLF terminator stripped
+/// * [`DataBlocks::to_utf8_lines_lossy`](Self::to_utf8_lines_lossy) returns the lines of
+///   [`unterminated`](Self::unterminated) as owned `String`s
+/// * [`DataBlocks::line`](Self::line) and [`DataBlocks::nth_line_trimmed`](Self::nth_line_trimmed)
+///   return a single line by index without iterating
 #[derive(Clone, Debug)]
 pub struct DataBlocks {
     pub(crate) payload: Vec<u8>,
@@ -86,7 +255,44 @@ pub struct DataBlocks {
 }
 
 impl DataBlocks {
+    /// Empty data blocks, to be built with [`push_line`](Self::push_line)
+    pub(crate) fn empty() -> Self {
+        Self {
+            payload: Vec::new(),
+            line_boundaries: Vec::new(),
+        }
+    }
+
+    /// Build data blocks from unstuffed lines without their terminators
+    ///
+    /// The terminating `.` line is added.
+    #[cfg(test)]
+    pub(crate) fn from_lines(lines: &[&str]) -> Self {
+        let mut data_blocks = Self::empty();
+        for line in lines {
+            data_blocks.push_line(line.as_bytes());
+        }
+        data_blocks.finish();
+        data_blocks
+    }
+
+    /// Append an unstuffed line, without its CRLF terminator
+    pub(crate) fn push_line(&mut self, line: &[u8]) {
+        let start = self.payload.len();
+        self.payload.extend_from_slice(line);
+        self.payload.extend_from_slice(b"\r\n");
+        self.line_boundaries.push((start, self.payload.len()));
+    }
+
+    /// Append the terminating `.` line
+    pub(crate) fn finish(&mut self) {
+        self.push_line(b".");
+    }
+
     /// Return the raw contained by the payload of the Datablocks
+    ///
+    /// Dot-stuffing has already been undone by the connection, so lines that the server sent
+    /// with a doubled leading `.` contain a single `.` and must not be unstuffed again.
     pub fn payload(&self) -> &[u8] {
         &self.payload
     }
@@ -97,6 +303,8 @@ impl DataBlocks {
     }
 
     /// An iterator over the lines within the data block
+    ///
+    /// As with [`payload`](Self::payload), the lines have already been unstuffed.
     pub fn lines(&self) -> Lines<'_> {
         Lines {
             data_blocks: self,
@@ -114,6 +322,46 @@ impl DataBlocks {
         }
     }
 
+    /// An iterator over the lines within the data block without their terminators
+    ///
+    /// Unlike [`lines`](Self::lines), the trailing CRLF (or a lone LF) is removed from each line.
+    /// Unlike [`unterminated`](Self::unterminated), the final `.` line is still returned.
+    pub fn lines_without_terminator(&self) -> LinesTrimmed<'_> {
+        LinesTrimmed {
+            inner: self.lines(),
+        }
+    }
+
+    /// The lines of the data block, decoded as UTF-8 lossily
+    ///
+    /// The lines are the same as those yielded by [`unterminated`](Self::unterminated), i.e.
+    /// unstuffed and without the CRLF terminator or the final `.` line. Unlike the iterators,
+    /// this allocates a `String` for every line so it is best suited to small responses.
+    pub fn to_utf8_lines_lossy(&self) -> Vec<String> {
+        self.unterminated()
+            .map(|line| String::from_utf8_lossy(line).into_owned())
+            .collect()
+    }
+
+    /// The line at `index`, including its terminator
+    ///
+    /// This is the `index`th line yielded by [`lines`](Self::lines), looked up in constant time.
+    /// Returns `None` if `index` is out of range.
+    pub fn line(&self, index: usize) -> Option<&[u8]> {
+        self.line_boundaries
+            .get(index)
+            .map(|(start, end)| &self.payload[*start..*end])
+    }
+
+    /// The line at `index` without its terminator
+    ///
+    /// This is the `index`th line yielded by
+    /// [`lines_without_terminator`](Self::lines_without_terminator), so the final `.` line can
+    /// be returned. Returns `None` if `index` is out of range.
+    pub fn nth_line_trimmed(&self, index: usize) -> Option<&[u8]> {
+        self.line(index).map(strip_terminator)
+    }
+
     /// The number of lines
     pub fn lines_len(&self) -> usize {
         self.line_boundaries.len()
@@ -159,12 +407,549 @@ impl<'a> Iterator for Unterminated<'a> {
     type Item = &'a [u8];
 
     fn next(&mut self) -> Option<Self::Item> {
-        match self.inner.next() {
-            Some(line) if line == b".\r\n" => None,
-            Some(line) => Some(&line[..line.len() - 2]),
-            None => None,
+        let line = strip_terminator(self.inner.next()?);
+        // n.b. only the final line terminates the data blocks, an unstuffed line may also be `.`
+        if self.inner.inner.len() == 0 && is_end_of_datablock(line) {
+            return None;
         }
-        //let foo: ()= self.data_blocks.lines().take_while(|line| line != b".\r\n");
-        //unimplemented!()
+        Some(line)
+    }
+}
+
+/// An iterator created by [`DataBlocks::lines_without_terminator`]
+#[derive(Clone, Debug)]
+pub struct LinesTrimmed<'a> {
+    inner: Lines<'a>,
+}
+
+impl<'a> Iterator for LinesTrimmed<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(strip_terminator)
+    }
+}
+
+/// Strip the CRLF (or a lone LF) terminating a line
+fn strip_terminator(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+/// Read the initial response from a stream
+///
+/// Per [RFC 3977](https://tools.ietf.org/html/rfc3977#section-3.1) the initial response
+/// should not exceed 512 bytes. If `lenient`, the line may be terminated by a lone LF.
+/// Reading fails with [`RawError::LineTooLong`] if the line exceeds `max_line_bytes`.
+pub(crate) fn read_initial_response<S: io::BufRead>(
+    stream: &mut S,
+    buffer: &mut Vec<u8>,
+    lenient: bool,
+    max_line_bytes: Option<usize>,
+) -> RawResult<ResponseCode> {
+    // n.b. read_until will keep reading until it finds the terminator (or the read times out)
+    // so greetings/responses that arrive slowly or across several packets are reassembled
+    let bytes_read = read_line(stream, buffer, line_budget(max_line_bytes))?;
+    check_line_length(bytes_read, max_line_bytes)?;
+    check_line_complete(buffer, buffer.len())?;
+
+    parse_response_code(buffer, lenient)
+}
+
+/// Read multi-line data block portion from a stream
+///
+/// * The data will be read line-by-line into the provided `buffer`
+/// * The `line_boundaries` vector will contain a list two-tuples containing the start and ending
+///   of every line within the `buffer`
+/// * Dot-stuffing is undone, i.e. the leading `.` is removed from lines beginning with `..`
+/// * Note that depending on the command the total data size may be on the order of several megabytes!
+/// * If a `limit` is provided, reading stops once the `buffer` exceeds it
+/// * If `max_line_bytes` is provided, reading stops once a single line exceeds it
+/// * If `lenient`, lines may be terminated by a lone LF
+///
+/// Returns the number of bytes read from the stream, which includes any dot-stuffing.
+pub(crate) fn read_data_blocks<S: io::BufRead>(
+    stream: &mut S,
+    buffer: &mut Vec<u8>,
+    line_boundaries: &mut Vec<(usize, usize)>,
+    limit: Option<usize>,
+    max_line_bytes: Option<usize>,
+    lenient: bool,
+) -> RawResult<usize> {
+    trace!("Reading data blocks...");
+    let mut bytes_read = 0;
+
+    // n.b. - icky imperative style so that we have zero allocations outside of the reader
+    loop {
+        let budget = min_budget(
+            response_budget(limit, buffer.len()),
+            line_budget(max_line_bytes),
+        );
+        let line_len = read_line(stream, buffer, budget)?;
+        bytes_read += line_len;
+        if let Some(limit) = limit.filter(|limit| buffer.len() > *limit) {
+            return Err(RawError::ResponseTooLarge { limit });
+        }
+        check_line_length(line_len, max_line_bytes)?;
+
+        let read_head = line_boundaries.last().map(|(_, end)| *end).unwrap_or(0);
+        check_line_complete(&buffer[read_head..], buffer.len())?;
+
+        if frame_data_block_line(buffer, line_boundaries, lenient)? {
+            trace!(
+                "Read {} bytes of data across {} lines",
+                buffer.len(),
+                line_boundaries.len()
+            );
+            break;
+        }
+    }
+
+    Ok(bytes_read)
+}
+
+/// Parse the response code from the first line of a response
+///
+/// Framing is shared by the blocking and async connections, which only differ in how the line
+/// is read from the stream.
+pub(crate) fn parse_response_code(line: &[u8], lenient: bool) -> RawResult<ResponseCode> {
+    if !is_status_line(line) {
+        return Err(RawError::DesyncedResponse { raw: line.to_vec() });
+    }
+
+    let (_rest, resp) = parse_first_line(line, lenient).map_err(|_e| {
+        io::Error::new(
+            ErrorKind::InvalidData,
+            "Failed to parse first line of response",
+        )
+    })?;
+
+    // This made it past the parser -> infallible
+    let code_str = from_utf8(resp.code).unwrap();
+    // All three digit integers will fit w/in u16 -> also infallible
+    let code_u16 = u16::from_str(code_str).unwrap();
+
+    Ok(code_u16.into())
+}
+
+/// Returns true if `line` is three digits followed by either a space or the line terminator
+///
+/// Anything else can't be the first line of a response, see [`RawError::DesyncedResponse`].
+/// A bare LF is accepted as the terminator as it may be with
+/// [lenient line endings](crate::raw::connection::ConnectionConfig::lenient_line_endings).
+fn is_status_line(line: &[u8]) -> bool {
+    match line {
+        [a, b, c, rest @ ..] if [a, b, c].iter().all(|d| d.is_ascii_digit()) => {
+            matches!(rest, [b' ', ..] | b"\r\n" | b"\n")
+        }
+        _ => false,
+    }
+}
+
+/// Append a line to `buffer`, reading at most `budget` bytes
+///
+/// Returns the number of bytes read.
+pub(crate) fn read_line<S: io::BufRead>(
+    stream: &mut S,
+    buffer: &mut Vec<u8>,
+    budget: Option<u64>,
+) -> io::Result<usize> {
+    // n.b. - read_until will _append_ data from the current end of the vector
+    match budget {
+        Some(budget) => stream.by_ref().take(budget).read_until(b'\n', buffer),
+        None => stream.read_until(b'\n', buffer),
+    }
+}
+
+/// The number of bytes to read for the next line so that a line longer than `max_line_bytes`
+/// is caught without buffering it in its entirety
+pub(crate) fn line_budget(max_line_bytes: Option<usize>) -> Option<u64> {
+    // Read at most one byte past the limit
+    max_line_bytes.map(|max| max as u64 + 1)
+}
+
+/// The number of bytes to read for the next line so that a response exceeding `limit` is caught
+pub(crate) fn response_budget(limit: Option<usize>, buffered: usize) -> Option<u64> {
+    limit.map(|limit| limit.saturating_sub(buffered) as u64 + 1)
+}
+
+/// The smaller of two budgets, if any
+pub(crate) fn min_budget(a: Option<u64>, b: Option<u64>) -> Option<u64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Fail with [`RawError::LineTooLong`] if a line of `line_len` bytes exceeds `max_line_bytes`
+pub(crate) fn check_line_length(line_len: usize, max_line_bytes: Option<usize>) -> RawResult<()> {
+    match max_line_bytes {
+        Some(limit) if line_len > limit => Err(RawError::LineTooLong { limit }),
+        _ => Ok(()),
+    }
+}
+
+/// Check that a line read via `read_until` is complete
+///
+/// `read_until` only stops before the LF terminator when the stream reaches EOF, so a line
+/// without one (including an empty read) means that the server closed the connection in the
+/// middle of a response. `bytes_read` is the number of bytes of the response read so far.
+pub(crate) fn check_line_complete(line: &[u8], bytes_read: usize) -> RawResult<()> {
+    if line.ends_with(b"\n") {
+        Ok(())
+    } else {
+        Err(RawError::ConnectionClosed { bytes_read })
+    }
+}
+
+/// Frame a data block line that was just appended to the end of `buffer`
+///
+/// The line starts at the end of the last entry in `line_boundaries`. Dot-stuffing is undone
+/// in place and the boundaries of the line are recorded.
+///
+/// Returns true if the line was the terminating `.` line.
+pub(crate) fn frame_data_block_line(
+    buffer: &mut Vec<u8>,
+    line_boundaries: &mut Vec<(usize, usize)>,
+    lenient: bool,
+) -> RawResult<bool> {
+    let read_head = line_boundaries.last().map(|(_, end)| *end).unwrap_or(0);
+
+    let (_empty, line) = parse_data_block_line(&buffer[read_head..], lenient).map_err(|e| {
+        trace!("parse_data_block_line failed -- {:?}", e);
+        io::Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "Failed to parse line {} of data blocks",
+                line_boundaries.len() + 1
+            ),
+        )
+    })?;
+    let is_end = is_end_of_datablock(line);
+
+    // Undo the dot-stuffing performed by the server
+    if !is_end && buffer.get(read_head) == Some(&b'.') {
+        buffer.remove(read_head);
+    }
+
+    // we keep track of line boundaries rather than slices as borrowck won't allow
+    // us to reuse the buffer AND keep track of sub-slices within it
+    // n.b. we use the end of the buffer rather than the length of line so that we don't drop
+    // the terminators
+    line_boundaries.push((read_head, buffer.len()));
+
+    Ok(is_end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::response_code::Kind;
+
+    #[test]
+    fn test_unterminated_unstuffed_dot() {
+        let mut session = &b"100 help\r\nfirst\r\n..\r\nafter\r\n.\r\n"[..];
+        let resp = RawResponse::parse(&mut session).unwrap();
+        let data_blocks = resp.data_blocks().unwrap();
+
+        assert_eq!(
+            data_blocks.unterminated().collect::<Vec<_>>(),
+            vec![&b"first"[..], b".", b"after"]
+        );
+        assert_eq!(
+            data_blocks.to_utf8_lines_lossy(),
+            vec!["first", ".", "after"]
+        );
+        assert_eq!(resp.data_line_count(), 3);
+    }
+
+    #[test]
+    fn test_first_line_without_code() {
+        let resp = |first_line: &[u8]| RawResponse {
+            code: ResponseCode::from(200),
+            first_line: first_line.to_vec(),
+            data_blocks: None,
+        };
+
+        assert_eq!(
+            resp(b"200 ready\r\n").first_line_without_code(),
+            b"ready\r\n"
+        );
+        assert_eq!(resp(b"200").first_line_without_code(), b"");
+        assert_eq!(resp(b"").first_line_without_code(), b"");
+    }
+
+    #[test]
+    fn test_exchange() {
+        let resp = |code: u16| RawResponse {
+            code: ResponseCode::from(code),
+            first_line: format!("{} text\r\n", code).into_bytes(),
+            data_blocks: None,
+        };
+
+        for code in &[335, 340, 381, 383, 399] {
+            assert!(Exchange::from(resp(*code)).is_continue(), "{}", code);
+        }
+        for code in &[111, 240, 281, 440, 482, 502] {
+            assert!(!Exchange::from(resp(*code)).is_continue(), "{}", code);
+        }
+        assert_eq!(
+            Exchange::from(resp(340)).into_response().code(),
+            ResponseCode::Known(Kind::PostSendArticle)
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        let mut resp = RawResponse {
+            code: ResponseCode::from(211),
+            first_line: b"211 3 1 5 misc.test\r\n".to_vec(),
+            data_blocks: None,
+        };
+        assert_eq!(resp.to_string(), "211 (GroupSelected) 3 1 5 misc.test");
+
+        resp.data_blocks = Some(DataBlocks {
+            payload: b"1\r\n3\r\n.\r\n".to_vec(),
+            line_boundaries: vec![(0, 3), (3, 6), (6, 9)],
+        });
+        assert_eq!(
+            resp.to_string(),
+            "211 (GroupSelected) 3 1 5 misc.test (+2 data lines)"
+        );
+
+        let resp = RawResponse {
+            code: ResponseCode::from(299),
+            first_line: b"299\r\n".to_vec(),
+            data_blocks: None,
+        };
+        assert_eq!(resp.to_string(), "299");
+    }
+
+    #[test]
+    fn test_lines_without_terminator() {
+        let payload = b"first\r\nsecond\n\r\n.\r\n".to_vec();
+        let data_blocks = DataBlocks {
+            line_boundaries: vec![(0, 7), (7, 14), (14, 16), (16, 19)],
+            payload,
+        };
+
+        assert_eq!(
+            data_blocks.lines_without_terminator().collect::<Vec<_>>(),
+            vec![&b"first"[..], b"second", b"", b"."]
+        );
+        assert_eq!(data_blocks.lines().next(), Some(&b"first\r\n"[..]));
+    }
+
+    #[test]
+    fn test_line() {
+        let payload = b"first\r\nsecond\n\r\n.\r\n".to_vec();
+        let data_blocks = DataBlocks {
+            line_boundaries: vec![(0, 7), (7, 14), (14, 16), (16, 19)],
+            payload,
+        };
+
+        for (i, line) in data_blocks.lines().enumerate() {
+            assert_eq!(data_blocks.line(i), Some(line));
+        }
+        assert_eq!(data_blocks.nth_line_trimmed(1), Some(&b"second"[..]));
+        assert_eq!(data_blocks.nth_line_trimmed(3), Some(&b"."[..]));
+        assert_eq!(data_blocks.line(4), None);
+        assert_eq!(data_blocks.nth_line_trimmed(4), None);
+    }
+
+    #[test]
+    fn test_to_utf8_lines_lossy() {
+        let payload = b".leading dot\r\nb\xffd\r\n\r\n.\r\n".to_vec();
+        let data_blocks = DataBlocks {
+            line_boundaries: vec![(0, 14), (14, 19), (19, 21), (21, 24)],
+            payload,
+        };
+        assert_eq!(
+            data_blocks.to_utf8_lines_lossy(),
+            vec![".leading dot", "b\u{fffd}d", ""]
+        );
+
+        let empty = DataBlocks {
+            line_boundaries: vec![(0, 3)],
+            payload: b".\r\n".to_vec(),
+        };
+        assert!(empty.to_utf8_lines_lossy().is_empty());
+    }
+
+    #[test]
+    fn test_truncated_data_blocks() {
+        let mut buffer = Vec::new();
+        let mut line_boundaries = Vec::new();
+        let err = read_data_blocks(
+            &mut &b"line1\r\nline2"[..],
+            &mut buffer,
+            &mut line_boundaries,
+            None,
+            None,
+            false,
+        )
+        .unwrap_err();
+        assert!(
+            matches!(err, RawError::ConnectionClosed { bytes_read: 12 }),
+            "Unexpected error {:?}",
+            err
+        );
+
+        // EOF at a line boundary is still missing the terminator
+        let mut buffer = Vec::new();
+        let mut line_boundaries = Vec::new();
+        let err = read_data_blocks(
+            &mut &b"line1\r\n"[..],
+            &mut buffer,
+            &mut line_boundaries,
+            None,
+            None,
+            false,
+        )
+        .unwrap_err();
+        assert!(
+            matches!(err, RawError::ConnectionClosed { bytes_read: 7 }),
+            "Unexpected error {:?}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_desynced_response() {
+        let mut session = &b"tail of the previous article\r\n.\r\n"[..];
+        match RawResponse::parse(&mut session) {
+            Err(RawError::DesyncedResponse { raw }) => {
+                assert_eq!(raw, b"tail of the previous article\r\n")
+            }
+            res => panic!("Unexpected result {:?}", res),
+        }
+
+        assert!(is_status_line(b"200 ready\r\n"));
+        assert!(is_status_line(b"205\r\n"));
+        assert!(is_status_line(b"205\n"));
+        assert!(!is_status_line(b"211-ish\r\n"));
+        assert!(!is_status_line(b"2112 ready\r\n"));
+        assert!(!is_status_line(b"20 ready\r\n"));
+        assert!(!is_status_line(b"205\r"));
+        assert!(!is_status_line(b"..200 dot-stuffed\r\n"));
+    }
+
+    #[test]
+    fn test_line_too_long() {
+        const LIMIT: usize = 64 * 1024;
+        let mut long_line = vec![b'x'; 1024 * 1024];
+        long_line.extend_from_slice(b"\r\n.\r\n");
+
+        let mut buffer = Vec::new();
+        let mut line_boundaries = Vec::new();
+        let err = read_data_blocks(
+            &mut &long_line[..],
+            &mut buffer,
+            &mut line_boundaries,
+            None,
+            Some(LIMIT),
+            false,
+        )
+        .unwrap_err();
+        assert!(
+            matches!(err, RawError::LineTooLong { limit: LIMIT }),
+            "Unexpected error {:?}",
+            err
+        );
+        // Only one byte past the limit was buffered
+        assert_eq!(buffer.len(), LIMIT + 1);
+
+        // A line of exactly the limit is accepted
+        let mut buffer = Vec::new();
+        let mut line_boundaries = Vec::new();
+        read_data_blocks(
+            &mut &b"line1\r\n.\r\n"[..],
+            &mut buffer,
+            &mut line_boundaries,
+            None,
+            Some(7),
+            false,
+        )
+        .unwrap();
+        assert_eq!(line_boundaries.len(), 2);
+
+        let mut first_line = Vec::new();
+        let err = read_initial_response(&mut &long_line[..], &mut first_line, false, Some(LIMIT))
+            .unwrap_err();
+        assert!(matches!(err, RawError::LineTooLong { limit: LIMIT }));
+    }
+
+    #[test]
+    fn test_parse() {
+        let mut session = &b"211 2 1 2 misc.test\r\n\
+            224 Overview follows\r\n1\tSubject\r\n..dot\r\n.\r\n\
+            205 Bye\r\n"[..];
+
+        let resp = RawResponse::parse(&mut session).unwrap();
+        assert_eq!(resp.code(), ResponseCode::Known(Kind::GroupSelected));
+        assert!(!resp.has_data_blocks());
+        assert_eq!(resp.total_len(), 21);
+        assert_eq!(resp.data_line_count(), 0);
+
+        let resp = RawResponse::parse(&mut session).unwrap();
+        assert_eq!(resp.code(), ResponseCode::Known(Kind::Overview));
+        assert_eq!(
+            resp.data_blocks()
+                .unwrap()
+                .unterminated()
+                .collect::<Vec<_>>(),
+            vec![&b"1\tSubject"[..], b".dot"]
+        );
+        assert_eq!(resp.total_len(), 22 + 11 + 6 + 3);
+        assert_eq!(resp.data_line_count(), 2);
+
+        let resp = RawResponse::parse(&mut session).unwrap();
+        assert_eq!(resp.first_line(), b"205 Bye\r\n");
+        assert!(session.is_empty());
+
+        let mut truncated = &b"224 Overview follows\r\n1\tSub"[..];
+        let err = RawResponse::parse(&mut truncated).unwrap_err();
+        assert!(
+            matches!(err, RawError::ConnectionClosed { bytes_read: 27 }),
+            "Unexpected error {:?}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_frame_data_block_line() {
+        let mut buffer = Vec::new();
+        let mut line_boundaries = Vec::new();
+
+        for (line, is_end) in &[
+            (&b"first\r\n"[..], false),
+            (b"..stuffed\r\n", false),
+            (b".\r\n", true),
+        ] {
+            buffer.extend_from_slice(line);
+            assert_eq!(
+                frame_data_block_line(&mut buffer, &mut line_boundaries, false).unwrap(),
+                *is_end
+            );
+        }
+
+        assert_eq!(buffer, b"first\r\n.stuffed\r\n.\r\n");
+        assert_eq!(line_boundaries, vec![(0, 7), (7, 17), (17, 20)]);
+
+        buffer.extend_from_slice(b"no terminator");
+        assert!(frame_data_block_line(&mut buffer, &mut line_boundaries, false).is_err());
+    }
+
+    #[test]
+    fn test_parse_response_code() {
+        assert_eq!(
+            parse_response_code(b"200 ready\r\n", false).unwrap(),
+            ResponseCode::from(200)
+        );
+        assert_eq!(
+            parse_response_code(b"205\r\n", false).unwrap(),
+            ResponseCode::from(205)
+        );
+        assert!(parse_response_code(b"hello\r\n", false).is_err());
     }
 }
@@ -1,16 +1,156 @@
-use std::net::TcpStream;
+use std::fmt;
+use std::net::{Shutdown, SocketAddr, TcpStream};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
 
 use native_tls::TlsStream;
 use std::io;
-use std::io::{Read, Write};
+use std::io::{ErrorKind, Read, Write};
+
+use crate::raw::compression::DeflateStream;
+
+/// A stream established outside of Brokaw (e.g. an SSH tunnel or an in-memory pipe)
+///
+/// This is implemented for every `Read + Write + Send` type.
+pub trait Transport: Read + Write + Send {}
+
+impl<T: Read + Write + Send> Transport for T {}
 
 /// A raw NNTP session
-#[derive(Debug)]
+///
+/// New kinds of stream may be added, so matching on this requires a wildcard arm.
+#[non_exhaustive]
 pub enum NntpStream {
     /// A stream using TLS
     Tls(TlsStream<TcpStream>),
     /// A plain text stream
     Tcp(TcpStream),
+    /// A stream compressed with `COMPRESS DEFLATE`
+    Deflate(Box<DeflateStream<NntpStream>>),
+    /// A plain text stream over a Unix domain socket
+    #[cfg(unix)]
+    Unix(UnixStream),
+    /// A stream provided by the caller via
+    /// [`NntpConnection::from_stream`](super::NntpConnection::from_stream)
+    Custom(Box<dyn Transport>),
+}
+
+impl NntpStream {
+    /// The address of the remote end of the stream
+    ///
+    /// An error is returned for [`Custom`](Self::Custom) and `Unix` streams as they have no
+    /// socket address.
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        match self.tcp_stream() {
+            Some(s) => s.peer_addr(),
+            None => Err(io::Error::new(
+                ErrorKind::NotConnected,
+                "The stream has no socket address",
+            )),
+        }
+    }
+
+    /// Returns true if the stream is using TLS
+    pub fn is_tls(&self) -> bool {
+        match self {
+            NntpStream::Tls(_) => true,
+            NntpStream::Tcp(_) | NntpStream::Custom(_) => false,
+            #[cfg(unix)]
+            NntpStream::Unix(_) => false,
+            NntpStream::Deflate(s) => s.get_ref().is_tls(),
+        }
+    }
+
+    /// Returns true if the stream is compressed
+    pub fn is_compressed(&self) -> bool {
+        matches!(self, NntpStream::Deflate(_))
+    }
+
+    /// A handle to the underlying plain text TCP stream, or `None` if TLS is already in use
+    pub(crate) fn try_clone_tcp(&self) -> Option<io::Result<TcpStream>> {
+        match self {
+            NntpStream::Tls(_) | NntpStream::Deflate(_) => None,
+            NntpStream::Tcp(s) => Some(s.try_clone()),
+            NntpStream::Custom(_) => Some(Err(io::Error::new(
+                ErrorKind::Unsupported,
+                "TLS cannot be negotiated over a custom stream",
+            ))),
+            #[cfg(unix)]
+            NntpStream::Unix(_) => Some(Err(io::Error::new(
+                ErrorKind::Unsupported,
+                "TLS is not used over Unix domain sockets",
+            ))),
+        }
+    }
+
+    /// The underlying TCP stream, or `None` if the stream isn't a TCP stream
+    pub(crate) fn tcp_stream(&self) -> Option<&TcpStream> {
+        match self {
+            NntpStream::Tls(s) => Some(s.get_ref()),
+            NntpStream::Tcp(s) => Some(s),
+            NntpStream::Deflate(s) => s.get_ref().tcp_stream(),
+            NntpStream::Custom(_) => None,
+            #[cfg(unix)]
+            NntpStream::Unix(_) => None,
+        }
+    }
+
+    /// The underlying socket, or `None` for [`Custom`](Self::Custom) streams
+    fn socket(&self) -> Option<Socket<'_>> {
+        match self {
+            #[cfg(unix)]
+            NntpStream::Unix(s) => Some(Socket::Unix(s)),
+            NntpStream::Deflate(s) => s.get_ref().socket(),
+            s => s.tcp_stream().map(Socket::Tcp),
+        }
+    }
+
+    /// The read timeout of the underlying socket
+    pub(crate) fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        match self.socket() {
+            Some(Socket::Tcp(s)) => s.read_timeout(),
+            #[cfg(unix)]
+            Some(Socket::Unix(s)) => s.read_timeout(),
+            None => Err(custom_timeouts()),
+        }
+    }
+
+    /// Set the read timeout of the underlying socket
+    pub(crate) fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        match self.socket() {
+            Some(Socket::Tcp(s)) => s.set_read_timeout(timeout),
+            #[cfg(unix)]
+            Some(Socket::Unix(s)) => s.set_read_timeout(timeout),
+            None => Err(custom_timeouts()),
+        }
+    }
+
+    /// Shut down both halves of the underlying socket
+    ///
+    /// This is a no-op for [`Custom`](Self::Custom) streams.
+    pub(crate) fn shutdown(&self) -> io::Result<()> {
+        match self.socket() {
+            Some(Socket::Tcp(s)) => s.shutdown(Shutdown::Both),
+            #[cfg(unix)]
+            Some(Socket::Unix(s)) => s.shutdown(Shutdown::Both),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A borrowed socket underlying an [`NntpStream`]
+enum Socket<'a> {
+    Tcp(&'a TcpStream),
+    #[cfg(unix)]
+    Unix(&'a UnixStream),
+}
+
+fn custom_timeouts() -> io::Error {
+    io::Error::new(
+        ErrorKind::Unsupported,
+        "The timeouts of a custom stream cannot be changed",
+    )
 }
 
 impl From<TlsStream<TcpStream>> for NntpStream {
@@ -25,11 +165,22 @@ impl From<TcpStream> for NntpStream {
     }
 }
 
+#[cfg(unix)]
+impl From<UnixStream> for NntpStream {
+    fn from(stream: UnixStream) -> NntpStream {
+        Self::Unix(stream)
+    }
+}
+
 impl Read for NntpStream {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         match self {
             NntpStream::Tls(s) => s.read(buf),
             NntpStream::Tcp(s) => s.read(buf),
+            NntpStream::Deflate(s) => s.read(buf),
+            NntpStream::Custom(s) => s.read(buf),
+            #[cfg(unix)]
+            NntpStream::Unix(s) => s.read(buf),
         }
     }
 }
@@ -39,6 +190,10 @@ impl Write for NntpStream {
         match self {
             NntpStream::Tls(s) => s.write(buf),
             NntpStream::Tcp(s) => s.write(buf),
+            NntpStream::Deflate(s) => s.write(buf),
+            NntpStream::Custom(s) => s.write(buf),
+            #[cfg(unix)]
+            NntpStream::Unix(s) => s.write(buf),
         }
     }
 
@@ -46,6 +201,23 @@ impl Write for NntpStream {
         match self {
             NntpStream::Tls(s) => s.flush(),
             NntpStream::Tcp(s) => s.flush(),
+            NntpStream::Deflate(s) => s.flush(),
+            NntpStream::Custom(s) => s.flush(),
+            #[cfg(unix)]
+            NntpStream::Unix(s) => s.flush(),
+        }
+    }
+}
+
+impl fmt::Debug for NntpStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NntpStream::Tls(s) => f.debug_tuple("Tls").field(s).finish(),
+            NntpStream::Tcp(s) => f.debug_tuple("Tcp").field(s).finish(),
+            NntpStream::Deflate(s) => f.debug_tuple("Deflate").field(s).finish(),
+            NntpStream::Custom(_) => f.debug_tuple("Custom").finish(),
+            #[cfg(unix)]
+            NntpStream::Unix(s) => f.debug_tuple("Unix").field(s).finish(),
         }
     }
 }
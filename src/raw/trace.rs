@@ -0,0 +1,203 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::io::{self, BufRead, Read};
+use std::sync::Arc;
+
+/// The direction of the bytes passed to a [trace hook](super::connection::ConnectionConfig::trace_hook)
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Direction {
+    /// Bytes written to the server
+    Send,
+    /// Bytes read from the server
+    Recv,
+}
+
+/// A callback invoked with the raw bytes sent to and received from the server
+pub type TraceFn = dyn Fn(Direction, &[u8]) + Send + Sync;
+
+/// See [`ConnectionConfig::trace_hook`](super::connection::ConnectionConfig::trace_hook)
+#[derive(Clone)]
+pub(crate) struct TraceHook(pub(crate) Arc<TraceFn>);
+
+impl TraceHook {
+    /// Pass bytes written to the server to the hook, optionally redacting credentials
+    ///
+    /// `sasl_exchange` tracks whether a SASL exchange is in progress so that its continuation
+    /// lines are redacted as well.
+    pub(crate) fn sent(&self, bytes: &[u8], redact: bool, sasl_exchange: &mut bool) {
+        if redact {
+            (self.0)(Direction::Send, &redact_passwords(bytes, sasl_exchange))
+        } else {
+            (self.0)(Direction::Send, bytes)
+        }
+    }
+
+    /// Pass bytes read from the server to the hook
+    pub(crate) fn received(&self, bytes: &[u8]) {
+        if !bytes.is_empty() {
+            (self.0)(Direction::Recv, bytes)
+        }
+    }
+}
+
+impl fmt::Debug for TraceHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TraceHook").finish()
+    }
+}
+
+/// Replace the credentials in `AUTHINFO PASS`, `AUTHINFO SASL`, and `AUTHINFO GENERIC` lines
+///
+/// The password, the SASL initial response, and the arguments to the generic authenticator are
+/// replaced, as is every line sent while `sasl_exchange` is set. `sasl_exchange` is set once an
+/// `AUTHINFO SASL` line is seen.
+fn redact_passwords<'a>(bytes: &'a [u8], sasl_exchange: &mut bool) -> Cow<'a, [u8]> {
+    const PASS: &[u8] = b"AUTHINFO PASS ";
+    const SASL: &[u8] = b"AUTHINFO SASL ";
+    const GENERIC: &[u8] = b"AUTHINFO GENERIC ";
+
+    let has_prefix = |line: &[u8], prefix: &[u8]| {
+        line.len() >= prefix.len() && line[..prefix.len()].eq_ignore_ascii_case(prefix)
+    };
+    // The end of the word starting at `from`, including the space that follows it
+    let word_end = |line: &[u8], from: usize| {
+        line[from..]
+            .iter()
+            .position(|&b| b == b' ')
+            .map_or(line.len(), |i| from + i + 1)
+    };
+
+    let mut redacted: Option<Vec<u8>> = None;
+    let mut offset = 0;
+    for line in bytes.split_inclusive(|&b| b == b'\n') {
+        let content = line
+            .strip_suffix(b"\r\n")
+            .or_else(|| line.strip_suffix(b"\n"))
+            .unwrap_or(line);
+        let keep = if *sasl_exchange {
+            Some(0)
+        } else if has_prefix(content, PASS) {
+            Some(PASS.len())
+        } else if has_prefix(content, SASL) {
+            *sasl_exchange = true;
+            Some(word_end(content, SASL.len()))
+        } else if has_prefix(content, GENERIC) {
+            Some(word_end(content, GENERIC.len()))
+        } else {
+            None
+        };
+
+        match keep {
+            Some(keep) if keep < content.len() => {
+                let redacted = redacted.get_or_insert_with(|| {
+                    let mut buf = Vec::with_capacity(bytes.len());
+                    buf.extend_from_slice(&bytes[..offset]);
+                    buf
+                });
+                redacted.extend_from_slice(&content[..keep]);
+                redacted.extend_from_slice(b"********\r\n");
+            }
+            _ => {
+                if let Some(redacted) = &mut redacted {
+                    redacted.extend_from_slice(line);
+                }
+            }
+        }
+        offset += line.len();
+    }
+
+    match redacted {
+        Some(redacted) => Cow::Owned(redacted),
+        None => Cow::Borrowed(bytes),
+    }
+}
+
+/// A reader that passes every byte consumed from `inner` to a trace hook
+pub(crate) struct Traced<'a, S> {
+    inner: S,
+    hook: Option<&'a TraceHook>,
+}
+
+impl<'a, S> Traced<'a, S> {
+    pub(crate) fn new(inner: S, hook: Option<&'a TraceHook>) -> Self {
+        Self { inner, hook }
+    }
+}
+
+impl<S: Read> Read for Traced<'_, S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if let Some(hook) = self.hook {
+            hook.received(&buf[..n]);
+        }
+        Ok(n)
+    }
+}
+
+impl<S: BufRead> BufRead for Traced<'_, S> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if let Some(hook) = self.hook {
+            // n.b. the buffer was just filled so this doesn't read from the stream
+            if let Ok(buf) = self.inner.fill_buf() {
+                hook.received(&buf[..amt.min(buf.len())]);
+            }
+        }
+        self.inner.consume(amt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_passwords() {
+        let redact = |bytes: &[u8]| redact_passwords(bytes, &mut false).into_owned();
+
+        assert_eq!(
+            redact(b"AUTHINFO USER user\r\n"),
+            &b"AUTHINFO USER user\r\n"[..]
+        );
+        assert_eq!(
+            redact(b"DATE\r\nauthinfo pass hunter2\r\nQUIT\r\n"),
+            &b"DATE\r\nauthinfo pass ********\r\nQUIT\r\n"[..]
+        );
+        assert_eq!(
+            redact(b"AUTHINFO SASL PLAIN AGFsaWNlAGh1bnRlcjI=\r\n"),
+            &b"AUTHINFO SASL PLAIN ********\r\n"[..]
+        );
+        assert_eq!(
+            redact(b"AUTHINFO GENERIC authsrv -p hunter2\r\n"),
+            &b"AUTHINFO GENERIC authsrv ********\r\n"[..]
+        );
+        assert_eq!(
+            redact(b"AUTHINFO GENERIC authsrv\r\n"),
+            &b"AUTHINFO GENERIC authsrv\r\n"[..]
+        );
+    }
+
+    #[test]
+    fn test_redact_sasl_continuations() {
+        let mut sasl_exchange = false;
+        assert_eq!(
+            redact_passwords(b"AUTHINFO SASL SCRAM-SHA-256\r\n", &mut sasl_exchange),
+            &b"AUTHINFO SASL SCRAM-SHA-256\r\n"[..]
+        );
+        assert!(sasl_exchange);
+        assert_eq!(
+            redact_passwords(b"bj1hbGljZSxyPWZ5a28=\r\n", &mut sasl_exchange),
+            &b"********\r\n"[..]
+        );
+
+        let mut sasl_exchange = false;
+        assert_eq!(
+            redact_passwords(b"DATE\r\n", &mut sasl_exchange),
+            &b"DATE\r\n"[..]
+        );
+        assert!(!sasl_exchange);
+    }
+}
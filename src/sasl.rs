@@ -0,0 +1,341 @@
+use std::fmt;
+
+use hmac::{Hmac, Mac};
+use md5::Md5;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use sha2::{Digest, Sha256};
+
+use crate::error::{Error, Result};
+
+type HmacSha256 = Hmac<Sha256>;
+type HmacMd5 = Hmac<Md5>;
+
+/// A SASL mechanism that can be used with `AUTHINFO SASL`
+///
+/// The mechanisms supported by a server can be found via
+/// [`Capabilities::sasl_mechanisms`](crate::types::prelude::Capabilities::sasl_mechanisms).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SaslMechanism {
+    /// [SCRAM-SHA-256](https://tools.ietf.org/html/rfc7677)
+    ///
+    /// The password is never sent to the server, even over plain text connections.
+    ScramSha256,
+    /// [PLAIN](https://tools.ietf.org/html/rfc4616)
+    ///
+    /// The password is sent to the server as-is, so this should only be used over TLS.
+    Plain,
+    /// [CRAM-MD5](https://tools.ietf.org/html/rfc2195)
+    ///
+    /// The password is not sent to the server, but the server cannot prove that it knows it and
+    /// MD5 is considered weak. Prefer `SCRAM-SHA-256` where it is available.
+    CramMd5,
+}
+
+impl SaslMechanism {
+    /// The name of the mechanism as it is sent over the wire
+    pub fn name(&self) -> &'static str {
+        match self {
+            SaslMechanism::ScramSha256 => "SCRAM-SHA-256",
+            SaslMechanism::Plain => "PLAIN",
+            SaslMechanism::CramMd5 => "CRAM-MD5",
+        }
+    }
+}
+
+impl fmt::Display for SaslMechanism {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Client side state for a [SCRAM-SHA-256](https://tools.ietf.org/html/rfc5802) exchange
+///
+/// The exchange consists of three messages:
+///
+/// 1. The client-first message is sent as the initial response of `AUTHINFO SASL`
+/// 2. The server-first message is returned with a `383` and answered with the client-final message
+/// 3. The server-final message is returned with a `283` and contains the server's signature
+///
+/// # Limitations
+///
+/// * Usernames and passwords are not normalized with SASLprep
+/// * Channel binding is not supported
+#[derive(Clone)]
+pub(crate) struct ScramSha256 {
+    username: String,
+    password: String,
+    client_nonce: String,
+    server_signature: Option<Vec<u8>>,
+}
+
+/// The GS2 header for a client that does not support channel binding
+const GS2_HEADER: &str = "n,,";
+
+impl ScramSha256 {
+    /// Create a new exchange with a random nonce
+    pub(crate) fn new(username: impl AsRef<str>, password: impl AsRef<str>) -> Self {
+        let client_nonce = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(24)
+            .map(char::from)
+            .collect::<String>();
+
+        Self::with_nonce(username, password, client_nonce)
+    }
+
+    fn with_nonce(
+        username: impl AsRef<str>,
+        password: impl AsRef<str>,
+        client_nonce: impl AsRef<str>,
+    ) -> Self {
+        Self {
+            username: username.as_ref().to_string(),
+            password: password.as_ref().to_string(),
+            client_nonce: client_nonce.as_ref().to_string(),
+            server_signature: None,
+        }
+    }
+
+    fn client_first_bare(&self) -> String {
+        let username = self.username.replace('=', "=3D").replace(',', "=2C");
+        format!("n={},r={}", username, self.client_nonce)
+    }
+
+    /// The client-first message
+    pub(crate) fn client_first(&self) -> String {
+        format!("{}{}", GS2_HEADER, self.client_first_bare())
+    }
+
+    /// Process the server-first message and return the client-final message
+    pub(crate) fn client_final(&mut self, server_first: &str) -> Result<String> {
+        let mut nonce = None;
+        let mut salt = None;
+        let mut iterations = None;
+
+        for attr in server_first.split(',') {
+            match attr.split_at(attr.find('=').map(|i| i + 1).unwrap_or(0)) {
+                ("r=", value) => nonce = Some(value),
+                ("s=", value) => {
+                    salt = Some(base64::decode(value).map_err(|_| Error::sasl("Invalid salt"))?)
+                }
+                ("i=", value) => {
+                    iterations = Some(
+                        value
+                            .parse::<u32>()
+                            .map_err(|_| Error::sasl("Invalid iteration count"))?,
+                    )
+                }
+                ("m=", _) => return Err(Error::sasl("Unsupported mandatory extension")),
+                _ => {}
+            }
+        }
+
+        let nonce = nonce.ok_or_else(|| Error::sasl("Server did not provide a nonce"))?;
+        let salt = salt.ok_or_else(|| Error::sasl("Server did not provide a salt"))?;
+        let iterations =
+            iterations.ok_or_else(|| Error::sasl("Server did not provide an iteration count"))?;
+
+        if !nonce.starts_with(&self.client_nonce) || nonce.len() == self.client_nonce.len() {
+            return Err(Error::sasl("Server nonce does not extend the client nonce"));
+        }
+        if iterations == 0 {
+            return Err(Error::sasl("Invalid iteration count"));
+        }
+
+        let salted_password = pbkdf2_sha256(self.password.as_bytes(), &salt, iterations);
+        let client_key = hmac_sha256(&salted_password, b"Client Key");
+        let stored_key = Sha256::digest(&client_key);
+
+        let client_final_without_proof = format!("c={},r={}", base64::encode(GS2_HEADER), nonce);
+        let auth_message = format!(
+            "{},{},{}",
+            self.client_first_bare(),
+            server_first,
+            client_final_without_proof
+        );
+
+        let client_signature = hmac_sha256(&stored_key, auth_message.as_bytes());
+        let client_proof = client_key
+            .iter()
+            .zip(client_signature.iter())
+            .map(|(k, s)| k ^ s)
+            .collect::<Vec<u8>>();
+
+        let server_key = hmac_sha256(&salted_password, b"Server Key");
+        self.server_signature = Some(hmac_sha256(&server_key, auth_message.as_bytes()));
+
+        Ok(format!(
+            "{},p={}",
+            client_final_without_proof,
+            base64::encode(&client_proof)
+        ))
+    }
+
+    /// Verify the server-final message
+    ///
+    /// This must be called after [`client_final`](Self::client_final)
+    pub(crate) fn verify_server_final(&self, server_final: &str) -> Result<()> {
+        let expected = self
+            .server_signature
+            .as_ref()
+            .ok_or_else(|| Error::sasl("Server-final message received out of sequence"))?;
+
+        if let Some(err) = server_final.strip_prefix("e=") {
+            return Err(Error::sasl(format!(
+                "Server rejected authentication -- {}",
+                err
+            )));
+        }
+
+        let signature = server_final
+            .split(',')
+            .find_map(|attr| attr.strip_prefix("v="))
+            .ok_or_else(|| Error::sasl("Server-final message is missing a signature"))
+            .and_then(|v| base64::decode(v).map_err(|_| Error::sasl("Invalid server signature")))?;
+
+        if &signature != expected {
+            return Err(Error::sasl("Server signature does not match"));
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for ScramSha256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScramSha256")
+            .field("username", &self.username)
+            .finish()
+    }
+}
+
+/// The initial response for a [PLAIN](https://tools.ietf.org/html/rfc4616) exchange
+///
+/// The message is `authzid NUL authcid NUL passwd`, the authorization identity is left empty.
+pub(crate) fn plain_initial_response(
+    username: impl AsRef<str>,
+    password: impl AsRef<str>,
+) -> String {
+    format!("\0{}\0{}", username.as_ref(), password.as_ref())
+}
+
+/// The response to a [CRAM-MD5](https://tools.ietf.org/html/rfc2195) challenge
+///
+/// The response is the username followed by the hex encoded HMAC-MD5 of the challenge keyed
+/// with the password.
+pub(crate) fn cram_md5_response(
+    username: impl AsRef<str>,
+    password: impl AsRef<str>,
+    challenge: impl AsRef<str>,
+) -> String {
+    // n.b. HMAC accepts keys of any length
+    let mut mac = HmacMd5::new_from_slice(password.as_ref().as_bytes())
+        .expect("HMAC can take a key of any size");
+    mac.update(challenge.as_ref().as_bytes());
+    let digest = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+
+    format!("{} {}", username.as_ref(), digest)
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    // n.b. HMAC accepts keys of any length
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC can take a key of any size");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// PBKDF2 with HMAC-SHA-256 for a single block of output
+///
+/// SCRAM only ever needs a single block as the derived key is the size of the hash
+fn pbkdf2_sha256(password: &[u8], salt: &[u8], iterations: u32) -> Vec<u8> {
+    let mut block = salt.to_vec();
+    block.extend_from_slice(&1u32.to_be_bytes());
+
+    let mut u = hmac_sha256(password, &block);
+    let mut output = u.clone();
+    for _ in 1..iterations {
+        u = hmac_sha256(password, &u);
+        output.iter_mut().zip(u.iter()).for_each(|(o, u)| *o ^= u);
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test vectors from https://tools.ietf.org/html/rfc7677#section-3
+    const CLIENT_FIRST: &str = "n,,n=user,r=rOprNGfwEbeRWgbNEkqO";
+    const SERVER_FIRST: &str =
+        "r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4096";
+    const CLIENT_FINAL: &str = "c=biws,r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,\
+                                p=dHzbZapWIk4jUhN+Ute9ytag9zjfMHgsqmmiz7AndVQ=";
+    const SERVER_FINAL: &str = "v=6rriTRBi23WpRR/wtup+mMhUZUn/dB5nLTJRsjl95G4=";
+
+    #[test]
+    fn test_rfc7677_exchange() {
+        let mut scram = ScramSha256::with_nonce("user", "pencil", "rOprNGfwEbeRWgbNEkqO");
+
+        assert_eq!(scram.client_first(), CLIENT_FIRST);
+        assert_eq!(scram.client_final(SERVER_FIRST).unwrap(), CLIENT_FINAL);
+        assert!(scram.verify_server_final(SERVER_FINAL).is_ok());
+    }
+
+    #[test]
+    fn test_bad_server_signature() {
+        let mut scram = ScramSha256::with_nonce("user", "pencil", "rOprNGfwEbeRWgbNEkqO");
+        scram.client_final(SERVER_FIRST).unwrap();
+
+        assert!(scram
+            .verify_server_final("v=AAAATRBi23WpRR/wtup+mMhUZUn/dB5nLTJRsjl95G4=")
+            .is_err());
+        assert!(scram.verify_server_final("e=invalid-proof").is_err());
+    }
+
+    #[test]
+    fn test_mismatched_nonce() {
+        let mut scram = ScramSha256::with_nonce("user", "pencil", "someothernonce");
+        assert!(scram.client_final(SERVER_FIRST).is_err());
+    }
+
+    #[test]
+    fn test_username_escaping() {
+        let scram = ScramSha256::with_nonce("a=b,c", "pencil", "nonce");
+        assert_eq!(scram.client_first(), "n,,n=a=3Db=2Cc,r=nonce");
+    }
+
+    #[test]
+    fn test_plain() {
+        assert_eq!(plain_initial_response("tim", "tanstaaf"), "\0tim\0tanstaaf");
+    }
+
+    #[test]
+    fn test_cram_md5() {
+        // Test vector from https://tools.ietf.org/html/rfc2195#section-2
+        assert_eq!(
+            cram_md5_response(
+                "tim",
+                "tanstaaftanstaaf",
+                "<1896.697170952@postoffice.reston.mci.net>"
+            ),
+            "tim b913a602c7eda7a495b4e6e7334d3890"
+        );
+    }
+
+    #[test]
+    fn test_random_nonce() {
+        let scram = ScramSha256::new("user", "pencil");
+        assert_eq!(scram.client_nonce.len(), 24);
+        assert_ne!(
+            scram.client_nonce,
+            ScramSha256::new("user", "pencil").client_nonce
+        );
+    }
+}
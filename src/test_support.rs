@@ -0,0 +1,483 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener};
+#[cfg(unix)]
+use std::os::unix::net::UnixListener;
+#[cfg(unix)]
+use std::path::Path;
+use std::thread::{self, JoinHandle};
+
+use log::*;
+
+/// The greeting sent by a [`MockScript`] unless [`greeting`](MockScript::greeting) is called
+pub const DEFAULT_GREETING: &[u8] = b"200 brokaw mock server ready\r\n";
+
+/// The response sent before closing the connection when the client deviates from the script
+const MISMATCH_RESPONSE: &[u8] = b"500 Unexpected command\r\n";
+
+/// The response to a `QUIT` that isn't part of the script
+const QUIT_RESPONSE: &[u8] = b"205 Bye\r\n";
+
+/// The exchanges a [`MockNntpServer`] expects, in order
+///
+/// Responses are sent exactly as given, so they must include their CRLF line endings and, for
+/// multi-line responses, the terminating `.` line.
+///
+/// ```
+/// use brokaw::test_support::MockScript;
+/// use brokaw::ClientConfig;
+///
+/// let server = MockScript::new()
+///     .expect("CAPABILITIES", b"101 Capability list:\r\nVERSION 2\r\nREADER\r\n.\r\n")
+///     .expect("DATE", b"111 20200101000000\r\n")
+///     .start()
+///     .unwrap();
+///
+/// let mut client = ClientConfig::default().connect(server.addr()).unwrap();
+/// client.date().unwrap();
+///
+/// drop(client);
+/// server.finish().unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct MockScript {
+    greeting: Vec<u8>,
+    steps: Vec<Step>,
+}
+
+#[derive(Clone, Debug)]
+enum Step {
+    /// A single command line, without the CRLF
+    Command { line: String, resp: Vec<u8> },
+    /// Data blocks sent after a continuation response, without dot-stuffing or the terminator
+    Data { block: Vec<u8>, resp: Vec<u8> },
+    /// A new connection, which is sent the greeting
+    Reconnect,
+    /// The server closes the connection
+    Disconnect,
+}
+
+impl Step {
+    fn resp(&self) -> &[u8] {
+        match self {
+            Step::Command { resp, .. } | Step::Data { resp, .. } => resp,
+            Step::Reconnect | Step::Disconnect => &[],
+        }
+    }
+}
+
+impl Default for MockScript {
+    fn default() -> Self {
+        Self {
+            greeting: DEFAULT_GREETING.to_vec(),
+            steps: Vec::new(),
+        }
+    }
+}
+
+impl MockScript {
+    /// Create an empty script that sends the [`DEFAULT_GREETING`]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set the greeting sent as soon as the client connects
+    pub fn greeting(&mut self, resp: impl AsRef<[u8]>) -> &mut Self {
+        self.greeting = resp.as_ref().to_vec();
+        self
+    }
+
+    /// Expect the client to send `command` (without the CRLF) and answer with `resp`
+    ///
+    /// Continuation flows such as `AUTHINFO USER`/`AUTHINFO PASS` are scripted as consecutive
+    /// commands.
+    pub fn expect(&mut self, command: impl AsRef<str>, resp: impl AsRef<[u8]>) -> &mut Self {
+        self.steps.push(Step::Command {
+            line: command.as_ref().to_string(),
+            resp: resp.as_ref().to_vec(),
+        });
+        self
+    }
+
+    /// Expect the client to send data blocks, e.g. the article after a `340` response to `POST`,
+    /// and answer with `resp`
+    ///
+    /// The data blocks are compared after undoing the dot-stuffing and removing the terminating
+    /// `.` line, so `block` is the article as it was passed to the client. A missing final CRLF
+    /// is added just as the client does when sending it.
+    pub fn expect_data(&mut self, block: impl AsRef<[u8]>, resp: impl AsRef<[u8]>) -> &mut Self {
+        let mut block = block.as_ref().to_vec();
+        if !block.is_empty() && !block.ends_with(b"\r\n") {
+            block.extend_from_slice(b"\r\n");
+        }
+        self.steps.push(Step::Data {
+            block,
+            resp: resp.as_ref().to_vec(),
+        });
+        self
+    }
+
+    /// Expect the client to open a new connection, e.g. via
+    /// [`NntpClient::reconnect`](crate::NntpClient::reconnect)
+    ///
+    /// The greeting is sent on the new connection and the rest of the script is played on it.
+    /// The previous connection is closed first, without waiting for the client to close it.
+    pub fn reconnect(&mut self) -> &mut Self {
+        self.steps.push(Step::Reconnect);
+        self
+    }
+
+    /// Close the connection, e.g. to test how the client copes with the server going away
+    ///
+    /// Only a [`reconnect`](Self::reconnect) may follow, and the script is finished without
+    /// waiting for the client to disconnect if none does.
+    pub fn disconnect(&mut self) -> &mut Self {
+        self.steps.push(Step::Disconnect);
+        self
+    }
+
+    /// Start a [`MockNntpServer`] that plays this script on a local port
+    pub fn start(&self) -> io::Result<MockNntpServer> {
+        MockNntpServer::start(self.clone())
+    }
+
+    /// Start a [`MockNntpServer`] that plays this script on a Unix domain socket at `path`
+    #[cfg(unix)]
+    pub fn start_unix(&self, path: impl AsRef<Path>) -> io::Result<MockNntpServer> {
+        MockNntpServer::start_unix(self.clone(), path)
+    }
+}
+
+/// A scripted NNTP server on a local socket for testing code that uses the clients
+///
+/// The server accepts a connection, sends the greeting, and then checks each command against
+/// its [`MockScript`]. Further connections are only accepted where the script
+/// [expects a reconnect](MockScript::reconnect). If the client sends something else the server answers with a
+/// `500` response and closes the connection. Once the script is exhausted, anything but a
+/// disconnect is reported as unexpected.
+///
+/// Since the clients send a `QUIT` when they are dropped, an unscripted `QUIT` is treated like
+/// a disconnect.
+///
+/// Call [`finish`](Self::finish) to find out whether the client followed the script.
+#[derive(Debug)]
+pub struct MockNntpServer {
+    addr: Option<SocketAddr>,
+    handle: JoinHandle<Result<(), MockServerError>>,
+}
+
+impl MockNntpServer {
+    /// Bind to an ephemeral port on the loopback interface and play `script` in the background
+    pub fn start(script: MockScript) -> io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let handle = thread::spawn(move || {
+            let accept = || {
+                let (stream, peer) = listener.accept()?;
+                debug!("Mock server accepted a connection from {}", peer);
+                Ok((stream.try_clone()?, stream))
+            };
+            play(accept, script)
+        });
+
+        Ok(Self {
+            addr: Some(addr),
+            handle,
+        })
+    }
+
+    /// Bind to a Unix domain socket at `path` and play `script` in the background
+    ///
+    /// The socket file must not exist yet, and it is not removed when the server finishes.
+    #[cfg(unix)]
+    pub fn start_unix(script: MockScript, path: impl AsRef<Path>) -> io::Result<Self> {
+        let listener = UnixListener::bind(path)?;
+        let handle = thread::spawn(move || {
+            let accept = || {
+                let (stream, _) = listener.accept()?;
+                debug!("Mock server accepted a connection on a Unix domain socket");
+                Ok((stream.try_clone()?, stream))
+            };
+            play(accept, script)
+        });
+
+        Ok(Self { addr: None, handle })
+    }
+
+    /// The address the server is listening on
+    ///
+    /// # Panics
+    ///
+    /// Panics if the server was [started on a Unix domain socket](Self::start_unix).
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+            .expect("The mock server is listening on a Unix domain socket")
+    }
+
+    /// Wait for the client to disconnect and return whether it followed the script
+    ///
+    /// This blocks until the connection is closed, so the client should be dropped first.
+    pub fn finish(self) -> Result<(), MockServerError> {
+        match self.handle.join() {
+            Ok(res) => res,
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+    }
+}
+
+/// The ways a client can deviate from a [`MockScript`]
+#[derive(Debug, thiserror::Error)]
+pub enum MockServerError {
+    /// The client sent something other than the next step of the script
+    #[error("Step {step}: expected {expected:?} but received {received:?}")]
+    Mismatch {
+        /// The 0-based index of the step
+        step: usize,
+        /// What the script expected
+        expected: String,
+        /// What the client sent
+        received: String,
+    },
+    /// The client disconnected before the script was finished
+    #[error("Client disconnected with {remaining} steps remaining")]
+    Incomplete {
+        /// The number of steps that were never played
+        remaining: usize,
+    },
+    /// The client sent a command after the script was finished
+    #[error("Unexpected command after the end of the script {0:?}")]
+    Unexpected(String),
+    /// The connection failed
+    #[error("IO Error")]
+    Io(#[from] io::Error),
+}
+
+/// Play `script` on the connection returned by `accept`, accepting another one for each
+/// [`Step::Reconnect`]
+fn play<R: Read, W: Write>(
+    mut accept: impl FnMut() -> io::Result<(R, W)>,
+    script: MockScript,
+) -> Result<(), MockServerError> {
+    let MockScript { greeting, steps } = script;
+    let mut open = || -> io::Result<(BufReader<R>, W)> {
+        let (reader, mut writer) = accept()?;
+        writer.write_all(&greeting)?;
+        Ok((BufReader::new(reader), writer))
+    };
+    let mut conn = Some(open()?);
+
+    let total = steps.len();
+    for (i, step) in steps.into_iter().enumerate() {
+        match step {
+            Step::Reconnect => {
+                // Hang up first so that a client still waiting for a response sees it
+                drop(conn.take());
+                conn = Some(open()?);
+                continue;
+            }
+            Step::Disconnect => {
+                conn = None;
+                continue;
+            }
+            Step::Command { .. } | Step::Data { .. } => {}
+        }
+        let (reader, writer) = match conn.as_mut() {
+            Some((reader, writer)) => (reader, writer),
+            None => {
+                return Err(MockServerError::Incomplete {
+                    remaining: total - i,
+                })
+            }
+        };
+
+        let res = match &step {
+            Step::Command { line, .. } => match read_line(reader)? {
+                Some(received) if received == format!("{}\r\n", line).as_bytes() => Ok(()),
+                None => Err(MockServerError::Incomplete {
+                    remaining: total - i,
+                }),
+                Some(received) if is_quit(&received) => {
+                    writer.write_all(QUIT_RESPONSE)?;
+                    Err(MockServerError::Incomplete {
+                        remaining: total - i,
+                    })
+                }
+                Some(received) => Err(MockServerError::Mismatch {
+                    step: i,
+                    expected: line.clone(),
+                    received: lossy(strip_crlf(&received)),
+                }),
+            },
+            Step::Data { block, .. } => match read_data_blocks(reader)? {
+                None => Err(MockServerError::Incomplete {
+                    remaining: total - i,
+                }),
+                Some(received) if &received == block => Ok(()),
+                Some(received) => Err(MockServerError::Mismatch {
+                    step: i,
+                    expected: lossy(block),
+                    received: lossy(&received),
+                }),
+            },
+            Step::Reconnect | Step::Disconnect => unreachable!("handled above"),
+        };
+
+        if let Err(e) = res {
+            if !matches!(e, MockServerError::Incomplete { .. }) {
+                writer.write_all(MISMATCH_RESPONSE)?;
+            }
+            return Err(e);
+        }
+        writer.write_all(step.resp())?;
+    }
+
+    let (reader, writer) = match conn.as_mut() {
+        Some((reader, writer)) => (reader, writer),
+        None => return Ok(()),
+    };
+    while let Some(received) = read_line(reader)? {
+        if is_quit(&received) {
+            writer.write_all(QUIT_RESPONSE)?;
+        } else {
+            writer.write_all(MISMATCH_RESPONSE)?;
+            return Err(MockServerError::Unexpected(lossy(strip_crlf(&received))));
+        }
+    }
+    Ok(())
+}
+
+fn is_quit(line: &[u8]) -> bool {
+    strip_crlf(line).eq_ignore_ascii_case(b"QUIT")
+}
+
+/// Read a line including its terminator, returning `None` at EOF
+fn read_line(reader: &mut impl BufRead) -> io::Result<Option<Vec<u8>>> {
+    let mut line = Vec::new();
+    match reader.read_until(b'\n', &mut line)? {
+        0 => Ok(None),
+        _ => Ok(Some(line)),
+    }
+}
+
+/// Read dot-stuffed data blocks up to the terminator, returning `None` at EOF
+fn read_data_blocks(reader: &mut impl BufRead) -> io::Result<Option<Vec<u8>>> {
+    let mut block = Vec::new();
+    while let Some(line) = read_line(reader)? {
+        if line == b".\r\n" {
+            return Ok(Some(block));
+        }
+        let unstuffed = line.strip_prefix(b".").unwrap_or(&line);
+        block.extend_from_slice(unstuffed);
+    }
+    Ok(None)
+}
+
+fn strip_crlf(line: &[u8]) -> &[u8] {
+    line.strip_suffix(b"\r\n").unwrap_or(line)
+}
+
+fn lossy(b: &[u8]) -> String {
+    String::from_utf8_lossy(b).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+    use crate::ClientConfig;
+
+    const CAPABILITIES: &[u8] =
+        b"101 Capability list:\r\nVERSION 2\r\nREADER\r\nPOST\r\nAUTHINFO USER\r\n.\r\n";
+
+    #[test]
+    fn test_authinfo_and_post() {
+        let article = b"Subject: hi\r\n\r\n.leading dot\r\nbody";
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("AUTHINFO USER alice", b"381 Password required\r\n")
+            .expect("AUTHINFO PASS secret", b"281 Authentication accepted\r\n")
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("POST", b"340 Input article; end with <CR-LF>.<CR-LF>\r\n")
+            .expect_data(article, b"240 Article received OK\r\n")
+            .expect(
+                "ARTICLE <a@example.com>",
+                b"220 0 <a@example.com>\r\nSubject: hi\r\n\r\n..leading dot\r\n.\r\n",
+            )
+            .start()
+            .unwrap();
+
+        let mut client = ClientConfig::default()
+            .authinfo_user_pass("alice", "secret")
+            .connect(server.addr())
+            .unwrap();
+        client.post(article).unwrap();
+        let article = client.article("<a@example.com>").unwrap();
+        assert_eq!(article.body(), b".leading dot\r\n");
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_mismatch() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("DATE", b"111 20200101000000\r\n")
+            .start()
+            .unwrap();
+
+        let mut client = ClientConfig::default().connect(server.addr()).unwrap();
+        match client.help() {
+            Err(Error::UnexpectedCode { got, .. }) => assert_eq!(u16::from(got), 500),
+            res => panic!("Unexpected result {:?}", res),
+        }
+
+        drop(client);
+        match server.finish() {
+            Err(MockServerError::Mismatch {
+                step: 1,
+                expected,
+                received,
+            }) => assert_eq!((expected.as_str(), received.as_str()), ("DATE", "HELP")),
+            res => panic!("Unexpected result {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_disconnect() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("DATE", b"")
+            .disconnect()
+            .start()
+            .unwrap();
+
+        let mut client = ClientConfig::default().connect(server.addr()).unwrap();
+        assert!(matches!(client.date(), Err(Error::Connection(_))));
+
+        drop(client);
+        server.finish().unwrap();
+    }
+
+    #[test]
+    fn test_incomplete_and_unexpected() {
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .expect("DATE", b"111 20200101000000\r\n")
+            .start()
+            .unwrap();
+        drop(ClientConfig::default().connect(server.addr()).unwrap());
+        assert!(matches!(
+            server.finish(),
+            Err(MockServerError::Incomplete { remaining: 1 })
+        ));
+
+        let server = MockScript::new()
+            .expect("CAPABILITIES", CAPABILITIES)
+            .start()
+            .unwrap();
+        let mut client = ClientConfig::default().connect(server.addr()).unwrap();
+        assert!(client.date().is_err());
+        drop(client);
+        assert!(matches!(server.finish(), Err(MockServerError::Unexpected(cmd)) if cmd == "DATE"));
+    }
+}
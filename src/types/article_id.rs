@@ -0,0 +1,87 @@
+use crate::types::command as cmd;
+use crate::types::ArticleNumber;
+
+/// Identifies an article for commands such as `ARTICLE`, `HEAD`, `BODY`, and `STAT`
+///
+/// Numbers are converted into [`Number`](Self::Number) and strings or a validated
+/// [`MessageId`](crate::types::MessageId) into [`MessageId`](Self::MessageId), so most methods
+/// accept either:
+///
+/// ```
+/// use brokaw::types::ArticleId;
+///
+/// assert_eq!(ArticleId::from(42), ArticleId::Number(42));
+/// assert_eq!(
+///     ArticleId::from("<foo@bar>"),
+///     ArticleId::MessageId("<foo@bar>".to_string())
+/// );
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum ArticleId {
+    /// Globally unique message ID (e.g. `<foo@bar>`)
+    MessageId(String),
+    /// Article number relative to the currently selected group
+    Number(ArticleNumber),
+    /// The currently selected article
+    Current,
+}
+
+impl ArticleId {
+    /// Returns true if the ID is only meaningful within a selected group
+    pub fn requires_group(&self) -> bool {
+        !matches!(self, ArticleId::MessageId(_))
+    }
+}
+
+impl From<ArticleNumber> for ArticleId {
+    fn from(number: ArticleNumber) -> Self {
+        ArticleId::Number(number)
+    }
+}
+
+impl From<&str> for ArticleId {
+    fn from(id: &str) -> Self {
+        ArticleId::MessageId(id.to_string())
+    }
+}
+
+impl From<String> for ArticleId {
+    fn from(id: String) -> Self {
+        ArticleId::MessageId(id)
+    }
+}
+
+impl From<&String> for ArticleId {
+    fn from(id: &String) -> Self {
+        ArticleId::MessageId(id.clone())
+    }
+}
+
+/// Implement conversions between `ArticleId` and commands that take an article ID
+macro_rules! impl_article_id_conversions {
+    ($($command:ident),*) => {
+        $(
+            impl From<cmd::$command> for ArticleId {
+                fn from(command: cmd::$command) -> Self {
+                    match command {
+                        cmd::$command::MessageId(id) => ArticleId::MessageId(id),
+                        cmd::$command::Number(number) => ArticleId::Number(number),
+                        cmd::$command::Current => ArticleId::Current,
+                    }
+                }
+            }
+
+            impl From<ArticleId> for cmd::$command {
+                fn from(id: ArticleId) -> Self {
+                    match id {
+                        ArticleId::MessageId(id) => cmd::$command::MessageId(id),
+                        ArticleId::Number(number) => cmd::$command::Number(number),
+                        ArticleId::Current => cmd::$command::Current,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_article_id_conversions!(Article, Body, Head, Stat);
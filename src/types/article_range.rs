@@ -0,0 +1,84 @@
+use std::fmt;
+use std::ops::{RangeFrom, RangeInclusive};
+
+use crate::error::{Error, Result};
+use crate::types::ArticleNumber;
+
+/// A range of articles within the selected group
+///
+/// Per [RFC 3977](https://tools.ietf.org/html/rfc3977#section-8.3.2) a range may be a single
+/// article number, an open ended range (`123-`), or a closed range (`123-456`). Commands such as
+/// `OVER` and `HDR` may also omit the range to refer to the current article, which is
+/// represented by [`ArticleRange::Current`]. Commands without such a form (e.g. `LISTGROUP` and
+/// `XPAT`) reject it rather than silently sending no range.
+///
+/// ```
+/// use brokaw::types::ArticleRange;
+/// use brokaw::types::command::{NntpCommand, Over};
+///
+/// assert_eq!(Over::Articles(123.into()).to_wire_bytes(), b"OVER 123\r\n");
+/// assert_eq!(Over::Articles((123..).into()).to_wire_bytes(), b"OVER 123-\r\n");
+/// assert_eq!(Over::Articles((123..=456).into()).to_wire_bytes(), b"OVER 123-456\r\n");
+/// assert_eq!(Over::Articles(ArticleRange::Current).to_wire_bytes(), b"OVER\r\n");
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum ArticleRange {
+    /// A single article
+    Single(ArticleNumber),
+    /// An article and all articles following it
+    From(ArticleNumber),
+    /// All articles between `low` and `high` (inclusive)
+    Between {
+        /// The lowest article number in the range
+        low: ArticleNumber,
+        /// The highest article number in the range
+        high: ArticleNumber,
+    },
+    /// The current article, sent by omitting the range
+    Current,
+}
+
+impl ArticleRange {
+    /// Write the range as a command argument, including the separating space
+    ///
+    /// Nothing is written for [`ArticleRange::Current`] so that commands are sent without a
+    /// trailing space.
+    pub(crate) fn fmt_arg(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArticleRange::Single(number) => write!(f, " {}", number),
+            ArticleRange::From(low) => write!(f, " {}-", low),
+            ArticleRange::Between { low, high } => write!(f, " {}-{}", low, high),
+            ArticleRange::Current => Ok(()),
+        }
+    }
+
+    /// Reject [`ArticleRange::Current`] for a `command` that has no current-article form
+    pub(crate) fn require_explicit(self, command: &str) -> Result<Self> {
+        match self {
+            ArticleRange::Current => Err(Error::InvalidCommand(format!(
+                "{} requires an explicit article range",
+                command
+            ))),
+            range => Ok(range),
+        }
+    }
+}
+
+impl From<ArticleNumber> for ArticleRange {
+    fn from(number: ArticleNumber) -> Self {
+        ArticleRange::Single(number)
+    }
+}
+
+impl From<RangeFrom<ArticleNumber>> for ArticleRange {
+    fn from(range: RangeFrom<ArticleNumber>) -> Self {
+        ArticleRange::From(range.start)
+    }
+}
+
+impl From<RangeInclusive<ArticleNumber>> for ArticleRange {
+    fn from(range: RangeInclusive<ArticleNumber>) -> Self {
+        let (low, high) = range.into_inner();
+        ArticleRange::Between { low, high }
+    }
+}
@@ -42,7 +42,42 @@
 ///
 /// assert_eq!(cmd.to_string(), "LISTGROUP misc.test 10-20")
 /// ```
-pub trait NntpCommand: Encode {}
+pub trait NntpCommand: Encode {
+    /// The capability the server must advertise for the command to be available
+    ///
+    /// Returns `None` for mandatory commands (e.g. `CAPABILITIES`) and extensions that servers
+    /// do not advertise (e.g. `XOVER`). Custom commands default to `None`.
+    /// See [`ClientConfig::enforce_capabilities`](crate::client::ClientConfig::enforce_capabilities).
+    fn required_capability(&self) -> Option<&str> {
+        None
+    }
+
+    /// The maximum length of the command line in octets, including the CRLF terminator
+    ///
+    /// This is [`MAX_COMMAND_LINE_LEN`] except for the lines of a SASL exchange, which may be up
+    /// to [`MAX_SASL_LINE_LEN`]. Connections refuse to send longer lines.
+    fn max_line_len(&self) -> usize {
+        MAX_COMMAND_LINE_LEN
+    }
+
+    /// The exact bytes written to the server for this command, including the CRLF terminator
+    ///
+    /// This is what [`NntpConnection::send`](crate::raw::connection::NntpConnection::send)
+    /// writes, so it can be used to inspect or log a command without sending it.
+    ///
+    /// ```
+    /// use brokaw::types::command::{Article, NntpCommand};
+    ///
+    /// let cmd = Article::Number(42);
+    /// assert_eq!(cmd.to_wire_bytes(), b"ARTICLE 42\r\n");
+    /// ```
+    ///
+    /// Connections refuse to send a line that isn't terminated by a single CRLF, so overriding
+    /// this to return anything else makes the command unsendable.
+    fn to_wire_bytes(&self) -> Vec<u8> {
+        to_wire_line(&self.encode())
+    }
+}
 
 /// A type that can be serialized for transmission
 ///
@@ -58,6 +93,84 @@ impl<T: ToString> Encode for T {
     }
 }
 
+/// The maximum length of a command line in octets, including the CRLF terminator
+///
+/// Per [RFC 3977](https://tools.ietf.org/html/rfc3977#section-3.1)
+pub const MAX_COMMAND_LINE_LEN: usize = 512;
+
+/// The maximum length of an `AUTHINFO SASL` command line or a SASL client response in octets,
+/// including the CRLF terminator
+///
+/// Per [RFC 4643](https://tools.ietf.org/html/rfc4643#section-2.4.2), as base64 encoded SASL
+/// responses may not fit in [`MAX_COMMAND_LINE_LEN`].
+pub const MAX_SASL_LINE_LEN: usize = 12288;
+
+/// Append the CRLF terminator to an encoded command
+pub(crate) fn to_wire_line(command: &[u8]) -> Vec<u8> {
+    let mut line = Vec::with_capacity(command.len() + 2);
+    line.extend_from_slice(command);
+    line.extend_from_slice(b"\r\n");
+    line
+}
+
+/// Remove the CRLF terminator from a command line produced by
+/// [`to_wire_bytes`](NntpCommand::to_wire_bytes)
+pub(crate) fn strip_crlf(line: &[u8]) -> &[u8] {
+    line.strip_suffix(b"\r\n").unwrap_or(line)
+}
+
+/// The maximum length of a raw command line, see [`NntpCommand::max_line_len`]
+///
+/// `AUTHINFO SASL` commands are allowed [`MAX_SASL_LINE_LEN`] octets. Raw SASL client responses
+/// can't be told apart from other commands, so they should be sent as
+/// [`SaslResponse`]s instead.
+pub(crate) fn raw_max_line_len(command: &[u8]) -> usize {
+    let prefix = b"AUTHINFO SASL ";
+    match command.get(..prefix.len()) {
+        Some(start) if start.eq_ignore_ascii_case(prefix) => MAX_SASL_LINE_LEN,
+        _ => MAX_COMMAND_LINE_LEN,
+    }
+}
+
+/// Check that a line produced by [`to_wire_bytes`](NntpCommand::to_wire_bytes) is a single
+/// CRLF terminated command line of at most `max_len` octets
+///
+/// See [`check_command_line`].
+pub(crate) fn check_wire_line(line: &[u8], max_len: usize) -> std::result::Result<(), String> {
+    match line.strip_suffix(b"\r\n") {
+        Some(command) => check_command_line(command, max_len),
+        None => Err(format!(
+            "{:?} is not terminated by a CRLF",
+            String::from_utf8_lossy(line)
+        )),
+    }
+}
+
+/// Check that an encoded command (without its CRLF terminator) can be sent as a single line
+///
+/// Returns a description of the problem if the command contains a CR or LF, as that would
+/// smuggle additional commands onto the connection, or if it exceeds `max_len` (e.g.
+/// [`MAX_COMMAND_LINE_LEN`]) including the CRLF.
+pub(crate) fn check_command_line(
+    command: &[u8],
+    max_len: usize,
+) -> std::result::Result<(), String> {
+    if command.iter().any(|b| *b == b'\r' || *b == b'\n') {
+        return Err(format!(
+            "{:?} contains a line terminator",
+            String::from_utf8_lossy(command)
+        ));
+    }
+    if command.len() + 2 > max_len {
+        return Err(format!(
+            "command is {} octets long, the limit is {} including the CRLF",
+            command.len() + 2,
+            max_len
+        ));
+    }
+    Ok(())
+}
+
 /// Commands specified in [RFC 3977](https://tools.ietf.org/html/rfc3977#appendix-B)
 mod rfc3977;
 
@@ -76,6 +189,18 @@ mod rfc4643;
 #[doc(inline)]
 pub use rfc4643::*;
 
+/// STARTTLS specified in [RFC 4642](https://tools.ietf.org/html/rfc4642)
+mod rfc4642;
+
+#[doc(inline)]
+pub use rfc4642::*;
+
+/// COMPRESS specified in [RFC 8054](https://tools.ietf.org/html/rfc8054)
+mod rfc8054;
+
+#[doc(inline)]
+pub use rfc8054::*;
+
 mod xfeature;
 
 #[doc(inline)]
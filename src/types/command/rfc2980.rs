@@ -1,6 +1,6 @@
 use std::fmt;
 
-use crate::types::prelude::{ArticleNumber, NntpCommand};
+use crate::types::prelude::{ArticleNumber, ArticleRange, NntpCommand};
 
 /// Retrieve a specific header from one or more articles
 #[derive(Clone, Debug)]
@@ -21,6 +21,13 @@ pub enum XHdr {
         /// The high number of the article range
         high: ArticleNumber,
     },
+    /// A single message, an open ended range, a closed range, or the current message
+    Articles {
+        /// The name of the header to retrieve
+        header: String,
+        /// The messages to retrieve the header from
+        range: ArticleRange,
+    },
 }
 
 impl fmt::Display for XHdr {
@@ -28,10 +35,45 @@ impl fmt::Display for XHdr {
         match self {
             XHdr::MessageId { header, id } => write!(f, "XHDR {} {}", header, id),
             XHdr::Range { header, low, high } => write!(f, "XHDR {} {}-{}", header, low, high),
+            XHdr::Articles { header, range } => {
+                write!(f, "XHDR {}", header)?;
+                range.fmt_arg(f)
+            }
+        }
+    }
+}
+
+impl NntpCommand for XHdr {}
+
+/// Retrieve the values of a header that match one or more wildmat patterns
+///
+/// The patterns are sent in order, separated by spaces.
+#[derive(Clone, Debug)]
+pub struct XPat {
+    /// The name of the header to match against
+    pub header: String,
+    /// The articles to search
+    ///
+    /// `XPAT` has no current-article form, so this must not be [`ArticleRange::Current`].
+    /// [`NntpClient::xpat`](crate::client::NntpClient::xpat) rejects it.
+    pub range: ArticleRange,
+    /// The wildmat patterns, at least one must be provided
+    pub patterns: Vec<String>,
+}
+
+impl fmt::Display for XPat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "XPAT {}", self.header)?;
+        self.range.fmt_arg(f)?;
+        for pattern in &self.patterns {
+            write!(f, " {}", pattern)?;
         }
+        Ok(())
     }
 }
 
+impl NntpCommand for XPat {}
+
 /// Get the headers for one or more articles
 #[derive(Copy, Clone, Debug)]
 pub enum XOver {
@@ -44,15 +86,110 @@ pub enum XOver {
     },
     /// The current message
     Current,
+    /// A single article, an open ended range, a closed range, or the current article
+    Articles(ArticleRange),
 }
 
 impl fmt::Display for XOver {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             XOver::Range { low, high } => write!(f, "XOVER {}-{}", low, high),
+            XOver::Articles(range) => {
+                write!(f, "XOVER")?;
+                range.fmt_arg(f)
+            }
             XOver::Current => write!(f, "XOVER"),
         }
     }
 }
 
 impl NntpCommand for XOver {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xhdr() {
+        assert_eq!(
+            XHdr::MessageId {
+                header: "Subject".to_string(),
+                id: "<id@example.com>".to_string()
+            }
+            .to_wire_bytes(),
+            b"XHDR Subject <id@example.com>\r\n"
+        );
+        assert_eq!(
+            XHdr::Range {
+                header: "Subject".to_string(),
+                low: 10,
+                high: 20
+            }
+            .to_wire_bytes(),
+            b"XHDR Subject 10-20\r\n"
+        );
+        assert_eq!(
+            XHdr::Articles {
+                header: "Subject".to_string(),
+                range: ArticleRange::Between { low: 10, high: 20 }
+            }
+            .to_wire_bytes(),
+            b"XHDR Subject 10-20\r\n"
+        );
+
+        let xhdr = |range| XHdr::Articles {
+            header: "Subject".to_string(),
+            range,
+        };
+        assert_eq!(
+            xhdr(ArticleRange::Single(10)).to_wire_bytes(),
+            b"XHDR Subject 10\r\n"
+        );
+        assert_eq!(
+            xhdr(ArticleRange::From(10)).to_wire_bytes(),
+            b"XHDR Subject 10-\r\n"
+        );
+        assert_eq!(
+            xhdr(ArticleRange::Current).to_wire_bytes(),
+            b"XHDR Subject\r\n"
+        );
+    }
+
+    #[test]
+    fn test_xpat() {
+        assert_eq!(
+            XPat {
+                header: "Subject".to_string(),
+                range: ArticleRange::From(10),
+                patterns: vec!["*rust*".to_string(), "*nntp*".to_string()]
+            }
+            .to_wire_bytes(),
+            b"XPAT Subject 10- *rust* *nntp*\r\n"
+        );
+    }
+
+    #[test]
+    fn test_xover() {
+        assert_eq!(
+            XOver::Range { low: 10, high: 20 }.to_wire_bytes(),
+            b"XOVER 10-20\r\n"
+        );
+        assert_eq!(XOver::Current.to_wire_bytes(), b"XOVER\r\n");
+        assert_eq!(
+            XOver::Articles(ArticleRange::Single(10)).to_wire_bytes(),
+            b"XOVER 10\r\n"
+        );
+        assert_eq!(
+            XOver::Articles(ArticleRange::From(10)).to_wire_bytes(),
+            b"XOVER 10-\r\n"
+        );
+        assert_eq!(
+            XOver::Articles(ArticleRange::Between { low: 10, high: 20 }).to_wire_bytes(),
+            b"XOVER 10-20\r\n"
+        );
+        assert_eq!(
+            XOver::Articles(ArticleRange::Current).to_wire_bytes(),
+            b"XOVER\r\n"
+        );
+    }
+}
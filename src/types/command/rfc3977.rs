@@ -1,6 +1,6 @@
 use std::fmt;
 
-use crate::types::prelude::{ArticleNumber, NntpCommand};
+use crate::types::prelude::{ArticleNumber, ArticleRange, NntpCommand};
 
 /// Retrieve an article's header and body
 #[derive(Clone, Debug)]
@@ -23,7 +23,11 @@ impl fmt::Display for Article {
     }
 }
 
-impl NntpCommand for Article {}
+impl NntpCommand for Article {
+    fn required_capability(&self) -> Option<&str> {
+        Some("READER")
+    }
+}
 
 /// Retrieve the body for an Article
 #[derive(Clone, Debug)]
@@ -46,7 +50,11 @@ impl fmt::Display for Body {
     }
 }
 
-impl NntpCommand for Body {}
+impl NntpCommand for Body {
+    fn required_capability(&self) -> Option<&str> {
+        Some("READER")
+    }
+}
 
 /// Get the capabilities provided by the server
 #[derive(Clone, Copy, Debug)]
@@ -62,7 +70,7 @@ impl NntpCommand for Capabilities {}
 
 /// Get the server time
 #[derive(Clone, Copy, Debug)]
-struct Date;
+pub struct Date;
 
 impl fmt::Display for Date {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -70,7 +78,11 @@ impl fmt::Display for Date {
     }
 }
 
-impl NntpCommand for Date {}
+impl NntpCommand for Date {
+    fn required_capability(&self) -> Option<&str> {
+        Some("READER")
+    }
+}
 
 /// Select a group
 #[derive(Clone, Debug)]
@@ -82,7 +94,11 @@ impl fmt::Display for Group {
     }
 }
 
-impl NntpCommand for Group {}
+impl NntpCommand for Group {
+    fn required_capability(&self) -> Option<&str> {
+        Some("READER")
+    }
+}
 
 /// Retrieve a specific header from one or more articles
 #[derive(Clone, Debug)]
@@ -108,6 +124,13 @@ pub enum Hdr {
         /// The name of the header
         field: String,
     },
+    /// A single article, an open ended range, a closed range, or the current article
+    Articles {
+        /// The name of the header
+        field: String,
+        /// The articles to retrieve the header from
+        range: ArticleRange,
+    },
 }
 
 impl fmt::Display for Hdr {
@@ -116,11 +139,19 @@ impl fmt::Display for Hdr {
             Hdr::MessageId { field, id } => write!(f, "HDR {} {}", field, id),
             Hdr::Range { field, low, high } => write!(f, "HDR {} {}-{}", field, low, high),
             Hdr::Current { field } => write!(f, "HDR {}", field),
+            Hdr::Articles { field, range } => {
+                write!(f, "HDR {}", field)?;
+                range.fmt_arg(f)
+            }
         }
     }
 }
 
-impl NntpCommand for Hdr {}
+impl NntpCommand for Hdr {
+    fn required_capability(&self) -> Option<&str> {
+        Some("HDR")
+    }
+}
 
 /// Retrieve the headers for an article
 #[derive(Clone, Debug)]
@@ -143,10 +174,15 @@ impl fmt::Display for Head {
     }
 }
 
-impl NntpCommand for Head {}
+impl NntpCommand for Head {
+    fn required_capability(&self) -> Option<&str> {
+        Some("READER")
+    }
+}
 
 /// Retrieve help text about the servers capabilities
-struct Help;
+#[derive(Clone, Copy, Debug)]
+pub struct Help;
 
 impl fmt::Display for Help {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -156,8 +192,15 @@ impl fmt::Display for Help {
 
 impl NntpCommand for Help {}
 
-/// Inform the server that you have an article for upload
-struct IHave(String);
+/// Inform the server that you have an article for transfer
+///
+/// IHAVE is a two part exchange like [`Post`]. After the server responds with
+/// [`IHaveSendArticle`](crate::types::prelude::Kind::IHaveSendArticle) (code 335), the article is
+/// sent as a data block.
+///
+/// For more information see [RFC 3977 6.3.2](https://tools.ietf.org/html/rfc3977#section-6.3.2)
+#[derive(Clone, Debug)]
+pub struct IHave(pub String);
 
 impl fmt::Display for IHave {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -165,10 +208,15 @@ impl fmt::Display for IHave {
     }
 }
 
-impl NntpCommand for IHave {}
+impl NntpCommand for IHave {
+    fn required_capability(&self) -> Option<&str> {
+        Some("IHAVE")
+    }
+}
 
 /// Attempt to set the current article to the previous article number
-struct Last;
+#[derive(Clone, Copy, Debug)]
+pub struct Last;
 
 impl fmt::Display for Last {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -176,7 +224,11 @@ impl fmt::Display for Last {
     }
 }
 
-impl NntpCommand for Last {}
+impl NntpCommand for Last {
+    fn required_capability(&self) -> Option<&str> {
+        Some("READER")
+    }
+}
 
 /// Retrieve a list of information from the server
 ///
@@ -208,6 +260,14 @@ pub enum List {
     ///
     /// [RFC 3977 8.4](https://tools.ietf.org/html/rfc3977#section-8.4)
     OverviewFmt,
+    /// Return the headers and metadata items that may be queried via HDR
+    ///
+    /// [RFC 3977 8.6](https://tools.ietf.org/html/rfc3977#section-8.6)
+    Headers,
+    /// Any other keyword (e.g. `MOTD`, `HEADERS`, or a vendor extension) and its arguments
+    ///
+    /// The keyword and arguments are sent as-is, separated by spaces
+    Other { keyword: String, args: Vec<String> },
 }
 
 impl fmt::Display for List {
@@ -232,15 +292,60 @@ impl fmt::Display for List {
                 print_wildmat(f, wildmat.as_ref())
             }
             List::Newsgroups { wildmat } => {
-                write!(f, " ACTIVE TIMES")?;
+                write!(f, " NEWSGROUPS")?;
                 print_wildmat(f, wildmat.as_ref())
             }
             List::DistribPats => write!(f, " DISTRIB.PATS"),
+            List::Headers => write!(f, " HEADERS"),
+            List::Other { keyword, args } => {
+                write!(f, " {}", keyword)?;
+                args.iter().try_for_each(|arg| write!(f, " {}", arg))
+            }
         }
     }
 }
 
-impl NntpCommand for List {}
+impl NntpCommand for List {
+    fn required_capability(&self) -> Option<&str> {
+        Some("LIST")
+    }
+}
+
+/// Select a group and list the numbers of the articles within it
+///
+/// The `range` is only sent alongside a `group` as the command does not accept a range on
+/// its own. If neither are provided the currently selected group is listed.
+///
+/// For more information see [RFC 3977 6.1.2](https://tools.ietf.org/html/rfc3977#section-6.1.2)
+#[derive(Clone, Debug)]
+pub struct ListGroup {
+    /// The group to select
+    pub group: Option<String>,
+    /// The articles to list
+    ///
+    /// `LISTGROUP` has no current-article form, so this must not be [`ArticleRange::Current`].
+    /// [`NntpClient::listgroup`](crate::client::NntpClient::listgroup) rejects it.
+    pub range: Option<ArticleRange>,
+}
+
+impl fmt::Display for ListGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "LISTGROUP")?;
+        if let Some(group) = &self.group {
+            write!(f, " {}", group)?;
+            if let Some(range) = &self.range {
+                range.fmt_arg(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl NntpCommand for ListGroup {
+    fn required_capability(&self) -> Option<&str> {
+        Some("READER")
+    }
+}
 
 /// Enable reader mode on a mode switching server
 #[derive(Clone, Copy, Debug)]
@@ -252,11 +357,103 @@ impl fmt::Display for ModeReader {
     }
 }
 
-impl NntpCommand for ModeReader {}
+impl NntpCommand for ModeReader {
+    fn required_capability(&self) -> Option<&str> {
+        Some("MODE-READER")
+    }
+}
 
-// TODO(commands) implement NEWGROUPS
+/// Retrieve the newsgroups created since a date and time
+///
+/// The `date` must be formatted as `yymmdd` or `yyyymmdd` and the `time` as `hhmmss`.
+///
+/// ## The `GMT` argument
+///
+/// Per [RFC 3977 7.3](https://tools.ietf.org/html/rfc3977#section-7.3), the date and time are in
+/// the server's local time unless the `GMT` argument is provided, in which case they are in UTC.
+/// Although the argument is part of the standard, a few servers reject it while others ignore the
+/// date entirely without it. [`NewGroups::new`] includes it for maximum compatibility;
+/// set `gmt` to `false` to omit it when talking to a server that rejects it.
+#[derive(Clone, Debug)]
+pub struct NewGroups {
+    /// The date formatted as `yymmdd` or `yyyymmdd`
+    pub date: String,
+    /// The time formatted as `hhmmss`
+    pub time: String,
+    /// Whether to send the `GMT` argument
+    pub gmt: bool,
+}
+
+impl NewGroups {
+    /// Create a NEWGROUPS command that includes the `GMT` argument
+    pub fn new(date: impl AsRef<str>, time: impl AsRef<str>) -> Self {
+        Self {
+            date: date.as_ref().to_string(),
+            time: time.as_ref().to_string(),
+            gmt: true,
+        }
+    }
+}
+
+impl fmt::Display for NewGroups {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "NEWGROUPS {} {}", self.date, self.time)?;
+        if self.gmt {
+            write!(f, " GMT")?;
+        }
+        Ok(())
+    }
+}
 
-// TODO(commands) implement NEWNEWS
+impl NntpCommand for NewGroups {
+    fn required_capability(&self) -> Option<&str> {
+        Some("READER")
+    }
+}
+
+/// Retrieve the message IDs of articles posted to matching groups since a date and time
+///
+/// The `date` and `time` are formatted as in [`NewGroups`], and the same interoperability
+/// caveats apply to the `GMT` argument.
+#[derive(Clone, Debug)]
+pub struct NewNews {
+    /// A wildmat matching the groups to search
+    pub wildmat: String,
+    /// The date formatted as `yymmdd` or `yyyymmdd`
+    pub date: String,
+    /// The time formatted as `hhmmss`
+    pub time: String,
+    /// Whether to send the `GMT` argument
+    pub gmt: bool,
+}
+
+impl NewNews {
+    /// Create a NEWNEWS command that includes the `GMT` argument
+    pub fn new(wildmat: impl AsRef<str>, date: impl AsRef<str>, time: impl AsRef<str>) -> Self {
+        Self {
+            wildmat: wildmat.as_ref().to_string(),
+            date: date.as_ref().to_string(),
+            time: time.as_ref().to_string(),
+            gmt: true,
+        }
+    }
+}
+
+impl fmt::Display for NewNews {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "NEWNEWS {} {} {}", self.wildmat, self.date, self.time)?;
+        if self.gmt {
+            write!(f, " GMT")?;
+        }
+        Ok(())
+    }
+}
+
+impl NntpCommand for NewNews {
+    fn required_capability(&self) -> Option<&str> {
+        Some("NEWNEWS")
+    }
+}
 
 /// Attempt to set the current article to the next article number
 #[derive(Clone, Copy, Debug)]
@@ -268,7 +465,11 @@ impl fmt::Display for Next {
     }
 }
 
-impl NntpCommand for Next {}
+impl NntpCommand for Next {
+    fn required_capability(&self) -> Option<&str> {
+        Some("READER")
+    }
+}
 
 /// Retrieve all of the fields (e.g. headers/metadata) for one or more articles
 #[derive(Clone, Debug)]
@@ -284,6 +485,8 @@ pub enum Over {
     },
     /// The current article
     Current,
+    /// A single article, an open ended range, a closed range, or the current article
+    Articles(ArticleRange),
 }
 
 impl fmt::Display for Over {
@@ -291,43 +494,42 @@ impl fmt::Display for Over {
         match self {
             Over::MessageId(id) => write!(f, "OVER {}", id),
             Over::Range { low, high } => write!(f, "OVER {}-{}", low, high),
+            Over::Articles(range) => {
+                write!(f, "OVER")?;
+                range.fmt_arg(f)
+            }
             Over::Current => write!(f, "OVER"),
         }
     }
 }
 
-impl NntpCommand for Over {}
+impl NntpCommand for Over {
+    fn required_capability(&self) -> Option<&str> {
+        Some("OVER")
+    }
+}
 
-// TODO(commands) complete POST implementation
-/*
 /// Post an article to the news server
 ///
-/// POSTING is a two part exchange. The [`Initial`](Post::Initial) variant is used
-/// to determine if the server will accept the article while the [`Article`](Post::Article) is
-/// used to send the data.
-///
+/// POST is a two part exchange. After the server responds with
+/// [`PostSendArticle`](crate::types::prelude::Kind::PostSendArticle) (code 340), the article is
+/// sent as a data block (see [`NntpConnection::send_data_blocks`](crate::raw::connection::NntpConnection::send_data_blocks)).
 ///
 /// For more information see [RFC 3977 6.3.1](https://tools.ietf.org/html/rfc3977#section-6.3.1)
-#[derive(Clone, Debug)]
-enum Post<'a> {
-    Initial,
-    /// The article body NOT including the terminating sequence
-    Article(&'a [u8])
-}
-
-impl NntpCommand for Post<'_> {}
+#[derive(Clone, Copy, Debug)]
+pub struct Post;
 
-impl fmt::Display for Post<'_> {
+impl fmt::Display for Post {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Post::Initial => write!(f, "POST"),
-            Post::Article(body) => {
-                unimplemented!()
-            },
-        }
+        write!(f, "POST")
+    }
+}
+
+impl NntpCommand for Post {
+    fn required_capability(&self) -> Option<&str> {
+        Some("POST")
     }
 }
-*/
 
 /// Close the connection
 #[derive(Clone, Copy, Debug)]
@@ -362,4 +564,250 @@ impl fmt::Display for Stat {
     }
 }
 
-impl NntpCommand for Stat {}
+impl NntpCommand for Stat {
+    fn required_capability(&self) -> Option<&str> {
+        Some("READER")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_article() {
+        assert_eq!(
+            Article::MessageId("<id@example.com>".to_string()).to_wire_bytes(),
+            b"ARTICLE <id@example.com>\r\n"
+        );
+        assert_eq!(Article::Number(42).to_wire_bytes(), b"ARTICLE 42\r\n");
+        assert_eq!(Article::Current.to_wire_bytes(), b"ARTICLE\r\n");
+    }
+
+    #[test]
+    fn test_body() {
+        assert_eq!(
+            Body::MessageId("<id@example.com>".to_string()).to_wire_bytes(),
+            b"BODY <id@example.com>\r\n"
+        );
+        assert_eq!(Body::Number(42).to_wire_bytes(), b"BODY 42\r\n");
+        assert_eq!(Body::Current.to_wire_bytes(), b"BODY\r\n");
+    }
+
+    #[test]
+    fn test_head() {
+        assert_eq!(
+            Head::MessageId("<id@example.com>".to_string()).to_wire_bytes(),
+            b"HEAD <id@example.com>\r\n"
+        );
+        assert_eq!(Head::Number(42).to_wire_bytes(), b"HEAD 42\r\n");
+        assert_eq!(Head::Current.to_wire_bytes(), b"HEAD\r\n");
+    }
+
+    #[test]
+    fn test_stat() {
+        assert_eq!(
+            Stat::MessageId("<id@example.com>".to_string()).to_wire_bytes(),
+            b"STAT <id@example.com>\r\n"
+        );
+        assert_eq!(Stat::Number(42).to_wire_bytes(), b"STAT 42\r\n");
+        assert_eq!(Stat::Current.to_wire_bytes(), b"STAT\r\n");
+    }
+
+    #[test]
+    fn test_simple_commands() {
+        assert_eq!(Capabilities.to_wire_bytes(), b"CAPABILITIES\r\n");
+        assert_eq!(Date.to_wire_bytes(), b"DATE\r\n");
+        assert_eq!(Help.to_wire_bytes(), b"HELP\r\n");
+        assert_eq!(Last.to_wire_bytes(), b"LAST\r\n");
+        assert_eq!(ModeReader.to_wire_bytes(), b"MODE READER\r\n");
+        assert_eq!(Next.to_wire_bytes(), b"NEXT\r\n");
+        assert_eq!(Post.to_wire_bytes(), b"POST\r\n");
+        assert_eq!(Quit.to_wire_bytes(), b"QUIT\r\n");
+    }
+
+    #[test]
+    fn test_listgroup() {
+        let listgroup = |group: Option<&str>, range: Option<ArticleRange>| ListGroup {
+            group: group.map(ToString::to_string),
+            range,
+        };
+
+        assert_eq!(listgroup(None, None).to_wire_bytes(), b"LISTGROUP\r\n");
+        assert_eq!(
+            listgroup(Some("misc.test"), None).to_wire_bytes(),
+            b"LISTGROUP misc.test\r\n"
+        );
+        assert_eq!(
+            listgroup(Some("misc.test"), Some((10..=20).into())).to_wire_bytes(),
+            b"LISTGROUP misc.test 10-20\r\n"
+        );
+    }
+
+    #[test]
+    fn test_group() {
+        assert_eq!(
+            Group("comp.lang.rust".to_string()).to_wire_bytes(),
+            b"GROUP comp.lang.rust\r\n"
+        );
+    }
+
+    #[test]
+    fn test_hdr() {
+        assert_eq!(
+            Hdr::MessageId {
+                field: "Subject".to_string(),
+                id: "<id@example.com>".to_string()
+            }
+            .to_wire_bytes(),
+            b"HDR Subject <id@example.com>\r\n"
+        );
+        assert_eq!(
+            Hdr::Range {
+                field: "Subject".to_string(),
+                low: 10,
+                high: 20
+            }
+            .to_wire_bytes(),
+            b"HDR Subject 10-20\r\n"
+        );
+        assert_eq!(
+            Hdr::Current {
+                field: "Subject".to_string()
+            }
+            .to_wire_bytes(),
+            b"HDR Subject\r\n"
+        );
+        assert_eq!(
+            Hdr::Articles {
+                field: "Subject".to_string(),
+                range: ArticleRange::From(10)
+            }
+            .to_wire_bytes(),
+            b"HDR Subject 10-\r\n"
+        );
+
+        let hdr = |range| Hdr::Articles {
+            field: "Subject".to_string(),
+            range,
+        };
+        assert_eq!(
+            hdr(ArticleRange::Single(10)).to_wire_bytes(),
+            b"HDR Subject 10\r\n"
+        );
+        assert_eq!(
+            hdr(ArticleRange::Between { low: 10, high: 20 }).to_wire_bytes(),
+            b"HDR Subject 10-20\r\n"
+        );
+        assert_eq!(
+            hdr(ArticleRange::Current).to_wire_bytes(),
+            b"HDR Subject\r\n"
+        );
+    }
+
+    #[test]
+    fn test_ihave() {
+        assert_eq!(
+            IHave("<id@example.com>".to_string()).to_wire_bytes(),
+            b"IHAVE <id@example.com>\r\n"
+        );
+    }
+
+    #[test]
+    fn test_list() {
+        assert_eq!(
+            List::Active { wildmat: None }.to_wire_bytes(),
+            b"LIST ACTIVE\r\n"
+        );
+        assert_eq!(
+            List::Active {
+                wildmat: Some("comp.*".to_string())
+            }
+            .to_wire_bytes(),
+            b"LIST ACTIVE comp.*\r\n"
+        );
+        assert_eq!(
+            List::ActiveTimes { wildmat: None }.to_wire_bytes(),
+            b"LIST ACTIVE TIMES\r\n"
+        );
+        assert_eq!(
+            List::Newsgroups {
+                wildmat: Some("comp.*".to_string())
+            }
+            .to_wire_bytes(),
+            b"LIST NEWSGROUPS comp.*\r\n"
+        );
+        assert_eq!(List::DistribPats.to_wire_bytes(), b"LIST DISTRIB.PATS\r\n");
+        assert_eq!(List::OverviewFmt.to_wire_bytes(), b"LIST OVERVIEW.FMT\r\n");
+        assert_eq!(List::Headers.to_wire_bytes(), b"LIST HEADERS\r\n");
+        assert_eq!(
+            List::Other {
+                keyword: "HEADERS".to_string(),
+                args: vec!["MSGID".to_string()],
+            }
+            .to_wire_bytes(),
+            b"LIST HEADERS MSGID\r\n"
+        );
+    }
+
+    #[test]
+    fn test_newgroups() {
+        let mut cmd = NewGroups::new("20201015", "123456");
+        assert_eq!(cmd.to_wire_bytes(), b"NEWGROUPS 20201015 123456 GMT\r\n");
+        cmd.gmt = false;
+        assert_eq!(cmd.to_wire_bytes(), b"NEWGROUPS 20201015 123456\r\n");
+    }
+
+    #[test]
+    fn test_newnews() {
+        let mut cmd = NewNews::new("comp.lang.*", "201015", "000000");
+        assert_eq!(
+            cmd.to_wire_bytes(),
+            b"NEWNEWS comp.lang.* 201015 000000 GMT\r\n"
+        );
+        cmd.gmt = false;
+        assert_eq!(
+            cmd.to_wire_bytes(),
+            b"NEWNEWS comp.lang.* 201015 000000\r\n"
+        );
+    }
+
+    #[test]
+    fn test_over() {
+        assert_eq!(
+            Over::MessageId("<id@example.com>".to_string()).to_wire_bytes(),
+            b"OVER <id@example.com>\r\n"
+        );
+        assert_eq!(
+            Over::Range { low: 10, high: 20 }.to_wire_bytes(),
+            b"OVER 10-20\r\n"
+        );
+        assert_eq!(Over::Current.to_wire_bytes(), b"OVER\r\n");
+        assert_eq!(
+            Over::Articles(ArticleRange::From(10)).to_wire_bytes(),
+            b"OVER 10-\r\n"
+        );
+        assert_eq!(
+            Over::Articles(ArticleRange::Single(10)).to_wire_bytes(),
+            b"OVER 10\r\n"
+        );
+        assert_eq!(
+            Over::Articles(ArticleRange::Between { low: 10, high: 20 }).to_wire_bytes(),
+            b"OVER 10-20\r\n"
+        );
+        assert_eq!(
+            Over::Articles(ArticleRange::Current).to_wire_bytes(),
+            b"OVER\r\n"
+        );
+    }
+
+    #[test]
+    fn test_required_capability() {
+        assert_eq!(Capabilities.required_capability(), None);
+        assert_eq!(Quit.required_capability(), None);
+        assert_eq!(Article::Current.required_capability(), Some("READER"));
+        assert_eq!(Over::Current.required_capability(), Some("OVER"));
+        assert_eq!(Post.required_capability(), Some("POST"));
+        assert_eq!(List::OverviewFmt.required_capability(), Some("LIST"));
+    }
+}
@@ -0,0 +1,34 @@
+use std::fmt;
+
+use crate::types::prelude::NntpCommand;
+
+/// Begin a TLS negotiation on a plain text connection
+///
+/// Once the server responds with
+/// [`ContinueWithTls`](crate::types::prelude::Kind::ContinueWithTls) (code 382) the TLS
+/// handshake is performed over the existing socket
+/// (see [`NntpConnection::starttls`](crate::raw::connection::NntpConnection::starttls)).
+#[derive(Clone, Copy, Debug)]
+pub struct StartTls;
+
+impl fmt::Display for StartTls {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "STARTTLS")
+    }
+}
+
+impl NntpCommand for StartTls {
+    fn required_capability(&self) -> Option<&str> {
+        Some("STARTTLS")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_starttls() {
+        assert_eq!(StartTls.to_wire_bytes(), b"STARTTLS\r\n");
+    }
+}
@@ -1,18 +1,26 @@
 use std::fmt;
 
-use super::NntpCommand;
+use super::{NntpCommand, MAX_COMMAND_LINE_LEN, MAX_SASL_LINE_LEN};
 
 /// Authenticate via `AUTHINFO` as specified in [RFC 4643](https://tools.ietf.org/html/rfc4643)
-///
-/// # Limitations
-///
-/// * SASL is not currently implemented
 #[derive(Clone, Debug)]
 pub enum AuthInfo {
     /// Username
     User(String),
     /// Password
     Pass(String),
+    /// Begin a [SASL exchange](https://tools.ietf.org/html/rfc4643#section-2.4)
+    Sasl {
+        /// The name of the SASL mechanism (e.g. `SCRAM-SHA-256`)
+        mechanism: String,
+        /// The base64 encoded initial response, if any
+        initial_response: Option<String>,
+    },
+    /// Authenticate with an external program via the legacy
+    /// [`AUTHINFO GENERIC`](https://tools.ietf.org/html/rfc2980#section-3.1.3) extension
+    ///
+    /// The string is sent as is and holds the program name followed by its arguments.
+    Generic(String),
 }
 
 impl fmt::Display for AuthInfo {
@@ -20,8 +28,124 @@ impl fmt::Display for AuthInfo {
         match self {
             AuthInfo::User(username) => write!(f, "AUTHINFO USER {}", username),
             AuthInfo::Pass(password) => write!(f, "AUTHINFO PASS {}", password),
+            AuthInfo::Sasl {
+                mechanism,
+                initial_response,
+            } => {
+                write!(f, "AUTHINFO SASL {}", mechanism)?;
+                if let Some(initial_response) = initial_response {
+                    write!(f, " {}", initial_response)?;
+                }
+                Ok(())
+            }
+            AuthInfo::Generic(args) => write!(f, "AUTHINFO GENERIC {}", args),
+        }
+    }
+}
+
+impl NntpCommand for AuthInfo {
+    fn required_capability(&self) -> Option<&str> {
+        match self {
+            // Predates CAPABILITIES and is not advertised by servers that support it
+            AuthInfo::Generic(_) => None,
+            _ => Some("AUTHINFO"),
+        }
+    }
+
+    fn max_line_len(&self) -> usize {
+        match self {
+            AuthInfo::Sasl { .. } => MAX_SASL_LINE_LEN,
+            _ => MAX_COMMAND_LINE_LEN,
         }
     }
 }
 
-impl NntpCommand for AuthInfo {}
+/// A base64 encoded response to a SASL challenge
+///
+/// This is sent in reply to a `383` response during an [`AuthInfo::Sasl`] exchange
+#[derive(Clone, Debug)]
+pub struct SaslResponse(pub String);
+
+impl fmt::Display for SaslResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl NntpCommand for SaslResponse {
+    fn max_line_len(&self) -> usize {
+        MAX_SASL_LINE_LEN
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_authinfo() {
+        assert_eq!(
+            AuthInfo::User("alice".to_string()).to_wire_bytes(),
+            b"AUTHINFO USER alice\r\n"
+        );
+        assert_eq!(
+            AuthInfo::Pass("hunter2".to_string()).to_wire_bytes(),
+            b"AUTHINFO PASS hunter2\r\n"
+        );
+        assert_eq!(
+            AuthInfo::Sasl {
+                mechanism: "SCRAM-SHA-256".to_string(),
+                initial_response: None
+            }
+            .to_wire_bytes(),
+            b"AUTHINFO SASL SCRAM-SHA-256\r\n"
+        );
+        assert_eq!(
+            AuthInfo::Sasl {
+                mechanism: "SCRAM-SHA-256".to_string(),
+                initial_response: Some("biws".to_string())
+            }
+            .to_wire_bytes(),
+            b"AUTHINFO SASL SCRAM-SHA-256 biws\r\n"
+        );
+        assert_eq!(
+            AuthInfo::Generic("authsrv -u alice".to_string()).to_wire_bytes(),
+            b"AUTHINFO GENERIC authsrv -u alice\r\n"
+        );
+        assert!(AuthInfo::Generic("authsrv".to_string())
+            .required_capability()
+            .is_none());
+    }
+
+    #[test]
+    fn test_sasl_response() {
+        assert_eq!(SaslResponse("=".to_string()).to_wire_bytes(), b"=\r\n");
+    }
+
+    #[test]
+    fn test_sasl_line_len() {
+        use crate::types::command::{check_wire_line, raw_max_line_len};
+
+        let initial_response = "a".repeat(MAX_SASL_LINE_LEN - 22);
+        let sasl = AuthInfo::Sasl {
+            mechanism: "PLAIN".to_string(),
+            initial_response: Some(initial_response.clone()),
+        };
+        assert!(check_wire_line(&sasl.to_wire_bytes(), sasl.max_line_len()).is_ok());
+        assert_eq!(
+            raw_max_line_len(format!("authinfo sasl PLAIN {}", initial_response).as_bytes()),
+            MAX_SASL_LINE_LEN
+        );
+
+        let resp = SaslResponse("a".repeat(MAX_SASL_LINE_LEN - 1));
+        assert!(check_wire_line(&resp.to_wire_bytes(), resp.max_line_len()).is_err());
+
+        // Other commands keep the RFC 3977 limit
+        let user = AuthInfo::User(initial_response);
+        assert!(check_wire_line(&user.to_wire_bytes(), user.max_line_len()).is_err());
+        assert_eq!(
+            raw_max_line_len(b"AUTHINFO USER alice"),
+            MAX_COMMAND_LINE_LEN
+        );
+    }
+}
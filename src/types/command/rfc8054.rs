@@ -0,0 +1,34 @@
+use std::fmt;
+
+use crate::types::prelude::NntpCommand;
+
+/// Compress the rest of the session with raw deflate
+///
+/// Once the server responds with
+/// [`CompressionActive`](crate::types::prelude::Kind::CompressionActive) (code 206) all data in
+/// both directions is compressed
+/// (see [`NntpConnection::compress_deflate`](crate::raw::connection::NntpConnection::compress_deflate)).
+#[derive(Clone, Copy, Debug)]
+pub struct CompressDeflate;
+
+impl fmt::Display for CompressDeflate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "COMPRESS DEFLATE")
+    }
+}
+
+impl NntpCommand for CompressDeflate {
+    fn required_capability(&self) -> Option<&str> {
+        Some("COMPRESS")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compress_deflate() {
+        assert_eq!(CompressDeflate.to_wire_bytes(), b"COMPRESS DEFLATE\r\n");
+    }
+}
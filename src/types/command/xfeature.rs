@@ -13,3 +13,16 @@ impl fmt::Display for XFeatureCompress {
 }
 
 impl NntpCommand for XFeatureCompress {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xfeature_compress() {
+        assert_eq!(
+            XFeatureCompress.to_wire_bytes(),
+            b"XFEATURE COMPRESS GZIP TERMINATOR\r\n"
+        );
+    }
+}
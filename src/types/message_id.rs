@@ -0,0 +1,164 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+use crate::types::ArticleId;
+
+/// The maximum length of a message-id in octets, including the angle brackets
+///
+/// Per [RFC 3977](https://tools.ietf.org/html/rfc3977#section-3.6)
+pub const MAX_MESSAGE_ID_LEN: usize = 250;
+
+/// A validated message-id (e.g. `<foo@bar>`)
+///
+/// Per [RFC 3977](https://tools.ietf.org/html/rfc3977#section-3.6) a message-id must begin with
+/// `<`, end with `>`, not contain `>` elsewhere, consist of printable US-ASCII characters, and be
+/// at most [`MAX_MESSAGE_ID_LEN`] octets long.
+///
+/// Constructing a `MessageId` up front catches mistakes such as forgetting the angle brackets
+/// before a command is sent to the server (which would respond with a `501`).
+///
+/// ```
+/// use brokaw::types::{ArticleId, MessageId};
+///
+/// let id = MessageId::new("<foo@bar>").unwrap();
+/// assert_eq!(id.to_string(), "<foo@bar>");
+/// assert_eq!(ArticleId::from(id), ArticleId::MessageId("<foo@bar>".to_string()));
+///
+/// assert!(MessageId::new("foo@bar").is_err());
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct MessageId(String);
+
+impl MessageId {
+    /// Validate a message-id
+    ///
+    /// Returns [`Error::InvalidMessageId`] if the ID is malformed.
+    pub fn new(id: impl AsRef<str>) -> Result<Self> {
+        let id = id.as_ref();
+        let invalid = |reason: &str| Err(Error::InvalidMessageId(format!("{:?} {}", id, reason)));
+
+        if id.len() > MAX_MESSAGE_ID_LEN {
+            return invalid(&format!(
+                "exceeds the maximum length of {} octets",
+                MAX_MESSAGE_ID_LEN
+            ));
+        }
+
+        let inner = match id.strip_prefix('<').and_then(|s| s.strip_suffix('>')) {
+            Some(inner) => inner,
+            None => return invalid("is not enclosed in angle brackets"),
+        };
+
+        if inner.is_empty() {
+            invalid("is empty")
+        } else if !inner.bytes().all(|b| b.is_ascii_graphic() && b != b'>') {
+            invalid("contains whitespace, control, non-ASCII, or `>` characters")
+        } else {
+            Ok(Self(id.to_string()))
+        }
+    }
+
+    /// The message-id, including the angle brackets
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Consume the message-id, returning the underlying string
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+impl fmt::Display for MessageId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for MessageId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for MessageId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::new(s)
+    }
+}
+
+impl TryFrom<&str> for MessageId {
+    type Error = Error;
+
+    fn try_from(id: &str) -> Result<Self> {
+        Self::new(id)
+    }
+}
+
+impl TryFrom<String> for MessageId {
+    type Error = Error;
+
+    fn try_from(id: String) -> Result<Self> {
+        Self::new(id)
+    }
+}
+
+impl From<MessageId> for ArticleId {
+    fn from(id: MessageId) -> Self {
+        ArticleId::MessageId(id.0)
+    }
+}
+
+impl From<&MessageId> for ArticleId {
+    fn from(id: &MessageId) -> Self {
+        ArticleId::MessageId(id.0.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid() {
+        for id in &[
+            "<foo@bar>",
+            "<45223423@example.com>",
+            "<a>",
+            "<\"quoted\"@x.y>",
+        ] {
+            assert_eq!(MessageId::new(id).unwrap().as_str(), *id);
+        }
+
+        let longest = format!("<{}>", "a".repeat(MAX_MESSAGE_ID_LEN - 2));
+        assert!(MessageId::new(&longest).is_ok());
+    }
+
+    #[test]
+    fn test_invalid() {
+        let too_long = format!("<{}>", "a".repeat(MAX_MESSAGE_ID_LEN - 1));
+        for id in &[
+            "",
+            "<>",
+            "foo@bar",
+            "<foo@bar",
+            "foo@bar>",
+            "<foo bar>",
+            "<foo@bar>\r\n",
+            "<foo\r\n@bar>",
+            "<foo>bar>",
+            "<föö@bar>",
+            too_long.as_str(),
+        ] {
+            assert!(
+                matches!(MessageId::new(id), Err(Error::InvalidMessageId(_))),
+                "{:?}",
+                id
+            );
+        }
+    }
+}
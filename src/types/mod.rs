@@ -6,9 +6,9 @@
 /// Brokaw provides implementations for most of the commands
 /// in [RFC 3977](https://tools.ietf.org/html/rfc3977).
 ///
-/// One notable exception is the [`LISTGROUP`](https://tools.ietf.org/html/rfc3977#section-6.1.2)
-/// command. This command is left unimplemented as it does not adhere to the response standards
-/// defined in the RFC.
+/// Note that [`LISTGROUP`](command::ListGroup) responds with a multi-line `211` which the
+/// generic [`NntpClient::command`](crate::client::NntpClient::command) reads as a single line,
+/// use [`NntpClient::listgroup`](crate::client::NntpClient::listgroup) instead.
 pub mod command;
 
 /// Typed NNTP responses for individual commands
@@ -17,6 +17,18 @@ pub mod response;
 /// NNTP response codes
 pub mod response_code;
 
+mod article_id;
+mod article_range;
+mod message_id;
+mod news_date_time;
+mod wildmat;
+
+pub use article_id::ArticleId;
+pub use article_range::ArticleRange;
+pub use message_id::{MessageId, MAX_MESSAGE_ID_LEN};
+pub use news_date_time::{parse_news_date, NewsDateTime};
+pub use wildmat::Wildmat;
+
 /// The number of an article relative to a specific Newsgroup
 ///
 /// Per [RFC 3977](https://tools.ietf.org/html/rfc3977#section-6) article numbers should fit within
@@ -30,7 +42,7 @@ pub mod prelude {
     pub use super::command::NntpCommand;
     pub use super::response::*;
     pub use super::response_code::*;
-    pub use super::ArticleNumber;
+    pub use super::{ArticleId, ArticleNumber, ArticleRange, MessageId, NewsDateTime, Wildmat};
 }
 
 #[doc(inline)]
@@ -0,0 +1,414 @@
+use crate::error::{Error, Result};
+use crate::types::command as cmd;
+use crate::types::response::ServerDate;
+
+/// A date and time for commands such as [`NEWGROUPS`](cmd::NewGroups) and
+/// [`NEWNEWS`](cmd::NewNews)
+///
+/// Per [RFC 3977](https://tools.ietf.org/html/rfc3977#section-7.3) the date is sent as
+/// `yyyymmdd` and the time as `hhmmss`. Some older servers only accept the two digit year form
+/// (`yymmdd`), which can be selected with [`two_digit_year`](Self::two_digit_year).
+///
+/// The date and time are sent in UTC (with the `GMT` argument) unless [`gmt`](Self::gmt) is
+/// disabled, in which case the server interprets them as its local time.
+///
+/// ```
+/// use brokaw::types::NewsDateTime;
+///
+/// let since = NewsDateTime::new(2020, 10, 15, 4, 38, 40).unwrap();
+/// assert_eq!(since.date(), "20201015");
+/// assert_eq!(since.time(), "043840");
+/// assert_eq!(since.two_digit_year(true).date(), "201015");
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct NewsDateTime {
+    year: u16,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8,
+    two_digit_year: bool,
+    gmt: bool,
+}
+
+impl NewsDateTime {
+    /// Create a date and time (in UTC)
+    ///
+    /// Returns `None` if any of the values are out of range.
+    pub fn new(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> Option<Self> {
+        let valid = year <= 9999
+            && (1..=12).contains(&month)
+            && (1..=31).contains(&day)
+            && hour <= 23
+            && minute <= 59
+            && second <= 60;
+
+        if valid {
+            Some(Self {
+                year,
+                month,
+                day,
+                hour,
+                minute,
+                second,
+                two_digit_year: false,
+                gmt: true,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Send the year as two digits (`yymmdd`) rather than four (`yyyymmdd`)
+    pub fn two_digit_year(mut self, enabled: bool) -> Self {
+        self.two_digit_year = enabled;
+        self
+    }
+
+    /// Send the `GMT` argument, enabled by default
+    ///
+    /// See [`NewGroups`](cmd::NewGroups) for the interoperability caveats.
+    pub fn gmt(mut self, enabled: bool) -> Self {
+        self.gmt = enabled;
+        self
+    }
+
+    /// Returns true if the `GMT` argument will be sent
+    pub fn gmt_enabled(&self) -> bool {
+        self.gmt
+    }
+
+    /// The date formatted as `yyyymmdd` or `yymmdd`
+    pub fn date(&self) -> String {
+        if self.two_digit_year {
+            format!("{:02}{:02}{:02}", self.year % 100, self.month, self.day)
+        } else {
+            format!("{:04}{:02}{:02}", self.year, self.month, self.day)
+        }
+    }
+
+    /// The time formatted as `hhmmss`
+    pub fn time(&self) -> String {
+        format!("{:02}{:02}{:02}", self.hour, self.minute, self.second)
+    }
+
+    /// The year (e.g. `2020`)
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    /// The month, from `1` to `12`
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    /// The day of the month, from `1` to `31`
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+
+    /// The hour, from `0` to `23`
+    pub fn hour(&self) -> u8 {
+        self.hour
+    }
+
+    /// The minute, from `0` to `59`
+    pub fn minute(&self) -> u8 {
+        self.minute
+    }
+
+    /// The second, from `0` to `60` (to allow for leap seconds)
+    pub fn second(&self) -> u8 {
+        self.second
+    }
+}
+
+/// Parse an [RFC 5322](https://tools.ietf.org/html/rfc5322#section-3.3) date and time, such as
+/// an article's `Date` header or the date field of an [`Overview`](crate::types::Overview)
+///
+/// The result is converted to UTC. As dates in the wild vary wildly, the parser is lenient:
+///
+/// * The day of the week is optional and ignored
+/// * Seconds may be omitted
+/// * Two and three digit years are interpreted per RFC 5322 (e.g. `98` is `1998`)
+/// * Obsolete zone names (`UT`, `GMT`, `EST`, `PDT`, etc.) are accepted, and unknown or missing
+///   zones are treated as UTC
+/// * Trailing comments (e.g. `(EST)`) are ignored
+///
+/// An [`Error::Deserialization`] is returned if the date cannot be parsed.
+///
+/// ```
+/// use brokaw::types::parse_news_date;
+///
+/// let date = parse_news_date(b"Tue, 6 Oct 1998 23:38 -0500 (EST)").unwrap();
+/// assert_eq!((date.year(), date.month(), date.day()), (1998, 10, 7));
+/// assert_eq!((date.hour(), date.minute(), date.second()), (4, 38, 0));
+///
+/// assert!(parse_news_date(b"yesterday").is_err());
+/// ```
+pub fn parse_news_date(date: &[u8]) -> Result<NewsDateTime> {
+    let date = String::from_utf8_lossy(date);
+    let invalid = |reason: &str| Error::de(format!("Invalid date {:?} -- {}", date, reason));
+
+    let mut tokens = date
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|t| !t.is_empty())
+        .peekable();
+
+    if tokens
+        .peek()
+        .is_some_and(|t| t.chars().all(|c| c.is_ascii_alphabetic()))
+    {
+        tokens.next();
+    }
+
+    let mut next = |name: &str| {
+        tokens
+            .next()
+            .ok_or_else(|| invalid(&format!("missing {}", name)))
+    };
+    let day: u8 = next("day")?.parse().map_err(|_| invalid("invalid day"))?;
+    let month = next("month")?;
+    let month = MONTHS
+        .iter()
+        .position(|m| m.eq_ignore_ascii_case(month))
+        .ok_or_else(|| invalid("invalid month"))? as u8
+        + 1;
+    let year = next("year")?;
+    let year = match (year.len(), year.parse::<u16>()) {
+        (2, Ok(year)) if year < 50 => 2000 + year,
+        (2, Ok(year)) | (3, Ok(year)) => 1900 + year,
+        (4, Ok(year)) => year,
+        _ => return Err(invalid("invalid year")),
+    };
+
+    let mut time = next("time")?.split(':').map(str::parse::<u8>);
+    let (hour, minute, second) = match (time.next(), time.next(), time.next(), time.next()) {
+        (Some(Ok(h)), Some(Ok(m)), None, None) => (h, m, 0),
+        (Some(Ok(h)), Some(Ok(m)), Some(Ok(s)), None) => (h, m, s),
+        _ => return Err(invalid("invalid time")),
+    };
+    let offset = next("zone")
+        .map(zone_offset)
+        .unwrap_or(Ok(0))
+        .map_err(invalid)?;
+
+    if day > days_in_month(year, month) {
+        return Err(invalid("day out of range"));
+    }
+    let local = NewsDateTime::new(year, month, day, hour, minute, second)
+        .ok_or_else(|| invalid("out of range"))?;
+
+    Ok(to_utc(local, offset))
+}
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// The offset of a zone from UTC in minutes
+fn zone_offset(zone: &str) -> std::result::Result<i32, &'static str> {
+    let sign = match zone.as_bytes().first() {
+        Some(b'+') => Some(1),
+        Some(b'-') => Some(-1),
+        _ => None,
+    };
+    if let Some(sign) = sign {
+        let digits = &zone[1..];
+        if digits.len() != 4 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err("invalid zone");
+        }
+        // n.b. both halves are two ASCII digits so these cannot fail
+        let hours: i32 = digits[..2].parse().unwrap();
+        let minutes: i32 = digits[2..].parse().unwrap();
+        if minutes >= 60 {
+            return Err("invalid zone");
+        }
+        return Ok(sign * (hours * 60 + minutes));
+    }
+
+    let hours = match zone.to_ascii_uppercase().as_str() {
+        "EDT" => -4,
+        "EST" | "CDT" => -5,
+        "CST" | "MDT" => -6,
+        "MST" | "PDT" => -7,
+        "PST" => -8,
+        // UT, GMT, Z, military zones, and unrecognized names (including comments) are UTC
+        _ => 0,
+    };
+    Ok(hours * 60)
+}
+
+fn is_leap_year(year: u16) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Convert a local time that is `offset` minutes ahead of UTC into UTC
+fn to_utc(local: NewsDateTime, offset: i32) -> NewsDateTime {
+    let mut minutes = i32::from(local.hour) * 60 + i32::from(local.minute) - offset;
+    let (mut year, mut month, mut day) = (local.year, local.month, local.day);
+
+    // n.b. offsets are less than 100 hours so this moves at most a few days
+    while minutes < 0 {
+        minutes += 24 * 60;
+        if day > 1 {
+            day -= 1;
+        } else {
+            if month > 1 {
+                month -= 1;
+            } else {
+                month = 12;
+                year = year.saturating_sub(1);
+            }
+            day = days_in_month(year, month);
+        }
+    }
+    while minutes >= 24 * 60 {
+        minutes -= 24 * 60;
+        if day < days_in_month(year, month) {
+            day += 1;
+        } else {
+            day = 1;
+            if month < 12 {
+                month += 1;
+            } else {
+                month = 1;
+                year += 1;
+            }
+        }
+    }
+
+    NewsDateTime {
+        year,
+        month,
+        day,
+        hour: (minutes / 60) as u8,
+        minute: (minutes % 60) as u8,
+        ..local
+    }
+}
+
+/// The server's time is a convenient checkpoint for the next `NEWGROUPS` or `NEWNEWS`
+impl From<ServerDate> for NewsDateTime {
+    fn from(date: ServerDate) -> Self {
+        Self {
+            year: date.year(),
+            month: date.month(),
+            day: date.day(),
+            hour: date.hour(),
+            minute: date.minute(),
+            second: date.second(),
+            two_digit_year: false,
+            gmt: true,
+        }
+    }
+}
+
+impl From<NewsDateTime> for cmd::NewGroups {
+    fn from(since: NewsDateTime) -> Self {
+        Self {
+            date: since.date(),
+            time: since.time(),
+            gmt: since.gmt,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        let since = NewsDateTime::new(1999, 1, 2, 3, 4, 5).unwrap();
+        assert_eq!(
+            (since.date(), since.time()),
+            ("19990102".into(), "030405".into())
+        );
+        assert_eq!(since.two_digit_year(true).date(), "990102");
+        assert_eq!(
+            NewsDateTime::new(2005, 12, 31, 0, 0, 0)
+                .unwrap()
+                .two_digit_year(true)
+                .date(),
+            "051231"
+        );
+
+        assert!(NewsDateTime::new(2020, 13, 1, 0, 0, 0).is_none());
+        assert!(NewsDateTime::new(2020, 1, 0, 0, 0, 0).is_none());
+        assert!(NewsDateTime::new(2020, 1, 1, 24, 0, 0).is_none());
+        assert!(NewsDateTime::new(10000, 1, 1, 0, 0, 0).is_none());
+    }
+
+    #[test]
+    fn test_parse_news_date() {
+        let parts = |date: &[u8]| {
+            let d = parse_news_date(date).unwrap();
+            (
+                d.year(),
+                d.month(),
+                d.day(),
+                d.hour(),
+                d.minute(),
+                d.second(),
+            )
+        };
+
+        assert_eq!(
+            parts(b"6 Oct 1998 04:38:40 -0500"),
+            (1998, 10, 6, 9, 38, 40)
+        );
+        assert_eq!(
+            parts(b"Thu, 31 Dec 2020 23:30:00 -0100"),
+            (2021, 1, 1, 0, 30, 0)
+        );
+        assert_eq!(
+            parts(b"Mon,1 Mar 2021 01:00 +0130"),
+            (2021, 2, 28, 23, 30, 0)
+        );
+        assert_eq!(parts(b"29 Feb 2024 12:00:00 GMT"), (2024, 2, 29, 12, 0, 0));
+        assert_eq!(parts(b"1 jan 99 00:00 UT"), (1999, 1, 1, 0, 0, 0));
+        assert_eq!(parts(b"1 Jan 05 00:00 PST"), (2005, 1, 1, 8, 0, 0));
+        assert_eq!(parts(b"1 Jan 2005 00:00:00"), (2005, 1, 1, 0, 0, 0));
+
+        for date in &[
+            &b""[..],
+            b"Tue,",
+            b"yesterday",
+            b"6 Oct",
+            b"6 Foo 1998 04:38:40 +0000",
+            b"29 Feb 2023 12:00:00 +0000",
+            b"6 Oct 1998 25:00:00 +0000",
+            b"6 Oct 1998 04:38:40:00 +0000",
+            b"6 Oct 1998 04:38:40 +05",
+            b"6 Oct 1998 04:38:40 +05x0",
+            b"6 Oct 19980 04:38:40",
+            b"\xff\xfe",
+        ] {
+            assert!(parse_news_date(date).is_err(), "{:?}", date);
+        }
+    }
+
+    #[test]
+    fn test_newgroups() {
+        let since = NewsDateTime::new(2020, 10, 15, 12, 34, 56).unwrap();
+        assert_eq!(
+            cmd::NewGroups::from(since).to_string(),
+            "NEWGROUPS 20201015 123456 GMT"
+        );
+        assert_eq!(
+            cmd::NewGroups::from(since.two_digit_year(true).gmt(false)).to_string(),
+            "NEWGROUPS 201015 123456"
+        );
+    }
+}
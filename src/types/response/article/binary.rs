@@ -1,7 +1,7 @@
 use std::convert::TryFrom;
 use std::fmt;
 use std::result::Result as StdResult;
-use std::str::from_utf8;
+use std::str::{from_utf8, Utf8Error};
 
 use log::*;
 
@@ -9,8 +9,12 @@ use crate::error::{Error, Result};
 use crate::types::prelude::*;
 use crate::types::response::article::iter::{Lines, Unterminated};
 use crate::types::response::article::parse::take_headers;
+use crate::types::response::article::segment::find_segments;
 use crate::types::response::util::{err_if_not_kind, process_article_first_line};
 
+/// The fraction of non-printable octets above which a body is considered binary
+const BINARY_THRESHOLD: f64 = 0.1;
+
 /// A binary Netnews article
 ///
 /// A `BinaryArticle` is created by calling `try_from` with a [`RawResponse`].
@@ -50,11 +54,53 @@ impl BinaryArticle {
         &self.headers
     }
 
+    /// The newsgroups the article was posted to
+    ///
+    /// See [`Headers::newsgroups`] for details
+    pub fn newsgroups(&self) -> Vec<String> {
+        self.headers.newsgroups()
+    }
+
     /// The raw contents of the body
     pub fn body(&self) -> &[u8] {
         &self.body
     }
 
+    /// The body as a UTF-8 string
+    ///
+    /// Unlike [`to_text`](Self::to_text), this borrows the body (including line terminators)
+    /// rather than copying it.
+    pub fn body_as_utf8(&self) -> StdResult<&str, Utf8Error> {
+        from_utf8(&self.body)
+    }
+
+    /// Returns true if the body is likely to be binary (e.g. yEnc) rather than text
+    ///
+    /// If the article has a [`Content-Type`](Headers::content_type), `text/*` bodies are treated
+    /// as text and `image/*`, `audio/*`, `video/*`, and `application/*` bodies as binary.
+    /// Otherwise (including for `multipart/*`), a body is considered binary if it contains a NUL
+    /// byte or if more than 10% of its octets are non-printable, i.e. control characters other
+    /// than whitespace, or non-ASCII octets if the body is not valid UTF-8.
+    pub fn is_probably_binary(&self) -> bool {
+        let content_type = self.headers.content_type();
+        match content_type.as_deref().and_then(|t| t.split('/').next()) {
+            Some("text") => false,
+            Some("image") | Some("audio") | Some("video") | Some("application") => true,
+            _ => is_probably_binary(&self.body),
+        }
+    }
+
+    /// Locate the yEnc or uuencoded segments in the body of a multipart binary post
+    ///
+    /// Every segment is returned in the order it appears in the body. This includes the lines of
+    /// a uuencoded file continued from an earlier article, which have no begin marker. The part
+    /// number and total are taken from the yEnc markers or else, if the body has a single
+    /// segment, from a `(1/50)` counter in the subject. Returns an empty `Vec` if the body has no
+    /// recognizable segment.
+    pub fn body_segments(&self) -> Vec<BinarySegment> {
+        find_segments(self.headers.get("Subject"), &self.body)
+    }
+
     /// The number of lines in the body
     pub fn lines_len(&self) -> usize {
         self.line_boundaries.len()
@@ -75,6 +121,21 @@ impl BinaryArticle {
         }
     }
 
+    /// The best available estimate of the size of the article
+    ///
+    /// The `Bytes` and `Lines` headers are preferred when present as they mirror
+    /// the `:bytes` and `:lines` overview metadata. Otherwise, the size is computed from the body,
+    /// so the byte count excludes the headers.
+    pub fn size_hint(&self) -> ArticleSize {
+        ArticleSize {
+            bytes: self.headers.bytes().unwrap_or(self.body.len() as u64),
+            lines: self
+                .headers
+                .lines()
+                .unwrap_or_else(|| self.lines_len() as u64),
+        }
+    }
+
     /// Convert the article into a [`TextArticle`]
     ///
     /// This will return an error if the body is not valid UTF-8
@@ -119,6 +180,39 @@ impl BinaryArticle {
     }
 }
 
+/// Guess whether `body` is binary from its content
+fn is_probably_binary(body: &[u8]) -> bool {
+    if body.is_empty() {
+        return false;
+    }
+    if body.contains(&0) {
+        return true;
+    }
+
+    let utf8 = from_utf8(body).is_ok();
+    let non_printable = body
+        .iter()
+        .filter(|&&b| match b {
+            b'\t' | b'\r' | b'\n' | 0x0c => false,
+            0x80..=0xff => !utf8,
+            b => b.is_ascii_control(),
+        })
+        .count();
+
+    non_printable as f64 / body.len() as f64 > BINARY_THRESHOLD
+}
+
+/// The size of an article
+///
+/// Created by [`BinaryArticle::size_hint`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ArticleSize {
+    /// The size of the article in bytes
+    pub bytes: u64,
+    /// The number of lines in the body of the article
+    pub lines: u64,
+}
+
 impl fmt::Display for BinaryArticle {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let num_headers = self.headers.len();
@@ -166,14 +260,87 @@ impl TryFrom<&RawResponse> for BinaryArticle {
             .skip_while(|(start, _end)| start < &bytes_read)
             .map(|(start, end)| (start - bytes_read, end - bytes_read))
             .collect::<Vec<_>>();
-        line_boundaries.pop();
+        // Drop the terminating `.` line
+        let body_len = line_boundaries
+            .pop()
+            .map(|(start, _end)| start)
+            .unwrap_or_else(|| body.len());
 
         Ok(Self {
             number,
             message_id,
             headers,
-            body: body.to_vec(),
+            body: body[..body_len].to_vec(),
             line_boundaries,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::types::response::article::parse::take_headers;
+
+    fn article(head: &[u8], body: &[u8]) -> BinaryArticle {
+        let (_rest, headers) = take_headers(head).unwrap();
+        BinaryArticle {
+            number: 1,
+            message_id: "<a@example.com>".to_string(),
+            headers,
+            body: body.to_vec(),
+            line_boundaries: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_body_as_utf8() {
+        let text = article(b"Subject: hi\r\n\r\n", "caf\u{e9}\r\n".as_bytes());
+        assert_eq!(text.body_as_utf8().unwrap(), "caf\u{e9}\r\n");
+        assert!(!text.is_probably_binary());
+
+        let latin1 = article(b"Subject: hi\r\n\r\n", b"caf\xe9 au lait\r\n");
+        assert!(latin1.body_as_utf8().is_err());
+        assert!(!latin1.is_probably_binary());
+    }
+
+    #[test]
+    fn test_is_probably_binary() {
+        let head = b"Subject: hi\r\n\r\n";
+        assert!(article(head, b"=ybegin\r\n\x00\x01\r\n").is_probably_binary());
+        assert!(article(head, b"\xfe\xed\xfa\xce\x8a\x9b\r\n").is_probably_binary());
+        assert!(!article(head, b"").is_probably_binary());
+
+        // The Content-Type takes precedence over the heuristic
+        let jpeg = b"Content-Type: image/jpeg\r\n\r\n";
+        assert!(article(jpeg, b"base64\r\n").is_probably_binary());
+        let text = b"Content-Type: text/plain; charset=iso-8859-1\r\n\r\n";
+        assert!(!article(text, b"\xfe\xed\xfa\xce\r\n").is_probably_binary());
+        let multipart = b"Content-Type: multipart/mixed\r\n\r\n";
+        assert!(article(multipart, b"\x00\r\n").is_probably_binary());
+    }
+
+    #[test]
+    fn test_size_hint() {
+        let mut from_body = article(b"Subject: hi\r\n\r\n", b"one\r\ntwo\r\n");
+        from_body.line_boundaries = vec![(0, 5), (5, 10)];
+        assert_eq!(
+            from_body.size_hint(),
+            ArticleSize {
+                bytes: 10,
+                lines: 2
+            }
+        );
+
+        // The headers take precedence over the body
+        let mut sized = article(b"Bytes: 1972\r\nLines: 17\r\n\r\n", b"one\r\n");
+        sized.line_boundaries = vec![(0, 5)];
+        assert_eq!(
+            sized.size_hint(),
+            ArticleSize {
+                bytes: 1972,
+                lines: 17
+            }
+        );
+    }
+}
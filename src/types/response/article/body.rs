@@ -57,14 +57,19 @@ impl TryFrom<&RawResponse> for Body {
         let (number, message_id) = process_article_first_line(&resp)?;
 
         let DataBlocks {
-            payload,
-            line_boundaries,
+            mut payload,
+            mut line_boundaries,
         } = resp
             .data_blocks
             .as_ref()
             .ok_or_else(Error::missing_data_blocks)?
             .clone();
 
+        // Drop the terminating `.` line
+        if let Some((start, _end)) = line_boundaries.pop() {
+            payload.truncate(start);
+        }
+
         Ok(Self {
             number,
             message_id,
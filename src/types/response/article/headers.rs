@@ -4,7 +4,7 @@ use std::convert::TryFrom;
 use crate::error::{Error, Result};
 use crate::raw::response::RawResponse;
 use crate::types::prelude::*;
-use crate::types::response::article::parse::take_headers;
+use crate::types::response::article::parse::{take_head_headers, take_raw_head_headers};
 use crate::types::response::util::{err_if_not_kind, process_article_first_line};
 
 /// Netnews article headers
@@ -23,7 +23,11 @@ pub struct Header {
     /// The name of the header
     pub name: String,
     /// One-or-more content values for the header
+    ///
+    /// Folded values are unfolded and lossily converted to UTF-8
     pub content: Vec<String>,
+    /// The unfolded content values as they were sent
+    pub(crate) raw: Vec<Vec<u8>>,
 }
 
 impl Headers {
@@ -40,8 +44,36 @@ impl Headers {
     }
 
     /// Get a header by name
-    pub fn get(&self, key: impl AsRef<str>) -> Option<&Header> {
-        self.inner.get(key.as_ref())
+    ///
+    /// Names are compared case-insensitively.
+    pub fn header(&self, key: impl AsRef<str>) -> Option<&Header> {
+        self.find(key.as_ref())
+    }
+
+    /// Get the unfolded value of the first header with the given name
+    ///
+    /// Names are compared case-insensitively and the value is returned as it was sent, so it
+    /// may not be UTF-8.
+    pub fn get(&self, key: impl AsRef<str>) -> Option<&[u8]> {
+        self.get_all(key).into_iter().next()
+    }
+
+    /// Get the unfolded values of every header with the given name
+    ///
+    /// See [`get`](Self::get)
+    pub fn get_all(&self, key: impl AsRef<str>) -> Vec<&[u8]> {
+        self.find(key.as_ref())
+            .map(|header| header.raw.iter().map(Vec::as_slice).collect())
+            .unwrap_or_default()
+    }
+
+    /// Find a header by name, falling back to a case-insensitive comparison
+    fn find(&self, key: &str) -> Option<&Header> {
+        self.inner.get(key).or_else(|| {
+            self.inner
+                .values()
+                .find(|header| header.name.eq_ignore_ascii_case(key))
+        })
     }
 
     /// An iterator over the headers
@@ -50,6 +82,98 @@ impl Headers {
             inner: self.inner.values(),
         }
     }
+
+    /// The number of lines in the article body as reported by the `Lines` header
+    ///
+    /// Returns `None` if the header is missing or is not a number
+    pub fn lines(&self) -> Option<u64> {
+        self.parse_first("Lines")
+    }
+
+    /// The size of the article in bytes as reported by the `Bytes` header
+    ///
+    /// Returns `None` if the header is missing or is not a number
+    pub fn bytes(&self) -> Option<u64> {
+        self.parse_first("Bytes")
+    }
+
+    /// The media type from the `Content-Type` header (e.g. `text/plain`)
+    ///
+    /// The type is lowercased and any parameters (e.g. `charset`) are removed.
+    /// Returns `None` if the header is missing or empty.
+    pub fn content_type(&self) -> Option<String> {
+        self.find("Content-Type")
+            .and_then(|header| header.content.first())
+            .and_then(|content| content.split(';').next())
+            .map(|media_type| media_type.trim().to_ascii_lowercase())
+            .filter(|media_type| !media_type.is_empty())
+    }
+
+    /// Parse the first value of a header
+    fn parse_first<T: std::str::FromStr>(&self, key: &str) -> Option<T> {
+        self.find(key)
+            .and_then(|header| header.content.first())
+            .and_then(|content| content.trim().parse().ok())
+    }
+
+    /// The newsgroups listed in the `Newsgroups` header
+    ///
+    /// The header is split on commas and stray whitespace around each group is removed.
+    /// An empty vector is returned if the header is missing.
+    pub fn newsgroups(&self) -> Vec<String> {
+        self.find("Newsgroups")
+            .into_iter()
+            .flat_map(|header| header.content.iter())
+            .flat_map(|content| content.split(','))
+            .map(str::trim)
+            .filter(|group| !group.is_empty())
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    /// The date the article was posted as reported by the `Date` header, in UTC
+    ///
+    /// Returns `None` if the header is missing or cannot be parsed. See
+    /// [`parse_news_date`](crate::types::parse_news_date).
+    pub fn date(&self) -> Option<NewsDateTime> {
+        self.get("Date")
+            .and_then(|date| crate::types::parse_news_date(date).ok())
+    }
+
+    /// The article's message-id as reported by the `Message-ID` header
+    ///
+    /// Returns `None` if the header is missing or malformed
+    pub fn message_id(&self) -> Option<MessageId> {
+        self.message_ids("Message-ID").into_iter().next()
+    }
+
+    /// The message-ids listed in the `References` header, oldest first
+    ///
+    /// Message-ids may be separated by any whitespace (including that left by unfolding) or
+    /// commas, or not at all (e.g. `<a@example.com><b@example.com>`). Malformed tokens are
+    /// skipped. An empty vector is returned if the header is missing.
+    pub fn references(&self) -> Vec<MessageId> {
+        self.message_ids("References")
+    }
+
+    /// The message-ids listed in the `In-Reply-To` header
+    ///
+    /// This is usually a single message-id but may list several. Tokens are parsed in the same
+    /// way as [`references`](Self::references).
+    pub fn in_reply_to(&self) -> Vec<MessageId> {
+        self.message_ids("In-Reply-To")
+    }
+
+    /// Parse every valid message-id from the values of a header
+    fn message_ids(&self, key: &str) -> Vec<MessageId> {
+        self.find(key)
+            .into_iter()
+            .flat_map(|header| header.content.iter())
+            .flat_map(|content| content.split(|c: char| c.is_whitespace() || c == ','))
+            .flat_map(|token| token.split_inclusive('>'))
+            .filter_map(|token| MessageId::new(token).ok())
+            .collect()
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -89,7 +213,7 @@ impl TryFrom<&RawResponse> for Head {
             .as_ref()
             .ok_or_else(Error::missing_data_blocks)?;
 
-        let (_, headers) = take_headers(&data_blocks.payload())
+        let (_, headers) = take_head_headers(&data_blocks.payload())
             .map_err(|e| Error::invalid_data_blocks(format!("{}", e)))?;
 
         Ok(Self {
@@ -99,3 +223,136 @@ impl TryFrom<&RawResponse> for Head {
         })
     }
 }
+
+/// Parse the headers from a [`HEAD`](https://tools.ietf.org/html/rfc3977#section-6.2.2) response
+/// as name/value pairs
+///
+/// Unlike [`Head`], headers are kept in the order they were sent, duplicates are preserved,
+/// and folded values are not unfolded.
+pub(crate) fn raw_headers(resp: &RawResponse) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+    err_if_not_kind(resp, Kind::Head)?;
+
+    let data_blocks = resp
+        .data_blocks
+        .as_ref()
+        .ok_or_else(Error::missing_data_blocks)?;
+
+    let (_, headers) = take_raw_head_headers(data_blocks.payload())
+        .map_err(|e| Error::invalid_data_blocks(format!("{}", e)))?;
+
+    Ok(headers
+        .into_iter()
+        .map(|(name, content)| (name.to_vec(), content.to_vec()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::types::response::article::parse::take_headers;
+
+    fn headers(raw: &[u8]) -> Headers {
+        let (_rest, headers) = take_headers(raw).unwrap();
+        headers
+    }
+
+    #[test]
+    fn test_newsgroups() {
+        let headers = headers(b"Newsgroups: comp.lang.rust , misc.test,alt.test\r\n\r\n");
+        assert_eq!(
+            headers.newsgroups(),
+            vec!["comp.lang.rust", "misc.test", "alt.test"]
+        );
+    }
+
+    #[test]
+    fn test_lines_and_bytes() {
+        let sized = headers(b"Bytes: 1972\r\nLines: 17\r\n\r\n");
+        assert_eq!(sized.bytes(), Some(1972));
+        assert_eq!(sized.lines(), Some(17));
+
+        // Names are case-insensitive
+        let lowercase = headers(b"bytes: 1972\r\nlines: 17\r\n\r\n");
+        assert_eq!(lowercase.bytes(), Some(1972));
+        assert_eq!(lowercase.lines(), Some(17));
+
+        let malformed = headers(b"Lines: seventeen\r\n\r\n");
+        assert_eq!(malformed.bytes(), None);
+        assert_eq!(malformed.lines(), None);
+    }
+
+    #[test]
+    fn test_get() {
+        let headers = headers(
+            b"Subject: Hello\r\n\tWorld\r\nX-Binary: \xff\xfe\r\nX-Dup: 1\r\nX-Dup: 2\r\n\r\n",
+        );
+
+        assert_eq!(headers.get("Subject"), Some(&b"Hello\tWorld"[..]));
+        assert_eq!(headers.get("subject"), Some(&b"Hello\tWorld"[..]));
+        assert_eq!(headers.get("X-Binary"), Some(&b"\xff\xfe"[..]));
+        assert_eq!(headers.get_all("X-Dup"), vec![&b"1"[..], b"2"]);
+        assert_eq!(headers.get("Missing"), None);
+        assert!(headers.get_all("Missing").is_empty());
+    }
+
+    #[test]
+    fn test_message_ids() {
+        let article = headers(
+            b"Message-Id: <c@example.com>\r\n\
+            References: <a@example.com>  <bogus>\r\n \
+            <b@example.com>,<x@example.com><y@example.com> junk\r\n\
+            In-Reply-To: <b@example.com>\r\n\
+            Date: Sat, 1 Jan 2000 00:00:00 +0000\r\n\r\n",
+        );
+        assert_eq!(article.date(), NewsDateTime::new(2000, 1, 1, 0, 0, 0));
+
+        assert_eq!(
+            article.message_id(),
+            Some(MessageId::new("<c@example.com>").unwrap())
+        );
+        assert_eq!(
+            article
+                .references()
+                .iter()
+                .map(MessageId::as_str)
+                .collect::<Vec<_>>(),
+            vec![
+                "<a@example.com>",
+                "<bogus>",
+                "<b@example.com>",
+                "<x@example.com>",
+                "<y@example.com>"
+            ]
+        );
+        assert_eq!(
+            article.in_reply_to(),
+            vec![MessageId::new("<b@example.com>").unwrap()]
+        );
+
+        let empty = headers(b"Subject: Hello\r\n\r\n");
+        assert_eq!(empty.date(), None);
+        assert_eq!(empty.message_id(), None);
+        assert!(empty.references().is_empty());
+    }
+
+    #[test]
+    fn test_content_type() {
+        let headers = |raw| headers(raw).content_type();
+        assert_eq!(
+            headers(b"Content-Type: Text/Plain; charset=utf-8\r\n\r\n").as_deref(),
+            Some("text/plain")
+        );
+        assert_eq!(
+            headers(b"content-type: image/jpeg\r\n\r\n").as_deref(),
+            Some("image/jpeg")
+        );
+        assert_eq!(headers(b"Subject: Hello\r\n\r\n"), None);
+    }
+
+    #[test]
+    fn test_missing_newsgroups() {
+        let headers = headers(b"Subject: Hello\r\n\r\n");
+        assert!(headers.newsgroups().is_empty());
+    }
+}
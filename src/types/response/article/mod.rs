@@ -13,14 +13,19 @@ mod iter;
 /// Parsing logic for for article headers
 mod parse;
 
+/// Binary multipart segments
+mod segment;
+
 /// Article status
 mod stat;
 
 /// Text articles
 mod text;
 
-pub use binary::BinaryArticle;
+pub use binary::{ArticleSize, BinaryArticle};
 pub use body::Body;
+pub(crate) use headers::raw_headers;
 pub use headers::{Head, Header, Headers};
+pub use segment::{BinarySegment, SegmentEncoding};
 pub use stat::Stat;
 pub use text::TextArticle;
@@ -2,11 +2,11 @@ use std::collections::HashMap;
 
 use log::*;
 use nom::branch::alt;
-use nom::bytes::complete::{take, take_while1};
+use nom::bytes::complete::{tag, take, take_while1};
 use nom::character::complete::{char, crlf, space0, space1};
 use nom::combinator::{opt, verify};
 use nom::lib::std::str::from_utf8;
-use nom::multi::{fold_many1, many0};
+use nom::multi::{fold_many1, many0, many1};
 use nom::sequence::{terminated, tuple};
 use nom::IResult;
 
@@ -70,13 +70,23 @@ fn take_utf8_non_ascii(b: &[u8]) -> IResult<&[u8], &[u8]> {
     ))(b)
 }
 
+/// Take a single byte that is not ASCII
+///
+/// Older articles may contain headers in 8-bit encodings (e.g. Latin-1), these bytes are kept
+/// so that the original header can be recovered.
+fn take_8bit_byte(b: &[u8]) -> IResult<&[u8], &[u8]> {
+    verify(take(1u8), |byte: &[u8]| !byte[0].is_ascii())(b)
+}
+
 /// Take a single `A-CHAR` or `UTF8-non-ascii` from the slice
 /// ```abnf
 /// P-CHAR     = A-CHAR / UTF8-non-ascii
 /// A-CHAR     = %x21-7E
 /// ```
+///
+/// Non-ASCII bytes that are not valid UTF-8 are also accepted (see [`take_8bit_byte`]).
 fn take_p_char(b: &[u8]) -> IResult<&[u8], &[u8]> {
-    alt((take_a_char, take_utf8_non_ascii))(b)
+    alt((take_a_char, take_utf8_non_ascii, take_8bit_byte))(b)
 }
 
 /// Take the header-name from a slice
@@ -157,37 +167,96 @@ fn take_header(b: &[u8]) -> IResult<&[u8], (&[u8], &[u8])> {
     Ok((rest, (header_name, header_content.unwrap_or_default())))
 }
 
-pub(crate) fn take_headers(b: &[u8]) -> IResult<&[u8], Headers> {
+/// Unfold header content by removing each CRLF that is followed by whitespace
+///
+/// Per [RFC 5322](https://tools.ietf.org/html/rfc5322#section-2.2.3) the whitespace itself is
+/// kept. This applies within quoted strings as well.
+fn unfold(content: &[u8]) -> Vec<u8> {
+    let mut unfolded = Vec::with_capacity(content.len());
+    let mut i = 0;
+    while i < content.len() {
+        let folded = content[i..].starts_with(b"\r\n")
+            && matches!(content.get(i + 2), Some(b' ') | Some(b'\t'));
+        if folded {
+            i += 2;
+        } else {
+            unfolded.push(content[i]);
+            i += 1;
+        }
+    }
+    unfolded
+}
+
+/// Take headers from a slice, merging duplicates and unfolding their contents
+fn fold_headers(b: &[u8]) -> IResult<&[u8], Headers> {
     // n.b. assuming there are no parsing bugs (big if there), it should be sound to use
     // from_utf8_unchecked on header names since we already did utf8 checks while parsing.
 
-    let fold_headers = fold_many1(
+    let (rest, (inner, len)) = fold_many1(
         take_header,
         (HashMap::new(), 0),
         |(mut map, mut len), (name, content)| {
             let name = String::from_utf8_lossy(name).to_string();
-            let content = String::from_utf8_lossy(content).to_string();
+            let raw = unfold(content);
+            let content = String::from_utf8_lossy(&raw).to_string();
             trace!("Found header name `{}` -- `{}`", name, content);
 
             let header = map.entry(name.clone()).or_insert(Header {
                 name,
                 content: vec![],
+                raw: vec![],
             });
             header.content.push(content);
+            header.raw.push(raw);
 
             len += 1;
 
             (map, len)
         },
-    );
-
-    let (rest, (inner, len)) = terminated(fold_headers, crlf)(b)?;
+    )(b)?;
 
     let headers = Headers { inner, len };
 
     Ok((rest, headers))
 }
 
+/// Take the headers of an article, which are terminated by a blank line
+pub(crate) fn take_headers(b: &[u8]) -> IResult<&[u8], Headers> {
+    terminated(fold_headers, crlf)(b)
+}
+
+/// Take the headers from the data blocks of a `HEAD` response
+///
+/// Unlike an article, the headers are followed by the end of the data blocks rather than a
+/// blank line.
+pub(crate) fn take_head_headers(b: &[u8]) -> IResult<&[u8], Headers> {
+    terminated(fold_headers, end_of_data_blocks)(b)
+}
+
+/// A header name and its unprocessed contents
+pub(crate) type RawHeader<'a> = (&'a [u8], &'a [u8]);
+
+/// Take headers from a slice without merging duplicates
+///
+/// * Headers are returned in the order they appear within the slice
+/// * Header contents are returned as-is, folded headers will retain their CRLF and whitespace
+/// * The blank line or end of data blocks following the headers is not consumed
+fn take_raw_headers(b: &[u8]) -> IResult<&[u8], Vec<RawHeader<'_>>> {
+    many1(take_header)(b)
+}
+
+/// Take headers from the data blocks of a `HEAD` response without merging duplicates
+///
+/// See [`take_raw_headers`] and [`take_head_headers`]
+pub(crate) fn take_raw_head_headers(b: &[u8]) -> IResult<&[u8], Vec<RawHeader<'_>>> {
+    terminated(take_raw_headers, end_of_data_blocks)(b)
+}
+
+/// The terminating line of a multi-line data block
+fn end_of_data_blocks(b: &[u8]) -> IResult<&[u8], &[u8]> {
+    tag(".\r\n")(b)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -334,6 +403,45 @@ mod tests {
 
         assert!(rest.starts_with(b"In bug 1630935 [1], I intend to deprecate support for drawing"));
         assert!(headers.inner.contains_key("X-Received"));
-        assert_eq!(headers.get("X-Received").unwrap().content.len(), 2);
+        assert_eq!(headers.header("X-Received").unwrap().content.len(), 2);
+        assert_eq!(
+            headers.get("X-Received").unwrap(),
+            &b"by 2002:ac8:2aed:: with SMTP id c42mr5587158qta.202.1591290821135;        \
+                Thu, 04 Jun 2020 10:13:41 -0700 (PDT)"[..]
+        );
+    }
+
+    #[test]
+    fn test_unfold() {
+        assert_eq!(unfold(b"a;\r\n b"), b"a; b");
+        assert_eq!(unfold(b"a;\r\n\tb\r\n  c"), b"a;\tb  c");
+        assert_eq!(unfold(b"\"quoted\r\n string\""), b"\"quoted string\"");
+        assert_eq!(unfold(b"\xff\r\n \xfe"), b"\xff \xfe");
+        assert_eq!(unfold(b"trailing\r\n"), b"trailing\r\n");
+    }
+
+    #[test]
+    fn test_leading_continuation() {
+        // A continuation cannot be the first line
+        assert!(take_headers(b" continued\r\nSubject: hi\r\n\r\n").is_err());
+    }
+
+    #[test]
+    fn test_take_raw_headers() {
+        let (_first_line, article) = TEXT_ARTICLE.split_once('\n').unwrap();
+        let (rest, headers) = take_raw_headers(article.as_bytes()).unwrap();
+
+        assert!(
+            rest.starts_with(b"\r\nIn bug 1630935 [1], I intend to deprecate support for drawing")
+        );
+
+        let names = headers.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+        assert_eq!(&names[..3], &[&b"X-Received"[..], b"X-Received", b"Path"]);
+        assert_eq!(names.last(), Some(&&b"Xref"[..]));
+
+        // folded contents are not unfolded
+        assert!(headers[0]
+            .1
+            .ends_with(b";\r\n        Thu, 04 Jun 2020 10:13:41 -0700 (PDT)"));
     }
 }
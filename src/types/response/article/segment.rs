@@ -0,0 +1,358 @@
+use std::ops::Range;
+use std::str::{from_utf8, FromStr};
+
+/// The encoding of a [`BinarySegment`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum SegmentEncoding {
+    /// [yEnc](http://www.yenc.org/yenc-draft.1.3.txt), delimited by `=ybegin` and `=yend` lines
+    YEnc,
+    /// uuencode, delimited by `begin <mode> <name>` and `end` lines
+    Uuencode,
+}
+
+/// One part of a binary file that was split across several articles
+///
+/// Created by [`BinaryArticle::body_segments`](super::BinaryArticle::body_segments)
+///
+/// The segment only describes where the encoded data is, decoding it and reassembling the parts
+/// (sorted by [`part`](Self::part)) is left to the caller.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BinarySegment {
+    /// The encoding of the payload
+    pub encoding: SegmentEncoding,
+    /// The 1-based index of the part, if known
+    ///
+    /// Taken from the yEnc `part=` parameter, or else from a `(1/50)` counter in the subject if
+    /// the body has a single segment.
+    pub part: Option<u32>,
+    /// The total number of parts, if known
+    ///
+    /// Taken from the yEnc `total=` parameter, or else from a `(1/50)` counter in the subject if
+    /// the body has a single segment.
+    pub total: Option<u32>,
+    /// The name of the encoded file, if present in the begin marker
+    ///
+    /// Always `None` for uuencoded parts that continue a file begun in an earlier article.
+    pub name: Option<String>,
+    /// The byte range of the encoded lines within the body, excluding the markers
+    pub payload: Range<usize>,
+    /// Whether the end marker was found
+    ///
+    /// If it is missing after a begin marker the payload extends to the end of the body, which
+    /// usually means that the article was truncated or that it is the first part of a uuencoded
+    /// file split across articles. The parts in between have neither marker.
+    pub complete: bool,
+}
+
+/// Find every yEnc or uuencoded segment in `body`
+///
+/// Returns an empty `Vec` if the body has no begin marker and no uuencoded lines.
+pub(crate) fn find_segments(subject: Option<&[u8]>, body: &[u8]) -> Vec<BinarySegment> {
+    let mut segments = Vec::new();
+    let mut from = 0;
+    while let Some((marker, segment)) = find_marked_segment(body, from) {
+        // A uuencoded file split across articles has no begin marker after the first part
+        if segments.is_empty() {
+            segments.extend(find_uuencode_continuation(&body[..marker]));
+        }
+        let resume = match segment.complete {
+            true => Some(segment.payload.end),
+            false => None,
+        };
+        segments.push(segment);
+        match resume {
+            Some(resume) => from = resume,
+            None => break,
+        }
+    }
+    if segments.is_empty() {
+        segments.extend(find_uuencode_continuation(body));
+    }
+
+    // The subject only describes the article as a whole
+    if let [segment] = segments.as_mut_slice() {
+        if segment.part.is_none() || segment.total.is_none() {
+            if let Some((part, total)) = subject.and_then(subject_counter) {
+                segment.part = segment.part.or(Some(part));
+                segment.total = segment.total.or(Some(total));
+            }
+        }
+    }
+
+    segments
+}
+
+/// Find the first segment with a begin marker at or after `from`
+///
+/// Returns the offset of the begin marker along with the segment.
+fn find_marked_segment(body: &[u8], from: usize) -> Option<(usize, BinarySegment)> {
+    let mut lines = lines_with_offsets(body, from);
+    let (encoding, params, marker, mut start) = lines.by_ref().find_map(|(offset, line)| {
+        let end = offset + line.len();
+        let line = trim_line_ending(line);
+        if let Some(params) = line.strip_prefix(b"=ybegin ") {
+            Some((SegmentEncoding::YEnc, params, offset, end))
+        } else if let Some(params) = line.strip_prefix(b"begin ") {
+            is_uuencode_begin(params).then_some((SegmentEncoding::Uuencode, params, offset, end))
+        } else {
+            None
+        }
+    })?;
+
+    let (part, total, name) = match encoding {
+        SegmentEncoding::YEnc => parse_ybegin(params),
+        SegmentEncoding::Uuencode => {
+            let name = params.splitn(2, |&b| b == b' ').nth(1);
+            (None, None, name.map(lossy))
+        }
+    };
+
+    let mut end = None;
+    for (offset, raw_line) in lines {
+        let line = trim_line_ending(raw_line);
+        match encoding {
+            // `=ypart` directly follows `=ybegin` in multipart messages
+            SegmentEncoding::YEnc if offset == start && line.starts_with(b"=ypart ") => {
+                start += raw_line.len();
+            }
+            SegmentEncoding::YEnc if line.starts_with(b"=yend") => {
+                end = Some(offset);
+                break;
+            }
+            SegmentEncoding::Uuencode if line == b"end" => {
+                end = Some(offset);
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    let segment = BinarySegment {
+        encoding,
+        part,
+        total,
+        name,
+        payload: start..end.unwrap_or(body.len()),
+        complete: end.is_some(),
+    };
+    Some((marker, segment))
+}
+
+/// Find a run of uuencoded lines that continues a file begun in an earlier article
+///
+/// The run must contain at least one full (`M`) line. It is complete if it is followed by the
+/// `end` marker, i.e. if this is the last part.
+fn find_uuencode_continuation(body: &[u8]) -> Option<BinarySegment> {
+    let mut lines = lines_with_offsets(body, 0);
+    let start = loop {
+        let (offset, line) = lines.next()?;
+        let line = trim_line_ending(line);
+        if line.first() == Some(&b'M') && is_uuencoded_line(line) {
+            break offset;
+        }
+    };
+
+    let mut end = body.len();
+    let mut complete = false;
+    for (offset, raw_line) in lines {
+        let line = trim_line_ending(raw_line);
+        if !is_uuencoded_line(line) {
+            end = offset;
+            complete = line == b"end";
+            break;
+        }
+    }
+
+    Some(BinarySegment {
+        encoding: SegmentEncoding::Uuencode,
+        part: None,
+        total: None,
+        name: None,
+        payload: start..end,
+        complete,
+    })
+}
+
+/// Returns true if `line` is a line of uuencoded data
+///
+/// The first character encodes the number of octets on the line, which determines the length of
+/// the line. Some encoders append a checksum character.
+fn is_uuencoded_line(line: &[u8]) -> bool {
+    let (len, data) = match line.split_first() {
+        Some((&len, data)) if (b' '..=b'`').contains(&len) => (len, data),
+        _ => return false,
+    };
+    let octets = usize::from((len - b' ') & 0x3f);
+    let expected = octets.div_ceil(3) * 4;
+    (expected..=expected + 1).contains(&data.len())
+        && data.iter().all(|b| (b' '..=b'`').contains(b))
+}
+
+/// Iterate over the lines of `body` from `from` (including their terminators) and their offsets
+fn lines_with_offsets(body: &[u8], from: usize) -> impl Iterator<Item = (usize, &[u8])> {
+    body[from..]
+        .split_inclusive(|&b| b == b'\n')
+        .scan(from, |offset, line| {
+            let start = *offset;
+            *offset += line.len();
+            Some((start, line))
+        })
+}
+
+fn trim_line_ending(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+/// Returns true if `params` start with an octal file mode, e.g. `644 file.bin`
+///
+/// This rules out prose lines such as "begin with the basics".
+fn is_uuencode_begin(params: &[u8]) -> bool {
+    let mode = params.split(|&b| b == b' ').next().unwrap_or_default();
+    (3..=4).contains(&mode.len()) && mode.iter().all(|b| (b'0'..=b'7').contains(b))
+}
+
+/// Parse the `part`, `total`, and `name` parameters of a `=ybegin` line
+///
+/// The name is always the last parameter and may contain spaces.
+fn parse_ybegin(params: &[u8]) -> (Option<u32>, Option<u32>, Option<String>) {
+    let (params, name) = match find(params, b"name=") {
+        Some(i) => (&params[..i], Some(lossy(&params[i + 5..]))),
+        None => (params, None),
+    };
+
+    let mut part = None;
+    let mut total = None;
+    for param in params.split(|&b| b == b' ') {
+        if let Some(value) = param.strip_prefix(b"part=") {
+            part = parse_number(value);
+        } else if let Some(value) = param.strip_prefix(b"total=") {
+            total = parse_number(value);
+        }
+    }
+
+    (part, total, name)
+}
+
+/// Parse the last `(part/total)` counter in a subject, e.g. `"file.rar" yEnc (1/50)`
+fn subject_counter(subject: &[u8]) -> Option<(u32, u32)> {
+    subject
+        .split(|&b| b == b'(')
+        .skip(1)
+        .filter_map(|s| {
+            let counter = &s[..s.iter().position(|&b| b == b')')?];
+            let slash = counter.iter().position(|&b| b == b'/')?;
+            Some((
+                parse_number(&counter[..slash])?,
+                parse_number(&counter[slash + 1..])?,
+            ))
+        })
+        .last()
+}
+
+fn parse_number<T: FromStr>(b: &[u8]) -> Option<T> {
+    if b.is_empty() || !b.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    from_utf8(b).ok()?.parse().ok()
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+fn lossy(b: &[u8]) -> String {
+    String::from_utf8_lossy(b).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find_segment(subject: Option<&[u8]>, body: &[u8]) -> BinarySegment {
+        let mut segments = find_segments(subject, body);
+        assert_eq!(segments.len(), 1);
+        segments.remove(0)
+    }
+
+    #[test]
+    fn test_yenc() {
+        let body = b"=ybegin part=2 total=3 line=128 size=300 name=my file.bin\r\n\
+            =ypart begin=101 end=200\r\n\
+            encoded\r\n\
+            =yend size=100 part=2 pcrc32=abcdef01\r\n";
+        let segment = find_segment(Some(b"\"my file.bin\" yEnc (9/9)"), body);
+
+        assert_eq!(segment.encoding, SegmentEncoding::YEnc);
+        // The yEnc parameters take precedence over the subject
+        assert_eq!((segment.part, segment.total), (Some(2), Some(3)));
+        assert_eq!(segment.name.as_deref(), Some("my file.bin"));
+        assert_eq!(&body[segment.payload], b"encoded\r\n");
+        assert!(segment.complete);
+    }
+
+    #[test]
+    fn test_yenc_single_part() {
+        let body = b"Here it is:\r\n=ybegin line=128 size=7 name=a.txt\r\nencoded\r\n";
+        let segment = find_segment(Some(b"a.txt yEnc (1/1) [ignored]"), body);
+
+        assert_eq!((segment.part, segment.total), (Some(1), Some(1)));
+        assert_eq!(&body[segment.payload], b"encoded\r\n");
+        assert!(!segment.complete);
+    }
+
+    #[test]
+    fn test_multiple_segments() {
+        let body = b"=ybegin part=1 total=2 line=128 size=20 name=a.bin\r\n\
+            first\r\n\
+            =yend size=10 part=1\r\n\
+            =ybegin part=2 total=2 line=128 size=20 name=a.bin\r\n\
+            second\r\n\
+            =yend size=10 part=2\r\n";
+        let segments = find_segments(Some(b"a.bin yEnc (1/1)"), body);
+
+        assert_eq!(segments.len(), 2);
+        assert_eq!(&body[segments[0].payload.clone()], b"first\r\n");
+        assert_eq!(&body[segments[1].payload.clone()], b"second\r\n");
+        assert_eq!(segments[1].part, Some(2));
+    }
+
+    #[test]
+    fn test_uuencode() {
+        let body = b"begin with the basics\r\nbegin 644 cat.jpg\r\nM9F]O\r\n`\r\nend\r\n";
+        let segment = find_segment(Some(b"cat.jpg (03/12)"), body);
+
+        assert_eq!(segment.encoding, SegmentEncoding::Uuencode);
+        assert_eq!((segment.part, segment.total), (Some(3), Some(12)));
+        assert_eq!(segment.name.as_deref(), Some("cat.jpg"));
+        assert_eq!(&body[segment.payload], b"M9F]O\r\n`\r\n");
+        assert!(segment.complete);
+    }
+
+    #[test]
+    fn test_uuencode_continuation() {
+        let full = format!("M{}\r\n", "0".repeat(60));
+        let middle = format!("{0}{0}", full);
+        let segment = find_segment(Some(b"cat.jpg (02/03)"), middle.as_bytes());
+        assert_eq!(segment.encoding, SegmentEncoding::Uuencode);
+        assert_eq!((segment.part, segment.total), (Some(2), Some(3)));
+        assert_eq!(segment.name, None);
+        assert_eq!(segment.payload, 0..middle.len());
+        assert!(!segment.complete);
+
+        let last = format!("{}#0V%T\r\n`\r\nend\r\n-- \r\nsig\r\n", full);
+        let segment = find_segment(Some(b"cat.jpg (03/03)"), last.as_bytes());
+        assert_eq!(
+            &last.as_bytes()[segment.payload],
+            format!("{}#0V%T\r\n`\r\n", full).as_bytes()
+        );
+        assert!(segment.complete);
+    }
+
+    #[test]
+    fn test_no_markers() {
+        assert_eq!(find_segments(Some(b"file (1/2)"), b"just text\r\n"), vec![]);
+        assert_eq!(find_segments(None, b"MAKE IT SO\r\n"), vec![]);
+        assert_eq!(find_segments(None, b""), vec![]);
+    }
+}
@@ -2,25 +2,100 @@ use std::convert::TryFrom;
 
 use crate::error::{Error, Result};
 use crate::types::prelude::*;
-use crate::types::response::util::{err_if_not_kind, process_article_first_line};
+use crate::types::response::util::{err_if_not_kind, parse_field};
 
 /// Article metadata returned by [`STAT`](https://tools.ietf.org/html/rfc3977#section-6.2.4)
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Stat {
     /// The number of the article unique to a particular newsgroup
     pub number: ArticleNumber,
     /// The unique message id for the article
-    pub message_id: String,
+    ///
+    /// `None` if the server omitted it or sent the `<0>` placeholder
+    pub message_id: Option<String>,
 }
 
+impl Stat {
+    /// The number of the article relative to the group it was retrieved from
+    ///
+    /// This is `0` if the article was requested by message ID and the server did not provide one
+    pub fn number(&self) -> ArticleNumber {
+        self.number
+    }
+
+    /// The message id of the article, if the server provided one
+    pub fn message_id(&self) -> Option<&str> {
+        self.message_id.as_deref()
+    }
+
+    /// Parse the article number and message ID from the first line of an `ARTICLE`, `HEAD`,
+    /// `BODY`, or `STAT` response without checking the response code
+    ///
+    /// Some older servers send only the article number followed by free-form text, or `<0>` in
+    /// place of the message ID, so the message ID is optional and only a `<...>` token counts.
+    pub(crate) fn from_first_line(resp: &RawResponse) -> Result<Self> {
+        let lossy = resp.first_line_to_utf8_lossy();
+        let mut iter = lossy.split_whitespace();
+
+        iter.next(); // skip response code since we already parsed it
+
+        let number = parse_field(&mut iter, "article-number")?;
+        let message_id = iter
+            .next()
+            .filter(|id| id.starts_with('<') && id.ends_with('>') && *id != PLACEHOLDER_MESSAGE_ID)
+            .map(ToString::to_string);
+
+        Ok(Self { number, message_id })
+    }
+}
+
+/// The message ID sent by some servers when they don't know it
+const PLACEHOLDER_MESSAGE_ID: &str = "<0>";
+
 impl TryFrom<&RawResponse> for Stat {
     type Error = Error;
 
+    /// Parse a `223 number message-id` response to `STAT`, `NEXT`, or `LAST`
     fn try_from(resp: &RawResponse) -> Result<Self> {
         err_if_not_kind(resp, Kind::ArticleExists)?;
 
-        let (number, message_id) = process_article_first_line(resp)?;
+        Self::from_first_line(resp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        Ok(Self { number, message_id })
+    fn stat(first_line: &[u8]) -> Result<Stat> {
+        Stat::try_from(&RawResponse {
+            code: ResponseCode::Known(Kind::ArticleExists),
+            first_line: first_line.to_vec(),
+            data_blocks: None,
+        })
+    }
+
+    #[test]
+    fn test_try_from() {
+        let full = stat(b"223 45 <a@example.com> article exists\r\n").unwrap();
+        assert_eq!(full.number(), 45);
+        assert_eq!(full.message_id(), Some("<a@example.com>"));
+
+        let number_only = stat(b"223 45\r\n").unwrap();
+        assert_eq!(number_only.number(), 45);
+        assert_eq!(number_only.message_id(), None);
+
+        let text_only = stat(b"223 45 article retrieved\r\n").unwrap();
+        assert_eq!(text_only.number(), 45);
+        assert_eq!(text_only.message_id(), None);
+
+        let placeholder = stat(b"223 45 <0>\r\n").unwrap();
+        assert_eq!(placeholder.number(), 45);
+        assert_eq!(placeholder.message_id(), None);
+
+        // The number is still required
+        assert!(stat(b"223 \r\n").is_err());
+        assert!(stat(b"223 <a@example.com>\r\n").is_err());
     }
 }
@@ -34,6 +34,13 @@ impl TextArticle {
         &self.headers
     }
 
+    /// The newsgroups the article was posted to
+    ///
+    /// See [`Headers::newsgroups`] for details
+    pub fn newsgroups(&self) -> Vec<String> {
+        self.headers.newsgroups()
+    }
+
     /// Return the body of the article
     pub fn body(&self) -> &[String] {
         self.body.as_slice()
@@ -7,8 +7,16 @@ use crate::types::prelude::*;
 use crate::types::response::util::err_if_not_kind;
 
 /// Server capabilities
-#[derive(Clone, Debug, Eq, PartialEq)]
-pub struct Capabilities(HashMap<String, Capability>);
+///
+/// Capabilities that are not modeled by a dedicated method can be looked up with
+/// [`get`](Self::get) or read from the raw [`lines`](Self::lines).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Capabilities {
+    capabilities: HashMap<String, Capability>,
+    /// The arguments of each capability in the order they were sent
+    ordered_args: HashMap<String, Vec<String>>,
+    lines: Vec<String>,
+}
 
 /// A capability advertised by the server
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -21,13 +29,133 @@ impl Capabilities {
     /// An iterator over the capabilities
     pub fn iter(&self) -> Iter<'_> {
         Iter {
-            inner: self.0.values(),
+            inner: self.capabilities.values(),
         }
     }
 
     /// Retrieve a capability if it exists
     pub fn get(&self, key: impl AsRef<str>) -> Option<&Capability> {
-        self.0.get(key.as_ref())
+        self.capabilities.get(key.as_ref())
+    }
+
+    /// Returns true if the server advertises the capability
+    ///
+    /// Unlike [`get`](Self::get), the label is compared case-insensitively.
+    pub fn supports(&self, cap: impl AsRef<str>) -> bool {
+        let cap = cap.as_ref();
+        self.capabilities
+            .keys()
+            .any(|label| label.eq_ignore_ascii_case(cap))
+    }
+
+    /// The highest protocol version from the `VERSION` capability
+    ///
+    /// Servers implementing [RFC 3977](https://tools.ietf.org/html/rfc3977#section-5.2.2)
+    /// advertise `VERSION 2`. Returns `None` if the capability is missing or malformed.
+    pub fn version(&self) -> Option<u32> {
+        self.get("VERSION")?
+            .args
+            .as_ref()?
+            .iter()
+            .filter_map(|version| version.parse().ok())
+            .max()
+    }
+
+    /// Returns true if the server advertises `READER`
+    pub fn reader(&self) -> bool {
+        self.get("READER").is_some()
+    }
+
+    /// Returns true if the server advertises `POST`
+    pub fn post(&self) -> bool {
+        self.get("POST").is_some()
+    }
+
+    /// The arguments to the `OVER` capability in the order they were sent
+    ///
+    /// Returns `None` if the server does not advertise `OVER` (in which case `XOVER` may still
+    /// work, see [`overview_command`](Self::overview_command)) and an empty slice if `OVER` has
+    /// no arguments. Per [RFC 3977](https://tools.ietf.org/html/rfc3977#section-8.3) the only
+    /// defined argument is `MSGID`, indicating that `OVER` accepts a message-id. The fields
+    /// of the overview itself are listed by `LIST OVERVIEW.FMT`.
+    pub fn over_fields(&self) -> Option<&[String]> {
+        self.ordered_args.get("OVER").map(Vec::as_slice)
+    }
+
+    /// The arguments of a capability in the order they were sent
+    ///
+    /// The label is compared case-insensitively. Returns `None` if the server does not advertise
+    /// the capability and an empty slice if it has no arguments.
+    pub fn arguments(&self, cap: impl AsRef<str>) -> Option<&[String]> {
+        let cap = cap.as_ref();
+        self.ordered_args
+            .iter()
+            .find(|(label, _)| label.eq_ignore_ascii_case(cap))
+            .map(|(_, args)| args.as_slice())
+    }
+
+    /// The free-form description of the server software from the `IMPLEMENTATION` capability
+    ///
+    /// Unlike [`arguments`](Self::arguments), the text is returned as sent rather than split
+    /// into words (e.g. `INN 2.6.3`). Returns `None` if the capability is missing or empty.
+    pub fn implementation(&self) -> Option<&str> {
+        const LABEL: &str = "IMPLEMENTATION";
+
+        self.lines.iter().find_map(|line| {
+            let label = line.get(..LABEL.len())?;
+            let rest = &line[LABEL.len()..];
+            if !label.eq_ignore_ascii_case(LABEL) || !rest.starts_with(char::is_whitespace) {
+                return None;
+            }
+            Some(rest.trim()).filter(|text| !text.is_empty())
+        })
+    }
+
+    /// The capability lines as sent by the server
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    /// The SASL mechanisms advertised by the server
+    ///
+    /// Per [RFC 4643](https://tools.ietf.org/html/rfc4643#section-2.1) these are the arguments
+    /// to the `SASL` capability (e.g. `SASL PLAIN SCRAM-SHA-256`).
+    /// The mechanisms are returned in sorted order and the list will be empty if the server does
+    /// not support `AUTHINFO SASL`.
+    pub fn sasl_mechanisms(&self) -> Vec<String> {
+        let mut mechanisms = self
+            .get("SASL")
+            .and_then(|cap| cap.args.as_ref())
+            .map(|args| args.iter().cloned().collect::<Vec<_>>())
+            .unwrap_or_default();
+        mechanisms.sort();
+        mechanisms
+    }
+
+    /// The verb used to retrieve overviews, `OVER` if advertised and `XOVER` otherwise
+    pub fn overview_command(&self) -> &'static str {
+        self.standard_or_legacy("OVER", "XOVER")
+    }
+
+    /// The verb used to retrieve a single header, `HDR` if advertised and `XHDR` otherwise
+    pub fn header_command(&self) -> &'static str {
+        self.standard_or_legacy("HDR", "XHDR")
+    }
+
+    /// Choose between a standard command and its legacy RFC 2980 equivalent
+    ///
+    /// Servers that implement the standard command MUST advertise the capability of the same name,
+    /// so the legacy command is used whenever the capability is missing.
+    pub(crate) fn standard_or_legacy(
+        &self,
+        standard: &'static str,
+        legacy: &'static str,
+    ) -> &'static str {
+        if self.get(standard).is_some() {
+            standard
+        } else {
+            legacy
+        }
     }
 }
 
@@ -64,7 +192,7 @@ impl<'a> Iterator for Iter<'a> {
 impl TryFrom<&RawResponse> for Capabilities {
     type Error = Error;
 
-    /// Parse capabilities from a response
+    /// Parse capabilities from a `101` response
     ///
     /// The specific format is taken from [RFC 3977](https://tools.ietf.org/html/rfc3977#section-9.5)
     fn try_from(resp: &RawResponse) -> Result<Self> {
@@ -76,8 +204,21 @@ impl TryFrom<&RawResponse> for Capabilities {
             .ok_or_else(|| Error::de("Missing data blocks."))
             .map(DataBlocks::unterminated)?;
 
-        let capabilities: HashMap<String, Capability> = db_iter
-            .map(String::from_utf8_lossy)
+        let lines = db_iter
+            .map(|line| String::from_utf8_lossy(line).into_owned())
+            .collect::<Vec<_>>();
+
+        let ordered_args = lines
+            .iter()
+            .filter_map(|line| {
+                let mut entry_iter = line.split_whitespace();
+                let label = entry_iter.next()?.to_string();
+                Some((label, entry_iter.map(ToString::to_string).collect()))
+            })
+            .collect();
+
+        let capabilities: HashMap<String, Capability> = lines
+            .iter()
             .map(|entry| {
                 let mut entry_iter = entry.split_whitespace().peekable();
                 let label = entry_iter
@@ -100,6 +241,125 @@ impl TryFrom<&RawResponse> for Capabilities {
             })
             .collect::<Result<_>>()?;
 
-        Ok(Self(capabilities))
+        Ok(Self {
+            capabilities,
+            ordered_args,
+            lines,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capabilities_response(lines: &[&str]) -> RawResponse {
+        RawResponse {
+            code: ResponseCode::Known(Kind::Capabilities),
+            first_line: b"101 Capability list:\r\n".to_vec(),
+            data_blocks: Some(DataBlocks::from_lines(lines)),
+        }
+    }
+
+    #[test]
+    fn test_sasl_mechanisms() {
+        let resp = capabilities_response(&[
+            "VERSION 2",
+            "READER",
+            "AUTHINFO USER SASL",
+            "SASL SCRAM-SHA-256 PLAIN",
+        ]);
+        let capabilities = Capabilities::try_from(&resp).unwrap();
+
+        assert_eq!(
+            capabilities.sasl_mechanisms(),
+            vec!["PLAIN".to_string(), "SCRAM-SHA-256".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_no_sasl_mechanisms() {
+        let resp = capabilities_response(&["VERSION 2", "READER", "AUTHINFO USER"]);
+        let capabilities = Capabilities::try_from(&resp).unwrap();
+
+        assert!(capabilities.sasl_mechanisms().is_empty());
+    }
+
+    #[test]
+    fn test_introspection() {
+        let resp = capabilities_response(&[
+            "VERSION 2 3",
+            "READER",
+            "OVER MSGID",
+            "LIST ACTIVE NEWSGROUPS",
+        ]);
+        let capabilities = Capabilities::try_from(&resp).unwrap();
+
+        assert_eq!(capabilities.version(), Some(3));
+        assert!(capabilities.reader());
+        assert!(!capabilities.post());
+        assert!(capabilities.supports("over"));
+        assert!(!capabilities.supports("HDR"));
+        assert_eq!(capabilities.over_fields(), Some(&["MSGID".to_string()][..]));
+        assert_eq!(capabilities.lines()[3], "LIST ACTIVE NEWSGROUPS");
+
+        let resp = capabilities_response(&["VERSION", "POST", "OVER"]);
+        let capabilities = Capabilities::try_from(&resp).unwrap();
+
+        assert_eq!(capabilities.version(), None);
+        assert!(!capabilities.reader());
+        assert!(capabilities.post());
+        assert_eq!(capabilities.over_fields(), Some(&[][..]));
+    }
+
+    #[test]
+    fn test_arguments_and_implementation() {
+        let resp = capabilities_response(&[
+            "VERSION 2",
+            "READER",
+            "IMPLEMENTATION INN 2.6.3  (Debian)",
+            "HDR",
+            "LIST ACTIVE ACTIVE.TIMES DISTRIB.PATS HEADERS NEWSGROUPS OVERVIEW.FMT",
+            "NEWNEWS",
+            "OVER MSGID",
+            "POST",
+            "AUTHINFO USER",
+            "SASL PLAIN SCRAM-SHA-256",
+            "STARTTLS",
+        ]);
+        let capabilities = Capabilities::try_from(&resp).unwrap();
+
+        assert_eq!(
+            capabilities.arguments("list").unwrap(),
+            &[
+                "ACTIVE",
+                "ACTIVE.TIMES",
+                "DISTRIB.PATS",
+                "HEADERS",
+                "NEWSGROUPS",
+                "OVERVIEW.FMT"
+            ][..]
+        );
+        assert_eq!(capabilities.arguments("OVER").unwrap(), &["MSGID"][..]);
+        assert_eq!(capabilities.arguments("POST"), Some(&[][..]));
+        assert_eq!(capabilities.arguments("MODE-READER"), None);
+        assert_eq!(capabilities.implementation(), Some("INN 2.6.3  (Debian)"));
+
+        let resp = capabilities_response(&["VERSION 2", "IMPLEMENTATION", "IMPLEMENTATIONS x"]);
+        let capabilities = Capabilities::try_from(&resp).unwrap();
+        assert_eq!(capabilities.implementation(), None);
+    }
+
+    #[test]
+    fn test_standard_or_legacy() {
+        let resp = capabilities_response(&["VERSION 2", "READER", "OVER MSGID"]);
+        let capabilities = Capabilities::try_from(&resp).unwrap();
+        assert_eq!(capabilities.overview_command(), "OVER");
+        assert_eq!(capabilities.header_command(), "XHDR");
+
+        let resp = capabilities_response(&["VERSION 2", "READER", "HDR"]);
+        let capabilities = Capabilities::try_from(&resp).unwrap();
+        assert_eq!(capabilities.overview_command(), "XOVER");
+        assert_eq!(capabilities.header_command(), "HDR");
     }
 }
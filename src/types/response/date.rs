@@ -0,0 +1,160 @@
+use std::convert::TryFrom;
+use std::fmt;
+
+use crate::error::{Error, Result};
+use crate::types::prelude::*;
+use crate::types::response::util::err_if_not_kind;
+
+/// The server's time (in UTC) returned by [`DATE`](https://tools.ietf.org/html/rfc3977#section-7.1)
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct ServerDate {
+    year: u16,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8,
+}
+
+impl ServerDate {
+    /// The year (e.g. `2020`)
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    /// The month, from `1` to `12`
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    /// The day of the month, from `1` to `31`
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+
+    /// The hour, from `0` to `23`
+    pub fn hour(&self) -> u8 {
+        self.hour
+    }
+
+    /// The minute, from `0` to `59`
+    pub fn minute(&self) -> u8 {
+        self.minute
+    }
+
+    /// The second, from `0` to `60` (to allow for leap seconds)
+    pub fn second(&self) -> u8 {
+        self.second
+    }
+
+    /// Parse a `yyyymmddhhmmss` timestamp
+    fn parse(token: &str) -> Result<Self> {
+        if token.len() != 14 || !token.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(Error::de(format!(
+                "Invalid DATE timestamp `{}`, expected 14 digits",
+                token
+            )));
+        }
+
+        // n.b. the token is all ASCII digits so slicing and parsing are infallible
+        let field = |range: std::ops::Range<usize>| token[range].parse::<u16>().unwrap();
+        let date = Self {
+            year: field(0..4),
+            month: field(4..6) as u8,
+            day: field(6..8) as u8,
+            hour: field(8..10) as u8,
+            minute: field(10..12) as u8,
+            second: field(12..14) as u8,
+        };
+
+        if !(1..=12).contains(&date.month)
+            || !(1..=31).contains(&date.day)
+            || date.hour > 23
+            || date.minute > 59
+            || date.second > 60
+        {
+            return Err(Error::de(format!(
+                "DATE timestamp `{}` is out of range",
+                token
+            )));
+        }
+
+        Ok(date)
+    }
+}
+
+impl fmt::Display for ServerDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+impl TryFrom<&RawResponse> for ServerDate {
+    type Error = Error;
+
+    fn try_from(resp: &RawResponse) -> Result<Self> {
+        err_if_not_kind(resp, Kind::Date)?;
+
+        let lossy = resp.first_line_to_utf8_lossy();
+        let token = lossy
+            .split_whitespace()
+            .nth(1)
+            .ok_or_else(|| Error::missing_field("date"))?;
+
+        Self::parse(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date_response(first_line: &[u8]) -> RawResponse {
+        RawResponse {
+            code: ResponseCode::Known(Kind::Date),
+            first_line: first_line.to_vec(),
+            data_blocks: None,
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let date = ServerDate::try_from(&date_response(b"111 20201015043840\r\n")).unwrap();
+        assert_eq!(
+            (
+                date.year(),
+                date.month(),
+                date.day(),
+                date.hour(),
+                date.minute(),
+                date.second()
+            ),
+            (2020, 10, 15, 4, 38, 40)
+        );
+        assert_eq!(date.to_string(), "2020-10-15T04:38:40Z");
+    }
+
+    #[test]
+    fn test_malformed() {
+        for line in &[
+            &b"111\r\n"[..],
+            b"111 2020101504384\r\n",
+            b"111 202010150438400\r\n",
+            b"111 2020101504384a\r\n",
+            b"111 2020-10-1504384\r\n",
+            b"111 20201315043840\r\n",
+            b"111 20201015243840\r\n",
+            b"111 2020101504\xc3\xa938\r\n",
+        ] {
+            assert!(
+                ServerDate::try_from(&date_response(line)).is_err(),
+                "{:?}",
+                line
+            );
+        }
+    }
+}
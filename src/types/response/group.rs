@@ -1,4 +1,5 @@
 use std::convert::TryFrom;
+use std::ops::RangeInclusive;
 
 use crate::error::{Error, Result};
 use crate::types::prelude::*;
@@ -6,6 +7,7 @@ use crate::types::response::util::{err_if_not_kind, parse_field};
 
 /// Newsgroup metadata returned by [`GROUP`](https://tools.ietf.org/html/rfc3977#section-6.1.1)
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Group {
     /// The _estimated_ number of articles in the group
     pub number: ArticleNumber,
@@ -15,11 +17,140 @@ pub struct Group {
     pub high: ArticleNumber,
     /// The name of the group
     pub name: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) posting_status: Option<GroupStatus>,
+}
+
+impl Group {
+    /// Whether posting to the group is permitted, if known
+    ///
+    /// `GROUP` responses don't include the posting status, so this is only populated by the
+    /// client from a previous [`LIST ACTIVE`](crate::NntpClient::list_active) or
+    /// [`NEWGROUPS`](crate::NntpClient::newgroups) response that included the group.
+    pub fn posting_status(&self) -> Option<&GroupStatus> {
+        self.posting_status.as_ref()
+    }
+
+    /// Returns true if the group does not contain any articles
+    ///
+    /// Per [RFC 3977](https://tools.ietf.org/html/rfc3977#section-6.1.1.2) an empty group
+    /// reports a count of zero and will usually report a high water mark that is one less than
+    /// the low water mark (e.g. `211 0 1 0 misc.test`).
+    pub fn is_empty(&self) -> bool {
+        self.number == 0 || self.high < self.low
+    }
+
+    /// Returns true if the group appears to have been renumbered since the water marks
+    /// `prev_low` and `prev_high` were recorded
+    ///
+    /// This is the case if the current low water mark exceeds the previous high water mark or
+    /// the high water mark has decreased. A previously empty group (`prev_high < prev_low`) is
+    /// only checked for the latter.
+    ///
+    /// # Article number stability
+    ///
+    /// Article numbers are only guaranteed to be stable for the lifetime of a session.
+    /// [RFC 3977](https://tools.ietf.org/html/rfc3977#section-6) permits a server to renumber a
+    /// group (e.g. when it is rebuilt), after which a persisted number may refer to a different
+    /// article. Readers that persist article numbers should discard them when this returns
+    /// true. Note that this can also be caused by every previously seen article expiring, so
+    /// message IDs should be preferred when an article must be identified reliably.
+    pub fn renumbered_since(&self, prev_low: ArticleNumber, prev_high: ArticleNumber) -> bool {
+        let was_empty = prev_high < prev_low;
+        self.high < prev_high || (!was_empty && self.low > prev_high)
+    }
+
+    /// The range of article numbers reported by the server
+    ///
+    /// Returns `None` if the [group is empty](Self::is_empty) so that callers don't need to
+    /// worry about underflow when `low > high`.
+    pub fn article_range(&self) -> Option<RangeInclusive<ArticleNumber>> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.low..=self.high)
+        }
+    }
+}
+
+impl Group {
+    /// Compare two group names
+    ///
+    /// Per [RFC 3977](https://tools.ietf.org/html/rfc3977#section-4.1) group names are
+    /// case-sensitive, however some servers and tools treat them case-insensitively.
+    /// Be careful when comparing case-insensitively: `comp.lang.rust` and `Comp.Lang.Rust` may be
+    /// two distinct groups on a server that follows the standard.
+    pub fn name_eq(a: impl AsRef<str>, b: impl AsRef<str>, case_insensitive: bool) -> bool {
+        let (a, b) = (a.as_ref(), b.as_ref());
+        if case_insensitive {
+            a.to_lowercase() == b.to_lowercase()
+        } else {
+            a == b
+        }
+    }
+}
+
+/// Returns true if `name` is a valid newsgroup name
+///
+/// A valid name is made of one or more non-empty, dot separated components
+/// (e.g. `comp.lang.rust`) and does not contain whitespace, control characters, or the wildmat
+/// special characters (`*`, `?`, `[`, `]`, `\`, `!`, and `,`) as recommended by
+/// [RFC 3977](https://tools.ietf.org/html/rfc3977#section-4.1).
+///
+/// This can be used to validate user input before calling
+/// [`NntpClient::select_group`](crate::client::NntpClient::select_group).
+pub fn is_valid_group_name(name: impl AsRef<str>) -> bool {
+    let name = name.as_ref();
+    name.split('.').all(|component| !component.is_empty())
+        && !name.chars().any(|c| {
+            c.is_whitespace()
+                || c.is_control()
+                || matches!(c, '*' | '?' | '[' | ']' | '\\' | '!' | ',')
+        })
+}
+
+impl Group {
+    /// Check that the article count and water marks are consistent with one another
+    ///
+    /// The rules are taken from [RFC 3977](https://tools.ietf.org/html/rfc3977#section-6.1.1.2):
+    ///
+    /// * A non-empty group must have `low <= high` and a count no greater than `high - low + 1`
+    /// * An empty group must either report all zeros, `high = low - 1`, or `low <= high`
+    pub(crate) fn validate(&self) -> std::result::Result<(), String> {
+        let Group {
+            number, low, high, ..
+        } = *self;
+
+        if number == 0 {
+            if low > high.saturating_add(1) {
+                return Err(format!(
+                    "Empty group has a low water mark ({}) greater than the high water mark ({}) + 1",
+                    low, high
+                ));
+            }
+        } else if low > high {
+            return Err(format!(
+                "Low water mark ({}) is greater than the high water mark ({})",
+                low, high
+            ));
+        } else if number > high - low + 1 {
+            return Err(format!(
+                "Article count ({}) exceeds the size of the range {}-{}",
+                number, low, high
+            ));
+        }
+
+        Ok(())
+    }
 }
 
 impl TryFrom<&RawResponse> for Group {
     type Error = Error;
 
+    /// Parse a `211 number low high name` response to `GROUP`
+    ///
+    /// Some servers omit the group name or append extra tokens, so a missing name is treated as
+    /// empty and anything following it is ignored. The three counts are required.
     fn try_from(resp: &RawResponse) -> Result<Self> {
         err_if_not_kind(resp, Kind::GroupSelected)?;
 
@@ -30,15 +161,214 @@ impl TryFrom<&RawResponse> for Group {
         iter.next()
             .ok_or_else(|| Error::missing_field("response code"))?;
 
-        let number = parse_field(&mut iter, "number")?;
-        let low = parse_field(&mut iter, "low")?;
-        let high = parse_field(&mut iter, "high")?;
-        let name = parse_field(&mut iter, "name")?;
+        let with_line = |e| match e {
+            Error::Deserialization(msg) => {
+                Error::Deserialization(format!("{} in `{}`", msg, lossy.trim_end()))
+            }
+            e => e,
+        };
+        let number = parse_field(&mut iter, "number").map_err(with_line)?;
+        let low = parse_field(&mut iter, "low").map_err(with_line)?;
+        let high = parse_field(&mut iter, "high").map_err(with_line)?;
+        let name = iter.next().unwrap_or_default().to_string();
         Ok(Self {
             number,
             low,
             high,
             name,
+            posting_status: None,
+        })
+    }
+}
+
+/// Parse the article numbers returned by
+/// [`LISTGROUP`](https://tools.ietf.org/html/rfc3977#section-6.1.2)
+pub(crate) fn parse_article_numbers(resp: &RawResponse) -> Result<Vec<ArticleNumber>> {
+    err_if_not_kind(resp, Kind::GroupSelected)?;
+
+    resp.data_blocks
+        .as_ref()
+        .ok_or_else(Error::missing_data_blocks)?
+        .unterminated()
+        .map(|line| {
+            std::str::from_utf8(line)
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .ok_or_else(|| {
+                    Error::invalid_data_blocks(format!(
+                        "Invalid article number `{}`",
+                        String::from_utf8_lossy(line)
+                    ))
+                })
         })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group_response(first_line: &[u8]) -> RawResponse {
+        RawResponse {
+            code: ResponseCode::Known(Kind::GroupSelected),
+            first_line: first_line.to_vec(),
+            data_blocks: None,
+        }
+    }
+
+    #[test]
+    fn happy_path() {
+        let resp = group_response(b"211 1234 3000234 3002322 misc.test\r\n");
+        let group = Group::try_from(&resp).unwrap();
+
+        assert_eq!(
+            group,
+            Group {
+                number: 1234,
+                low: 3000234,
+                high: 3002322,
+                name: "misc.test".to_string(),
+                posting_status: None,
+            }
+        );
+        assert!(!group.is_empty());
+        assert_eq!(group.article_range(), Some(3000234..=3002322));
+    }
+
+    #[test]
+    fn missing_name() {
+        let resp = group_response(b"211 1234 3000234 3002322\r\n");
+        let group = Group::try_from(&resp).unwrap();
+
+        assert_eq!(group.number, 1234);
+        assert_eq!(group.high, 3002322);
+        assert_eq!(group.name, "");
+    }
+
+    #[test]
+    fn trailing_tokens() {
+        let resp = group_response(b"211 1234 3000234 3002322 misc.test selected ok\r\n");
+        let group = Group::try_from(&resp).unwrap();
+
+        assert_eq!(group.low, 3000234);
+        assert_eq!(group.name, "misc.test");
+    }
+
+    #[test]
+    fn invalid_counts() {
+        for line in &[
+            &b"211 1234 low 3002322 misc.test\r\n"[..],
+            b"211 1234 3000234\r\n",
+        ] {
+            match Group::try_from(&group_response(line)) {
+                Err(Error::Deserialization(msg)) => assert!(
+                    msg.contains(String::from_utf8_lossy(line).trim_end()),
+                    "{}",
+                    msg
+                ),
+                other => panic!("Unexpected result {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn empty_group() {
+        let resp = group_response(b"211 0 1 0 misc.test\r\n");
+        let group = Group::try_from(&resp).unwrap();
+
+        assert_eq!(group.number, 0);
+        assert_eq!(group.low, 1);
+        assert_eq!(group.high, 0);
+        assert!(group.is_empty());
+        assert_eq!(group.article_range(), None);
+        assert!(group.validate().is_ok());
+    }
+
+    #[test]
+    fn article_numbers() {
+        let resp = |lines: &[&str]| {
+            let mut resp = group_response(b"211 3 1 5 misc.test list follows\r\n");
+            resp.data_blocks = Some(DataBlocks::from_lines(lines));
+            resp
+        };
+
+        assert_eq!(
+            parse_article_numbers(&resp(&["1", "3", "5"])).unwrap(),
+            vec![1, 3, 5]
+        );
+        assert!(parse_article_numbers(&resp(&[])).unwrap().is_empty());
+
+        match parse_article_numbers(&resp(&["1", "three"])) {
+            Err(Error::Deserialization(msg)) => assert!(msg.contains("`three`"), "{}", msg),
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn renumbered_since() {
+        let group = |low, high| Group {
+            number: 10,
+            low,
+            high,
+            name: "misc.test".to_string(),
+            posting_status: None,
+        };
+
+        // Articles were added and expired
+        assert!(!group(100, 200).renumbered_since(100, 150));
+        assert!(!group(150, 200).renumbered_since(100, 200));
+        // The group was reset
+        assert!(group(1, 10).renumbered_since(100, 200));
+        assert!(group(201, 300).renumbered_since(100, 200));
+        // The group was previously empty
+        assert!(!group(1, 10).renumbered_since(1, 0));
+        assert!(!group(50, 60).renumbered_since(10, 9));
+        assert!(group(1, 5).renumbered_since(10, 9));
+    }
+
+    #[test]
+    fn validate() {
+        let group = |number, low, high| Group {
+            number,
+            low,
+            high,
+            name: "misc.test".to_string(),
+            posting_status: None,
+        };
+
+        assert!(group(0, 0, 0).validate().is_ok());
+        assert!(group(0, 10, 9).validate().is_ok());
+        assert!(group(0, 10, 20).validate().is_ok());
+        assert!(group(11, 10, 20).validate().is_ok());
+        assert!(group(5, 10, 20).validate().is_ok());
+
+        assert!(group(0, 10, 5).validate().is_err());
+        assert!(group(1, 10, 9).validate().is_err());
+        assert!(group(12, 10, 20).validate().is_err());
+    }
+
+    #[test]
+    fn test_name_eq() {
+        assert!(Group::name_eq("comp.lang.rust", "comp.lang.rust", false));
+        assert!(!Group::name_eq("comp.lang.rust", "Comp.Lang.Rust", false));
+        assert!(Group::name_eq("comp.lang.rust", "Comp.Lang.Rust", true));
+        assert!(!Group::name_eq("comp.lang.rust", "comp.lang.c", true));
+    }
+
+    #[test]
+    fn test_is_valid_group_name() {
+        assert!(is_valid_group_name("comp.lang.rust"));
+        assert!(is_valid_group_name("misc.test"));
+        assert!(is_valid_group_name("alt.binaries.teevee"));
+        assert!(is_valid_group_name("local"));
+
+        assert!(!is_valid_group_name(""));
+        assert!(!is_valid_group_name(".comp.lang"));
+        assert!(!is_valid_group_name("comp.lang."));
+        assert!(!is_valid_group_name("comp..lang"));
+        assert!(!is_valid_group_name("comp.lang rust"));
+        assert!(!is_valid_group_name("comp.lang.*"));
+        assert!(!is_valid_group_name("comp.lang,misc.test"));
+        assert!(!is_valid_group_name("comp.lang\r\n"));
     }
 }
@@ -0,0 +1,71 @@
+use crate::error::{Error, Result};
+use crate::types::prelude::*;
+use crate::types::response::util::err_if_not_kind;
+
+/// The value some servers send for articles that lack the requested header
+const NO_VALUE: &[u8] = b"(none)";
+
+/// Parse every line of an `HDR` (`225`) or `XHDR` (`221`) response
+///
+/// Each line is an article number followed by a space and the header value. Values are `None`
+/// if the server sent `(none)`, whereas an empty header is returned as an empty value.
+pub(crate) fn parse_hdr(
+    resp: &RawResponse,
+    kind: Kind,
+) -> Result<Vec<(ArticleNumber, Option<Vec<u8>>)>> {
+    err_if_not_kind(resp, kind)?;
+
+    resp.data_blocks
+        .as_ref()
+        .ok_or_else(Error::missing_data_blocks)?
+        .unterminated()
+        .map(parse_line)
+        .collect()
+}
+
+fn parse_line(line: &[u8]) -> Result<(ArticleNumber, Option<Vec<u8>>)> {
+    let (number, value) = match line.iter().position(|&b| b == b' ' || b == b'\t') {
+        Some(i) => (&line[..i], &line[i + 1..]),
+        None => (line, &b""[..]),
+    };
+
+    let number = std::str::from_utf8(number)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| {
+            Error::invalid_data_blocks(format!(
+                "Invalid article number in header line `{}`",
+                String::from_utf8_lossy(line)
+            ))
+        })?;
+
+    let value = if value == NO_VALUE {
+        None
+    } else {
+        Some(value.to_vec())
+    };
+
+    Ok((number, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line() {
+        assert_eq!(
+            parse_line(b"3000234 I am just a test article").unwrap(),
+            (3000234, Some(b"I am just a test article".to_vec()))
+        );
+        assert_eq!(parse_line(b"3000235 ").unwrap(), (3000235, Some(vec![])));
+        assert_eq!(parse_line(b"3000236").unwrap(), (3000236, Some(vec![])));
+        assert_eq!(parse_line(b"3000237 (none)").unwrap(), (3000237, None));
+        assert_eq!(
+            parse_line(b"3000238  leading space").unwrap(),
+            (3000238, Some(b" leading space".to_vec()))
+        );
+        assert!(parse_line(b"abc Subject").is_err());
+        assert!(parse_line(b"").is_err());
+    }
+}
@@ -0,0 +1,251 @@
+use std::convert::TryFrom;
+
+use crate::error::{Error, Result};
+use crate::types::prelude::*;
+use crate::types::response::util::{err_if_not_kind, parse_field};
+
+/// A newsgroup returned by [`LIST ACTIVE`](https://tools.ietf.org/html/rfc3977#section-7.6.3)
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ActiveGroup {
+    /// The name of the group
+    pub name: String,
+    /// The highest reported article number
+    pub high: ArticleNumber,
+    /// The lowest reported article number
+    pub low: ArticleNumber,
+    /// Whether posting to the group is permitted
+    pub status: GroupStatus,
+}
+
+impl ActiveGroup {
+    /// Returns true if the group does not contain any articles
+    ///
+    /// As with [`Group::is_empty`], an empty group usually reports a high water mark that is one
+    /// less than the low water mark.
+    pub fn is_empty(&self) -> bool {
+        self.high < self.low
+    }
+
+    /// Parse a single line of a `LIST ACTIVE` or `NEWGROUPS` response
+    pub(crate) fn parse(line: &[u8]) -> Result<Self> {
+        let lossy = String::from_utf8_lossy(line);
+        let mut iter = lossy.split_whitespace();
+
+        let name = parse_field(&mut iter, "name")?;
+        let high = parse_field(&mut iter, "high")?;
+        let low = parse_field(&mut iter, "low")?;
+        let status = iter
+            .next()
+            .map(GroupStatus::from)
+            .ok_or_else(|| Error::missing_field("status"))?;
+
+        Ok(Self {
+            name,
+            high,
+            low,
+            status,
+        })
+    }
+}
+
+/// The posting status of a group in a [`LIST ACTIVE`](https://tools.ietf.org/html/rfc3977#section-7.6.3)
+/// response
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GroupStatus {
+    /// Posting is permitted (`y`)
+    PostingAllowed,
+    /// Posting is not permitted (`n`)
+    NoPosting,
+    /// Postings will be forwarded to the moderator (`m`)
+    Moderated,
+    /// Any other status (e.g. `x` or `=alias.group`), as sent by the server
+    Other(String),
+}
+
+impl From<&str> for GroupStatus {
+    fn from(s: &str) -> Self {
+        match s {
+            "y" => GroupStatus::PostingAllowed,
+            "n" => GroupStatus::NoPosting,
+            "m" => GroupStatus::Moderated,
+            other => GroupStatus::Other(other.to_string()),
+        }
+    }
+}
+
+/// A group description returned by
+/// [`LIST NEWSGROUPS`](https://tools.ietf.org/html/rfc3977#section-7.6.6)
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GroupDescription {
+    /// The name of the group
+    pub name: String,
+    /// The description of the group, empty if the server did not provide one
+    pub description: String,
+}
+
+impl GroupDescription {
+    /// Parse a single line of a `LIST NEWSGROUPS` response
+    ///
+    /// Servers separate the name and description with spaces or tabs. Only the first run of
+    /// whitespace is treated as a separator, the rest of the line is kept verbatim.
+    fn parse(line: &[u8]) -> Self {
+        let lossy = String::from_utf8_lossy(line);
+        let (name, description) = match lossy.find(|c: char| c.is_ascii_whitespace()) {
+            Some(i) => (
+                &lossy[..i],
+                lossy[i..].trim_start_matches(|c: char| c.is_ascii_whitespace()),
+            ),
+            None => (lossy.as_ref(), ""),
+        };
+
+        Self {
+            name: name.to_string(),
+            description: description.to_string(),
+        }
+    }
+}
+
+/// Parse every line of a `LIST ACTIVE` (`215`) or `NEWGROUPS` (`231`) response
+pub(crate) fn parse_active(resp: &RawResponse, kind: Kind) -> Result<Vec<ActiveGroup>> {
+    err_if_not_kind(resp, kind)?;
+
+    resp.data_blocks
+        .as_ref()
+        .ok_or_else(Error::missing_data_blocks)?
+        .unterminated()
+        .map(ActiveGroup::parse)
+        .collect()
+}
+
+impl TryFrom<&RawResponse> for Vec<ActiveGroup> {
+    type Error = Error;
+
+    /// Parse every line of a `215` response to `LIST ACTIVE` or a `231` response to `NEWGROUPS`
+    fn try_from(resp: &RawResponse) -> Result<Self> {
+        match resp.code() {
+            ResponseCode::Known(Kind::NewGroups) => parse_active(resp, Kind::NewGroups),
+            _ => parse_active(resp, Kind::List),
+        }
+    }
+}
+
+/// Parse every line of a `LIST NEWSGROUPS` response
+pub(crate) fn parse_newsgroups(resp: &RawResponse) -> Result<Vec<GroupDescription>> {
+    err_if_not_kind(resp, Kind::List)?;
+
+    Ok(resp
+        .data_blocks
+        .as_ref()
+        .ok_or_else(Error::missing_data_blocks)?
+        .unterminated()
+        .map(GroupDescription::parse)
+        .collect())
+}
+
+/// Parse every line of a `LIST HEADERS` response
+///
+/// The special `:` (any header) and metadata items such as `:bytes` and `:lines` are kept as-is.
+pub(crate) fn parse_list_headers(resp: &RawResponse) -> Result<Vec<String>> {
+    err_if_not_kind(resp, Kind::List)?;
+
+    Ok(resp
+        .data_blocks
+        .as_ref()
+        .ok_or_else(Error::missing_data_blocks)?
+        .unterminated()
+        .map(|line| String::from_utf8_lossy(line).trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_active() {
+        assert_eq!(
+            ActiveGroup::parse(b"misc.test 3002322 3000234 y").unwrap(),
+            ActiveGroup {
+                name: "misc.test".to_string(),
+                high: 3002322,
+                low: 3000234,
+                status: GroupStatus::PostingAllowed,
+            }
+        );
+
+        let empty = ActiveGroup::parse(b"alt.empty 0 1 m").unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(empty.status, GroupStatus::Moderated);
+
+        let alias = ActiveGroup::parse(b"alt.old\t10\t1\t=alt.new").unwrap();
+        assert_eq!(alias.status, GroupStatus::Other("=alt.new".to_string()));
+
+        assert!(ActiveGroup::parse(b"misc.test 10 1").is_err());
+        assert!(ActiveGroup::parse(b"misc.test ten 1 y").is_err());
+    }
+
+    #[test]
+    fn test_try_from() {
+        let resp = |code, first_line: &[u8]| {
+            let payload = b"misc.test 3002322 3000234 y\r\nalt.empty 0 1 m\r\n.\r\n".to_vec();
+            RawResponse {
+                code: ResponseCode::Known(code),
+                first_line: first_line.to_vec(),
+                data_blocks: Some(DataBlocks {
+                    payload,
+                    line_boundaries: vec![(0, 29), (29, 46), (46, 49)],
+                }),
+            }
+        };
+
+        let groups = Vec::<ActiveGroup>::try_from(&resp(Kind::List, b"215 list\r\n")).unwrap();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].name, "misc.test");
+        assert!(groups[1].is_empty());
+
+        let new = Vec::<ActiveGroup>::try_from(&resp(Kind::NewGroups, b"231 new\r\n")).unwrap();
+        assert_eq!(new, groups);
+
+        assert!(Vec::<ActiveGroup>::try_from(&resp(Kind::Overview, b"224 over\r\n")).is_err());
+    }
+
+    #[test]
+    fn test_parse_list_headers() {
+        let payload = b"Subject\r\n:\r\n:lines \r\n:bytes\r\n.\r\n".to_vec();
+        let resp = RawResponse {
+            code: ResponseCode::Known(Kind::List),
+            first_line: b"215 headers supported:\r\n".to_vec(),
+            data_blocks: Some(DataBlocks {
+                payload,
+                line_boundaries: vec![(0, 9), (9, 12), (12, 21), (21, 29), (29, 32)],
+            }),
+        };
+        assert_eq!(
+            parse_list_headers(&resp).unwrap(),
+            vec!["Subject", ":", ":lines", ":bytes"]
+        );
+    }
+
+    #[test]
+    fn test_parse_newsgroups() {
+        let description = |line: &[u8]| GroupDescription::parse(line).description;
+
+        assert_eq!(
+            GroupDescription::parse(b"misc.test\tGeneral  testing\tof things"),
+            GroupDescription {
+                name: "misc.test".to_string(),
+                description: "General  testing\tof things".to_string(),
+            }
+        );
+        assert_eq!(description(b"misc.test   Spaces"), "Spaces");
+        assert_eq!(description(b"misc.test \t Mixed "), "Mixed ");
+
+        let no_description = GroupDescription::parse(b"alt.test");
+        assert_eq!(no_description.name, "alt.test");
+        assert_eq!(no_description.description, "");
+    }
+}
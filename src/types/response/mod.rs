@@ -1,10 +1,28 @@
 mod article;
 mod capabilities;
+mod date;
 mod group;
+mod hdr;
+mod list;
+mod newnews;
+mod overview;
 mod util;
 
 pub use article::*;
 
+pub(crate) use group::parse_article_numbers;
 pub use group::*;
 
+pub(crate) use hdr::parse_hdr;
+
+pub(crate) use list::{parse_active, parse_list_headers, parse_newsgroups};
+pub use list::{ActiveGroup, GroupDescription, GroupStatus};
+
+pub(crate) use newnews::parse_newnews;
+
+pub(crate) use overview::parse_overviews;
+pub use overview::{Overview, OverviewField, OverviewFmt};
+
 pub use capabilities::Capabilities;
+
+pub use date::ServerDate;
@@ -0,0 +1,49 @@
+use crate::error::{Error, Result};
+use crate::types::prelude::*;
+use crate::types::response::util::err_if_not_kind;
+use crate::types::MessageId;
+
+/// Parse the message IDs returned by [`NEWNEWS`](https://tools.ietf.org/html/rfc3977#section-7.4)
+///
+/// Each line must contain a single valid [`MessageId`].
+pub(crate) fn parse_newnews(resp: &RawResponse) -> Result<Vec<String>> {
+    err_if_not_kind(resp, Kind::NewNews)?;
+
+    resp.data_blocks
+        .as_ref()
+        .ok_or_else(Error::missing_data_blocks)?
+        .unterminated()
+        .map(parse_message_id)
+        .collect()
+}
+
+fn parse_message_id(line: &[u8]) -> Result<String> {
+    let id = std::str::from_utf8(line).map(str::trim).unwrap_or_default();
+
+    MessageId::new(id).map(MessageId::into_string).map_err(|_| {
+        Error::invalid_data_blocks(format!(
+            "Invalid message ID `{}`",
+            String::from_utf8_lossy(line)
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_message_id() {
+        assert_eq!(
+            parse_message_id(b"<45223423@example.com>").unwrap(),
+            "<45223423@example.com>"
+        );
+        assert_eq!(parse_message_id(b"<i.am@x> ").unwrap(), "<i.am@x>");
+
+        assert!(parse_message_id(b"45223423@example.com").is_err());
+        assert!(parse_message_id(b"<>").is_err());
+        assert!(parse_message_id(b"<a@b> <c@d>").is_err());
+        assert!(parse_message_id(b"<a>b@c>").is_err());
+        assert!(parse_message_id(b"<\xff@example.com>").is_err());
+    }
+}
@@ -0,0 +1,489 @@
+use std::convert::TryFrom;
+
+use crate::error::{Error, Result};
+use crate::types::parse_news_date;
+use crate::types::prelude::*;
+use crate::types::response::util::err_if_not_kind;
+
+/// The number of fields that must be present in every overview
+///
+/// Per [RFC 3977](https://tools.ietf.org/html/rfc3977#section-8.3) these are the Subject, From,
+/// Date, Message-ID, References, `:bytes`, and `:lines` fields.
+const STANDARD_FIELDS: usize = 7;
+
+/// The overview of an article returned by [`OVER`](https://tools.ietf.org/html/rfc3977#section-8.3)
+/// or `XOVER`
+///
+/// The standard fields are converted to UTF-8 lossily. Any additional fields sent by the server
+/// are available via [`extra`](Self::extra).
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Overview {
+    /// The number of the article (`0` when requested by message ID)
+    pub number: ArticleNumber,
+    /// The contents of the `Subject` header
+    pub subject: String,
+    /// The contents of the `From` header
+    pub from: String,
+    /// The contents of the `Date` header
+    pub date: String,
+    /// The contents of the `Message-ID` header
+    pub message_id: String,
+    /// The contents of the `References` header
+    pub references: String,
+    /// The size of the article in bytes, if reported by the server
+    pub bytes: Option<u64>,
+    /// The number of lines in the article body, if reported by the server
+    pub lines: Option<u64>,
+    extra: Vec<ExtraField>,
+}
+
+/// A field beyond the standard seven
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct ExtraField {
+    name: Option<String>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::types::response::util::base64_bytes")
+    )]
+    value: Vec<u8>,
+}
+
+impl Overview {
+    /// Parse a single line of an overview response
+    ///
+    /// The line should NOT include the CRLF terminator.
+    ///
+    /// If the server's [`OverviewFmt`] is provided, additional fields are named according to it.
+    /// Otherwise, additional fields are only named if they are in the `Name: value` form used by
+    /// `:full` fields.
+    pub fn parse(line: &[u8], fmt: Option<&OverviewFmt>) -> Result<Self> {
+        let mut fields = line.split(|&b| b == b'\t');
+
+        let number = fields
+            .next()
+            .map(String::from_utf8_lossy)
+            .ok_or_else(|| Error::missing_field("article-number"))?
+            .trim()
+            .parse()
+            .map_err(|_| Error::parse_error("article-number"))?;
+
+        let mut next_str = |name: &str| {
+            fields
+                .next()
+                .map(|f| String::from_utf8_lossy(f).into_owned())
+                .ok_or_else(|| Error::missing_field(name))
+        };
+        let subject = next_str("subject")?;
+        let from = next_str("from")?;
+        let date = next_str("date")?;
+        let message_id = next_str("message-id")?;
+        let references = next_str("references")?;
+        let bytes = parse_count(&next_str("bytes")?);
+        let lines = parse_count(&next_str("lines")?);
+
+        let extra_fmt = fmt.map(OverviewFmt::extra_fields).unwrap_or_default();
+        let extra = fields
+            .enumerate()
+            .map(|(i, value)| match extra_fmt.get(i) {
+                Some(field) if field.full => ExtraField {
+                    name: Some(field.name.clone()),
+                    value: strip_header_name(value, &field.name).to_vec(),
+                },
+                Some(field) => ExtraField {
+                    name: Some(field.name.clone()),
+                    value: value.to_vec(),
+                },
+                None => match split_header(value) {
+                    Some((name, value)) => ExtraField {
+                        name: Some(name),
+                        value: value.to_vec(),
+                    },
+                    None => ExtraField {
+                        name: None,
+                        value: value.to_vec(),
+                    },
+                },
+            })
+            .collect();
+
+        Ok(Self {
+            number,
+            subject,
+            from,
+            date,
+            message_id,
+            references,
+            bytes,
+            lines,
+            extra,
+        })
+    }
+
+    /// The date field parsed as UTC
+    ///
+    /// Returns `None` if the date cannot be parsed. See [`parse_news_date`] for the formats
+    /// that are accepted.
+    pub fn date_parsed(&self) -> Option<NewsDateTime> {
+        parse_news_date(self.date.as_bytes()).ok()
+    }
+
+    /// Get an additional field by name (e.g. `Xref`)
+    ///
+    /// Names are compared case-insensitively. For `:full` fields, the leading header name
+    /// (e.g. `Xref: `) is removed from the value.
+    pub fn extra(&self, name: impl AsRef<str>) -> Option<&[u8]> {
+        let name = name.as_ref();
+        self.extra
+            .iter()
+            .find(|f| matches!(&f.name, Some(n) if n.eq_ignore_ascii_case(name)))
+            .map(|f| f.value.as_slice())
+    }
+
+    /// The values of all fields beyond the standard seven, in the order they were sent
+    pub fn extra_fields(&self) -> impl Iterator<Item = &[u8]> {
+        self.extra.iter().map(|f| f.value.as_slice())
+    }
+}
+
+/// Parse every line of an `OVER`/`XOVER` response
+pub(crate) fn parse_overviews(
+    resp: &RawResponse,
+    fmt: Option<&OverviewFmt>,
+) -> Result<Vec<Overview>> {
+    err_if_not_kind(resp, Kind::Overview)?;
+
+    resp.data_blocks
+        .as_ref()
+        .ok_or_else(Error::missing_data_blocks)?
+        .unterminated()
+        .map(|line| Overview::parse(line, fmt))
+        .collect()
+}
+
+impl TryFrom<&RawResponse> for Vec<Overview> {
+    type Error = Error;
+
+    /// Parse every line of a `224` response to `OVER` or `XOVER`
+    ///
+    /// Without the server's [`OverviewFmt`] the fields following the standard seven are unnamed,
+    /// use [`Overview::parse`] on each line to look them up by name.
+    fn try_from(resp: &RawResponse) -> Result<Self> {
+        parse_overviews(resp, None)
+    }
+}
+
+/// Parse the `:bytes` or `:lines` field
+///
+/// Servers may leave these empty or send placeholders, which are treated as unknown rather than
+/// as an error so that the rest of the overview is still usable.
+fn parse_count(field: &str) -> Option<u64> {
+    let field = field.trim();
+    if field.is_empty() || !field.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    field.parse().ok()
+}
+
+/// Strip a leading `Name:` (and any following whitespace) from a `:full` field
+fn strip_header_name<'a>(value: &'a [u8], name: &str) -> &'a [u8] {
+    match split_header(value) {
+        Some((n, rest)) if n.eq_ignore_ascii_case(name) => rest,
+        _ => value,
+    }
+}
+
+/// Split a field in the form `Name: value`
+fn split_header(field: &[u8]) -> Option<(String, &[u8])> {
+    let colon = field.iter().position(|&b| b == b':')?;
+    let name = &field[..colon];
+    if name.is_empty() || !name.iter().all(|b| (33..=126).contains(b)) {
+        return None;
+    }
+
+    let mut rest = &field[colon + 1..];
+    while let Some((b' ', tail)) = rest.split_first() {
+        rest = tail;
+    }
+
+    Some((String::from_utf8_lossy(name).into_owned(), rest))
+}
+
+/// The fields returned by `OVER`, as listed by
+/// [`LIST OVERVIEW.FMT`](https://tools.ietf.org/html/rfc3977#section-8.4)
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OverviewFmt {
+    fields: Vec<OverviewField>,
+}
+
+/// A field within an [`OverviewFmt`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OverviewField {
+    /// The name of the header (e.g. `Subject`) or metadata item (e.g. `:bytes`)
+    pub name: String,
+    /// True if the field is sent with the header name prepended (e.g. `Xref:full`)
+    pub full: bool,
+}
+
+/// The names of the mandatory fields, in the order they must appear
+const STANDARD_FIELD_NAMES: [&str; STANDARD_FIELDS] = [
+    "Subject",
+    "From",
+    "Date",
+    "Message-ID",
+    "References",
+    ":bytes",
+    ":lines",
+];
+
+impl OverviewFmt {
+    /// The fields in the order they appear in an overview
+    ///
+    /// Note that the article number is not included.
+    pub fn fields(&self) -> &[OverviewField] {
+        &self.fields
+    }
+
+    /// The index of a field within [`fields`](Self::fields)
+    ///
+    /// Names are compared case-insensitively and leading/trailing colons are ignored, so
+    /// `"xref"`, `"Xref:"`, and `"bytes"` all match. Note that the article number is not a field,
+    /// so the field is found at `index + 1` within a tab separated overview line.
+    pub fn field_index(&self, name: &str) -> Option<usize> {
+        let name = name.trim_matches(':');
+        self.fields
+            .iter()
+            .position(|f| f.name.trim_matches(':').eq_ignore_ascii_case(name))
+    }
+
+    /// The fields that follow the standard seven
+    pub fn extra_fields(&self) -> &[OverviewField] {
+        self.fields.get(STANDARD_FIELDS..).unwrap_or_default()
+    }
+}
+
+impl TryFrom<&RawResponse> for OverviewFmt {
+    type Error = Error;
+
+    fn try_from(resp: &RawResponse) -> Result<Self> {
+        err_if_not_kind(resp, Kind::List)?;
+
+        let fields = resp
+            .data_blocks
+            .as_ref()
+            .ok_or_else(Error::missing_data_blocks)?
+            .unterminated()
+            .map(String::from_utf8_lossy)
+            .map(|line| {
+                let line = line.trim();
+                match line.strip_suffix(":full") {
+                    Some(name) => OverviewField {
+                        name: name.to_string(),
+                        full: true,
+                    },
+                    None => OverviewField {
+                        name: line.strip_suffix(':').unwrap_or(line).to_string(),
+                        full: false,
+                    },
+                }
+            })
+            .enumerate()
+            .map(|(i, mut field)| {
+                // The mandatory fields are always in the same order, but some servers list them
+                // without the colon or use the legacy `Bytes:`/`Lines:` names
+                if let Some(name) = STANDARD_FIELD_NAMES.get(i) {
+                    field.name = name.to_string();
+                    field.full = false;
+                }
+                field
+            })
+            .collect();
+
+        Ok(Self { fields })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overview_fmt(lines: &[&str]) -> OverviewFmt {
+        let resp = RawResponse {
+            code: ResponseCode::Known(Kind::List),
+            first_line: b"215 Order of fields in overview database.\r\n".to_vec(),
+            data_blocks: Some(DataBlocks::from_lines(lines)),
+        };
+
+        OverviewFmt::try_from(&resp).unwrap()
+    }
+
+    const LINE: &[u8] = b"3000234\tI am just a test article\t\"Demo User\" <nobody@example.com>\t\
+        6 Oct 1998 04:38:40 -0500\t<45223423@example.com>\t<45454@example.net>\t1234\t17\t\
+        Xref: news.example.com misc.test:3000363";
+
+    #[test]
+    fn test_parse() {
+        let overview = Overview::parse(LINE, None).unwrap();
+        assert_eq!(overview.number, 3000234);
+        assert_eq!(overview.subject, "I am just a test article");
+        assert_eq!(overview.from, "\"Demo User\" <nobody@example.com>");
+        assert_eq!(overview.date, "6 Oct 1998 04:38:40 -0500");
+        assert_eq!(overview.message_id, "<45223423@example.com>");
+        assert_eq!(overview.references, "<45454@example.net>");
+        assert_eq!(overview.bytes, Some(1234));
+        assert_eq!(overview.lines, Some(17));
+        assert_eq!(
+            overview.date_parsed(),
+            NewsDateTime::new(1998, 10, 6, 9, 38, 40)
+        );
+        assert_eq!(
+            overview.extra("xref"),
+            Some(&b"news.example.com misc.test:3000363"[..])
+        );
+    }
+
+    #[test]
+    fn test_try_from_response() {
+        let mut payload = LINE.to_vec();
+        payload.extend_from_slice(b"\r\n.\r\n");
+        let mut resp = RawResponse {
+            code: ResponseCode::Known(Kind::Overview),
+            first_line: b"224 Overview information follows\r\n".to_vec(),
+            data_blocks: Some(DataBlocks {
+                line_boundaries: vec![(0, LINE.len() + 2), (LINE.len() + 2, payload.len())],
+                payload,
+            }),
+        };
+
+        let overviews = Vec::<Overview>::try_from(&resp).unwrap();
+        assert_eq!(overviews, vec![Overview::parse(LINE, None).unwrap()]);
+
+        resp.code = ResponseCode::Known(Kind::List);
+        assert!(Vec::<Overview>::try_from(&resp).is_err());
+    }
+
+    #[test]
+    fn test_parse_missing_fields() {
+        assert!(Overview::parse(b"3000234\tsubject\tfrom", None).is_err());
+        assert!(Overview::parse(b"abc\t\t\t\t\t\t\t", None).is_err());
+
+        let overview = Overview::parse(b"1\t\t\t\t\t\t\t", None).unwrap();
+        assert_eq!(overview.bytes, None);
+        assert_eq!(overview.lines, None);
+        assert_eq!(overview.date_parsed(), None);
+    }
+
+    #[test]
+    fn test_parse_placeholder_counts() {
+        for (bytes, lines) in &[("12a", "-"), ("+12", "1 7"), ("-1", "unknown")] {
+            let line = format!("1\t\t\t\t\t\t{}\t{}", bytes, lines);
+            let overview = Overview::parse(line.as_bytes(), None).unwrap();
+            assert_eq!(overview.bytes, None, "{:?}", bytes);
+            assert_eq!(overview.lines, None, "{:?}", lines);
+        }
+
+        // A zero count is distinct from an unknown one
+        let overview = Overview::parse(b"1\t\t\t\t\t\t 0 \t0", None).unwrap();
+        assert_eq!(overview.bytes, Some(0));
+        assert_eq!(overview.lines, Some(0));
+    }
+
+    #[test]
+    fn test_extra_aligned_with_fmt() {
+        let fmt = overview_fmt(&[
+            "Subject:",
+            "From:",
+            "Date:",
+            "Message-ID:",
+            "References:",
+            ":bytes",
+            ":lines",
+            "Xref:full",
+            "Distribution:full",
+            "X-Unprefixed:",
+        ]);
+        assert_eq!(
+            fmt.extra_fields(),
+            &[
+                OverviewField {
+                    name: "Xref".to_string(),
+                    full: true
+                },
+                OverviewField {
+                    name: "Distribution".to_string(),
+                    full: true
+                },
+                OverviewField {
+                    name: "X-Unprefixed".to_string(),
+                    full: false
+                },
+            ]
+        );
+
+        assert_eq!(fmt.field_index("subject"), Some(0));
+        assert_eq!(fmt.field_index("Bytes"), Some(5));
+        assert_eq!(fmt.field_index("Xref:"), Some(7));
+        assert_eq!(fmt.field_index("Newsgroups"), None);
+
+        // The empty Distribution field is aligned by position rather than by its contents
+        let line = [LINE, b"\t\tsome: value"].concat();
+        let overview = Overview::parse(&line, Some(&fmt)).unwrap();
+        assert_eq!(
+            overview.extra("Xref"),
+            Some(&b"news.example.com misc.test:3000363"[..])
+        );
+        assert_eq!(overview.extra("Distribution"), Some(&b""[..]));
+        assert_eq!(overview.extra("X-Unprefixed"), Some(&b"some: value"[..]));
+        assert_eq!(overview.extra("some"), None);
+        assert_eq!(overview.extra_fields().count(), 3);
+    }
+
+    #[test]
+    fn test_standard_field_names() {
+        let fmt = overview_fmt(&[
+            "Subject",
+            "From:",
+            "Date:",
+            "Message-ID:",
+            "References:",
+            "Bytes:",
+            "Lines:",
+            "Xref:full",
+        ]);
+        let names = fmt
+            .fields()
+            .iter()
+            .map(|f| f.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                "Subject",
+                "From",
+                "Date",
+                "Message-ID",
+                "References",
+                ":bytes",
+                ":lines",
+                "Xref"
+            ]
+        );
+        assert_eq!(fmt.field_index(":lines"), Some(6));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let mut line = LINE.to_vec();
+        line.extend_from_slice(b"\tX-Binary: \xff\xfe");
+        let overview = Overview::parse(&line, None).unwrap();
+
+        let json = serde_json::to_string(&overview).unwrap();
+        assert!(json.contains(r#""value":"//4=""#), "{}", json);
+
+        let decoded: Overview = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, overview);
+        assert_eq!(decoded.extra("X-Binary"), Some(&b"\xff\xfe"[..]));
+    }
+}
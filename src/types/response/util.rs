@@ -49,3 +49,22 @@ pub(crate) fn process_article_first_line(resp: &RawResponse) -> Result<(ArticleN
 
     Ok((number, message_id))
 }
+
+/// (De)serialize raw bytes as a base64 string
+///
+/// Header values and other raw fields aren't guaranteed to be UTF-8, base64 keeps them lossless.
+#[cfg(feature = "serde")]
+pub(crate) mod base64_bytes {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub(crate) fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64::encode(bytes))
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<u8>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        base64::decode(s).map_err(serde::de::Error::custom)
+    }
+}
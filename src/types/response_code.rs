@@ -9,6 +9,7 @@ use std::fmt;
 /// Because proprietary NNTP extensions may define their own codes, there is no way for this library
 /// to know about all of the codes that exist. Unknown codes will be stored as `u16`s.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ResponseCode {
     /// A response code implemented by the library
     Known(Kind),
@@ -49,6 +50,31 @@ impl ResponseCode {
         code >= 500 && code < 600
     }
 
+    /// The response is informational (1xx), equivalent to [`is_info`](Self::is_info)
+    pub fn is_informational(&self) -> bool {
+        self.is_info()
+    }
+
+    /// The command can continue (3xx), equivalent to [`is_success_so_far`](Self::is_success_so_far)
+    ///
+    /// The server expects more input, e.g. the article after `POST` or the password after
+    /// `AUTHINFO USER`.
+    pub fn is_continuation(&self) -> bool {
+        self.is_success_so_far()
+    }
+
+    /// The command failed but may succeed later or in another state (4xx), equivalent to
+    /// [`is_failure`](Self::is_failure)
+    pub fn is_transient_error(&self) -> bool {
+        self.is_failure()
+    }
+
+    /// The command failed and retrying it won't help (5xx), equivalent to
+    /// [`is_error`](Self::is_error)
+    pub fn is_permanent_error(&self) -> bool {
+        self.is_error()
+    }
+
     /// Returns true if the response is a Known multiline response
     ///
     /// Unknown responses are always false
@@ -84,10 +110,14 @@ impl From<&ResponseCode> for u16 {
     }
 }
 
+/// Known codes are displayed with their name (e.g. `211 (GroupSelected)`) while unknown codes are
+/// displayed as a number
 impl fmt::Display for ResponseCode {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let code: u16 = self.into();
-        write!(f, "{}", code)
+        match self {
+            ResponseCode::Known(kind) => write!(f, "{}", kind),
+            ResponseCode::Unknown(code) => write!(f, "{}", code),
+        }
     }
 }
 
@@ -99,6 +129,7 @@ impl fmt::Display for ResponseCode {
 /// * [RFC 3977 Appendix C](https://tools.ietf.org/html/rfc3977#appendix-C)
 #[repr(u16)]
 #[derive(Copy, Clone, Debug, Eq, PartialEq, num_enum::TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
 pub enum Kind {
     Help = 100,
@@ -108,6 +139,7 @@ pub enum Kind {
     PostingAllowed = 200,
     PostingProhibited = 201,
     ConnectionClosing = 205,
+    CompressionActive = 206,
     /// This is generated by `GROUP` and `LISTGROUP` and the bodies are different depending
     /// on which command is used. Buyer beware!
     GroupSelected = 211,
@@ -118,8 +150,12 @@ pub enum Kind {
     Body = 222,
     ArticleExists = 223,
     Overview = 224,
+    Hdr = 225,
+    NewNews = 230,
+    NewGroups = 231,
 
     ArticleTransferredOk = 235,
+    ArticlePostedOk = 240,
 
     IHaveSendArticle = 335,
     PostSendArticle = 340,
@@ -150,9 +186,15 @@ pub enum Kind {
 
     // Authentication https://tools.ietf.org/html/rfc4643
     AuthenticationAccepted = 281,
+    AuthenticationAcceptedWithData = 283,
     PasswordRequired = 381,
+    SaslContinue = 383,
     AuthenticationFailed = 481,
     AuthenticationOutOfSequence = 482,
+
+    // TLS https://tools.ietf.org/html/rfc4642
+    ContinueWithTls = 382,
+    TlsNegotiationFailed = 580,
 }
 
 impl Kind {
@@ -177,6 +219,13 @@ impl Kind {
     }
 }
 
+/// Kinds are displayed as the code followed by the name (e.g. `211 (GroupSelected)`)
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({:?})", *self as u16, self)
+    }
+}
+
 impl From<Kind> for u16 {
     fn from(code: Kind) -> Self {
         code as u16
@@ -188,3 +237,49 @@ impl From<Kind> for ResponseCode {
         ResponseCode::Known(kind)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Kind::GroupSelected.to_string(), "211 (GroupSelected)");
+        assert_eq!(
+            ResponseCode::Known(Kind::NoSuchNewsgroup).to_string(),
+            "411 (NoSuchNewsgroup)"
+        );
+        assert_eq!(ResponseCode::Unknown(299).to_string(), "299");
+    }
+
+    #[test]
+    fn test_classification() {
+        let classes = |code: u16| {
+            let code = ResponseCode::from(code);
+            [
+                code.is_informational(),
+                code.is_success(),
+                code.is_continuation(),
+                code.is_transient_error(),
+                code.is_permanent_error(),
+            ]
+        };
+
+        assert_eq!(classes(100), [true, false, false, false, false]);
+        assert_eq!(classes(199), [true, false, false, false, false]);
+        assert_eq!(classes(200), [false, true, false, false, false]);
+        assert_eq!(classes(299), [false, true, false, false, false]);
+        assert_eq!(classes(300), [false, false, true, false, false]);
+        assert_eq!(classes(381), [false, false, true, false, false]);
+        assert_eq!(classes(411), [false, false, false, true, false]);
+        assert_eq!(classes(599), [false, false, false, false, true]);
+
+        // Codes outside 100-599 aren't in any class
+        for code in &[0, 99, 600, 999, u16::MAX] {
+            assert_eq!(classes(*code), [false; 5], "{}", code);
+        }
+
+        assert!(matches!(ResponseCode::from(299), ResponseCode::Unknown(_)));
+        assert!(matches!(ResponseCode::from(411), ResponseCode::Known(_)));
+    }
+}
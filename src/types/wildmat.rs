@@ -0,0 +1,195 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+
+/// A [wildmat](https://tools.ietf.org/html/rfc3977#section-4) matching newsgroup names or
+/// header values (e.g. `comp.*,!comp.lang.*`)
+///
+/// A wildmat is a comma separated list of patterns, where `*` matches any sequence of
+/// characters, `?` matches a single character, and a pattern prefixed with `!` excludes the
+/// names it matches. The last pattern that matches a name determines the result.
+///
+/// Wildmats converted from strings are [validated](Self::validate) before they are sent by the
+/// client, so a stray space or CRLF results in an [`Error::InvalidWildmat`] rather than a
+/// protocol error.
+///
+/// ```
+/// use brokaw::types::Wildmat;
+///
+/// let wildmat = Wildmat::prefix("comp.").except("comp.lang.*").to_owned();
+/// assert_eq!(wildmat.to_string(), "comp.*,!comp.lang.*");
+///
+/// assert!(Wildmat::new("misc.test").is_ok());
+/// assert!(Wildmat::new("comp.* misc.*").is_err());
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Wildmat(String);
+
+impl Wildmat {
+    /// Validate a wildmat
+    ///
+    /// Returns [`Error::InvalidWildmat`] if the wildmat is malformed.
+    pub fn new(pattern: impl AsRef<str>) -> Result<Self> {
+        let wildmat = Self(pattern.as_ref().to_string());
+        wildmat.validate()?;
+        Ok(wildmat)
+    }
+
+    /// A wildmat matching exactly one name (e.g. `misc.test`)
+    pub fn literal(name: impl AsRef<str>) -> Self {
+        Self(name.as_ref().to_string())
+    }
+
+    /// A wildmat matching every name that starts with `prefix`
+    ///
+    /// `Wildmat::prefix("comp.")` is equivalent to `comp.*`.
+    pub fn prefix(prefix: impl AsRef<str>) -> Self {
+        Self(format!("{}*", prefix.as_ref()))
+    }
+
+    /// Also match names matching `pattern`
+    pub fn or(&mut self, pattern: impl AsRef<str>) -> &mut Self {
+        self.0.push(',');
+        self.0.push_str(pattern.as_ref());
+        self
+    }
+
+    /// Exclude names matching `pattern`
+    ///
+    /// As the last matching pattern wins, this only excludes names matched by the preceding
+    /// patterns.
+    pub fn except(&mut self, pattern: impl AsRef<str>) -> &mut Self {
+        self.0.push_str(",!");
+        self.0.push_str(pattern.as_ref());
+        self
+    }
+
+    /// Check that the wildmat is well formed
+    ///
+    /// Per [RFC 3977](https://tools.ietf.org/html/rfc3977#section-4.1) a wildmat may not contain
+    /// whitespace or control characters, each pattern must be non-empty, and only patterns
+    /// after the first may be negated.
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |reason: &str| Err(Error::InvalidWildmat(format!("{:?} {}", self.0, reason)));
+
+        if self.0.is_empty() {
+            return invalid("is empty");
+        }
+        if self.0.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            return invalid("contains whitespace or control characters");
+        }
+
+        for (i, pattern) in self.0.split(',').enumerate() {
+            let pattern = match pattern.strip_prefix('!') {
+                Some(_) if i == 0 => return invalid("starts with a negated pattern"),
+                Some(negated) => negated,
+                None => pattern,
+            };
+
+            if pattern.is_empty() {
+                return invalid("contains an empty pattern");
+            }
+            if pattern.contains('!') {
+                return invalid("contains `!` within a pattern");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The wildmat as it is sent to the server
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Consume the wildmat, returning the underlying string
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+impl fmt::Display for Wildmat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for Wildmat {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for Wildmat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::new(s)
+    }
+}
+
+impl From<&str> for Wildmat {
+    fn from(pattern: &str) -> Self {
+        Self(pattern.to_string())
+    }
+}
+
+impl From<String> for Wildmat {
+    fn from(pattern: String) -> Self {
+        Self(pattern)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builders() {
+        assert_eq!(Wildmat::literal("misc.test").as_str(), "misc.test");
+        assert_eq!(Wildmat::prefix("comp.").as_str(), "comp.*");
+        assert_eq!(
+            Wildmat::prefix("comp.")
+                .except("comp.lang.*")
+                .or("comp.lang.rust")
+                .to_string(),
+            "comp.*,!comp.lang.*,comp.lang.rust"
+        );
+    }
+
+    #[test]
+    fn test_valid() {
+        for pattern in &[
+            "*",
+            "misc.test",
+            "comp.*,!comp.lang.*",
+            "a?c",
+            "*@example.com",
+            "alt.bücher",
+        ] {
+            assert_eq!(Wildmat::new(pattern).unwrap().as_str(), *pattern);
+        }
+    }
+
+    #[test]
+    fn test_invalid() {
+        for pattern in &[
+            "",
+            "comp.* misc.*",
+            "misc.*\r\nQUIT",
+            "misc\t*",
+            "!comp.*",
+            "comp.*,,misc.*",
+            "comp.*,!",
+            "comp.*,",
+            "comp!.*",
+        ] {
+            assert!(
+                matches!(Wildmat::new(pattern), Err(Error::InvalidWildmat(_))),
+                "{:?}",
+                pattern
+            );
+        }
+    }
+}